@@ -0,0 +1,522 @@
+//! 资源包 v2 格式的读写
+//!
+//! 布局（均为小端序）:
+//! ```text
+//! [Header: 32字节]
+//! [Entry; entry_count]   每项固定 40 字节，按路径排序
+//! [路径字符串表]          所有路径（UTF-8，小写，`/`分隔）依次拼接
+//! [文件数据]              每个文件单独存储，可选 zstd 压缩
+//! ```
+//!
+//! 由于索引项是定长的，已知索引号时可以 O(1) 定位任意文件，
+//! 索引号可以在编译期通过 phf 表从路径映射得到。
+//!
+//! 本 crate 不负责压缩与解压，调用者自行选择 zstd 的实现。
+
+use std::fmt;
+
+/// 文件头魔数
+pub const MAGIC: [u8; 4] = *b"THPK";
+/// 当前格式版本
+pub const VERSION: u32 = 2;
+/// 文件头长度
+pub const HEADER_SIZE: usize = 32;
+/// 索引项长度
+pub const ENTRY_SIZE: usize = 40;
+/// 索引项标志：数据使用 zstd 压缩
+pub const FLAG_ZSTD: u32 = 1;
+
+/// 格式错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// 魔数不匹配，可能是 v1 格式或其他文件
+    BadMagic,
+    /// 不支持的版本
+    UnsupportedVersion(u32),
+    /// 数据被截断或偏移越界
+    OutOfBounds { offset: u64, len: u64 },
+    /// 索引号越界
+    IndexOutOfRange(usize),
+    /// 路径不是合法的 UTF-8
+    InvalidPath(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BadMagic => write!(f, "bad magic, not a v2 resource pack"),
+            Error::UnsupportedVersion(v) => write!(f, "unsupported resource pack version {v}"),
+            Error::OutOfBounds { offset, len } => {
+                write!(f, "resource pack is truncated (offset={offset}, len={len})")
+            }
+            Error::IndexOutOfRange(i) => write!(f, "entry index {i} out of range"),
+            Error::InvalidPath(i) => write!(f, "entry {i} has an invalid utf-8 path"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// 文件头
+#[derive(Debug, Clone, Copy)]
+pub struct Header {
+    pub version: u32,
+    pub entry_count: u32,
+    pub strings_offset: u64,
+    pub strings_len: u64,
+}
+
+/// 索引项
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    /// 路径在字符串表中的偏移
+    pub path_offset: u32,
+    /// 路径长度
+    pub path_len: u32,
+    /// 数据在整个资源包中的偏移
+    pub offset: u64,
+    /// 原始大小
+    pub size: u64,
+    /// 存储大小（未压缩时与原始大小相同）
+    pub compressed_size: u64,
+    /// 原始数据的 CRC32
+    pub crc32: u32,
+    /// 标志位
+    pub flags: u32,
+}
+
+impl Entry {
+    /// 数据是否使用 zstd 压缩
+    pub fn is_compressed(&self) -> bool {
+        self.flags & FLAG_ZSTD != 0
+    }
+}
+
+/// 判断数据是否以 v2 格式的魔数开头
+pub fn is_v2(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+fn slice(data: &[u8], offset: u64, len: u64) -> Result<&[u8]> {
+    usize::try_from(offset)
+        .ok()
+        .zip(usize::try_from(len).ok())
+        .and_then(|(start, len)| data.get(start..start.checked_add(len)?))
+        .ok_or(Error::OutOfBounds { offset, len })
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+/// 资源包读取器，只借用数据，不做任何拷贝
+#[derive(Debug, Clone, Copy)]
+pub struct PakReader<'a> {
+    data: &'a [u8],
+    header: Header,
+}
+
+impl<'a> PakReader<'a> {
+    /// 解析文件头并检查索引与字符串表的边界
+    pub fn new(data: &'a [u8]) -> Result<Self> {
+        let head = slice(data, 0, HEADER_SIZE as u64)?;
+        if head[0..4] != MAGIC {
+            return Err(Error::BadMagic);
+        }
+
+        let header = Header {
+            version: read_u32(head, 4),
+            entry_count: read_u32(head, 8),
+            strings_offset: read_u64(head, 16),
+            strings_len: read_u64(head, 24),
+        };
+
+        if header.version != VERSION {
+            return Err(Error::UnsupportedVersion(header.version));
+        }
+
+        slice(
+            data,
+            HEADER_SIZE as u64,
+            header.entry_count as u64 * ENTRY_SIZE as u64,
+        )?;
+        slice(data, header.strings_offset, header.strings_len)?;
+
+        Ok(Self { data, header })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// 文件数量
+    pub fn len(&self) -> usize {
+        self.header.entry_count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 按索引号读取索引项，O(1)
+    pub fn entry(&self, index: usize) -> Result<Entry> {
+        if index >= self.len() {
+            return Err(Error::IndexOutOfRange(index));
+        }
+
+        let at = HEADER_SIZE + index * ENTRY_SIZE;
+        let raw = &self.data[at..at + ENTRY_SIZE];
+
+        Ok(Entry {
+            path_offset: read_u32(raw, 0),
+            path_len: read_u32(raw, 4),
+            offset: read_u64(raw, 8),
+            size: read_u64(raw, 16),
+            compressed_size: read_u64(raw, 24),
+            crc32: read_u32(raw, 32),
+            flags: read_u32(raw, 36),
+        })
+    }
+
+    /// 读取索引项对应的路径
+    pub fn path(&self, index: usize, entry: &Entry) -> Result<&'a str> {
+        let strings = slice(
+            self.data,
+            self.header.strings_offset,
+            self.header.strings_len,
+        )?;
+        let bytes = slice(strings, entry.path_offset as u64, entry.path_len as u64)?;
+        std::str::from_utf8(bytes).map_err(|_| Error::InvalidPath(index))
+    }
+
+    /// 读取索引项对应的存储数据（可能是压缩后的）
+    pub fn stored_data(&self, entry: &Entry) -> Result<&'a [u8]> {
+        slice(self.data, entry.offset, entry.compressed_size)
+    }
+
+    /// 遍历所有索引项
+    pub fn entries(&self) -> impl Iterator<Item = Result<(&'a str, Entry)>> + '_ {
+        (0..self.len()).map(|index| {
+            let entry = self.entry(index)?;
+            Ok((self.path(index, &entry)?, entry))
+        })
+    }
+
+    /// 通过二分查找路径获取索引号，O(log n)
+    ///
+    /// 运行时应优先使用编译期生成的 phf 表
+    pub fn find(&self, path: &str) -> Option<usize> {
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let entry = self.entry(mid).ok()?;
+            match self.path(mid, &entry).ok()?.cmp(path) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+}
+
+struct PendingEntry {
+    path: String,
+    stored: Vec<u8>,
+    size: u64,
+    crc32: u32,
+    flags: u32,
+}
+
+/// 资源包写入器
+#[derive(Default)]
+pub struct PakWriter {
+    entries: Vec<PendingEntry>,
+}
+
+impl PakWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一个文件
+    ///
+    /// 若提供了压缩数据且比原始数据小，则存储压缩数据，否则存储原始数据
+    pub fn add(&mut self, path: &str, content: &[u8], compressed: Option<Vec<u8>>) {
+        let (stored, flags) = match compressed {
+            Some(compressed) if compressed.len() < content.len() => (compressed, FLAG_ZSTD),
+            _ => (content.to_vec(), 0),
+        };
+
+        self.entries.push(PendingEntry {
+            path: path.to_string(),
+            stored,
+            size: content.len() as u64,
+            crc32: crc32(content),
+            flags,
+        });
+    }
+
+    /// 按路径排序后输出整个资源包，返回的顺序即为索引号
+    pub fn finish(mut self) -> (Vec<u8>, Vec<String>) {
+        self.entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let strings_offset = (HEADER_SIZE + self.entries.len() * ENTRY_SIZE) as u64;
+        let strings_len: u64 = self.entries.iter().map(|e| e.path.len() as u64).sum();
+        let mut data_offset = strings_offset + strings_len;
+
+        let mut out = Vec::new();
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&strings_offset.to_le_bytes());
+        out.extend_from_slice(&strings_len.to_le_bytes());
+
+        let mut path_offset = 0u32;
+        for entry in &self.entries {
+            out.extend_from_slice(&path_offset.to_le_bytes());
+            out.extend_from_slice(&(entry.path.len() as u32).to_le_bytes());
+            out.extend_from_slice(&data_offset.to_le_bytes());
+            out.extend_from_slice(&entry.size.to_le_bytes());
+            out.extend_from_slice(&(entry.stored.len() as u64).to_le_bytes());
+            out.extend_from_slice(&entry.crc32.to_le_bytes());
+            out.extend_from_slice(&entry.flags.to_le_bytes());
+
+            path_offset += entry.path.len() as u32;
+            data_offset += entry.stored.len() as u64;
+        }
+
+        for entry in &self.entries {
+            out.extend_from_slice(entry.path.as_bytes());
+        }
+
+        for entry in &self.entries {
+            out.extend_from_slice(&entry.stored);
+        }
+
+        let paths = self.entries.into_iter().map(|e| e.path).collect();
+        (out, paths)
+    }
+}
+
+/// CRC32（IEEE 802.3，与 zip/png 相同）
+pub fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut c = i as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 {
+                    0xEDB8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[i] = c;
+            i += 1;
+        }
+        table
+    };
+
+    let mut crc = !0u32;
+    for &b in data {
+        crc = TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_pack() -> (Vec<u8>, Vec<String>) {
+        let mut writer = PakWriter::new();
+        writer.add("script/b.txt", b"hello world", None);
+        writer.add("image/a.png", &[0u8; 64], Some(vec![1, 2, 3]));
+        writer.add("empty.bin", b"", None);
+        writer.finish()
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn round_trip() {
+        let (data, paths) = sample_pack();
+        assert_eq!(paths, ["empty.bin", "image/a.png", "script/b.txt"]);
+        assert!(is_v2(&data));
+
+        let reader = PakReader::new(&data).unwrap();
+        assert_eq!(reader.len(), 3);
+        assert_eq!(reader.header().version, VERSION);
+
+        let entries: Vec<_> = reader.entries().collect::<Result<_>>().unwrap();
+        let read_paths: Vec<_> = entries.iter().map(|(path, _)| *path).collect();
+        assert_eq!(read_paths, paths);
+
+        let (_, text) = entries[2];
+        assert!(!text.is_compressed());
+        assert_eq!(text.size, 11);
+        assert_eq!(reader.stored_data(&text).unwrap(), b"hello world");
+        assert_eq!(text.crc32, crc32(b"hello world"));
+
+        let (_, image) = entries[1];
+        assert!(image.is_compressed());
+        assert_eq!(image.size, 64);
+        assert_eq!(image.compressed_size, 3);
+        assert_eq!(reader.stored_data(&image).unwrap(), [1, 2, 3]);
+        assert_eq!(image.crc32, crc32(&[0u8; 64]));
+
+        let (_, empty) = entries[0];
+        assert_eq!(reader.stored_data(&empty).unwrap(), b"");
+
+        for (index, path) in paths.iter().enumerate() {
+            assert_eq!(reader.find(path), Some(index));
+        }
+        assert_eq!(reader.find("missing.txt"), None);
+    }
+
+    #[test]
+    fn larger_compressed_data_is_not_stored() {
+        let mut writer = PakWriter::new();
+        writer.add("a.txt", b"abc", Some(vec![0; 8]));
+        let (data, _) = writer.finish();
+
+        let reader = PakReader::new(&data).unwrap();
+        let entry = reader.entry(0).unwrap();
+        assert!(!entry.is_compressed());
+        assert_eq!(reader.stored_data(&entry).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn empty_pack() {
+        let (data, paths) = PakWriter::new().finish();
+        assert!(paths.is_empty());
+        assert_eq!(data.len(), HEADER_SIZE);
+
+        let reader = PakReader::new(&data).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(reader.entries().count(), 0);
+        assert_eq!(reader.find("a.txt"), None);
+    }
+
+    #[test]
+    fn entry_index_out_of_range() {
+        let (data, _) = sample_pack();
+        let reader = PakReader::new(&data).unwrap();
+        assert_eq!(reader.entry(3).unwrap_err(), Error::IndexOutOfRange(3));
+    }
+
+    #[test]
+    fn bad_magic_and_version() {
+        let (mut data, _) = sample_pack();
+        data[4..8].copy_from_slice(&3u32.to_le_bytes());
+        assert_eq!(
+            PakReader::new(&data).unwrap_err(),
+            Error::UnsupportedVersion(3)
+        );
+
+        data[0] = b'X';
+        assert!(!is_v2(&data));
+        assert_eq!(PakReader::new(&data).unwrap_err(), Error::BadMagic);
+    }
+
+    #[test]
+    fn truncated_input_never_panics() {
+        let (data, _) = sample_pack();
+
+        for len in 0..data.len() {
+            let truncated = &data[..len];
+            let Ok(reader) = PakReader::new(truncated) else {
+                continue;
+            };
+
+            // 文件头、索引与字符串表完整时，只有文件数据可能越界
+            let mut failed = false;
+            for index in 0..reader.len() {
+                let entry = reader.entry(index).unwrap();
+                reader.path(index, &entry).unwrap();
+                failed |= reader.stored_data(&entry).is_err();
+            }
+            assert!(
+                failed,
+                "truncated to {len} bytes but all entries are readable"
+            );
+        }
+
+        assert!(matches!(
+            PakReader::new(&data[..HEADER_SIZE - 1]),
+            Err(Error::OutOfBounds { .. })
+        ));
+        assert!(matches!(
+            PakReader::new(&data[..HEADER_SIZE + ENTRY_SIZE]),
+            Err(Error::OutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn malformed_offsets_are_rejected() {
+        let (data, _) = sample_pack();
+
+        // 索引项数量远超数据长度
+        let mut bad_count = data.clone();
+        bad_count[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            PakReader::new(&bad_count),
+            Err(Error::OutOfBounds { .. })
+        ));
+
+        // 字符串表偏移溢出
+        let mut bad_strings = data.clone();
+        bad_strings[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            PakReader::new(&bad_strings),
+            Err(Error::OutOfBounds { .. })
+        ));
+
+        // 第一个索引项的数据偏移溢出，路径越界
+        let mut bad_entry = data.clone();
+        let at = HEADER_SIZE;
+        bad_entry[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        bad_entry[at + 8..at + 16].copy_from_slice(&u64::MAX.to_le_bytes());
+        let reader = PakReader::new(&bad_entry).unwrap();
+        let entry = reader.entry(0).unwrap();
+        assert!(matches!(
+            reader.path(0, &entry),
+            Err(Error::OutOfBounds { .. })
+        ));
+        assert!(matches!(
+            reader.stored_data(&entry),
+            Err(Error::OutOfBounds { .. })
+        ));
+        assert!(reader.entries().next().unwrap().is_err());
+    }
+
+    #[test]
+    fn invalid_utf8_path() {
+        let mut writer = PakWriter::new();
+        writer.add("a.txt", b"a", None);
+        let (mut data, _) = writer.finish();
+
+        let strings_offset = HEADER_SIZE + ENTRY_SIZE;
+        data[strings_offset] = 0xFF;
+
+        let reader = PakReader::new(&data).unwrap();
+        let entry = reader.entry(0).unwrap();
+        assert_eq!(reader.path(0, &entry).unwrap_err(), Error::InvalidPath(0));
+        assert_eq!(reader.find("a.txt"), None);
+    }
+}
@@ -1,10 +1,11 @@
-use windows_sys::Win32::Globalization::{MultiByteToWideChar, WideCharToMultiByte};
+use windows_sys::Win32::Globalization::{
+    IsDBCSLeadByteEx, MultiByteToWideChar, WideCharToMultiByte,
+};
 use windows_sys::Win32::Graphics::Gdi::{
     ANSI_CHARSET, ARABIC_CHARSET, BALTIC_CHARSET, CHINESEBIG5_CHARSET, EASTEUROPE_CHARSET,
     GB2312_CHARSET, GREEK_CHARSET, HANGUL_CHARSET, HEBREW_CHARSET, RUSSIAN_CHARSET,
     SHIFTJIS_CHARSET, THAI_CHARSET, TURKISH_CHARSET, VIETNAMESE_CHARSET,
 };
-use windows_sys::Win32::UI::WindowsAndMessaging::CharNextExA;
 
 use crate::constant::{CHAR_FILTER, CHAR_SET};
 use crate::print_last_error_message;
@@ -194,17 +195,78 @@ pub const fn get_cp_by_char_set() -> u32 {
     }
 }
 
+/// 常用双字节代码页的前导字节范围（闭区间）
+fn lead_byte_ranges(code_page: u32) -> Option<&'static [(u8, u8)]> {
+    match code_page {
+        // 日语 Shift-JIS
+        932 => Some(&[(0x81, 0x9F), (0xE0, 0xFC)]),
+        // 简体中文 GBK、韩语、繁体中文 Big5
+        936 | 949 | 950 => Some(&[(0x81, 0xFE)]),
+        // 韩语 Johab
+        1361 => Some(&[(0x84, 0xD3), (0xD8, 0xDE), (0xE0, 0xF9)]),
+        _ => None,
+    }
+}
+
+/// 判断字节在指定代码页中是否为前导字节
+///
+/// 常用的双字节代码页使用内置的表，不受当前进程ANSI代码页以及钩子的影响，
+/// 其他代码页交给`IsDBCSLeadByteEx`
+pub fn is_lead_byte(code_page: u32, byte: u8) -> bool {
+    match lead_byte_ranges(code_page) {
+        Some(ranges) => ranges
+            .iter()
+            .any(|&(start, end)| (start..=end).contains(&byte)),
+        None => unsafe { IsDBCSLeadByteEx(code_page, byte) != 0 },
+    }
+}
+
+/// 返回指定代码页中下一个字符的位置，语义与`CharNextExA`相同
+///
+/// 位于结尾的 null 时返回`ptr`本身，前导字节后紧跟 null 时只前进一个字节
+///
+/// # Safety
+/// `ptr`必须指向以 null 结尾的字符串
+pub unsafe fn char_next(ptr: *const u8, code_page: u32) -> *const u8 {
+    unsafe {
+        match *ptr {
+            0 => ptr,
+            byte if is_lead_byte(code_page, byte) && *ptr.add(1) != 0 => ptr.add(2),
+            _ => ptr.add(1),
+        }
+    }
+}
+
+/// 返回指定代码页中`current`之前一个字符的位置，语义与`CharPrevExA`相同
+///
+/// 从`start`开始向后遍历，因此不会把尾字节误认为前导字节，`current`不大于`start`时返回`start`
+///
+/// # Safety
+/// `start`到`current`之间必须是有效的字符串
+pub unsafe fn char_prev(start: *const u8, current: *const u8, code_page: u32) -> *const u8 {
+    let mut prev = start;
+    let mut cur = start;
+
+    while cur < current {
+        let next = unsafe { char_next(cur, code_page) };
+        if next == cur {
+            break;
+        }
+        prev = cur;
+        cur = next;
+    }
+
+    prev
+}
+
 /// 根据字符数和代码页计算传入字符串的字节长度
-pub fn byte_len(ptr: *const u8, chars: usize, code_page: u16) -> usize {
+pub fn byte_len(ptr: *const u8, chars: usize, code_page: u32) -> usize {
     let mut cur = ptr;
     let mut byte_len = 0usize;
 
     unsafe {
         for _ in 0..chars {
-            let next = CharNextExA(code_page, cur, 0);
-            if next.is_null() {
-                break;
-            }
+            let next = char_next(cur, code_page);
             byte_len += next.offset_from(cur) as usize;
             cur = next;
         }
@@ -212,3 +274,86 @@ pub fn byte_len(ptr: *const u8, chars: usize, code_page: u16) -> usize {
 
     byte_len
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lead_bytes(code_page: u32) -> Vec<u8> {
+        (0..=u8::MAX)
+            .filter(|&byte| is_lead_byte(code_page, byte))
+            .collect()
+    }
+
+    #[test]
+    fn shift_jis_lead_bytes() {
+        let expected: Vec<u8> = (0x81..=0x9F).chain(0xE0..=0xFC).collect();
+        assert_eq!(lead_bytes(932), expected);
+        // 半角片假名是单字节字符
+        assert!(!is_lead_byte(932, 0xA0));
+        assert!(!is_lead_byte(932, 0xB1));
+        assert!(!is_lead_byte(932, 0xDF));
+        assert!(!is_lead_byte(932, 0xFD));
+    }
+
+    #[test]
+    fn gbk_lead_bytes() {
+        let expected: Vec<u8> = (0x81..=0xFE).collect();
+        for code_page in [936, 949, 950] {
+            assert_eq!(lead_bytes(code_page), expected);
+        }
+        assert!(!is_lead_byte(936, 0x80));
+        assert!(!is_lead_byte(936, 0xFF));
+    }
+
+    #[test]
+    fn johab_lead_bytes() {
+        let expected: Vec<u8> = (0x84..=0xD3)
+            .chain(0xD8..=0xDE)
+            .chain(0xE0..=0xF9)
+            .collect();
+        assert_eq!(lead_bytes(1361), expected);
+        for byte in [0x83, 0xD4, 0xD7, 0xDF, 0xFA] {
+            assert!(!is_lead_byte(1361, byte));
+        }
+    }
+
+    #[test]
+    fn char_next_stops_at_null() {
+        let text = [0x82, 0xA0, 0x41, 0x82, 0];
+        let ptr = text.as_ptr();
+        unsafe {
+            assert_eq!(char_next(ptr, 932), ptr.add(2));
+            assert_eq!(char_next(ptr.add(2), 932), ptr.add(3));
+            // 前导字节后紧跟 null 时只前进一个字节
+            assert_eq!(char_next(ptr.add(3), 932), ptr.add(4));
+            assert_eq!(char_next(ptr.add(4), 932), ptr.add(4));
+        }
+    }
+
+    #[test]
+    fn char_prev_with_trail_in_lead_range() {
+        // GBK 的尾字节 0xB0 也在前导字节的范围内，只能从开头向后遍历
+        let text = [0xB0, 0xB0, 0xB0, 0xB0, 0x41, 0];
+        let start = text.as_ptr();
+        unsafe {
+            assert_eq!(char_prev(start, start.add(5), 936), start.add(4));
+            assert_eq!(char_prev(start, start.add(4), 936), start.add(2));
+            assert_eq!(char_prev(start, start.add(3), 936), start.add(2));
+            assert_eq!(char_prev(start, start.add(2), 936), start);
+            assert_eq!(char_prev(start, start, 936), start);
+        }
+    }
+
+    #[test]
+    fn byte_len_counts_double_byte_chars() {
+        let text = [0x82, 0xA0, 0xB1, 0x88, 0x9F, 0x82, 0];
+        let ptr = text.as_ptr();
+        assert_eq!(byte_len(ptr, 0, 932), 0);
+        assert_eq!(byte_len(ptr, 2, 932), 3);
+        assert_eq!(byte_len(ptr, 3, 932), 5);
+        // 不完整的双字节字符只算一个字节，到达 null 后不再前进
+        assert_eq!(byte_len(ptr, 4, 932), 6);
+        assert_eq!(byte_len(ptr, 10, 932), 6);
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use core::ffi::c_void;
+
+use crate::debug;
+
+pub const SEEK_SET: i32 = 0;
+pub const SEEK_CUR: i32 = 1;
+pub const SEEK_END: i32 = 2;
+
+/// 已匹配补丁的`FILE*`，读取操作由内存中的补丁数据提供
+struct PatchedFile {
+    data: &'static [u8],
+    pos: usize,
+}
+
+/// `FILE*` -> 补丁数据及当前读取位置
+static FILES: LazyLock<Mutex<HashMap<usize, PatchedFile>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 判断打开模式是否为二进制只读，只有这种情况才能直接用补丁数据代替文件内容
+///
+/// 文本模式下CRT会转换换行符，此时只在`fread`之后对缓冲区进行patch
+pub fn is_binary_read_mode(mode: &str) -> bool {
+    mode.starts_with('r') && !mode.contains('+') && mode.contains('b')
+}
+
+/// 在`fopen`成功后调用，若文件匹配某个补丁（按文件名或哈希），则记录该`FILE*`
+///
+/// - `fseek`、`ftell`、`fread` 须为同一个CRT的原函数，用于获取文件长度以及读取文件内容
+/// - 读取完成后文件位置会被重置到开头
+///
+/// 注意：记录后只有`fread`、`fseek`、`ftell`由补丁数据提供，
+/// 其他读取函数（如`fgetc`、`fgets`）仍然读取原文件
+///
+/// # Safety
+/// - `file` 必须是刚刚由`fopen`返回的有效`FILE*`
+pub unsafe fn on_file_opened(
+    file: *mut c_void,
+    file_name: Option<&str>,
+    fseek: impl Fn(*mut c_void, i32, i32) -> i32,
+    ftell: impl Fn(*mut c_void) -> i32,
+    fread: impl Fn(*mut u8, usize, usize, *mut c_void) -> usize,
+) {
+    if file.is_null() {
+        return;
+    }
+
+    if fseek(file, 0, SEEK_END) != 0 {
+        return;
+    }
+    let len = ftell(file);
+    fseek(file, 0, SEEK_SET);
+
+    if len < 0 || !crate::patch::is_patch_len(len as usize) {
+        return;
+    }
+    let len = len as usize;
+
+    let patch = file_name
+        .and_then(|name| crate::patch::get_patch_by_name(name, len))
+        .or_else(|| {
+            let mut content = vec![0u8; len];
+            let read = fread(content.as_mut_ptr(), 1, len, file);
+            fseek(file, 0, SEEK_SET);
+
+            if read != len {
+                debug!("fread returned {read}, expected {len}");
+                return None;
+            }
+
+            crate::patch::get_patch(&content)
+        });
+
+    if let Some(data) = patch {
+        debug!(
+            "FILE {file:p} of {} matched a patch (len={len})",
+            file_name.unwrap_or("<unknown>")
+        );
+        FILES
+            .lock()
+            .unwrap()
+            .insert(file as usize, PatchedFile { data, pos: 0 });
+    }
+}
+
+/// 若`FILE*`已匹配补丁，则从补丁数据中读取，返回读取的元素个数
+///
+/// 返回`None`表示该文件无需处理，应调用原函数
+///
+/// # Safety
+/// - `buffer` 至少需要 `size * count` 字节的可写空间
+pub unsafe fn read(buffer: *mut u8, size: usize, count: usize, file: *mut c_void) -> Option<usize> {
+    let mut files = FILES.lock().ok()?;
+    let entry = files.get_mut(&(file as usize))?;
+
+    if size == 0 || count == 0 {
+        return Some(0);
+    }
+
+    // fseek 允许移动到文件末尾之后，此时读取不到任何数据
+    let pos = entry.pos.min(entry.data.len());
+    let bytes = size.saturating_mul(count).min(entry.data.len() - pos);
+
+    unsafe {
+        core::ptr::copy_nonoverlapping(entry.data.as_ptr().add(pos), buffer, bytes);
+    }
+    entry.pos += bytes;
+
+    Some(bytes / size)
+}
+
+/// 若`FILE*`已匹配补丁，则移动读取位置，返回值与`fseek`相同
+pub fn seek(file: *mut c_void, offset: i32, origin: i32) -> Option<i32> {
+    let mut files = FILES.lock().ok()?;
+    let entry = files.get_mut(&(file as usize))?;
+
+    let base = match origin {
+        SEEK_SET => 0,
+        SEEK_CUR => entry.pos as i64,
+        SEEK_END => entry.data.len() as i64,
+        _ => return Some(-1),
+    };
+
+    let pos = base + offset as i64;
+    if pos < 0 {
+        return Some(-1);
+    }
+
+    entry.pos = pos as usize;
+    Some(0)
+}
+
+/// 若`FILE*`已匹配补丁，则返回当前读取位置
+pub fn tell(file: *mut c_void) -> Option<i32> {
+    let files = FILES.lock().ok()?;
+    files.get(&(file as usize)).map(|entry| entry.pos as i32)
+}
+
+/// 在`fclose`前调用，移除对应的记录
+pub fn on_file_closed(file: *mut c_void) {
+    if let Ok(mut files) = FILES.lock()
+        && files.remove(&(file as usize)).is_some()
+    {
+        debug!("Patched FILE {file:p} closed");
+    }
+}
@@ -5,3 +5,13 @@
 compile_error!(
     "特性 `apply_1337_patch_on_attach` 和 `apply_1337_patch_on_hwbp_hit` 不能同时启用，因为它们都涉及对同一补丁的应用时机控制，可能导致冲突和不确定行为。请根据需要选择一个特性启用。"
 );
+
+#[cfg(all(feature = "resource_pack_vfs", feature = "resource_pack_lazy"))]
+compile_error!(
+    "特性 `resource_pack_vfs` 和 `resource_pack_lazy` 不能同时启用，前者不会将资源包写入磁盘，后者会在文件第一次打开时解压到临时目录。请根据需要选择一个特性启用。"
+);
+
+#[cfg(all(feature = "locale_emulator", feature = "locale_emulator_lite"))]
+compile_error!(
+    "特性 `locale_emulator` 和 `locale_emulator_lite` 不能同时启用，前者会使用 Locale Emulator 重新启动进程，后者在当前进程内模拟区域。请根据需要选择一个特性启用。"
+);
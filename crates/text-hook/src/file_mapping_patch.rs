@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+use windows_sys::Win32::{
+    Foundation::{GetLastError, HANDLE, INVALID_HANDLE_VALUE},
+    Storage::FileSystem::{FILE_NAME_NORMALIZED, GetFileSizeEx, GetFinalPathNameByHandleW},
+    System::Memory::{
+        FILE_MAP_COPY, FILE_MAP_EXECUTE, FILE_MAP_READ, FILE_MAP_WRITE, MEMORY_MAPPED_VIEW_ADDRESS,
+        PAGE_EXECUTE_READ, PAGE_READONLY, UnmapViewOfFile, VirtualProtect,
+    },
+};
+
+use crate::debug;
+use crate::hook::traits::file_hook::{HOOK_MAP_VIEW_OF_FILE, HOOK_MAP_VIEW_OF_FILE_EX};
+use crate::utils::{
+    exts::slice_ext::WideSliceExt,
+    win32::{FetchResult, fetch_win32_string},
+};
+
+/// 匹配补丁的映射对象
+#[derive(Debug, Clone, Copy)]
+struct PatchedMapping {
+    /// 整个文件的补丁数据
+    patch: &'static [u8],
+    /// 映射对象的大小，可能小于文件
+    mapping_len: usize,
+}
+
+/// 映射对象句柄 -> 对应的补丁数据
+static MAPPINGS: LazyLock<RwLock<HashMap<usize, PatchedMapping>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// 获取文件句柄对应的文件名（不含目录）
+fn get_file_name_by_handle(file: HANDLE) -> Option<String> {
+    let path = fetch_win32_string(false, |ptr, size| unsafe {
+        let k = GetFinalPathNameByHandleW(file, ptr, size as u32, FILE_NAME_NORMALIZED) as usize;
+
+        if k == 0 {
+            FetchResult::Error(GetLastError())
+        } else if k >= size {
+            // 缓冲区不足时返回值包含 null
+            FetchResult::Required(k)
+        } else {
+            FetchResult::Success(k)
+        }
+    })
+    .ok()?
+    .to_path_buf();
+
+    path.file_name().map(|n| n.to_string_lossy().into_owned())
+}
+
+/// 读取映射对象的前`len`字节（即整个文件）并按哈希查找补丁
+unsafe fn find_patch_by_content(mapping: HANDLE, len: usize) -> Option<&'static [u8]> {
+    let view = unsafe { crate::call!(HOOK_MAP_VIEW_OF_FILE, mapping, FILE_MAP_READ, 0, 0, len) };
+    if view.Value.is_null() {
+        crate::print_last_error_message!();
+        return None;
+    }
+
+    scopeguard::defer!(unsafe {
+        UnmapViewOfFile(view);
+    });
+
+    let content = unsafe { core::slice::from_raw_parts(view.Value as *const u8, len) };
+    crate::patch::get_patch(content)
+}
+
+/// 在`CreateFileMapping`成功后调用，若映射的文件匹配某个补丁（按路径或哈希），则记录该映射对象
+///
+/// `max_size_high`与`max_size_low`为传入`CreateFileMapping`的映射大小，均为 0 时为整个文件
+///
+/// # Safety
+/// - `file` 与 `mapping` 必须是刚刚传入与返回自`CreateFileMapping`的句柄
+pub unsafe fn on_mapping_created(
+    file: HANDLE,
+    mapping: HANDLE,
+    max_size_high: u32,
+    max_size_low: u32,
+) {
+    if mapping.is_null() || file.is_null() || file == INVALID_HANDLE_VALUE {
+        return;
+    }
+
+    let mut size = 0i64;
+    if unsafe { GetFileSizeEx(file, &mut size) } == 0 {
+        crate::print_last_error_message!();
+        return;
+    }
+
+    let len = size as usize;
+    if !crate::patch::is_patch_len(len) {
+        return;
+    }
+
+    let max_size = ((max_size_high as u64) << 32 | max_size_low as u64) as usize;
+    let mapping_len = if max_size == 0 { len } else { max_size };
+    // 映射大于文件时会扩展文件，内容已经不是原始数据
+    if mapping_len > len {
+        return;
+    }
+
+    let file_name = get_file_name_by_handle(file);
+
+    // 映射只覆盖文件的一部分时无法通过映射读取整个文件，只能按文件名匹配
+    let patch = file_name
+        .as_deref()
+        .and_then(|name| crate::patch::get_patch_by_name(name, len))
+        .or_else(|| {
+            (mapping_len == len)
+                .then(|| unsafe { find_patch_by_content(mapping, len) })
+                .flatten()
+        });
+
+    if let Some(patch) = patch {
+        debug!(
+            "File mapping {mapping:p} of {} matched a patch (len={len})",
+            file_name.as_deref().unwrap_or("<unknown>")
+        );
+        if let Ok(mut mappings) = MAPPINGS.write() {
+            mappings.insert(mapping as usize, PatchedMapping { patch, mapping_len });
+        }
+    }
+}
+
+/// 在句柄关闭前调用，移除对应的映射记录，防止句柄值被复用后误匹配
+pub fn on_handle_closed(handle: HANDLE) {
+    if let Ok(mut mappings) = MAPPINGS.write()
+        && mappings.remove(&(handle as usize)).is_some()
+    {
+        debug!("File mapping {handle:p} closed");
+    }
+}
+
+/// 视图是否为写时复制，`FILE_MAP_ALL_ACCESS`等包含`FILE_MAP_WRITE`的访问权限不算
+fn is_copy_on_write(desired_access: u32) -> bool {
+    desired_access & FILE_MAP_WRITE == 0 && desired_access & FILE_MAP_COPY != 0
+}
+
+/// 视图中需要写入补丁的字节数，`offset`超出映射范围时返回`None`
+///
+/// `number_of_bytes`为 0 时视图延伸到映射的结尾
+fn patched_view_len(mapping_len: usize, offset: usize, number_of_bytes: usize) -> Option<usize> {
+    let remaining = mapping_len.checked_sub(offset).filter(|&n| n > 0)?;
+    Some(match number_of_bytes {
+        0 => remaining,
+        n => n.min(remaining),
+    })
+}
+
+/// 若映射对象匹配补丁，则创建一个写时复制的私有视图，并写入对应的补丁数据
+///
+/// 返回`None`表示该映射对象无需处理，应调用原函数
+///
+/// # Safety
+/// - 参数须与`MapViewOfFileEx`一致，`base_address` 为空时等价于`MapViewOfFile`
+pub unsafe fn map_patched_view(
+    mapping: HANDLE,
+    desired_access: u32,
+    offset_high: u32,
+    offset_low: u32,
+    number_of_bytes: usize,
+    base_address: *const core::ffi::c_void,
+) -> Option<MEMORY_MAPPED_VIEW_ADDRESS> {
+    let PatchedMapping { patch, mapping_len } = *MAPPINGS.read().ok()?.get(&(mapping as usize))?;
+
+    // 调用者需要写回文件时，不能替换为私有页
+    if desired_access & FILE_MAP_WRITE != 0 {
+        debug!("Writable view of patched mapping {mapping:p}, skipped");
+        return None;
+    }
+
+    let offset = ((offset_high as u64) << 32 | offset_low as u64) as usize;
+    let view_len = patched_view_len(patch.len().min(mapping_len), offset, number_of_bytes)?;
+
+    let executable = desired_access & FILE_MAP_EXECUTE != 0;
+    let access = FILE_MAP_COPY | (desired_access & FILE_MAP_EXECUTE);
+
+    let view = unsafe {
+        if base_address.is_null() {
+            crate::call!(
+                HOOK_MAP_VIEW_OF_FILE,
+                mapping,
+                access,
+                offset_high,
+                offset_low,
+                number_of_bytes,
+            )
+        } else {
+            crate::call!(
+                HOOK_MAP_VIEW_OF_FILE_EX,
+                mapping,
+                access,
+                offset_high,
+                offset_low,
+                number_of_bytes,
+                base_address,
+            )
+        }
+    };
+
+    if view.Value.is_null() {
+        return Some(view);
+    }
+
+    unsafe {
+        // 写时复制的页面初始即可写，写入后成为进程私有页，不会影响原文件
+        core::ptr::copy_nonoverlapping(patch.as_ptr().add(offset), view.Value as *mut u8, view_len);
+
+        if !is_copy_on_write(desired_access) {
+            let protect = if executable {
+                PAGE_EXECUTE_READ
+            } else {
+                PAGE_READONLY
+            };
+            let mut old = 0;
+            if VirtualProtect(view.Value, view_len, protect, &mut old) == 0 {
+                crate::print_last_error_message!();
+            }
+        }
+    }
+
+    debug!(
+        "Patched view {:p} of mapping {mapping:p}, offset={offset}, len={view_len}",
+        view.Value
+    );
+
+    Some(view)
+}
+
+#[cfg(test)]
+mod tests {
+    use windows_sys::Win32::System::Memory::FILE_MAP_ALL_ACCESS;
+
+    use super::*;
+
+    #[test]
+    fn copy_on_write_access() {
+        assert!(is_copy_on_write(FILE_MAP_COPY));
+        assert!(is_copy_on_write(FILE_MAP_COPY | FILE_MAP_READ));
+        assert!(is_copy_on_write(FILE_MAP_COPY | FILE_MAP_EXECUTE));
+        assert!(!is_copy_on_write(FILE_MAP_READ));
+        assert!(!is_copy_on_write(FILE_MAP_WRITE));
+        assert!(!is_copy_on_write(FILE_MAP_ALL_ACCESS));
+    }
+
+    #[test]
+    fn whole_mapping_view() {
+        assert_eq!(patched_view_len(100, 0, 0), Some(100));
+        assert_eq!(patched_view_len(100, 40, 0), Some(60));
+        assert_eq!(patched_view_len(100, 40, 10), Some(10));
+        assert_eq!(patched_view_len(100, 40, 1000), Some(60));
+        assert_eq!(patched_view_len(100, 100, 0), None);
+        assert_eq!(patched_view_len(100, 200, 0), None);
+    }
+
+    #[test]
+    fn partial_mapping_view() {
+        // 文件 100 字节，映射只覆盖前 30 字节，视图不能超出映射
+        let mapping_len = 30;
+        assert_eq!(patched_view_len(mapping_len, 0, 0), Some(30));
+        assert_eq!(patched_view_len(mapping_len, 16, 0), Some(14));
+        assert_eq!(patched_view_len(mapping_len, 16, 64), Some(14));
+        assert_eq!(patched_view_len(mapping_len, 30, 0), None);
+        assert_eq!(patched_view_len(mapping_len, 64, 8), None);
+    }
+}
@@ -44,7 +44,6 @@ impl CoreHook for Hitocos2Hook {
         let module = handle as *mut u8;
 
         unsafe {
-            TEXT_RETURN_ADDR = module.add(0xE495) as usize;
             SUB_402B70 = module.add(0x2B70) as usize;
         };
 
@@ -57,6 +56,12 @@ impl CoreHook for Hitocos2Hook {
                     )
                     .unwrap();
 
+                    // sub esp, 0x8; push ebx; push ebp;
+                    TEXT_RETURN_ADDR =
+                        crate::utils::mem::code_cave::build_code_cave(module.add(0xE490) as _, 5)
+                            .unwrap()
+                            .entry;
+
                     crate::utils::mem::patch::write_jmp_instruction(
                         module.add(0xE490),
                         text_trampoline as _,
@@ -104,11 +109,6 @@ unsafe extern "system" fn text_trampoline() {
         mov [esp + 0x2C], eax;
         popfd;
         popad;
-
-        sub esp, 0x8;
-        push ebx;
-        push ebp;
-
         jmp dword ptr [{1}];
         ",
         sym hook_text,
@@ -12,13 +12,14 @@ pub struct LoveBindHook;
 impl CoreHook for LoveBindHook {
     fn on_process_attach(_hinst_dll: HMODULE) {
         unsafe {
-            HOOK_MESSAGE_BOX_TIMEOUT_A.enable().unwrap();
+            HOOK_LOVE_BIND_MESSAGE_BOX_TIMEOUT_A.enable().unwrap();
         };
     }
 }
 
+// 函数名加上前缀，避免开启`export_hooks`时与`WindowHook::message_box_timeout_a`的导出符号冲突
 #[detour_fn(dll = "user32.dll", symbol = "MessageBoxTimeoutA", fallback = "1")]
-unsafe extern "system" fn message_box_timeout_a(
+unsafe extern "system" fn love_bind_message_box_timeout_a(
     h_wnd: HWND,
     lp_text: *const u8,
     lp_caption: *const u8,
@@ -38,7 +39,7 @@ unsafe extern "system" fn message_box_timeout_a(
         }
 
         crate::call!(
-            HOOK_MESSAGE_BOX_TIMEOUT_A,
+            HOOK_LOVE_BIND_MESSAGE_BOX_TIMEOUT_A,
             h_wnd,
             lp_text,
             lp_caption,
@@ -69,6 +69,16 @@ pub fn default_dll_main(
                 crate::debug!("Extract resource pack failed with {e:?}");
             }
 
+            #[cfg(feature = "save_redirect")]
+            if let Err(e) = crate::save_redirect::init() {
+                crate::debug!("Load shell32.dll failed with {e:?}");
+            }
+
+            #[cfg(feature = "registry_virtualization")]
+            if let Err(e) = crate::registry_virtualization::init() {
+                crate::debug!("Load advapi32.dll failed with {e:?}");
+            }
+
             #[cfg(feature = "worker_thread")]
             if let Err(e) = unsafe { crate::worker_thread::start() } {
                 crate::debug!("Start worker thread failed with {e:?}");
@@ -120,6 +130,11 @@ pub fn attach_clean_up() {
         crate::debug!("Failed to save extracted items to JSON: {e:?}");
     }
 
+    #[cfg(feature = "patch_extracting")]
+    if let Err(e) = crate::patch::flush_manifest() {
+        crate::debug!("Failed to save extracted patch manifest: {e:?}");
+    }
+
     #[cfg(feature = "resource_pack")]
     if let Err(e) = crate::resource_pack::clean_up() {
         crate::debug!("Clean up resource pack failed with {e:?}");
@@ -130,6 +145,11 @@ pub fn attach_clean_up() {
         crate::debug!("Stop worker thread failed with {e:?}");
     }
 
+    #[cfg(feature = "revert_1337_patch_on_clean_up")]
+    if let Err(e) = crate::x64dbg_1337_patch::revert() {
+        crate::debug!("Revert 1337 patch failed with {e:?}");
+    }
+
     #[cfg(feature = "dll_hijacking")]
     unsafe {
         crate::dll_hijacking::unload_library();
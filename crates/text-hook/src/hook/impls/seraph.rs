@@ -1,5 +1,5 @@
 use std::{borrow::Cow, path::Path};
-use translate_macros::{DefaultHook, detour_fn};
+use translate_macros::DefaultHook;
 use windows_sys::{
     Win32::Foundation::{HMODULE, MAX_PATH},
     core::{PCSTR, PSTR},
@@ -8,13 +8,22 @@ use windows_sys::{
 use crate::{
     constant::ARG_NAME,
     debug,
-    hook::traits::CoreHook,
-    utils::exts::{ptr_ext::PtrExt, slice_ext::ByteSliceExt},
+    hook::traits::{
+        CoreHook,
+        profile_hook::{
+            HOOK_GET_PRIVATE_PROFILE_INT_A, HOOK_GET_PRIVATE_PROFILE_STRING_A, ProfileHook,
+        },
+    },
+    utils::{
+        exts::{ptr_ext::PtrExt, slice_ext::ByteSliceExt},
+        profile::write_profile_string,
+    },
 };
 
 // 之前版本的ARG_NAME为"LUSTS"
 
 #[derive(DefaultHook)]
+#[exclude(ProfileHook)]
 pub struct SeraphHook;
 
 impl CoreHook for SeraphHook {
@@ -71,99 +80,83 @@ unsafe fn to_string(app_name: PCSTR, key_name: PCSTR) -> (String, String) {
     (section.into_owned(), key.into_owned())
 }
 
-#[detour_fn(
-    dll = "kernel32.dll",
-    symbol = "GetPrivateProfileStringA",
-    fallback = "0u32"
-)]
-pub unsafe extern "system" fn get_private_profile_string_a(
-    lp_app_name: PCSTR,
-    lp_key_name: PCSTR,
-    lp_default: PCSTR,
-    lp_returned_string: PSTR,
-    n_size: u32,
-    lp_file_name: PCSTR,
-) -> u32 {
-    unsafe {
-        if lp_file_name.is_null() {
-            return 0;
-        }
-
-        if matched_ini(lp_file_name) {
-            let (section, key) = to_string(lp_app_name, lp_key_name);
-            debug!("section: {section}, key: {key}");
+impl ProfileHook for SeraphHook {
+    unsafe fn get_private_profile_string_a(
+        lp_app_name: PCSTR,
+        lp_key_name: PCSTR,
+        lp_default: PCSTR,
+        lp_returned_string: PSTR,
+        n_size: u32,
+        lp_file_name: PCSTR,
+    ) -> u32 {
+        unsafe {
+            if lp_file_name.is_null() {
+                return 0;
+            }
 
-            if let Some(val) = query_game_ini_string(&section, &key) {
-                debug!("found value: {val}");
+            if matched_ini(lp_file_name) {
+                let (section, key) = to_string(lp_app_name, lp_key_name);
+                debug!("section: {section}, key: {key}");
 
-                // 计算实际需要复制的长度
-                let copy_len = val.len().min(n_size as usize);
+                if let Some(val) = query_game_ini_string(&section, &key) {
+                    debug!("found value: {val}");
 
-                // 复制到输出缓冲区
-                core::ptr::copy_nonoverlapping(val.as_ptr(), lp_returned_string, copy_len);
+                    if lp_returned_string.is_null() {
+                        return 0;
+                    }
 
-                // 确保在缓冲区不足时正确终止字符串
-                if copy_len < n_size as usize {
-                    // 如果空间足够，确保null终止
-                    *lp_returned_string.add(copy_len) = 0;
-                } else if n_size > 0 {
-                    // 如果缓冲区不足，确保在末尾null终止
-                    *lp_returned_string.add(n_size as usize - 1) = 0;
+                    return write_profile_string(
+                        val.as_bytes(),
+                        lp_returned_string.to_slice_mut(n_size as usize),
+                    );
                 }
-
-                return copy_len.min(n_size as usize).saturating_sub(1) as u32;
             }
-        }
 
-        debug!("passed");
-
-        crate::call!(
-            HOOK_GET_PRIVATE_PROFILE_STRING_A,
-            lp_app_name,
-            lp_key_name,
-            lp_default,
-            lp_returned_string,
-            n_size,
-            lp_file_name,
-        )
+            debug!("passed");
+
+            crate::call!(
+                HOOK_GET_PRIVATE_PROFILE_STRING_A,
+                lp_app_name,
+                lp_key_name,
+                lp_default,
+                lp_returned_string,
+                n_size,
+                lp_file_name,
+            )
+        }
     }
-}
 
-#[detour_fn(
-    dll = "kernel32.dll",
-    symbol = "GetPrivateProfileIntA",
-    fallback = "n_default as _"
-)]
-unsafe extern "system" fn get_private_profile_int_a(
-    lp_app_name: PCSTR,
-    lp_key_name: PCSTR,
-    n_default: i32,
-    lp_file_name: PCSTR,
-) -> u32 {
-    unsafe {
-        if lp_file_name.is_null() {
-            return n_default as _;
-        }
+    unsafe fn get_private_profile_int_a(
+        lp_app_name: PCSTR,
+        lp_key_name: PCSTR,
+        n_default: i32,
+        lp_file_name: PCSTR,
+    ) -> u32 {
+        unsafe {
+            if lp_file_name.is_null() {
+                return n_default as _;
+            }
 
-        if matched_ini(lp_file_name) {
-            let (section, key) = to_string(lp_app_name, lp_key_name);
-            debug!("section: {section}, key: {key}");
+            if matched_ini(lp_file_name) {
+                let (section, key) = to_string(lp_app_name, lp_key_name);
+                debug!("section: {section}, key: {key}");
 
-            if let Some(val) = query_game_ini_int(&section, &key) {
-                debug!("found value: {val}");
-                return val as _;
+                if let Some(val) = query_game_ini_int(&section, &key) {
+                    debug!("found value: {val}");
+                    return val as _;
+                }
+
+                return n_default as _;
             }
 
-            return n_default as _;
+            debug!("passed");
+            crate::call!(
+                HOOK_GET_PRIVATE_PROFILE_INT_A,
+                lp_app_name,
+                lp_key_name,
+                n_default,
+                lp_file_name
+            )
         }
-
-        debug!("passed");
-        crate::call!(
-            HOOK_GET_PRIVATE_PROFILE_INT_A,
-            lp_app_name,
-            lp_key_name,
-            n_default,
-            lp_file_name
-        )
     }
 }
@@ -56,37 +56,18 @@ unsafe extern "system" fn trampoline() {
 #[translate_macros::ffi_catch_unwind]
 pub unsafe extern "system" fn extract_script(ptr: *mut u8, len: usize, filename: PCSTR) {
     unsafe {
-        use crate::utils::exts::slice_ext::{ByteSliceExt, WideSliceExt};
-        use std::io::Write;
+        use crate::utils::exts::slice_ext::ByteSliceExt;
         use windows_sys::Win32::Foundation::MAX_PATH;
 
         let filename = filename
             .to_slice_until_null(MAX_PATH as _)
             .to_string_lossy();
-        if crate::patch::try_extracting(ptr, len) {
-            let new_filename = format!("{filename}.isf");
 
-            // 读取cwd的`raw/filenames.txt`，如果没有就创建，然后在末尾添加`{new_filename}\n`
-            if let Ok(current_dir) = crate::utils::win32::get_current_dir(false) {
-                let raw_dir = current_dir.to_path_buf().join("raw");
-
-                // 创建raw目录（如果不存在）
-                if let Err(e) = std::fs::create_dir_all(&raw_dir) {
-                    crate::debug!("Failed to create raw directory: {e}");
-                    return;
-                }
-
-                // 文件名列表文件路径
-                let filenames_path = raw_dir.join("filenames.txt");
-
-                if let Ok(mut file) = std::fs::OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(&filenames_path)
-                {
-                    writeln!(file, "{}", new_filename).unwrap();
-                }
-            }
-        }
+        crate::patch::try_extracting_with_source(
+            ptr,
+            len,
+            Some(&format!("{filename}.isf")),
+            crate::fn_name!(),
+        );
     }
 }
@@ -1,4 +1,6 @@
 use translate_macros::detour_trait;
+use windows_sys::Win32::Globalization::CPINFO;
+use windows_sys::Win32::System::Time::TIME_ZONE_INFORMATION;
 use windows_sys::core::{BOOL, PCSTR, PCWSTR, PSTR, PWSTR};
 
 #[detour_trait]
@@ -12,7 +14,21 @@ pub trait CodeCvtHook: Send + Sync + 'static {
         _lp_wide_char_str: PWSTR,
         _cch_wide_char: i32,
     ) -> i32 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
         unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        unsafe {
+            crate::call!(
+                HOOK_MULTI_BYTE_TO_WIDE_CHAR,
+                crate::locale_emulator_lite::map_code_page(_code_page),
+                _dw_flags,
+                _lp_multi_byte_str,
+                _cb_multi_byte,
+                _lp_wide_char_str,
+                _cch_wide_char,
+            )
+        }
     }
 
     #[detour(dll = "kernel32.dll", symbol = "WideCharToMultiByte", fallback = "0")]
@@ -26,6 +42,199 @@ pub trait CodeCvtHook: Send + Sync + 'static {
         _lp_default_char: PCSTR,
         _lp_used_default_char: *mut BOOL,
     ) -> i32 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
+        unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        unsafe {
+            crate::call!(
+                HOOK_WIDE_CHAR_TO_MULTI_BYTE,
+                crate::locale_emulator_lite::map_code_page(_code_page),
+                _dw_flags,
+                _lp_wide_char_str,
+                _cch_wide_char,
+                _lp_multi_byte_str,
+                _cb_multi_byte,
+                _lp_default_char,
+                _lp_used_default_char,
+            )
+        }
+    }
+
+    #[detour(dll = "kernel32.dll", symbol = "GetACP", fallback = "0")]
+    unsafe fn get_acp() -> u32 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
+        unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        crate::constant::EMULATE_LOCALE_CODEPAGE
+    }
+
+    #[detour(dll = "kernel32.dll", symbol = "GetOEMCP", fallback = "0")]
+    unsafe fn get_oemcp() -> u32 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
+        unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        crate::constant::EMULATE_LOCALE_CODEPAGE
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetCPInfo",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn get_cp_info(_code_page: u32, _lp_cp_info: *mut CPINFO) -> BOOL {
+        #[cfg(not(feature = "locale_emulator_lite"))]
+        unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        unsafe {
+            crate::call!(
+                HOOK_GET_CP_INFO,
+                crate::locale_emulator_lite::map_code_page(_code_page),
+                _lp_cp_info,
+            )
+        }
+    }
+
+    #[detour(dll = "kernel32.dll", symbol = "GetLocaleInfoA", fallback = "0")]
+    unsafe fn get_locale_info_a(
+        _locale: u32,
+        _lc_type: u32,
+        _lp_lc_data: PSTR,
+        _cch_data: i32,
+    ) -> i32 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
         unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        unsafe {
+            crate::call!(
+                HOOK_GET_LOCALE_INFO_A,
+                crate::locale_emulator_lite::map_locale(_locale),
+                _lc_type,
+                _lp_lc_data,
+                _cch_data,
+            )
+        }
+    }
+
+    #[detour(dll = "kernel32.dll", symbol = "GetLocaleInfoW", fallback = "0")]
+    unsafe fn get_locale_info_w(
+        _locale: u32,
+        _lc_type: u32,
+        _lp_lc_data: PWSTR,
+        _cch_data: i32,
+    ) -> i32 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
+        unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        unsafe {
+            crate::call!(
+                HOOK_GET_LOCALE_INFO_W,
+                crate::locale_emulator_lite::map_locale(_locale),
+                _lc_type,
+                _lp_lc_data,
+                _cch_data,
+            )
+        }
+    }
+
+    #[detour(dll = "kernel32.dll", symbol = "GetLocaleInfoEx", fallback = "0")]
+    unsafe fn get_locale_info_ex(
+        _lp_locale_name: PCWSTR,
+        _lc_type: u32,
+        _lp_lc_data: PWSTR,
+        _cch_data: i32,
+    ) -> i32 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
+        unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        unsafe {
+            // 默认区域改为通过区域 ID 查询模拟的区域
+            if crate::locale_emulator_lite::is_default_locale_name(_lp_locale_name) {
+                return crate::call!(
+                    HOOK_GET_LOCALE_INFO_W,
+                    crate::constant::EMULATE_LOCALE_LOCALE,
+                    _lc_type,
+                    _lp_lc_data,
+                    _cch_data,
+                );
+            }
+
+            crate::call!(
+                HOOK_GET_LOCALE_INFO_EX,
+                _lp_locale_name,
+                _lc_type,
+                _lp_lc_data,
+                _cch_data,
+            )
+        }
+    }
+
+    #[detour(dll = "kernel32.dll", symbol = "GetUserDefaultLCID", fallback = "0")]
+    unsafe fn get_user_default_lcid() -> u32 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
+        unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        crate::constant::EMULATE_LOCALE_LOCALE
+    }
+
+    #[detour(dll = "kernel32.dll", symbol = "GetUserDefaultLangID", fallback = "0")]
+    unsafe fn get_user_default_lang_id() -> u16 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
+        unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        crate::locale_emulator_lite::lang_id()
+    }
+
+    #[detour(dll = "kernel32.dll", symbol = "GetSystemDefaultLCID", fallback = "0")]
+    unsafe fn get_system_default_lcid() -> u32 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
+        unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        crate::constant::EMULATE_LOCALE_LOCALE
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetSystemDefaultLangID",
+        fallback = "0"
+    )]
+    unsafe fn get_system_default_lang_id() -> u16 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
+        unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        crate::locale_emulator_lite::lang_id()
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetTimeZoneInformation",
+        fallback = "windows_sys::Win32::System::Time::TIME_ZONE_ID_INVALID"
+    )]
+    unsafe fn get_time_zone_information(
+        _lp_time_zone_information: *mut TIME_ZONE_INFORMATION,
+    ) -> u32 {
+        #[cfg(not(feature = "locale_emulator_lite"))]
+        unimplemented!();
+
+        #[cfg(feature = "locale_emulator_lite")]
+        unsafe {
+            if let Some(result) =
+                crate::locale_emulator_lite::get_time_zone_information(_lp_time_zone_information)
+            {
+                return result;
+            }
+
+            crate::call!(HOOK_GET_TIME_ZONE_INFORMATION, _lp_time_zone_information)
+        }
     }
 }
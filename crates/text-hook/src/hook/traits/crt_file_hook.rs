@@ -0,0 +1,416 @@
+use core::ffi::c_void;
+
+use translate_macros::detour_trait;
+use windows_sys::core::{PCSTR, PCWSTR};
+
+use crate::utils::exts::ptr_ext::PtrExt;
+
+/// CRT文件相关的钩子，分别对应`msvcrt.dll`与`ucrtbase.dll`中的同名函数
+///
+/// `FILE*` 统一使用 `*mut c_void` 表示，`long` 在Windows上为`i32`
+#[detour_trait]
+pub trait CrtFileHook: Send + Sync + 'static {
+    #[detour(
+        dll = "msvcrt.dll",
+        symbol = "fopen",
+        fallback = "core::ptr::null_mut()",
+        calling_convention = "C"
+    )]
+    unsafe fn msvcrt_fopen(_file_name: PCSTR, _mode: PCSTR) -> *mut c_void {
+        #[cfg(any(feature = "resource_pack", feature = "crt_file_patch_impl"))]
+        unsafe {
+            use crate::utils::exts::slice_ext::ByteSliceExt;
+
+            Crt::Msvcrt.open(
+                &_file_name.to_slice_until_null(4096).to_wide_ansi(),
+                &_mode.to_slice_until_null(64).to_wide_ansi(),
+                || crate::call!(HOOK_MSVCRT_FOPEN, _file_name, _mode),
+            )
+        }
+
+        #[cfg(not(any(feature = "resource_pack", feature = "crt_file_patch_impl")))]
+        unimplemented!();
+    }
+
+    #[detour(
+        dll = "msvcrt.dll",
+        symbol = "_wfopen",
+        fallback = "core::ptr::null_mut()",
+        calling_convention = "C"
+    )]
+    unsafe fn msvcrt_wfopen(_file_name: PCWSTR, _mode: PCWSTR) -> *mut c_void {
+        #[cfg(any(feature = "resource_pack", feature = "crt_file_patch_impl"))]
+        unsafe {
+            Crt::Msvcrt.open(
+                _file_name.to_slice_until_null(4096),
+                _mode.to_slice_until_null(64),
+                || crate::call!(HOOK_MSVCRT_WFOPEN, _file_name, _mode),
+            )
+        }
+
+        #[cfg(not(any(feature = "resource_pack", feature = "crt_file_patch_impl")))]
+        unimplemented!();
+    }
+
+    #[detour(
+        dll = "msvcrt.dll",
+        symbol = "fread",
+        fallback = "0",
+        calling_convention = "C"
+    )]
+    unsafe fn msvcrt_fread(
+        _buffer: *mut u8,
+        _element_size: usize,
+        _element_count: usize,
+        _stream: *mut c_void,
+    ) -> usize {
+        #[cfg(not(feature = "crt_file_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "crt_file_patch_impl")]
+        unsafe {
+            if let Some(count) =
+                crate::crt_file_patch::read(_buffer, _element_size, _element_count, _stream)
+            {
+                return count;
+            }
+
+            let count = crate::call!(
+                HOOK_MSVCRT_FREAD,
+                _buffer,
+                _element_size,
+                _element_count,
+                _stream,
+            );
+
+            // 文本模式或未整体匹配的文件，退化为对读取结果进行patch
+            crate::patch::process_buffer(_buffer, count.saturating_mul(_element_size));
+            count
+        }
+    }
+
+    #[detour(
+        dll = "msvcrt.dll",
+        symbol = "fseek",
+        fallback = "-1",
+        calling_convention = "C"
+    )]
+    unsafe fn msvcrt_fseek(_stream: *mut c_void, _offset: i32, _origin: i32) -> i32 {
+        #[cfg(not(feature = "crt_file_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "crt_file_patch_impl")]
+        unsafe {
+            if let Some(result) = crate::crt_file_patch::seek(_stream, _offset, _origin) {
+                return result;
+            }
+
+            crate::call!(HOOK_MSVCRT_FSEEK, _stream, _offset, _origin)
+        }
+    }
+
+    #[detour(
+        dll = "msvcrt.dll",
+        symbol = "ftell",
+        fallback = "-1",
+        calling_convention = "C"
+    )]
+    unsafe fn msvcrt_ftell(_stream: *mut c_void) -> i32 {
+        #[cfg(not(feature = "crt_file_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "crt_file_patch_impl")]
+        unsafe {
+            if let Some(pos) = crate::crt_file_patch::tell(_stream) {
+                return pos;
+            }
+
+            crate::call!(HOOK_MSVCRT_FTELL, _stream)
+        }
+    }
+
+    #[detour(
+        dll = "msvcrt.dll",
+        symbol = "fclose",
+        fallback = "-1",
+        calling_convention = "C"
+    )]
+    unsafe fn msvcrt_fclose(_stream: *mut c_void) -> i32 {
+        #[cfg(not(feature = "crt_file_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "crt_file_patch_impl")]
+        unsafe {
+            crate::crt_file_patch::on_file_closed(_stream);
+            crate::call!(HOOK_MSVCRT_FCLOSE, _stream)
+        }
+    }
+
+    #[detour(
+        dll = "ucrtbase.dll",
+        symbol = "fopen",
+        fallback = "core::ptr::null_mut()",
+        calling_convention = "C"
+    )]
+    unsafe fn ucrt_fopen(_file_name: PCSTR, _mode: PCSTR) -> *mut c_void {
+        #[cfg(any(feature = "resource_pack", feature = "crt_file_patch_impl"))]
+        unsafe {
+            use crate::utils::exts::slice_ext::ByteSliceExt;
+
+            Crt::Ucrt.open(
+                &_file_name.to_slice_until_null(4096).to_wide_ansi(),
+                &_mode.to_slice_until_null(64).to_wide_ansi(),
+                || crate::call!(HOOK_UCRT_FOPEN, _file_name, _mode),
+            )
+        }
+
+        #[cfg(not(any(feature = "resource_pack", feature = "crt_file_patch_impl")))]
+        unimplemented!();
+    }
+
+    #[detour(
+        dll = "ucrtbase.dll",
+        symbol = "_wfopen",
+        fallback = "core::ptr::null_mut()",
+        calling_convention = "C"
+    )]
+    unsafe fn ucrt_wfopen(_file_name: PCWSTR, _mode: PCWSTR) -> *mut c_void {
+        #[cfg(any(feature = "resource_pack", feature = "crt_file_patch_impl"))]
+        unsafe {
+            Crt::Ucrt.open(
+                _file_name.to_slice_until_null(4096),
+                _mode.to_slice_until_null(64),
+                || crate::call!(HOOK_UCRT_WFOPEN, _file_name, _mode),
+            )
+        }
+
+        #[cfg(not(any(feature = "resource_pack", feature = "crt_file_patch_impl")))]
+        unimplemented!();
+    }
+
+    #[detour(
+        dll = "ucrtbase.dll",
+        symbol = "fread",
+        fallback = "0",
+        calling_convention = "C"
+    )]
+    unsafe fn ucrt_fread(
+        _buffer: *mut u8,
+        _element_size: usize,
+        _element_count: usize,
+        _stream: *mut c_void,
+    ) -> usize {
+        #[cfg(not(feature = "crt_file_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "crt_file_patch_impl")]
+        unsafe {
+            if let Some(count) =
+                crate::crt_file_patch::read(_buffer, _element_size, _element_count, _stream)
+            {
+                return count;
+            }
+
+            let count = crate::call!(
+                HOOK_UCRT_FREAD,
+                _buffer,
+                _element_size,
+                _element_count,
+                _stream,
+            );
+
+            // 文本模式或未整体匹配的文件，退化为对读取结果进行patch
+            crate::patch::process_buffer(_buffer, count.saturating_mul(_element_size));
+            count
+        }
+    }
+
+    #[detour(
+        dll = "ucrtbase.dll",
+        symbol = "fseek",
+        fallback = "-1",
+        calling_convention = "C"
+    )]
+    unsafe fn ucrt_fseek(_stream: *mut c_void, _offset: i32, _origin: i32) -> i32 {
+        #[cfg(not(feature = "crt_file_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "crt_file_patch_impl")]
+        unsafe {
+            if let Some(result) = crate::crt_file_patch::seek(_stream, _offset, _origin) {
+                return result;
+            }
+
+            crate::call!(HOOK_UCRT_FSEEK, _stream, _offset, _origin)
+        }
+    }
+
+    #[detour(
+        dll = "ucrtbase.dll",
+        symbol = "ftell",
+        fallback = "-1",
+        calling_convention = "C"
+    )]
+    unsafe fn ucrt_ftell(_stream: *mut c_void) -> i32 {
+        #[cfg(not(feature = "crt_file_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "crt_file_patch_impl")]
+        unsafe {
+            if let Some(pos) = crate::crt_file_patch::tell(_stream) {
+                return pos;
+            }
+
+            crate::call!(HOOK_UCRT_FTELL, _stream)
+        }
+    }
+
+    #[detour(
+        dll = "ucrtbase.dll",
+        symbol = "fclose",
+        fallback = "-1",
+        calling_convention = "C"
+    )]
+    unsafe fn ucrt_fclose(_stream: *mut c_void) -> i32 {
+        #[cfg(not(feature = "crt_file_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "crt_file_patch_impl")]
+        unsafe {
+            crate::crt_file_patch::on_file_closed(_stream);
+            crate::call!(HOOK_UCRT_FCLOSE, _stream)
+        }
+    }
+}
+
+/// 区分被HOOK的CRT，用于在内部调用同一个CRT的原函数
+#[cfg(any(feature = "resource_pack", feature = "crt_file_patch_impl"))]
+#[derive(Clone, Copy)]
+enum Crt {
+    Msvcrt,
+    Ucrt,
+}
+
+#[cfg(any(feature = "resource_pack", feature = "crt_file_patch_impl"))]
+impl Crt {
+    unsafe fn wfopen(self, path: PCWSTR, mode: PCWSTR) -> *mut c_void {
+        unsafe {
+            match self {
+                Crt::Msvcrt => crate::call!(HOOK_MSVCRT_WFOPEN, path, mode),
+                Crt::Ucrt => crate::call!(HOOK_UCRT_WFOPEN, path, mode),
+            }
+        }
+    }
+
+    unsafe fn fseek(self, stream: *mut c_void, offset: i32, origin: i32) -> i32 {
+        unsafe {
+            match self {
+                Crt::Msvcrt => crate::call!(HOOK_MSVCRT_FSEEK, stream, offset, origin),
+                Crt::Ucrt => crate::call!(HOOK_UCRT_FSEEK, stream, offset, origin),
+            }
+        }
+    }
+
+    unsafe fn ftell(self, stream: *mut c_void) -> i32 {
+        unsafe {
+            match self {
+                Crt::Msvcrt => crate::call!(HOOK_MSVCRT_FTELL, stream),
+                Crt::Ucrt => crate::call!(HOOK_UCRT_FTELL, stream),
+            }
+        }
+    }
+
+    unsafe fn fread(
+        self,
+        buffer: *mut u8,
+        element_size: usize,
+        element_count: usize,
+        stream: *mut c_void,
+    ) -> usize {
+        unsafe {
+            match self {
+                Crt::Msvcrt => crate::call!(
+                    HOOK_MSVCRT_FREAD,
+                    buffer,
+                    element_size,
+                    element_count,
+                    stream
+                ),
+                Crt::Ucrt => {
+                    crate::call!(HOOK_UCRT_FREAD, buffer, element_size, element_count, stream)
+                }
+            }
+        }
+    }
+
+    /// `fopen`/`_wfopen`的公共处理：先尝试资源包重定向，再打开原文件并尝试匹配补丁
+    #[allow(unused_variables)]
+    unsafe fn open(
+        self,
+        path: &[u16],
+        mode: &[u16],
+        open_orig: impl FnOnce() -> *mut c_void,
+    ) -> *mut c_void {
+        // 开启`resource_pack_vfs`时资源包不会解压到磁盘，CRT无法重定向
+        #[cfg(all(feature = "resource_pack", not(feature = "resource_pack_vfs")))]
+        if let Some(file) = unsafe { self.try_redirect(path, mode) } {
+            return file;
+        }
+
+        let file = open_orig();
+
+        #[cfg(feature = "crt_file_patch_impl")]
+        if crate::crt_file_patch::is_binary_read_mode(&String::from_utf16_lossy(mode)) {
+            use crate::utils::exts::slice_ext::WideSliceExt;
+
+            let file_name = path
+                .to_path_buf()
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned());
+
+            unsafe {
+                crate::crt_file_patch::on_file_opened(
+                    file,
+                    file_name.as_deref(),
+                    |stream, offset, origin| self.fseek(stream, offset, origin),
+                    |stream| self.ftell(stream),
+                    |buffer, size, count, stream| self.fread(buffer, size, count, stream),
+                );
+            }
+        }
+
+        file
+    }
+
+    /// 尝试将传入文件路径重定向到资源包中的替代文件。
+    #[cfg(all(feature = "resource_pack", not(feature = "resource_pack_vfs")))]
+    unsafe fn try_redirect(self, path: &[u16], mode: &[u16]) -> Option<*mut c_void> {
+        use crate::utils::exts::{path_ext::PathExt, slice_ext::WideSliceExt};
+
+        let orig_path = path.to_path_buf();
+        match crate::resource_pack::get_resource_path(&orig_path) {
+            Ok(Some(new_path)) => {
+                crate::debug!(
+                    "Resource pack hooked CRT file: {}, replace to {}",
+                    orig_path.to_string_lossy(),
+                    new_path.to_string_lossy()
+                );
+
+                let file = unsafe {
+                    self.wfopen(new_path.to_wide_null().as_ptr(), mode.with_null().as_ptr())
+                };
+
+                return Some(file);
+            }
+            Err(e) => {
+                crate::debug!(
+                    "Failed to get resource path for {}: {e:?}",
+                    orig_path.to_string_lossy()
+                );
+            }
+            _ => (),
+        }
+
+        None
+    }
+}
@@ -0,0 +1,45 @@
+use translate_macros::detour_trait;
+use windows_sys::core::{BOOL, PCSTR, PSTR};
+
+use crate::code_cvt::{char_next, char_prev, is_lead_byte};
+use crate::constant::DBCS_CODE_PAGE;
+
+/// DBCS字符串相关的钩子
+///
+/// 按`DBCS_CODE_PAGE`而不是进程的ANSI代码页判断前导字节，
+/// 用于引擎以CP932处理换行、光标等逻辑，但实际传入的是译文（如GBK）字节的情况。
+///
+/// `lstrlenA`返回的是字节数，与代码页无关，因此不需要处理
+#[detour_trait]
+pub trait DbcsHook: Send + Sync + 'static {
+    #[detour(dll = "kernel32.dll", symbol = "IsDBCSLeadByte", fallback = "0")]
+    unsafe fn is_dbcs_lead_byte(test_char: u8) -> BOOL {
+        is_lead_byte(DBCS_CODE_PAGE, test_char) as BOOL
+    }
+
+    #[detour(
+        dll = "user32.dll",
+        symbol = "CharNextA",
+        fallback = "core::ptr::null_mut()"
+    )]
+    unsafe fn char_next_a(lpsz: PCSTR) -> PSTR {
+        if lpsz.is_null() {
+            return core::ptr::null_mut();
+        }
+
+        unsafe { char_next(lpsz, DBCS_CODE_PAGE) as PSTR }
+    }
+
+    #[detour(
+        dll = "user32.dll",
+        symbol = "CharPrevA",
+        fallback = "core::ptr::null_mut()"
+    )]
+    unsafe fn char_prev_a(lpsz_start: PCSTR, lpsz_current: PCSTR) -> PSTR {
+        if lpsz_start.is_null() || lpsz_current.is_null() {
+            return lpsz_current as PSTR;
+        }
+
+        unsafe { char_prev(lpsz_start, lpsz_current, DBCS_CODE_PAGE) as PSTR }
+    }
+}
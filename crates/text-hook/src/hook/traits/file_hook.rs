@@ -1,10 +1,14 @@
+use core::ffi::c_void;
+
 use translate_macros::detour_trait;
 use windows_sys::{
     Win32::{
         Foundation::HANDLE,
         Security::SECURITY_ATTRIBUTES,
-        Storage::FileSystem::{WIN32_FIND_DATAA, WIN32_FIND_DATAW},
-        System::IO::OVERLAPPED,
+        Storage::FileSystem::{
+            BY_HANDLE_FILE_INFORMATION, GET_FILEEX_INFO_LEVELS, WIN32_FIND_DATAA, WIN32_FIND_DATAW,
+        },
+        System::{IO::OVERLAPPED, Memory::MEMORY_MAPPED_VIEW_ADDRESS},
     },
     core::{BOOL, PCSTR, PCWSTR},
 };
@@ -27,58 +31,45 @@ pub trait FileHook: Send + Sync + 'static {
         _dw_flags_and_attributes: u32,
         _h_template_file: HANDLE,
     ) -> HANDLE {
-        #[cfg(any(feature = "create_file_redirect", feature = "resource_pack"))]
+        #[cfg(any(feature = "redirect_rules", feature = "resource_pack"))]
         unsafe {
+            #[cfg(feature = "redirect_rules")]
+            let redirected = redirect_ansi(_lp_file_name);
+
             #[cfg(feature = "resource_pack")]
-            use crate::utils::exts::slice_ext::ByteSliceExt;
+            {
+                use crate::utils::exts::slice_ext::ByteSliceExt;
 
-            let filename_bytes = _lp_file_name.to_slice_until_null(4096);
+                let filename = _lp_file_name.to_slice_until_null(4096).to_wide(0);
+                #[cfg(feature = "redirect_rules")]
+                let filename = redirected.as_deref().map_or(&filename[..], without_null);
 
-            #[cfg(feature = "create_file_redirect")]
-            {
-                use crate::constant::{REDIRECTION_SRC_PATH, REDIRECTION_TARGET_PATH};
-
-                // 检查文件名是否以 REDIRECTION_SRC_PATH 结尾
-                if let Some(tail) = filename_bytes.get(
-                    filename_bytes
-                        .len()
-                        .saturating_sub(REDIRECTION_SRC_PATH.len())..,
-                ) && tail.eq_ignore_ascii_case(REDIRECTION_SRC_PATH.as_bytes())
-                {
-                    crate::debug!(
-                        "'{REDIRECTION_SRC_PATH}' file hooked, replace to '{REDIRECTION_TARGET_PATH}'"
-                    );
-                    let mut new_path = filename_bytes
-                        [..filename_bytes.len() - REDIRECTION_SRC_PATH.len()]
-                        .to_vec();
-                    new_path.extend_from_slice(
-                        const_str::concat!(REDIRECTION_TARGET_PATH, "\0").as_bytes(),
-                    );
-
-                    return crate::call!(
-                        HOOK_CREATE_FILE_A,
-                        new_path.as_ptr(),
-                        _dw_desired_access,
-                        _dw_share_mode,
-                        _lp_security_attributes,
-                        _dw_creation_disposition,
-                        _dw_flags_and_attributes,
-                        _h_template_file,
-                    );
+                if let Some(handle) = try_redirect(
+                    filename,
+                    _dw_desired_access,
+                    _dw_share_mode,
+                    _lp_security_attributes,
+                    _dw_creation_disposition,
+                    _dw_flags_and_attributes,
+                    _h_template_file,
+                ) {
+                    return handle;
                 }
             }
 
-            #[cfg(feature = "resource_pack")]
-            if let Some(handle) = try_redirect(
-                &filename_bytes.to_wide(0),
-                _dw_desired_access,
-                _dw_share_mode,
-                _lp_security_attributes,
-                _dw_creation_disposition,
-                _dw_flags_and_attributes,
-                _h_template_file,
-            ) {
-                return handle;
+            // 重定向后的路径不一定能用ANSI表示，因此改用W版本打开
+            #[cfg(feature = "redirect_rules")]
+            if let Some(path) = &redirected {
+                return crate::call!(
+                    HOOK_CREATE_FILE_W,
+                    path.as_ptr(),
+                    _dw_desired_access,
+                    _dw_share_mode,
+                    _lp_security_attributes,
+                    _dw_creation_disposition,
+                    _dw_flags_and_attributes,
+                    _h_template_file,
+                );
             }
 
             crate::call!(
@@ -93,7 +84,7 @@ pub trait FileHook: Send + Sync + 'static {
             )
         }
 
-        #[cfg(not(any(feature = "create_file_redirect", feature = "resource_pack")))]
+        #[cfg(not(any(feature = "redirect_rules", feature = "resource_pack")))]
         unimplemented!();
     }
 
@@ -111,8 +102,14 @@ pub trait FileHook: Send + Sync + 'static {
         _dw_flags_and_attributes: u32,
         _h_template_file: HANDLE,
     ) -> HANDLE {
-        #[cfg(feature = "resource_pack")]
+        #[cfg(any(feature = "redirect_rules", feature = "resource_pack"))]
         unsafe {
+            #[cfg(feature = "redirect_rules")]
+            let redirected = redirect_wide(_lp_file_name);
+            #[cfg(feature = "redirect_rules")]
+            let _lp_file_name = redirected.as_ref().map_or(_lp_file_name, |p| p.as_ptr());
+
+            #[cfg(feature = "resource_pack")]
             if let Some(handle) = try_redirect(
                 _lp_file_name.to_slice_until_null(4096),
                 _dw_desired_access,
@@ -137,7 +134,7 @@ pub trait FileHook: Send + Sync + 'static {
             )
         }
 
-        #[cfg(not(feature = "resource_pack"))]
+        #[cfg(not(any(feature = "redirect_rules", feature = "resource_pack")))]
         unimplemented!();
     }
 
@@ -154,9 +151,34 @@ pub trait FileHook: Send + Sync + 'static {
         _lp_number_of_bytes_read: *mut u32,
         _lp_overlapped: *mut OVERLAPPED,
     ) -> BOOL {
-        #[cfg(not(feature = "read_file_patch_impl"))]
+        #[cfg(not(any(feature = "read_file_patch_impl", feature = "resource_pack_vfs")))]
         unimplemented!();
 
+        #[cfg(feature = "resource_pack_vfs")]
+        if let Some(result) = unsafe {
+            crate::resource_pack::vfs::read_file(
+                _h_file,
+                _lp_buffer,
+                _n_number_of_bytes_to_read,
+                _lp_number_of_bytes_read,
+                _lp_overlapped,
+            )
+        } {
+            return result;
+        }
+
+        #[cfg(all(feature = "resource_pack_vfs", not(feature = "read_file_patch_impl")))]
+        unsafe {
+            crate::call!(
+                HOOK_READ_FILE,
+                _h_file,
+                _lp_buffer,
+                _n_number_of_bytes_to_read,
+                _lp_number_of_bytes_read,
+                _lp_overlapped,
+            )
+        }
+
         #[cfg(feature = "read_file_patch_impl")]
         unsafe {
             use windows_sys::Win32::Foundation::FALSE;
@@ -199,7 +221,701 @@ pub trait FileHook: Send + Sync + 'static {
         fallback = "windows_sys::Win32::Foundation::FALSE"
     )]
     unsafe fn close_handle(_h_object: HANDLE) -> BOOL {
+        #[cfg(not(any(feature = "file_mapping_patch_impl", feature = "resource_pack_vfs")))]
         unimplemented!();
+
+        #[cfg(any(feature = "file_mapping_patch_impl", feature = "resource_pack_vfs"))]
+        unsafe {
+            // 必须先移除记录再关闭，避免句柄值被其他线程复用
+            #[cfg(feature = "file_mapping_patch_impl")]
+            crate::file_mapping_patch::on_handle_closed(_h_object);
+
+            #[cfg(feature = "resource_pack_vfs")]
+            crate::resource_pack::vfs::on_handle_closed(_h_object);
+
+            crate::call!(HOOK_CLOSE_HANDLE, _h_object)
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "SetFilePointer",
+        fallback = "windows_sys::Win32::Storage::FileSystem::INVALID_SET_FILE_POINTER"
+    )]
+    unsafe fn set_file_pointer(
+        _h_file: HANDLE,
+        _l_distance_to_move: i32,
+        _lp_distance_to_move_high: *mut i32,
+        _dw_move_method: u32,
+    ) -> u32 {
+        #[cfg(not(feature = "resource_pack_vfs"))]
+        unimplemented!();
+
+        #[cfg(feature = "resource_pack_vfs")]
+        unsafe {
+            if let Some(result) = crate::resource_pack::vfs::set_file_pointer(
+                _h_file,
+                _l_distance_to_move,
+                _lp_distance_to_move_high,
+                _dw_move_method,
+            ) {
+                return result;
+            }
+
+            crate::call!(
+                HOOK_SET_FILE_POINTER,
+                _h_file,
+                _l_distance_to_move,
+                _lp_distance_to_move_high,
+                _dw_move_method,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "SetFilePointerEx",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn set_file_pointer_ex(
+        _h_file: HANDLE,
+        _li_distance_to_move: i64,
+        _lp_new_file_pointer: *mut i64,
+        _dw_move_method: u32,
+    ) -> BOOL {
+        #[cfg(not(feature = "resource_pack_vfs"))]
+        unimplemented!();
+
+        #[cfg(feature = "resource_pack_vfs")]
+        unsafe {
+            if let Some(result) = crate::resource_pack::vfs::set_file_pointer_ex(
+                _h_file,
+                _li_distance_to_move,
+                _lp_new_file_pointer,
+                _dw_move_method,
+            ) {
+                return result;
+            }
+
+            crate::call!(
+                HOOK_SET_FILE_POINTER_EX,
+                _h_file,
+                _li_distance_to_move,
+                _lp_new_file_pointer,
+                _dw_move_method,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetFileSize",
+        fallback = "windows_sys::Win32::Storage::FileSystem::INVALID_FILE_SIZE"
+    )]
+    unsafe fn get_file_size(_h_file: HANDLE, _lp_file_size_high: *mut u32) -> u32 {
+        #[cfg(not(feature = "resource_pack_vfs"))]
+        unimplemented!();
+
+        #[cfg(feature = "resource_pack_vfs")]
+        unsafe {
+            if let Some(result) =
+                crate::resource_pack::vfs::get_file_size(_h_file, _lp_file_size_high)
+            {
+                return result;
+            }
+
+            crate::call!(HOOK_GET_FILE_SIZE, _h_file, _lp_file_size_high)
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetFileSizeEx",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn get_file_size_ex(_h_file: HANDLE, _lp_file_size: *mut i64) -> BOOL {
+        #[cfg(not(feature = "resource_pack_vfs"))]
+        unimplemented!();
+
+        #[cfg(feature = "resource_pack_vfs")]
+        unsafe {
+            if let Some(result) =
+                crate::resource_pack::vfs::get_file_size_ex(_h_file, _lp_file_size)
+            {
+                return result;
+            }
+
+            crate::call!(HOOK_GET_FILE_SIZE_EX, _h_file, _lp_file_size)
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetFileType",
+        fallback = "windows_sys::Win32::Storage::FileSystem::FILE_TYPE_UNKNOWN"
+    )]
+    unsafe fn get_file_type(_h_file: HANDLE) -> u32 {
+        #[cfg(not(feature = "resource_pack_vfs"))]
+        unimplemented!();
+
+        #[cfg(feature = "resource_pack_vfs")]
+        unsafe {
+            if let Some(result) = crate::resource_pack::vfs::get_file_type(_h_file) {
+                return result;
+            }
+
+            crate::call!(HOOK_GET_FILE_TYPE, _h_file)
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetFileInformationByHandle",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn get_file_information_by_handle(
+        _h_file: HANDLE,
+        _lp_file_information: *mut BY_HANDLE_FILE_INFORMATION,
+    ) -> BOOL {
+        #[cfg(not(feature = "resource_pack_vfs"))]
+        unimplemented!();
+
+        #[cfg(feature = "resource_pack_vfs")]
+        unsafe {
+            if let Some(result) = crate::resource_pack::vfs::get_file_information_by_handle(
+                _h_file,
+                _lp_file_information,
+            ) {
+                return result;
+            }
+
+            crate::call!(
+                HOOK_GET_FILE_INFORMATION_BY_HANDLE,
+                _h_file,
+                _lp_file_information
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetFileAttributesA",
+        fallback = "windows_sys::Win32::Storage::FileSystem::INVALID_FILE_ATTRIBUTES"
+    )]
+    unsafe fn get_file_attributes_a(_lp_file_name: PCSTR) -> u32 {
+        #[cfg(not(any(feature = "resource_pack_vfs", feature = "redirect_rules")))]
+        unimplemented!();
+
+        #[cfg(any(feature = "resource_pack_vfs", feature = "redirect_rules"))]
+        unsafe {
+            #[cfg(feature = "redirect_rules")]
+            let redirected = redirect_ansi(_lp_file_name);
+
+            #[cfg(feature = "resource_pack_vfs")]
+            {
+                use crate::utils::exts::slice_ext::{ByteSliceExt, WideSliceExt};
+
+                let filename = _lp_file_name.to_slice_until_null(4096).to_wide(0);
+                #[cfg(feature = "redirect_rules")]
+                let filename = redirected.as_deref().map_or(&filename[..], without_null);
+
+                if let Some(attributes) =
+                    crate::resource_pack::vfs::get_file_attributes(&filename.to_path_buf())
+                {
+                    return attributes;
+                }
+            }
+
+            #[cfg(feature = "redirect_rules")]
+            if let Some(path) = &redirected {
+                return crate::call!(HOOK_GET_FILE_ATTRIBUTES_W, path.as_ptr());
+            }
+
+            crate::call!(HOOK_GET_FILE_ATTRIBUTES_A, _lp_file_name)
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetFileAttributesW",
+        fallback = "windows_sys::Win32::Storage::FileSystem::INVALID_FILE_ATTRIBUTES"
+    )]
+    unsafe fn get_file_attributes_w(_lp_file_name: PCWSTR) -> u32 {
+        #[cfg(not(any(feature = "resource_pack_vfs", feature = "redirect_rules")))]
+        unimplemented!();
+
+        #[cfg(any(feature = "resource_pack_vfs", feature = "redirect_rules"))]
+        unsafe {
+            #[cfg(feature = "redirect_rules")]
+            let redirected = redirect_wide(_lp_file_name);
+            #[cfg(feature = "redirect_rules")]
+            let _lp_file_name = redirected.as_ref().map_or(_lp_file_name, |p| p.as_ptr());
+
+            #[cfg(feature = "resource_pack_vfs")]
+            {
+                use crate::utils::exts::slice_ext::WideSliceExt;
+
+                let path = _lp_file_name.to_slice_until_null(4096).to_path_buf();
+                if let Some(attributes) = crate::resource_pack::vfs::get_file_attributes(&path) {
+                    return attributes;
+                }
+            }
+
+            crate::call!(HOOK_GET_FILE_ATTRIBUTES_W, _lp_file_name)
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetFileAttributesExA",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn get_file_attributes_ex_a(
+        _lp_file_name: PCSTR,
+        _f_info_level_id: GET_FILEEX_INFO_LEVELS,
+        _lp_file_information: *mut c_void,
+    ) -> BOOL {
+        #[cfg(not(any(feature = "resource_pack_vfs", feature = "redirect_rules")))]
+        unimplemented!();
+
+        #[cfg(any(feature = "resource_pack_vfs", feature = "redirect_rules"))]
+        unsafe {
+            #[cfg(feature = "redirect_rules")]
+            let redirected = redirect_ansi(_lp_file_name);
+
+            #[cfg(feature = "resource_pack_vfs")]
+            {
+                use crate::utils::exts::slice_ext::{ByteSliceExt, WideSliceExt};
+
+                let filename = _lp_file_name.to_slice_until_null(4096).to_wide(0);
+                #[cfg(feature = "redirect_rules")]
+                let filename = redirected.as_deref().map_or(&filename[..], without_null);
+
+                if let Some(result) = crate::resource_pack::vfs::get_file_attributes_ex(
+                    &filename.to_path_buf(),
+                    _f_info_level_id,
+                    _lp_file_information,
+                ) {
+                    return result;
+                }
+            }
+
+            #[cfg(feature = "redirect_rules")]
+            if let Some(path) = &redirected {
+                return crate::call!(
+                    HOOK_GET_FILE_ATTRIBUTES_EX_W,
+                    path.as_ptr(),
+                    _f_info_level_id,
+                    _lp_file_information,
+                );
+            }
+
+            crate::call!(
+                HOOK_GET_FILE_ATTRIBUTES_EX_A,
+                _lp_file_name,
+                _f_info_level_id,
+                _lp_file_information,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetFileAttributesExW",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn get_file_attributes_ex_w(
+        _lp_file_name: PCWSTR,
+        _f_info_level_id: GET_FILEEX_INFO_LEVELS,
+        _lp_file_information: *mut c_void,
+    ) -> BOOL {
+        #[cfg(not(any(feature = "resource_pack_vfs", feature = "redirect_rules")))]
+        unimplemented!();
+
+        #[cfg(any(feature = "resource_pack_vfs", feature = "redirect_rules"))]
+        unsafe {
+            #[cfg(feature = "redirect_rules")]
+            let redirected = redirect_wide(_lp_file_name);
+            #[cfg(feature = "redirect_rules")]
+            let _lp_file_name = redirected.as_ref().map_or(_lp_file_name, |p| p.as_ptr());
+
+            #[cfg(feature = "resource_pack_vfs")]
+            {
+                use crate::utils::exts::slice_ext::WideSliceExt;
+
+                let path = _lp_file_name.to_slice_until_null(4096).to_path_buf();
+                if let Some(result) = crate::resource_pack::vfs::get_file_attributes_ex(
+                    &path,
+                    _f_info_level_id,
+                    _lp_file_information,
+                ) {
+                    return result;
+                }
+            }
+
+            crate::call!(
+                HOOK_GET_FILE_ATTRIBUTES_EX_W,
+                _lp_file_name,
+                _f_info_level_id,
+                _lp_file_information,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "CreateDirectoryA",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn create_directory_a(
+        _lp_path_name: PCSTR,
+        _lp_security_attributes: *const SECURITY_ATTRIBUTES,
+    ) -> BOOL {
+        #[cfg(not(feature = "redirect_rules"))]
+        unimplemented!();
+
+        #[cfg(feature = "redirect_rules")]
+        unsafe {
+            if let Some(path) = redirect_ansi(_lp_path_name) {
+                return crate::call!(
+                    HOOK_CREATE_DIRECTORY_W,
+                    path.as_ptr(),
+                    _lp_security_attributes
+                );
+            }
+
+            crate::call!(
+                HOOK_CREATE_DIRECTORY_A,
+                _lp_path_name,
+                _lp_security_attributes
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "CreateDirectoryW",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn create_directory_w(
+        _lp_path_name: PCWSTR,
+        _lp_security_attributes: *const SECURITY_ATTRIBUTES,
+    ) -> BOOL {
+        #[cfg(not(feature = "redirect_rules"))]
+        unimplemented!();
+
+        #[cfg(feature = "redirect_rules")]
+        unsafe {
+            let redirected = redirect_wide(_lp_path_name);
+            let _lp_path_name = redirected.as_ref().map_or(_lp_path_name, |p| p.as_ptr());
+
+            crate::call!(
+                HOOK_CREATE_DIRECTORY_W,
+                _lp_path_name,
+                _lp_security_attributes
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "DeleteFileA",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn delete_file_a(_lp_file_name: PCSTR) -> BOOL {
+        #[cfg(not(feature = "redirect_rules"))]
+        unimplemented!();
+
+        #[cfg(feature = "redirect_rules")]
+        unsafe {
+            if let Some(path) = redirect_ansi(_lp_file_name) {
+                return crate::call!(HOOK_DELETE_FILE_W, path.as_ptr());
+            }
+
+            crate::call!(HOOK_DELETE_FILE_A, _lp_file_name)
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "DeleteFileW",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn delete_file_w(_lp_file_name: PCWSTR) -> BOOL {
+        #[cfg(not(feature = "redirect_rules"))]
+        unimplemented!();
+
+        #[cfg(feature = "redirect_rules")]
+        unsafe {
+            let redirected = redirect_wide(_lp_file_name);
+            let _lp_file_name = redirected.as_ref().map_or(_lp_file_name, |p| p.as_ptr());
+
+            crate::call!(HOOK_DELETE_FILE_W, _lp_file_name)
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "MoveFileA",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn move_file_a(_lp_existing_file_name: PCSTR, _lp_new_file_name: PCSTR) -> BOOL {
+        #[cfg(not(feature = "redirect_rules"))]
+        unimplemented!();
+
+        #[cfg(feature = "redirect_rules")]
+        unsafe {
+            if let Some((existing, new)) =
+                redirect_ansi_pair(_lp_existing_file_name, _lp_new_file_name)
+            {
+                return crate::call!(HOOK_MOVE_FILE_W, existing.as_ptr(), new.as_ptr());
+            }
+
+            crate::call!(HOOK_MOVE_FILE_A, _lp_existing_file_name, _lp_new_file_name)
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "MoveFileW",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn move_file_w(_lp_existing_file_name: PCWSTR, _lp_new_file_name: PCWSTR) -> BOOL {
+        #[cfg(not(feature = "redirect_rules"))]
+        unimplemented!();
+
+        #[cfg(feature = "redirect_rules")]
+        unsafe {
+            let existing = redirect_wide(_lp_existing_file_name);
+            let new = redirect_wide(_lp_new_file_name);
+
+            crate::call!(
+                HOOK_MOVE_FILE_W,
+                existing
+                    .as_ref()
+                    .map_or(_lp_existing_file_name, |p| p.as_ptr()),
+                new.as_ref().map_or(_lp_new_file_name, |p| p.as_ptr()),
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "MoveFileExA",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn move_file_ex_a(
+        _lp_existing_file_name: PCSTR,
+        _lp_new_file_name: PCSTR,
+        _dw_flags: u32,
+    ) -> BOOL {
+        #[cfg(not(feature = "redirect_rules"))]
+        unimplemented!();
+
+        #[cfg(feature = "redirect_rules")]
+        unsafe {
+            if let Some((existing, new)) =
+                redirect_ansi_pair(_lp_existing_file_name, _lp_new_file_name)
+            {
+                // 新路径为NULL时（MOVEFILE_DELAY_UNTIL_REBOOT删除文件）保持为NULL
+                let new = if _lp_new_file_name.is_null() {
+                    core::ptr::null()
+                } else {
+                    new.as_ptr()
+                };
+                return crate::call!(HOOK_MOVE_FILE_EX_W, existing.as_ptr(), new, _dw_flags);
+            }
+
+            crate::call!(
+                HOOK_MOVE_FILE_EX_A,
+                _lp_existing_file_name,
+                _lp_new_file_name,
+                _dw_flags,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "MoveFileExW",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn move_file_ex_w(
+        _lp_existing_file_name: PCWSTR,
+        _lp_new_file_name: PCWSTR,
+        _dw_flags: u32,
+    ) -> BOOL {
+        #[cfg(not(feature = "redirect_rules"))]
+        unimplemented!();
+
+        #[cfg(feature = "redirect_rules")]
+        unsafe {
+            let existing = redirect_wide(_lp_existing_file_name);
+            let new = redirect_wide(_lp_new_file_name);
+
+            crate::call!(
+                HOOK_MOVE_FILE_EX_W,
+                existing
+                    .as_ref()
+                    .map_or(_lp_existing_file_name, |p| p.as_ptr()),
+                new.as_ref().map_or(_lp_new_file_name, |p| p.as_ptr()),
+                _dw_flags,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "CreateFileMappingA",
+        fallback = "core::ptr::null_mut()"
+    )]
+    unsafe fn create_file_mapping_a(
+        _h_file: HANDLE,
+        _lp_file_mapping_attributes: *const SECURITY_ATTRIBUTES,
+        _fl_protect: u32,
+        _dw_maximum_size_high: u32,
+        _dw_maximum_size_low: u32,
+        _lp_name: PCSTR,
+    ) -> HANDLE {
+        #[cfg(not(feature = "file_mapping_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "file_mapping_patch_impl")]
+        unsafe {
+            let mapping = crate::call!(
+                HOOK_CREATE_FILE_MAPPING_A,
+                _h_file,
+                _lp_file_mapping_attributes,
+                _fl_protect,
+                _dw_maximum_size_high,
+                _dw_maximum_size_low,
+                _lp_name,
+            );
+            crate::file_mapping_patch::on_mapping_created(
+                _h_file,
+                mapping,
+                _dw_maximum_size_high,
+                _dw_maximum_size_low,
+            );
+            mapping
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "CreateFileMappingW",
+        fallback = "core::ptr::null_mut()"
+    )]
+    unsafe fn create_file_mapping_w(
+        _h_file: HANDLE,
+        _lp_file_mapping_attributes: *const SECURITY_ATTRIBUTES,
+        _fl_protect: u32,
+        _dw_maximum_size_high: u32,
+        _dw_maximum_size_low: u32,
+        _lp_name: PCWSTR,
+    ) -> HANDLE {
+        #[cfg(not(feature = "file_mapping_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "file_mapping_patch_impl")]
+        unsafe {
+            let mapping = crate::call!(
+                HOOK_CREATE_FILE_MAPPING_W,
+                _h_file,
+                _lp_file_mapping_attributes,
+                _fl_protect,
+                _dw_maximum_size_high,
+                _dw_maximum_size_low,
+                _lp_name,
+            );
+            crate::file_mapping_patch::on_mapping_created(
+                _h_file,
+                mapping,
+                _dw_maximum_size_high,
+                _dw_maximum_size_low,
+            );
+            mapping
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "MapViewOfFile",
+        fallback = "windows_sys::Win32::System::Memory::MEMORY_MAPPED_VIEW_ADDRESS { Value: core::ptr::null_mut() }"
+    )]
+    unsafe fn map_view_of_file(
+        _h_file_mapping_object: HANDLE,
+        _dw_desired_access: u32,
+        _dw_file_offset_high: u32,
+        _dw_file_offset_low: u32,
+        _dw_number_of_bytes_to_map: usize,
+    ) -> MEMORY_MAPPED_VIEW_ADDRESS {
+        #[cfg(not(feature = "file_mapping_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "file_mapping_patch_impl")]
+        unsafe {
+            if let Some(view) = crate::file_mapping_patch::map_patched_view(
+                _h_file_mapping_object,
+                _dw_desired_access,
+                _dw_file_offset_high,
+                _dw_file_offset_low,
+                _dw_number_of_bytes_to_map,
+                core::ptr::null(),
+            ) {
+                return view;
+            }
+
+            crate::call!(
+                HOOK_MAP_VIEW_OF_FILE,
+                _h_file_mapping_object,
+                _dw_desired_access,
+                _dw_file_offset_high,
+                _dw_file_offset_low,
+                _dw_number_of_bytes_to_map,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "MapViewOfFileEx",
+        fallback = "windows_sys::Win32::System::Memory::MEMORY_MAPPED_VIEW_ADDRESS { Value: core::ptr::null_mut() }"
+    )]
+    unsafe fn map_view_of_file_ex(
+        _h_file_mapping_object: HANDLE,
+        _dw_desired_access: u32,
+        _dw_file_offset_high: u32,
+        _dw_file_offset_low: u32,
+        _dw_number_of_bytes_to_map: usize,
+        _lp_base_address: *const c_void,
+    ) -> MEMORY_MAPPED_VIEW_ADDRESS {
+        #[cfg(not(feature = "file_mapping_patch_impl"))]
+        unimplemented!();
+
+        #[cfg(feature = "file_mapping_patch_impl")]
+        unsafe {
+            if let Some(view) = crate::file_mapping_patch::map_patched_view(
+                _h_file_mapping_object,
+                _dw_desired_access,
+                _dw_file_offset_high,
+                _dw_file_offset_low,
+                _dw_number_of_bytes_to_map,
+                _lp_base_address,
+            ) {
+                return view;
+            }
+
+            crate::call!(
+                HOOK_MAP_VIEW_OF_FILE_EX,
+                _h_file_mapping_object,
+                _dw_desired_access,
+                _dw_file_offset_high,
+                _dw_file_offset_low,
+                _dw_number_of_bytes_to_map,
+                _lp_base_address,
+            )
+        }
     }
 
     #[detour(
@@ -211,7 +927,44 @@ pub trait FileHook: Send + Sync + 'static {
         _lp_file_name: PCSTR,
         _lp_find_file_data: *mut WIN32_FIND_DATAA,
     ) -> HANDLE {
+        #[cfg(not(any(feature = "resource_pack_find", feature = "redirect_rules")))]
         unimplemented!();
+
+        #[cfg(any(feature = "resource_pack_find", feature = "redirect_rules"))]
+        unsafe {
+            #[cfg(feature = "redirect_rules")]
+            let redirected = redirect_ansi(_lp_file_name);
+
+            #[cfg(feature = "resource_pack_find")]
+            if !_lp_file_name.is_null() && !_lp_find_file_data.is_null() {
+                use crate::resource_pack::find;
+                use crate::utils::exts::slice_ext::ByteSliceExt;
+
+                let pattern = _lp_file_name.to_slice_until_null(4096).to_wide_null(0);
+                #[cfg(feature = "redirect_rules")]
+                let pattern = redirected.clone().unwrap_or(pattern);
+
+                let mut data = core::mem::zeroed();
+                if let Some(handle) = find::find_first_file(pattern.as_ptr(), &mut data) {
+                    *_lp_find_file_data = crate::utils::win32::to_find_data_a(&data);
+                    return handle;
+                }
+            }
+
+            #[cfg(feature = "redirect_rules")]
+            if let Some(path) = &redirected
+                && !_lp_find_file_data.is_null()
+            {
+                let mut data: WIN32_FIND_DATAW = core::mem::zeroed();
+                let handle = crate::call!(HOOK_FIND_FIRST_FILE_W, path.as_ptr(), &mut data);
+                if handle != windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE {
+                    *_lp_find_file_data = crate::utils::win32::to_find_data_a(&data);
+                }
+                return handle;
+            }
+
+            crate::call!(HOOK_FIND_FIRST_FILE_A, _lp_file_name, _lp_find_file_data)
+        }
     }
 
     #[detour(
@@ -223,7 +976,25 @@ pub trait FileHook: Send + Sync + 'static {
         _lp_file_name: PCWSTR,
         _lp_find_file_data: *mut WIN32_FIND_DATAW,
     ) -> HANDLE {
+        #[cfg(not(any(feature = "resource_pack_find", feature = "redirect_rules")))]
         unimplemented!();
+
+        #[cfg(any(feature = "resource_pack_find", feature = "redirect_rules"))]
+        unsafe {
+            #[cfg(feature = "redirect_rules")]
+            let redirected = redirect_wide(_lp_file_name);
+            #[cfg(feature = "redirect_rules")]
+            let _lp_file_name = redirected.as_ref().map_or(_lp_file_name, |p| p.as_ptr());
+
+            #[cfg(feature = "resource_pack_find")]
+            if let Some(handle) =
+                crate::resource_pack::find::find_first_file(_lp_file_name, _lp_find_file_data)
+            {
+                return handle;
+            }
+
+            crate::call!(HOOK_FIND_FIRST_FILE_W, _lp_file_name, _lp_find_file_data)
+        }
     }
 
     #[detour(
@@ -235,7 +1006,23 @@ pub trait FileHook: Send + Sync + 'static {
         _h_find_file: HANDLE,
         _lp_find_file_data: *mut WIN32_FIND_DATAA,
     ) -> BOOL {
+        #[cfg(not(feature = "resource_pack_find"))]
         unimplemented!();
+
+        #[cfg(feature = "resource_pack_find")]
+        unsafe {
+            use crate::resource_pack::find;
+
+            let mut data = core::mem::zeroed();
+            if let Some(result) = find::find_next_file(_h_find_file, &mut data) {
+                if result != windows_sys::Win32::Foundation::FALSE {
+                    *_lp_find_file_data = crate::utils::win32::to_find_data_a(&data);
+                }
+                return result;
+            }
+
+            crate::call!(HOOK_FIND_NEXT_FILE_A, _h_find_file, _lp_find_file_data)
+        }
     }
 
     #[detour(
@@ -247,7 +1034,19 @@ pub trait FileHook: Send + Sync + 'static {
         _h_find_file: HANDLE,
         _lp_find_file_data: *mut WIN32_FIND_DATAW,
     ) -> BOOL {
+        #[cfg(not(feature = "resource_pack_find"))]
         unimplemented!();
+
+        #[cfg(feature = "resource_pack_find")]
+        unsafe {
+            if let Some(result) =
+                crate::resource_pack::find::find_next_file(_h_find_file, _lp_find_file_data)
+            {
+                return result;
+            }
+
+            crate::call!(HOOK_FIND_NEXT_FILE_W, _h_find_file, _lp_find_file_data)
+        }
     }
 
     #[detour(
@@ -256,12 +1055,68 @@ pub trait FileHook: Send + Sync + 'static {
         fallback = "windows_sys::Win32::Foundation::FALSE"
     )]
     unsafe fn find_close(_h_find_file: HANDLE) -> BOOL {
+        #[cfg(not(feature = "resource_pack_find"))]
         unimplemented!();
+
+        #[cfg(feature = "resource_pack_find")]
+        unsafe {
+            if let Some(result) = crate::resource_pack::find::find_close(_h_find_file) {
+                return result;
+            }
+
+            crate::call!(HOOK_FIND_CLOSE, _h_find_file)
+        }
+    }
+}
+
+/// 按重定向规则改写ANSI路径，返回以 null 结尾的宽字符路径
+#[cfg(feature = "redirect_rules")]
+fn redirect_ansi(path: PCSTR) -> Option<Vec<u16>> {
+    use crate::utils::exts::slice_ext::ByteSliceExt;
+
+    let path = unsafe { path.to_slice_until_null(4096) };
+    if path.is_empty() {
+        return None;
+    }
+    crate::redirect_rules::redirect_path(&path.to_wide(0))
+}
+
+/// 按重定向规则改写宽字符路径，返回以 null 结尾的宽字符路径
+#[cfg(feature = "redirect_rules")]
+fn redirect_wide(path: PCWSTR) -> Option<Vec<u16>> {
+    let path = unsafe { path.to_slice_until_null(4096) };
+    if path.is_empty() {
+        return None;
+    }
+    crate::redirect_rules::redirect_path(path)
+}
+
+/// 按重定向规则改写一对ANSI路径，任意一个命中时返回两者以 null 结尾的宽字符路径
+#[cfg(feature = "redirect_rules")]
+fn redirect_ansi_pair(first: PCSTR, second: PCSTR) -> Option<(Vec<u16>, Vec<u16>)> {
+    use crate::utils::exts::slice_ext::ByteSliceExt;
+
+    let first_redirected = redirect_ansi(first);
+    let second_redirected = redirect_ansi(second);
+    if first_redirected.is_none() && second_redirected.is_none() {
+        return None;
     }
+
+    let to_wide = |path: PCSTR| unsafe { path.to_slice_until_null(4096).to_wide_null(0) };
+    Some((
+        first_redirected.unwrap_or_else(|| to_wide(first)),
+        second_redirected.unwrap_or_else(|| to_wide(second)),
+    ))
+}
+
+/// 去掉末尾的 null
+#[cfg(feature = "redirect_rules")]
+fn without_null(path: &[u16]) -> &[u16] {
+    path.strip_suffix(&[0]).unwrap_or(path)
 }
 
 /// 尝试将传入文件路径重定向到资源包中的替代文件。
-#[cfg(feature = "resource_pack")]
+#[cfg(all(feature = "resource_pack", not(feature = "resource_pack_vfs")))]
 fn try_redirect(
     u16_filename: &[u16],
     dw_desired_access: u32,
@@ -308,3 +1163,23 @@ fn try_redirect(
 
     None
 }
+
+/// 尝试将传入文件路径作为资源包中的虚拟文件打开。
+#[cfg(feature = "resource_pack_vfs")]
+fn try_redirect(
+    u16_filename: &[u16],
+    dw_desired_access: u32,
+    _dw_share_mode: u32,
+    _lp_security_attributes: *const SECURITY_ATTRIBUTES,
+    dw_creation_disposition: u32,
+    _dw_flags_and_attributes: u32,
+    _h_template_file: HANDLE,
+) -> Option<HANDLE> {
+    use crate::utils::exts::slice_ext::WideSliceExt;
+
+    crate::resource_pack::vfs::open(
+        &u16_filename.to_path_buf(),
+        dw_desired_access,
+        dw_creation_disposition,
+    )
+}
@@ -0,0 +1,362 @@
+use translate_macros::detour_trait;
+use windows_sys::core::{BOOL, PCSTR, PCWSTR, PSTR, PWSTR};
+
+#[cfg(feature = "ini_virtualization")]
+use crate::utils::exts::ptr_ext::PtrExt;
+
+/// 节名、键名以及文件路径的最大长度
+#[cfg(feature = "ini_virtualization")]
+const MAX_NAME_LENGTH: usize = 32767;
+
+#[cfg(feature = "ini_virtualization")]
+unsafe fn ansi_arg(ptr: PCSTR) -> Option<String> {
+    use crate::utils::exts::slice_ext::ByteSliceExt;
+
+    (!ptr.is_null()).then(|| {
+        String::from_utf16_lossy(
+            &unsafe { ptr.to_slice_until_null(MAX_NAME_LENGTH) }.to_wide_ansi(),
+        )
+    })
+}
+
+#[cfg(feature = "ini_virtualization")]
+unsafe fn wide_arg(ptr: PCWSTR) -> Option<String> {
+    (!ptr.is_null())
+        .then(|| String::from_utf16_lossy(unsafe { ptr.to_slice_until_null(MAX_NAME_LENGTH) }))
+}
+
+#[cfg(feature = "ini_virtualization")]
+fn encode_ansi(s: &str) -> Vec<u8> {
+    use crate::utils::exts::slice_ext::WideSliceExt;
+
+    s.encode_utf16().collect::<Vec<_>>().to_ansi()
+}
+
+#[cfg(feature = "ini_virtualization")]
+fn encode_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().collect()
+}
+
+/// INI配置文件相关的钩子
+#[detour_trait]
+pub trait ProfileHook: Send + Sync + 'static {
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetPrivateProfileStringA",
+        fallback = "0u32"
+    )]
+    unsafe fn get_private_profile_string_a(
+        _lp_app_name: PCSTR,
+        _lp_key_name: PCSTR,
+        _lp_default: PCSTR,
+        _lp_returned_string: PSTR,
+        _n_size: u32,
+        _lp_file_name: PCSTR,
+    ) -> u32 {
+        #[cfg(not(feature = "ini_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "ini_virtualization")]
+        unsafe {
+            if let Some(file) = ansi_arg(_lp_file_name) {
+                let app = ansi_arg(_lp_app_name);
+                let key = ansi_arg(_lp_key_name);
+                let default = ansi_arg(_lp_default).unwrap_or_default();
+                if let Some(profile) = crate::ini_virtualization::get_string(
+                    app.as_deref(),
+                    key.as_deref(),
+                    &default,
+                    &file,
+                ) {
+                    return crate::ini_virtualization::write_result(
+                        &profile,
+                        _lp_returned_string,
+                        _n_size,
+                        encode_ansi,
+                    );
+                }
+            }
+
+            crate::call!(
+                HOOK_GET_PRIVATE_PROFILE_STRING_A,
+                _lp_app_name,
+                _lp_key_name,
+                _lp_default,
+                _lp_returned_string,
+                _n_size,
+                _lp_file_name,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetPrivateProfileStringW",
+        fallback = "0u32"
+    )]
+    unsafe fn get_private_profile_string_w(
+        _lp_app_name: PCWSTR,
+        _lp_key_name: PCWSTR,
+        _lp_default: PCWSTR,
+        _lp_returned_string: PWSTR,
+        _n_size: u32,
+        _lp_file_name: PCWSTR,
+    ) -> u32 {
+        #[cfg(not(feature = "ini_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "ini_virtualization")]
+        unsafe {
+            if let Some(file) = wide_arg(_lp_file_name) {
+                let app = wide_arg(_lp_app_name);
+                let key = wide_arg(_lp_key_name);
+                let default = wide_arg(_lp_default).unwrap_or_default();
+                if let Some(profile) = crate::ini_virtualization::get_string(
+                    app.as_deref(),
+                    key.as_deref(),
+                    &default,
+                    &file,
+                ) {
+                    return crate::ini_virtualization::write_result(
+                        &profile,
+                        _lp_returned_string,
+                        _n_size,
+                        encode_wide,
+                    );
+                }
+            }
+
+            crate::call!(
+                HOOK_GET_PRIVATE_PROFILE_STRING_W,
+                _lp_app_name,
+                _lp_key_name,
+                _lp_default,
+                _lp_returned_string,
+                _n_size,
+                _lp_file_name,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetPrivateProfileIntA",
+        fallback = "_n_default as u32"
+    )]
+    unsafe fn get_private_profile_int_a(
+        _lp_app_name: PCSTR,
+        _lp_key_name: PCSTR,
+        _n_default: i32,
+        _lp_file_name: PCSTR,
+    ) -> u32 {
+        #[cfg(not(feature = "ini_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "ini_virtualization")]
+        unsafe {
+            if let Some(file) = ansi_arg(_lp_file_name)
+                && let Some(app) = ansi_arg(_lp_app_name)
+                && let Some(key) = ansi_arg(_lp_key_name)
+                && let Some(value) =
+                    crate::ini_virtualization::get_int(&app, &key, _n_default, &file)
+            {
+                return value;
+            }
+
+            crate::call!(
+                HOOK_GET_PRIVATE_PROFILE_INT_A,
+                _lp_app_name,
+                _lp_key_name,
+                _n_default,
+                _lp_file_name,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetPrivateProfileIntW",
+        fallback = "_n_default as u32"
+    )]
+    unsafe fn get_private_profile_int_w(
+        _lp_app_name: PCWSTR,
+        _lp_key_name: PCWSTR,
+        _n_default: i32,
+        _lp_file_name: PCWSTR,
+    ) -> u32 {
+        #[cfg(not(feature = "ini_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "ini_virtualization")]
+        unsafe {
+            if let Some(file) = wide_arg(_lp_file_name)
+                && let Some(app) = wide_arg(_lp_app_name)
+                && let Some(key) = wide_arg(_lp_key_name)
+                && let Some(value) =
+                    crate::ini_virtualization::get_int(&app, &key, _n_default, &file)
+            {
+                return value;
+            }
+
+            crate::call!(
+                HOOK_GET_PRIVATE_PROFILE_INT_W,
+                _lp_app_name,
+                _lp_key_name,
+                _n_default,
+                _lp_file_name,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetPrivateProfileSectionA",
+        fallback = "0u32"
+    )]
+    unsafe fn get_private_profile_section_a(
+        _lp_app_name: PCSTR,
+        _lp_returned_string: PSTR,
+        _n_size: u32,
+        _lp_file_name: PCSTR,
+    ) -> u32 {
+        #[cfg(not(feature = "ini_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "ini_virtualization")]
+        unsafe {
+            if let Some(file) = ansi_arg(_lp_file_name)
+                && let Some(app) = ansi_arg(_lp_app_name)
+                && let Some(profile) = crate::ini_virtualization::get_section(&app, &file)
+            {
+                return crate::ini_virtualization::write_result(
+                    &profile,
+                    _lp_returned_string,
+                    _n_size,
+                    encode_ansi,
+                );
+            }
+
+            crate::call!(
+                HOOK_GET_PRIVATE_PROFILE_SECTION_A,
+                _lp_app_name,
+                _lp_returned_string,
+                _n_size,
+                _lp_file_name,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "GetPrivateProfileSectionW",
+        fallback = "0u32"
+    )]
+    unsafe fn get_private_profile_section_w(
+        _lp_app_name: PCWSTR,
+        _lp_returned_string: PWSTR,
+        _n_size: u32,
+        _lp_file_name: PCWSTR,
+    ) -> u32 {
+        #[cfg(not(feature = "ini_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "ini_virtualization")]
+        unsafe {
+            if let Some(file) = wide_arg(_lp_file_name)
+                && let Some(app) = wide_arg(_lp_app_name)
+                && let Some(profile) = crate::ini_virtualization::get_section(&app, &file)
+            {
+                return crate::ini_virtualization::write_result(
+                    &profile,
+                    _lp_returned_string,
+                    _n_size,
+                    encode_wide,
+                );
+            }
+
+            crate::call!(
+                HOOK_GET_PRIVATE_PROFILE_SECTION_W,
+                _lp_app_name,
+                _lp_returned_string,
+                _n_size,
+                _lp_file_name,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "WritePrivateProfileStringA",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn write_private_profile_string_a(
+        _lp_app_name: PCSTR,
+        _lp_key_name: PCSTR,
+        _lp_string: PCSTR,
+        _lp_file_name: PCSTR,
+    ) -> BOOL {
+        #[cfg(not(feature = "ini_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "ini_virtualization")]
+        unsafe {
+            if let Some(file) = ansi_arg(_lp_file_name)
+                && let Some(app) = ansi_arg(_lp_app_name)
+                && let Some(result) = crate::ini_virtualization::write_string(
+                    &app,
+                    ansi_arg(_lp_key_name).as_deref(),
+                    ansi_arg(_lp_string).as_deref(),
+                    &file,
+                )
+            {
+                return result as BOOL;
+            }
+
+            crate::call!(
+                HOOK_WRITE_PRIVATE_PROFILE_STRING_A,
+                _lp_app_name,
+                _lp_key_name,
+                _lp_string,
+                _lp_file_name,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "kernel32.dll",
+        symbol = "WritePrivateProfileStringW",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn write_private_profile_string_w(
+        _lp_app_name: PCWSTR,
+        _lp_key_name: PCWSTR,
+        _lp_string: PCWSTR,
+        _lp_file_name: PCWSTR,
+    ) -> BOOL {
+        #[cfg(not(feature = "ini_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "ini_virtualization")]
+        unsafe {
+            if let Some(file) = wide_arg(_lp_file_name)
+                && let Some(app) = wide_arg(_lp_app_name)
+                && let Some(result) = crate::ini_virtualization::write_string(
+                    &app,
+                    wide_arg(_lp_key_name).as_deref(),
+                    wide_arg(_lp_string).as_deref(),
+                    &file,
+                )
+            {
+                return result as BOOL;
+            }
+
+            crate::call!(
+                HOOK_WRITE_PRIVATE_PROFILE_STRING_W,
+                _lp_app_name,
+                _lp_key_name,
+                _lp_string,
+                _lp_file_name,
+            )
+        }
+    }
+}
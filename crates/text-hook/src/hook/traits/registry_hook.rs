@@ -0,0 +1,593 @@
+use translate_macros::detour_trait;
+use windows_sys::{
+    Win32::{
+        Foundation::{FILETIME, WIN32_ERROR},
+        Security::SECURITY_ATTRIBUTES,
+        System::Registry::{
+            HKEY, REG_CREATE_KEY_DISPOSITION, REG_OPEN_CREATE_OPTIONS, REG_SAM_FLAGS,
+            REG_VALUE_TYPE,
+        },
+    },
+    core::{PCSTR, PCWSTR, PSTR, PWSTR},
+};
+
+#[cfg(feature = "registry_virtualization")]
+use crate::utils::exts::ptr_ext::PtrExt;
+
+/// 注册表路径的最大长度
+#[cfg(feature = "registry_virtualization")]
+const MAX_KEY_LENGTH: usize = 32767;
+
+/// 注册表相关的钩子
+#[detour_trait]
+pub trait RegistryHook: Send + Sync + 'static {
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegOpenKeyExA",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_open_key_ex_a(
+        _hkey: HKEY,
+        _lp_sub_key: PCSTR,
+        _ul_options: u32,
+        _sam_desired: REG_SAM_FLAGS,
+        _phk_result: *mut HKEY,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            use crate::utils::exts::slice_ext::ByteSliceExt;
+
+            let sub_key = _lp_sub_key
+                .to_slice_until_null(MAX_KEY_LENGTH)
+                .to_wide_ansi();
+            if let Some(result) = crate::registry_virtualization::open_key(
+                _hkey,
+                &sub_key,
+                _ul_options,
+                _sam_desired,
+                _phk_result,
+            ) {
+                return result;
+            }
+
+            crate::call!(
+                HOOK_REG_OPEN_KEY_EX_A,
+                _hkey,
+                _lp_sub_key,
+                _ul_options,
+                _sam_desired,
+                _phk_result,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegOpenKeyExW",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_open_key_ex_w(
+        _hkey: HKEY,
+        _lp_sub_key: PCWSTR,
+        _ul_options: u32,
+        _sam_desired: REG_SAM_FLAGS,
+        _phk_result: *mut HKEY,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            let sub_key = _lp_sub_key.to_slice_until_null(MAX_KEY_LENGTH);
+            if let Some(result) = crate::registry_virtualization::open_key(
+                _hkey,
+                sub_key,
+                _ul_options,
+                _sam_desired,
+                _phk_result,
+            ) {
+                return result;
+            }
+
+            crate::call!(
+                HOOK_REG_OPEN_KEY_EX_W,
+                _hkey,
+                _lp_sub_key,
+                _ul_options,
+                _sam_desired,
+                _phk_result,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegCreateKeyExA",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_create_key_ex_a(
+        _hkey: HKEY,
+        _lp_sub_key: PCSTR,
+        _reserved: u32,
+        _lp_class: PCSTR,
+        _dw_options: REG_OPEN_CREATE_OPTIONS,
+        _sam_desired: REG_SAM_FLAGS,
+        _lp_security_attributes: *const SECURITY_ATTRIBUTES,
+        _phk_result: *mut HKEY,
+        _lpdw_disposition: *mut REG_CREATE_KEY_DISPOSITION,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            use crate::utils::exts::slice_ext::ByteSliceExt;
+
+            let sub_key = _lp_sub_key
+                .to_slice_until_null(MAX_KEY_LENGTH)
+                .to_wide_ansi();
+            let class = (!_lp_class.is_null()).then(|| {
+                _lp_class
+                    .to_slice_until_null(MAX_KEY_LENGTH)
+                    .to_wide_null_ansi()
+            });
+            if let Some(result) = crate::registry_virtualization::create_key(
+                _hkey,
+                &sub_key,
+                class
+                    .as_ref()
+                    .map_or(core::ptr::null(), |class| class.as_ptr()),
+                _dw_options,
+                _sam_desired,
+                _lp_security_attributes,
+                _phk_result,
+                _lpdw_disposition,
+            ) {
+                return result;
+            }
+
+            crate::call!(
+                HOOK_REG_CREATE_KEY_EX_A,
+                _hkey,
+                _lp_sub_key,
+                _reserved,
+                _lp_class,
+                _dw_options,
+                _sam_desired,
+                _lp_security_attributes,
+                _phk_result,
+                _lpdw_disposition,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegCreateKeyExW",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_create_key_ex_w(
+        _hkey: HKEY,
+        _lp_sub_key: PCWSTR,
+        _reserved: u32,
+        _lp_class: PCWSTR,
+        _dw_options: REG_OPEN_CREATE_OPTIONS,
+        _sam_desired: REG_SAM_FLAGS,
+        _lp_security_attributes: *const SECURITY_ATTRIBUTES,
+        _phk_result: *mut HKEY,
+        _lpdw_disposition: *mut REG_CREATE_KEY_DISPOSITION,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            let sub_key = _lp_sub_key.to_slice_until_null(MAX_KEY_LENGTH);
+            if let Some(result) = crate::registry_virtualization::create_key(
+                _hkey,
+                sub_key,
+                _lp_class,
+                _dw_options,
+                _sam_desired,
+                _lp_security_attributes,
+                _phk_result,
+                _lpdw_disposition,
+            ) {
+                return result;
+            }
+
+            crate::call!(
+                HOOK_REG_CREATE_KEY_EX_W,
+                _hkey,
+                _lp_sub_key,
+                _reserved,
+                _lp_class,
+                _dw_options,
+                _sam_desired,
+                _lp_security_attributes,
+                _phk_result,
+                _lpdw_disposition,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegQueryValueExA",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_query_value_ex_a(
+        _hkey: HKEY,
+        _lp_value_name: PCSTR,
+        _lp_reserved: *const u32,
+        _lp_type: *mut REG_VALUE_TYPE,
+        _lp_data: *mut u8,
+        _lpcb_data: *mut u32,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            use crate::utils::exts::slice_ext::ByteSliceExt;
+
+            let name = _lp_value_name
+                .to_slice_until_null(MAX_KEY_LENGTH)
+                .to_wide_ansi();
+            if let Some(result) = crate::registry_virtualization::query_value(
+                _hkey, &name, false, _lp_type, _lp_data, _lpcb_data,
+            ) {
+                return result;
+            }
+
+            crate::call!(
+                HOOK_REG_QUERY_VALUE_EX_A,
+                _hkey,
+                _lp_value_name,
+                _lp_reserved,
+                _lp_type,
+                _lp_data,
+                _lpcb_data,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegQueryValueExW",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_query_value_ex_w(
+        _hkey: HKEY,
+        _lp_value_name: PCWSTR,
+        _lp_reserved: *const u32,
+        _lp_type: *mut REG_VALUE_TYPE,
+        _lp_data: *mut u8,
+        _lpcb_data: *mut u32,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            let name = _lp_value_name.to_slice_until_null(MAX_KEY_LENGTH);
+            if let Some(result) = crate::registry_virtualization::query_value(
+                _hkey, name, true, _lp_type, _lp_data, _lpcb_data,
+            ) {
+                return result;
+            }
+
+            crate::call!(
+                HOOK_REG_QUERY_VALUE_EX_W,
+                _hkey,
+                _lp_value_name,
+                _lp_reserved,
+                _lp_type,
+                _lp_data,
+                _lpcb_data,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegSetValueExA",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_set_value_ex_a(
+        _hkey: HKEY,
+        _lp_value_name: PCSTR,
+        _reserved: u32,
+        _dw_type: REG_VALUE_TYPE,
+        _lp_data: *const u8,
+        _cb_data: u32,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            use crate::utils::exts::slice_ext::ByteSliceExt;
+
+            let name = _lp_value_name
+                .to_slice_until_null(MAX_KEY_LENGTH)
+                .to_wide_ansi();
+            let data = _lp_data.to_slice(_cb_data as usize);
+            if let Some(result) =
+                crate::registry_virtualization::set_value(_hkey, &name, _dw_type, data, false)
+            {
+                return result;
+            }
+
+            crate::call!(
+                HOOK_REG_SET_VALUE_EX_A,
+                _hkey,
+                _lp_value_name,
+                _reserved,
+                _dw_type,
+                _lp_data,
+                _cb_data,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegSetValueExW",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_set_value_ex_w(
+        _hkey: HKEY,
+        _lp_value_name: PCWSTR,
+        _reserved: u32,
+        _dw_type: REG_VALUE_TYPE,
+        _lp_data: *const u8,
+        _cb_data: u32,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            let name = _lp_value_name.to_slice_until_null(MAX_KEY_LENGTH);
+            let data = _lp_data.to_slice(_cb_data as usize);
+            if let Some(result) =
+                crate::registry_virtualization::set_value(_hkey, name, _dw_type, data, true)
+            {
+                return result;
+            }
+
+            crate::call!(
+                HOOK_REG_SET_VALUE_EX_W,
+                _hkey,
+                _lp_value_name,
+                _reserved,
+                _dw_type,
+                _lp_data,
+                _cb_data,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegCloseKey",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_close_key(_hkey: HKEY) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            if let Some(result) = crate::registry_virtualization::close_key(_hkey) {
+                return result;
+            }
+
+            crate::call!(HOOK_REG_CLOSE_KEY, _hkey)
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegEnumKeyA",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_enum_key_a(
+        _hkey: HKEY,
+        _dw_index: u32,
+        _lp_name: PSTR,
+        _cch_name: u32,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            if let Some(result) = crate::registry_virtualization::enum_key(
+                _hkey, _dw_index, false, _lp_name, _cch_name,
+            ) {
+                return result
+                    .err()
+                    .unwrap_or(windows_sys::Win32::Foundation::ERROR_SUCCESS);
+            }
+
+            crate::call!(HOOK_REG_ENUM_KEY_A, _hkey, _dw_index, _lp_name, _cch_name)
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegEnumKeyW",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_enum_key_w(
+        _hkey: HKEY,
+        _dw_index: u32,
+        _lp_name: PWSTR,
+        _cch_name: u32,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            if let Some(result) = crate::registry_virtualization::enum_key(
+                _hkey,
+                _dw_index,
+                true,
+                _lp_name as *mut u8,
+                _cch_name,
+            ) {
+                return result
+                    .err()
+                    .unwrap_or(windows_sys::Win32::Foundation::ERROR_SUCCESS);
+            }
+
+            crate::call!(HOOK_REG_ENUM_KEY_W, _hkey, _dw_index, _lp_name, _cch_name)
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegEnumKeyExA",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_enum_key_ex_a(
+        _hkey: HKEY,
+        _dw_index: u32,
+        _lp_name: PSTR,
+        _lpcch_name: *mut u32,
+        _lp_reserved: *const u32,
+        _lp_class: PSTR,
+        _lpcch_class: *mut u32,
+        _lpft_last_write_time: *mut FILETIME,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            if !_lpcch_name.is_null()
+                && let Some(result) = crate::registry_virtualization::enum_key(
+                    _hkey,
+                    _dw_index,
+                    false,
+                    _lp_name,
+                    *_lpcch_name,
+                )
+            {
+                return finish_enum_key_ex(
+                    result,
+                    false,
+                    _lpcch_name,
+                    _lp_class as *mut u8,
+                    _lpcch_class,
+                    _lpft_last_write_time,
+                );
+            }
+
+            crate::call!(
+                HOOK_REG_ENUM_KEY_EX_A,
+                _hkey,
+                _dw_index,
+                _lp_name,
+                _lpcch_name,
+                _lp_reserved,
+                _lp_class,
+                _lpcch_class,
+                _lpft_last_write_time,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "advapi32.dll",
+        symbol = "RegEnumKeyExW",
+        fallback = "windows_sys::Win32::Foundation::ERROR_INVALID_FUNCTION"
+    )]
+    unsafe fn reg_enum_key_ex_w(
+        _hkey: HKEY,
+        _dw_index: u32,
+        _lp_name: PWSTR,
+        _lpcch_name: *mut u32,
+        _lp_reserved: *const u32,
+        _lp_class: PWSTR,
+        _lpcch_class: *mut u32,
+        _lpft_last_write_time: *mut FILETIME,
+    ) -> WIN32_ERROR {
+        #[cfg(not(feature = "registry_virtualization"))]
+        unimplemented!();
+
+        #[cfg(feature = "registry_virtualization")]
+        unsafe {
+            if !_lpcch_name.is_null()
+                && let Some(result) = crate::registry_virtualization::enum_key(
+                    _hkey,
+                    _dw_index,
+                    true,
+                    _lp_name as *mut u8,
+                    *_lpcch_name,
+                )
+            {
+                return finish_enum_key_ex(
+                    result,
+                    true,
+                    _lpcch_name,
+                    _lp_class as *mut u8,
+                    _lpcch_class,
+                    _lpft_last_write_time,
+                );
+            }
+
+            crate::call!(
+                HOOK_REG_ENUM_KEY_EX_W,
+                _hkey,
+                _dw_index,
+                _lp_name,
+                _lpcch_name,
+                _lp_reserved,
+                _lp_class,
+                _lpcch_class,
+                _lpft_last_write_time,
+            )
+        }
+    }
+}
+
+/// 写入虚拟键`RegEnumKeyEx`的输出参数，虚拟键没有类名与修改时间
+#[cfg(feature = "registry_virtualization")]
+unsafe fn finish_enum_key_ex(
+    result: Result<u32, WIN32_ERROR>,
+    wide: bool,
+    lpcch_name: *mut u32,
+    lp_class: *mut u8,
+    lpcch_class: *mut u32,
+    lpft_last_write_time: *mut FILETIME,
+) -> WIN32_ERROR {
+    let len = match result {
+        Ok(len) => len,
+        Err(code) => return code,
+    };
+
+    unsafe {
+        *lpcch_name = len;
+        if !lp_class.is_null() && !lpcch_class.is_null() && *lpcch_class > 0 {
+            match wide {
+                true => *(lp_class as *mut u16) = 0,
+                false => *lp_class = 0,
+            }
+        }
+        if !lpcch_class.is_null() {
+            *lpcch_class = 0;
+        }
+        if !lpft_last_write_time.is_null() {
+            *lpft_last_write_time = core::mem::zeroed();
+        }
+    }
+
+    windows_sys::Win32::Foundation::ERROR_SUCCESS
+}
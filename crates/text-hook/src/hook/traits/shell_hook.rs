@@ -0,0 +1,194 @@
+use translate_macros::detour_trait;
+use windows_sys::{
+    Win32::Foundation::{HANDLE, HWND},
+    core::{BOOL, GUID, HRESULT, PSTR, PWSTR},
+};
+
+/// Shell文件夹路径相关的钩子
+#[detour_trait]
+pub trait ShellHook: Send + Sync + 'static {
+    #[detour(
+        dll = "shell32.dll",
+        symbol = "SHGetFolderPathA",
+        fallback = "windows_sys::Win32::Foundation::E_FAIL"
+    )]
+    unsafe fn sh_get_folder_path_a(
+        _hwnd: HWND,
+        _csidl: i32,
+        _h_token: HANDLE,
+        _dw_flags: u32,
+        _psz_path: PSTR,
+    ) -> HRESULT {
+        #[cfg(not(feature = "save_redirect"))]
+        unimplemented!();
+
+        #[cfg(feature = "save_redirect")]
+        unsafe {
+            use crate::save_redirect::{KnownFolder, folder_target, write_path_a};
+
+            if !_psz_path.is_null()
+                && let Some(path) = KnownFolder::from_csidl(_csidl).and_then(folder_target)
+                && write_path_a(&path, _psz_path)
+            {
+                return windows_sys::Win32::Foundation::S_OK;
+            }
+
+            crate::call!(
+                HOOK_SH_GET_FOLDER_PATH_A,
+                _hwnd,
+                _csidl,
+                _h_token,
+                _dw_flags,
+                _psz_path,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "shell32.dll",
+        symbol = "SHGetFolderPathW",
+        fallback = "windows_sys::Win32::Foundation::E_FAIL"
+    )]
+    unsafe fn sh_get_folder_path_w(
+        _hwnd: HWND,
+        _csidl: i32,
+        _h_token: HANDLE,
+        _dw_flags: u32,
+        _psz_path: PWSTR,
+    ) -> HRESULT {
+        #[cfg(not(feature = "save_redirect"))]
+        unimplemented!();
+
+        #[cfg(feature = "save_redirect")]
+        unsafe {
+            use crate::save_redirect::{KnownFolder, folder_target, write_path_w};
+
+            if !_psz_path.is_null()
+                && let Some(path) = KnownFolder::from_csidl(_csidl).and_then(folder_target)
+                && write_path_w(&path, _psz_path)
+            {
+                return windows_sys::Win32::Foundation::S_OK;
+            }
+
+            crate::call!(
+                HOOK_SH_GET_FOLDER_PATH_W,
+                _hwnd,
+                _csidl,
+                _h_token,
+                _dw_flags,
+                _psz_path,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "shell32.dll",
+        symbol = "SHGetSpecialFolderPathA",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn sh_get_special_folder_path_a(
+        _hwnd: HWND,
+        _psz_path: PSTR,
+        _csidl: i32,
+        _f_create: BOOL,
+    ) -> BOOL {
+        #[cfg(not(feature = "save_redirect"))]
+        unimplemented!();
+
+        #[cfg(feature = "save_redirect")]
+        unsafe {
+            use crate::save_redirect::{KnownFolder, folder_target, write_path_a};
+
+            if !_psz_path.is_null()
+                && let Some(path) = KnownFolder::from_csidl(_csidl).and_then(folder_target)
+                && write_path_a(&path, _psz_path)
+            {
+                return windows_sys::Win32::Foundation::TRUE;
+            }
+
+            crate::call!(
+                HOOK_SH_GET_SPECIAL_FOLDER_PATH_A,
+                _hwnd,
+                _psz_path,
+                _csidl,
+                _f_create,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "shell32.dll",
+        symbol = "SHGetSpecialFolderPathW",
+        fallback = "windows_sys::Win32::Foundation::FALSE"
+    )]
+    unsafe fn sh_get_special_folder_path_w(
+        _hwnd: HWND,
+        _psz_path: PWSTR,
+        _csidl: i32,
+        _f_create: BOOL,
+    ) -> BOOL {
+        #[cfg(not(feature = "save_redirect"))]
+        unimplemented!();
+
+        #[cfg(feature = "save_redirect")]
+        unsafe {
+            use crate::save_redirect::{KnownFolder, folder_target, write_path_w};
+
+            if !_psz_path.is_null()
+                && let Some(path) = KnownFolder::from_csidl(_csidl).and_then(folder_target)
+                && write_path_w(&path, _psz_path)
+            {
+                return windows_sys::Win32::Foundation::TRUE;
+            }
+
+            crate::call!(
+                HOOK_SH_GET_SPECIAL_FOLDER_PATH_W,
+                _hwnd,
+                _psz_path,
+                _csidl,
+                _f_create,
+            )
+        }
+    }
+
+    #[detour(
+        dll = "shell32.dll",
+        symbol = "SHGetKnownFolderPath",
+        fallback = "windows_sys::Win32::Foundation::E_FAIL"
+    )]
+    unsafe fn sh_get_known_folder_path(
+        _rfid: *const GUID,
+        _dw_flags: i32,
+        _h_token: HANDLE,
+        _ppsz_path: *mut PWSTR,
+    ) -> HRESULT {
+        #[cfg(not(feature = "save_redirect"))]
+        unimplemented!();
+
+        #[cfg(feature = "save_redirect")]
+        unsafe {
+            use crate::save_redirect::{KnownFolder, alloc_path_w, folder_target};
+
+            if !_rfid.is_null()
+                && !_ppsz_path.is_null()
+                && let Some(path) = KnownFolder::from_id(&*_rfid).and_then(folder_target)
+            {
+                let buffer = alloc_path_w(&path);
+                *_ppsz_path = buffer;
+                return if buffer.is_null() {
+                    windows_sys::Win32::Foundation::E_OUTOFMEMORY
+                } else {
+                    windows_sys::Win32::Foundation::S_OK
+                };
+            }
+
+            crate::call!(
+                HOOK_SH_GET_KNOWN_FOLDER_PATH,
+                _rfid,
+                _dw_flags,
+                _h_token,
+                _ppsz_path,
+            )
+        }
+    }
+}
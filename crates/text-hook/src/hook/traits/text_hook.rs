@@ -643,7 +643,7 @@ fn get_byte_len(ptr: *const u8, chars: usize) -> usize {
     #[cfg(not(feature = "text_out_arg_c_is_bytes"))]
     {
         use crate::{code_cvt::byte_len, constant::ANSI_CODE_PAGE};
-        byte_len(ptr, chars, ANSI_CODE_PAGE as u16)
+        byte_len(ptr, chars, ANSI_CODE_PAGE)
     }
 
     #[cfg(feature = "text_out_arg_c_is_bytes")]
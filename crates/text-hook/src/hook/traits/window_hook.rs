@@ -2,7 +2,8 @@ use translate_macros::detour_trait;
 use windows_sys::Win32::{
     Foundation::{HWND, LPARAM, LRESULT, WPARAM},
     UI::WindowsAndMessaging::{
-        CREATESTRUCTA, CREATESTRUCTW, GetParent, HMENU, WM_NCCREATE, WM_SETTEXT,
+        CREATESTRUCTA, CREATESTRUCTW, GetParent, HMENU, MSGBOXPARAMSA, MSGBOXPARAMSW, WM_NCCREATE,
+        WM_SETTEXT,
     },
 };
 use windows_sys::core::BOOL;
@@ -239,6 +240,20 @@ pub trait WindowHook: Send + Sync + 'static {
         lp_caption: *const u8,
         u_type: u32,
     ) -> i32 {
+        #[cfg(feature = "message_box_rules")]
+        unsafe {
+            use crate::message_box_rules::{decode_a, handle};
+
+            if let Some(result) = handle(
+                &decode_a(lp_text),
+                &decode_a(lp_caption),
+                u_type,
+                |text, caption| crate::call!(HOOK_MESSAGE_BOX_W, h_wnd, text, caption, u_type),
+            ) {
+                return result;
+            }
+        }
+
         #[cfg(feature = "text_patch")]
         unsafe {
             if lp_text.is_null() && lp_caption.is_null() {
@@ -289,6 +304,281 @@ pub trait WindowHook: Send + Sync + 'static {
         unsafe { crate::call!(HOOK_MESSAGE_BOX_A, h_wnd, lp_text, lp_caption, u_type) }
     }
 
+    #[detour(dll = "user32.dll", symbol = "MessageBoxW", fallback = "0")]
+    unsafe fn message_box_w(
+        h_wnd: HWND,
+        lp_text: *const u16,
+        lp_caption: *const u16,
+        u_type: u32,
+    ) -> i32 {
+        #[cfg(feature = "message_box_rules")]
+        unsafe {
+            use crate::message_box_rules::{decode_w, handle};
+
+            if let Some(result) = handle(
+                &decode_w(lp_text),
+                &decode_w(lp_caption),
+                u_type,
+                |text, caption| crate::call!(HOOK_MESSAGE_BOX_W, h_wnd, text, caption, u_type),
+            ) {
+                return result;
+            }
+        }
+
+        unsafe { crate::call!(HOOK_MESSAGE_BOX_W, h_wnd, lp_text, lp_caption, u_type) }
+    }
+
+    #[detour(dll = "user32.dll", symbol = "MessageBoxExA", fallback = "0")]
+    unsafe fn message_box_ex_a(
+        h_wnd: HWND,
+        lp_text: *const u8,
+        lp_caption: *const u8,
+        u_type: u32,
+        w_language_id: u16,
+    ) -> i32 {
+        #[cfg(feature = "message_box_rules")]
+        unsafe {
+            use crate::message_box_rules::{decode_a, handle};
+
+            if let Some(result) = handle(
+                &decode_a(lp_text),
+                &decode_a(lp_caption),
+                u_type,
+                |text, caption| {
+                    crate::call!(
+                        HOOK_MESSAGE_BOX_EX_W,
+                        h_wnd,
+                        text,
+                        caption,
+                        u_type,
+                        w_language_id
+                    )
+                },
+            ) {
+                return result;
+            }
+        }
+
+        unsafe {
+            crate::call!(
+                HOOK_MESSAGE_BOX_EX_A,
+                h_wnd,
+                lp_text,
+                lp_caption,
+                u_type,
+                w_language_id
+            )
+        }
+    }
+
+    #[detour(dll = "user32.dll", symbol = "MessageBoxExW", fallback = "0")]
+    unsafe fn message_box_ex_w(
+        h_wnd: HWND,
+        lp_text: *const u16,
+        lp_caption: *const u16,
+        u_type: u32,
+        w_language_id: u16,
+    ) -> i32 {
+        #[cfg(feature = "message_box_rules")]
+        unsafe {
+            use crate::message_box_rules::{decode_w, handle};
+
+            if let Some(result) = handle(
+                &decode_w(lp_text),
+                &decode_w(lp_caption),
+                u_type,
+                |text, caption| {
+                    crate::call!(
+                        HOOK_MESSAGE_BOX_EX_W,
+                        h_wnd,
+                        text,
+                        caption,
+                        u_type,
+                        w_language_id
+                    )
+                },
+            ) {
+                return result;
+            }
+        }
+
+        unsafe {
+            crate::call!(
+                HOOK_MESSAGE_BOX_EX_W,
+                h_wnd,
+                lp_text,
+                lp_caption,
+                u_type,
+                w_language_id
+            )
+        }
+    }
+
+    #[detour(dll = "user32.dll", symbol = "MessageBoxIndirectA", fallback = "0")]
+    unsafe fn message_box_indirect_a(lpmbp: *const MSGBOXPARAMSA) -> i32 {
+        #[cfg(feature = "message_box_rules")]
+        unsafe {
+            use crate::message_box_rules::{decode_a, handle};
+            use windows_sys::Win32::Foundation::MAX_PATH;
+
+            if let Some(params) = lpmbp.as_ref()
+                && let Some(result) = handle(
+                    &decode_a(params.lpszText),
+                    &decode_a(params.lpszCaption),
+                    params.dwStyle,
+                    |text, caption| {
+                        // 图标为资源名时同样需要转换为宽字符串
+                        let icon = ((params.lpszIcon as usize) > u16::MAX as usize).then(|| {
+                            params
+                                .lpszIcon
+                                .to_slice_until_null(MAX_PATH as usize)
+                                .to_wide_null_ansi()
+                        });
+                        let params_w = MSGBOXPARAMSW {
+                            cbSize: size_of::<MSGBOXPARAMSW>() as u32,
+                            hwndOwner: params.hwndOwner,
+                            hInstance: params.hInstance,
+                            lpszText: text,
+                            lpszCaption: caption,
+                            dwStyle: params.dwStyle,
+                            lpszIcon: icon
+                                .as_ref()
+                                .map_or(params.lpszIcon as *const u16, |icon| icon.as_ptr()),
+                            dwContextHelpId: params.dwContextHelpId,
+                            lpfnMsgBoxCallback: params.lpfnMsgBoxCallback,
+                            dwLanguageId: params.dwLanguageId,
+                        };
+                        crate::call!(HOOK_MESSAGE_BOX_INDIRECT_W, &params_w)
+                    },
+                )
+            {
+                return result;
+            }
+        }
+
+        unsafe { crate::call!(HOOK_MESSAGE_BOX_INDIRECT_A, lpmbp) }
+    }
+
+    #[detour(dll = "user32.dll", symbol = "MessageBoxIndirectW", fallback = "0")]
+    unsafe fn message_box_indirect_w(lpmbp: *const MSGBOXPARAMSW) -> i32 {
+        #[cfg(feature = "message_box_rules")]
+        unsafe {
+            use crate::message_box_rules::{decode_w, handle};
+
+            if let Some(params) = lpmbp.as_ref()
+                && let Some(result) = handle(
+                    &decode_w(params.lpszText),
+                    &decode_w(params.lpszCaption),
+                    params.dwStyle,
+                    |text, caption| {
+                        let params_w = MSGBOXPARAMSW {
+                            lpszText: text,
+                            lpszCaption: caption,
+                            ..*params
+                        };
+                        crate::call!(HOOK_MESSAGE_BOX_INDIRECT_W, &params_w)
+                    },
+                )
+            {
+                return result;
+            }
+        }
+
+        unsafe { crate::call!(HOOK_MESSAGE_BOX_INDIRECT_W, lpmbp) }
+    }
+
+    #[detour(dll = "user32.dll", symbol = "MessageBoxTimeoutA", fallback = "0")]
+    unsafe fn message_box_timeout_a(
+        h_wnd: HWND,
+        lp_text: *const u8,
+        lp_caption: *const u8,
+        u_type: u32,
+        w_language_id: u16,
+        dw_milliseconds: u32,
+    ) -> i32 {
+        #[cfg(feature = "message_box_rules")]
+        unsafe {
+            use crate::message_box_rules::{decode_a, handle};
+
+            if let Some(result) = handle(
+                &decode_a(lp_text),
+                &decode_a(lp_caption),
+                u_type,
+                |text, caption| {
+                    crate::call!(
+                        HOOK_MESSAGE_BOX_TIMEOUT_W,
+                        h_wnd,
+                        text,
+                        caption,
+                        u_type,
+                        w_language_id,
+                        dw_milliseconds
+                    )
+                },
+            ) {
+                return result;
+            }
+        }
+
+        unsafe {
+            crate::call!(
+                HOOK_MESSAGE_BOX_TIMEOUT_A,
+                h_wnd,
+                lp_text,
+                lp_caption,
+                u_type,
+                w_language_id,
+                dw_milliseconds
+            )
+        }
+    }
+
+    #[detour(dll = "user32.dll", symbol = "MessageBoxTimeoutW", fallback = "0")]
+    unsafe fn message_box_timeout_w(
+        h_wnd: HWND,
+        lp_text: *const u16,
+        lp_caption: *const u16,
+        u_type: u32,
+        w_language_id: u16,
+        dw_milliseconds: u32,
+    ) -> i32 {
+        #[cfg(feature = "message_box_rules")]
+        unsafe {
+            use crate::message_box_rules::{decode_w, handle};
+
+            if let Some(result) = handle(
+                &decode_w(lp_text),
+                &decode_w(lp_caption),
+                u_type,
+                |text, caption| {
+                    crate::call!(
+                        HOOK_MESSAGE_BOX_TIMEOUT_W,
+                        h_wnd,
+                        text,
+                        caption,
+                        u_type,
+                        w_language_id,
+                        dw_milliseconds
+                    )
+                },
+            ) {
+                return result;
+            }
+        }
+
+        unsafe {
+            crate::call!(
+                HOOK_MESSAGE_BOX_TIMEOUT_W,
+                h_wnd,
+                lp_text,
+                lp_caption,
+                u_type,
+                w_language_id,
+                dw_milliseconds
+            )
+        }
+    }
+
     #[detour(dll = "user32.dll", symbol = "SetDlgItemTextA", fallback = "0")]
     unsafe fn set_dlg_item_text_a(h_dlg: HWND, n_id_dlg_item: i32, lp_string: *const u8) -> BOOL {
         #[cfg(feature = "text_patch")]
@@ -0,0 +1,511 @@
+//! INI 虚拟化
+//!
+//! 规则来自`assets/ini_overrides.json`，按顺序匹配，使用第一个命中的规则：
+//!
+//! - `value`：键直接返回固定的值，写入会被忽略
+//! - `redirect`：命中的读写都作用于另一个 INI 文件（相对路径相对于exe目录），文件不存在时会在写入时创建
+//!
+//! 枚举节名或键名、读取整个节时，`value`规则中不含通配符的节名与键名会被合并到结果中。
+//! 返回值以及缓冲区不足时的截断方式与 Win32 一致（见`crate::utils::profile`）
+
+use std::path::PathBuf;
+
+use crate::debug;
+use crate::hook::traits::profile_hook::{
+    HOOK_GET_PRIVATE_PROFILE_INT_W, HOOK_GET_PRIVATE_PROFILE_SECTION_W,
+    HOOK_GET_PRIVATE_PROFILE_STRING_W, HOOK_WRITE_PRIVATE_PROFILE_STRING_W,
+};
+use crate::utils::exts::path_ext::PathExt;
+use crate::utils::exts::ptr_ext::PtrExt;
+use crate::utils::profile::{parse_profile_int, write_profile_list, write_profile_string};
+
+/// 命中规则后的处理方式
+#[derive(Debug, Clone, Copy)]
+pub enum IniAction {
+    /// 固定的值
+    Value(&'static str),
+    /// 重定向到的 INI 文件
+    Redirect(&'static str),
+}
+
+/// 配置中的一条规则
+#[derive(Debug, Clone, Copy)]
+pub struct IniRule {
+    /// INI 文件的匹配模式，不含路径分隔符时只匹配文件名
+    pub file: &'static str,
+    /// 节名的匹配模式，为`None`时匹配任意节
+    pub section: Option<&'static str>,
+    /// 键名的匹配模式，为`None`时匹配任意键
+    pub key: Option<&'static str>,
+    pub action: IniAction,
+}
+
+static RULES: &[IniRule] = translate_macros::generate_ini_overrides!("assets/ini_overrides.json");
+
+/// 读取的结果
+#[derive(Debug, Clone)]
+pub enum Profile {
+    /// 单个字符串
+    String(String),
+    /// 节名、键名或`键=值`的列表
+    List(Vec<String>),
+}
+
+/// 大小写不敏感地匹配通配符，`*`匹配任意个字符，`?`匹配单个字符
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+
+    let (mut p, mut t) = (0, 0);
+    // 最近一个`*`的位置，以及它当前匹配到的文本位置
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// INI 文件是否匹配模式，模式不含路径分隔符时只比较文件名
+pub fn file_matches(pattern: &str, file: &str) -> bool {
+    let pattern = pattern.replace('/', "\\");
+    let file = file.replace('/', "\\");
+
+    if pattern.contains('\\') {
+        wildcard_match(&pattern, &file)
+    } else {
+        wildcard_match(&pattern, file.rsplit('\\').next().unwrap_or_default())
+    }
+}
+
+/// 可选的模式是否匹配可选的名称，模式为`None`时匹配任意名称（包括`None`）
+fn name_matches(pattern: Option<&str>, name: Option<&str>) -> bool {
+    match (pattern, name) {
+        (None, _) => true,
+        (Some(pattern), Some(name)) => wildcard_match(pattern, name),
+        (Some(_), None) => false,
+    }
+}
+
+/// 查找第一个命中的规则
+pub fn find_rule(
+    rules: &'static [IniRule],
+    file: &str,
+    section: Option<&str>,
+    key: Option<&str>,
+) -> Option<&'static IniRule> {
+    rules.iter().find(|rule| {
+        file_matches(rule.file, file)
+            && name_matches(rule.section, section)
+            && name_matches(rule.key, key)
+    })
+}
+
+/// 由`value`规则补充的名称，`section`为`None`时为节名，否则为该节中的键名
+///
+/// 只包含不含通配符的名称
+pub fn extra_names(rules: &[IniRule], file: &str, section: Option<&str>) -> Vec<&'static str> {
+    let is_literal = |name: &str| !name.contains(['*', '?']);
+
+    let mut names: Vec<&'static str> = Vec::new();
+    let candidates = rules
+        .iter()
+        .filter(|rule| matches!(rule.action, IniAction::Value(_)) && file_matches(rule.file, file))
+        .filter_map(|rule| match section {
+            None => rule.section,
+            Some(section) => {
+                let section_matches = rule.section.is_some_and(|s| wildcard_match(s, section));
+                section_matches.then_some(rule.key).flatten()
+            }
+        })
+        .filter(|name| is_literal(name));
+
+    for name in candidates {
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// 合并名称列表，不区分 ASCII 大小写去重，保留原有的顺序
+fn merge_names(mut names: Vec<String>, extra: &[&str]) -> Vec<String> {
+    for name in extra {
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+fn redirect_path(target: &str) -> PathBuf {
+    crate::utils::get_executable_dir().join(target)
+}
+
+fn to_wide_null(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain([0]).collect()
+}
+
+fn optional_ptr(s: &Option<Vec<u16>>) -> *const u16 {
+    s.as_ref().map_or(core::ptr::null(), |s| s.as_ptr())
+}
+
+/// 反复调用`read`直到缓冲区足够大，`read`与 Win32 一样返回写入的字符数
+fn read_until_fit(list: bool, read: impl Fn(&mut [u16]) -> u32) -> Vec<u16> {
+    let mut size = 1024;
+    loop {
+        let mut buffer = vec![0u16; size];
+        let len = read(&mut buffer) as usize;
+        let truncated = if list {
+            len + 2 >= size
+        } else {
+            len + 1 >= size
+        };
+
+        if !truncated || size >= 1 << 20 {
+            buffer.truncate(len);
+            return buffer;
+        }
+        size *= 2;
+    }
+}
+
+fn split_list(data: &[u16]) -> Vec<String> {
+    data.split(|c| *c == 0)
+        .filter(|s| !s.is_empty())
+        .map(String::from_utf16_lossy)
+        .collect()
+}
+
+/// 通过原始的`GetPrivateProfileStringW`读取，`list`为`true`时表示枚举节名或键名
+fn original_string(
+    app: Option<&str>,
+    key: Option<&str>,
+    default: &str,
+    file: &[u16],
+    list: bool,
+) -> Vec<u16> {
+    let app = app.map(to_wide_null);
+    let key = key.map(to_wide_null);
+    let default = to_wide_null(default);
+
+    read_until_fit(list, |buffer| unsafe {
+        crate::call!(
+            HOOK_GET_PRIVATE_PROFILE_STRING_W,
+            optional_ptr(&app),
+            optional_ptr(&key),
+            default.as_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len() as u32,
+            file.as_ptr(),
+        )
+    })
+}
+
+/// 通过原始的`GetPrivateProfileSectionW`读取整个节
+fn original_section(app: &str, file: &[u16]) -> Vec<String> {
+    let app = to_wide_null(app);
+    split_list(&read_until_fit(true, |buffer| unsafe {
+        crate::call!(
+            HOOK_GET_PRIVATE_PROFILE_SECTION_W,
+            app.as_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len() as u32,
+            file.as_ptr(),
+        )
+    }))
+}
+
+/// 读取键的值，命中`redirect`时从重定向的文件中读取，没有命中规则时为`None`
+fn resolve_value(file: &str, app: &str, key: &str) -> Option<String> {
+    match find_rule(RULES, file, Some(app), Some(key))?.action {
+        IniAction::Value(value) => Some(value.to_string()),
+        IniAction::Redirect(target) => {
+            let target = redirect_path(target).to_wide_null();
+            let value = original_string(Some(app), Some(key), "", &target, false);
+            Some(String::from_utf16_lossy(&value))
+        }
+    }
+}
+
+/// 处理`GetPrivateProfileString`，返回`None`时应调用原函数
+///
+/// `app`为`None`时枚举节名，`key`为`None`时枚举该节中的键名
+pub fn get_string(
+    app: Option<&str>,
+    key: Option<&str>,
+    default: &str,
+    file: &str,
+) -> Option<Profile> {
+    if RULES.is_empty() {
+        return None;
+    }
+
+    // 枚举节名时忽略键名
+    let key = app.and(key);
+
+    if let Some(app) = app
+        && let Some(key) = key
+    {
+        let rule = find_rule(RULES, file, Some(app), Some(key))?;
+        debug!("INI [{app}] {key} in {file} matched rule {rule:?}");
+
+        let value = match rule.action {
+            IniAction::Value(value) => value.to_string(),
+            IniAction::Redirect(target) => {
+                let target = redirect_path(target).to_wide_null();
+                let value = original_string(Some(app), Some(key), default, &target, false);
+                String::from_utf16_lossy(&value)
+            }
+        };
+        return Some(Profile::String(value));
+    }
+
+    if let Some(rule) = find_rule(RULES, file, app, None)
+        && let IniAction::Redirect(target) = rule.action
+    {
+        let target = redirect_path(target).to_wide_null();
+        let names = original_string(app, None, default, &target, true);
+        return Some(Profile::List(split_list(&names)));
+    }
+
+    let extra = extra_names(RULES, file, app);
+    if extra.is_empty() {
+        return None;
+    }
+
+    let names = original_string(app, None, default, &to_wide_null(file), true);
+    Some(Profile::List(merge_names(split_list(&names), &extra)))
+}
+
+/// 处理`GetPrivateProfileInt`，返回`None`时应调用原函数
+pub fn get_int(app: &str, key: &str, default: i32, file: &str) -> Option<u32> {
+    if RULES.is_empty() {
+        return None;
+    }
+
+    let rule = find_rule(RULES, file, Some(app), Some(key))?;
+    debug!("INI [{app}] {key} in {file} matched rule {rule:?}");
+
+    Some(match rule.action {
+        IniAction::Value(value) => parse_profile_int(value).unwrap_or(default as u32),
+        IniAction::Redirect(target) => unsafe {
+            let target = redirect_path(target).to_wide_null();
+            crate::call!(
+                HOOK_GET_PRIVATE_PROFILE_INT_W,
+                to_wide_null(app).as_ptr(),
+                to_wide_null(key).as_ptr(),
+                default,
+                target.as_ptr(),
+            )
+        },
+    })
+}
+
+/// 处理`GetPrivateProfileSection`，返回`None`时应调用原函数
+pub fn get_section(app: &str, file: &str) -> Option<Profile> {
+    if RULES.is_empty() {
+        return None;
+    }
+
+    if let Some(rule) = find_rule(RULES, file, Some(app), None)
+        && let IniAction::Redirect(target) = rule.action
+    {
+        let target = redirect_path(target).to_wide_null();
+        return Some(Profile::List(original_section(app, &target)));
+    }
+
+    let extra = extra_names(RULES, file, Some(app));
+    if extra.is_empty() {
+        return None;
+    }
+
+    let mut lines: Vec<String> = original_section(app, &to_wide_null(file))
+        .into_iter()
+        .map(|line| {
+            let Some((key, _)) = line.split_once('=') else {
+                return line;
+            };
+            match resolve_value(file, app, key.trim()) {
+                Some(value) => format!("{key}={value}"),
+                None => line,
+            }
+        })
+        .collect();
+
+    for key in extra {
+        let exists = lines.iter().any(|line| {
+            line.split_once('=')
+                .is_some_and(|(k, _)| k.trim().eq_ignore_ascii_case(key))
+        });
+        if !exists && let Some(value) = resolve_value(file, app, key) {
+            lines.push(format!("{key}={value}"));
+        }
+    }
+
+    Some(Profile::List(lines))
+}
+
+/// 处理`WritePrivateProfileString`，返回`None`时应调用原函数
+///
+/// `key`为`None`时删除整个节，`value`为`None`时删除该键
+pub fn write_string(app: &str, key: Option<&str>, value: Option<&str>, file: &str) -> Option<bool> {
+    if RULES.is_empty() {
+        return None;
+    }
+
+    let rule = find_rule(RULES, file, Some(app), key)?;
+    match rule.action {
+        IniAction::Value(_) => {
+            debug!("Ignore write to overridden INI [{app}] {key:?} in {file}");
+            Some(true)
+        }
+        IniAction::Redirect(target) => {
+            let target = redirect_path(target);
+            if let Some(parent) = target.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+
+            debug!("Redirect INI write [{app}] {key:?} -> {}", target.display());
+            let key = key.map(to_wide_null);
+            let value = value.map(to_wide_null);
+            let result = unsafe {
+                crate::call!(
+                    HOOK_WRITE_PRIVATE_PROFILE_STRING_W,
+                    to_wide_null(app).as_ptr(),
+                    optional_ptr(&key),
+                    optional_ptr(&value),
+                    target.to_wide_null().as_ptr(),
+                )
+            };
+            Some(result != 0)
+        }
+    }
+}
+
+/// 将结果写入调用者的缓冲区，返回值与 Win32 一致
+///
+/// # Safety
+/// `buffer`必须可以写入`size`个字符
+pub unsafe fn write_result<T: Copy + Default + PartialEq>(
+    profile: &Profile,
+    buffer: *mut T,
+    size: u32,
+    encode: impl Fn(&str) -> Vec<T>,
+) -> u32 {
+    if buffer.is_null() {
+        return 0;
+    }
+
+    let buffer = unsafe { buffer.to_slice_mut(size as usize) };
+    match profile {
+        Profile::String(value) => write_profile_string(&encode(value), buffer),
+        Profile::List(items) => {
+            let items: Vec<Vec<T>> = items.iter().map(|item| encode(item)).collect();
+            write_profile_list(&items, buffer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TEST_RULES: &[IniRule] = &[
+        IniRule {
+            file: "config.ini",
+            section: Some("Video"),
+            key: Some("Width"),
+            action: IniAction::Value("1280"),
+        },
+        IniRule {
+            file: "config.ini",
+            section: Some("Video"),
+            key: None,
+            action: IniAction::Redirect("video.ini"),
+        },
+        IniRule {
+            file: "*\\save\\*.ini",
+            section: None,
+            key: None,
+            action: IniAction::Redirect("save.ini"),
+        },
+        IniRule {
+            file: "*.ini",
+            section: Some("Sound*"),
+            key: Some("?olume"),
+            action: IniAction::Value("0"),
+        },
+    ];
+
+    #[test]
+    fn wildcard() {
+        assert!(wildcard_match("", ""));
+        assert!(!wildcard_match("", "a"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("a?c", "ABC"));
+        assert!(!wildcard_match("a?c", "ac"));
+        assert!(wildcard_match("*.ini", "Config.INI"));
+        assert!(!wildcard_match("*.ini", "config.ini.bak"));
+        assert!(wildcard_match("a*b*c", "aXbYbZc"));
+        assert!(!wildcard_match("a*b*c", "aXbYbZ"));
+        assert!(wildcard_match("**a", "bba"));
+        assert!(wildcard_match("设定*", "设定文件"));
+    }
+
+    #[test]
+    fn file_pattern() {
+        assert!(file_matches("config.ini", "C:\\Game\\CONFIG.ini"));
+        assert!(file_matches("config.ini", "C:/Game/config.ini"));
+        assert!(!file_matches("config.ini", "C:\\Game\\config.ini\\x.ini"));
+        assert!(file_matches("*\\save\\*.ini", "C:\\Game\\Save\\1.ini"));
+        assert!(!file_matches("*\\save\\*.ini", "C:\\Game\\1.ini"));
+    }
+
+    #[test]
+    fn find_first_matching_rule() {
+        let index = |file, section, key| {
+            let rule = find_rule(TEST_RULES, file, section, key)?;
+            TEST_RULES.iter().position(|r| core::ptr::eq(r, rule))
+        };
+
+        assert_eq!(
+            index("C:\\Game\\config.ini", Some("video"), Some("WIDTH")),
+            Some(0)
+        );
+        assert_eq!(
+            index("C:\\Game\\config.ini", Some("Video"), Some("Height")),
+            Some(1)
+        );
+        // 枚举节中的键时键名为`None`，只有不限定键名的规则能命中
+        assert_eq!(index("C:\\Game\\config.ini", Some("Video"), None), Some(1));
+        assert_eq!(index("C:\\Game\\config.ini", None, None), None);
+        assert_eq!(
+            index("C:\\Game\\config.ini", Some("Sound2"), Some("Volume")),
+            Some(3)
+        );
+        assert_eq!(
+            index("C:\\Game\\config.ini", Some("Sound"), Some("Volumes")),
+            None
+        );
+        assert_eq!(index("C:\\Game\\Save\\1.ini", None, None), Some(2));
+        assert_eq!(
+            index("C:\\Game\\other.txt", Some("Video"), Some("Width")),
+            None
+        );
+    }
+}
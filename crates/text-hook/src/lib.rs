@@ -26,6 +26,27 @@ pub(crate) mod win_event_hook;
 #[cfg(feature = "patch")]
 pub(crate) mod patch;
 
+#[cfg(feature = "file_mapping_patch_impl")]
+pub(crate) mod file_mapping_patch;
+
+#[cfg(feature = "crt_file_patch_impl")]
+pub(crate) mod crt_file_patch;
+
+#[cfg(feature = "redirect_rules")]
+pub(crate) mod redirect_rules;
+
+#[cfg(feature = "save_redirect")]
+pub(crate) mod save_redirect;
+
+#[cfg(feature = "registry_virtualization")]
+pub(crate) mod registry_virtualization;
+
+#[cfg(feature = "ini_virtualization")]
+pub(crate) mod ini_virtualization;
+
+#[cfg(feature = "message_box_rules")]
+pub(crate) mod message_box_rules;
+
 #[cfg(feature = "custom_font")]
 pub(crate) mod custom_font;
 
@@ -38,6 +59,9 @@ pub(crate) mod dll_hijacking;
 #[cfg(feature = "locale_emulator")]
 pub(crate) mod locale_emulator;
 
+#[cfg(feature = "locale_emulator_lite")]
+pub(crate) mod locale_emulator_lite;
+
 #[cfg(feature = "resource_pack")]
 pub(crate) mod resource_pack;
 
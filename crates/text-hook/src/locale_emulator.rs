@@ -1,21 +1,16 @@
 use core::ffi::c_void;
+use core::mem::{offset_of, size_of};
 
 use windows_sys::Win32::Foundation::{ERROR_SUCCESS, SYSTEMTIME};
 use windows_sys::Win32::Globalization::GetACP;
 use windows_sys::Win32::Graphics::Gdi::LF_FACESIZE;
 use windows_sys::Win32::System::Environment::GetCommandLineW;
-use windows_sys::Win32::System::Registry::{
-    HKEY, HKEY_LOCAL_MACHINE, KEY_READ, REG_BINARY, REG_SZ, RegCloseKey, RegOpenKeyExW,
-    RegQueryValueExW,
-};
 use windows_sys::Win32::System::Threading::{
     ExitProcess, INFINITE, PROCESS_INFORMATION, STARTUPINFOW, WaitForSingleObject,
 };
-use windows_sys::core::PCWSTR;
 use windows_sys::{s, w};
 
 use crate::debug;
-use crate::utils::exts::slice_ext::ByteSliceExt;
 
 #[repr(C)]
 #[derive(Clone, Copy, Default)]
@@ -58,21 +53,13 @@ struct RtlTimeZoneInformation {
     daylight_bias: i32,
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Default)]
-struct RegTziFormat {
-    bias: i32,
-    standard_bias: i32,
-    daylight_bias: i32,
-    standard_date: SYSTEMTIME,
-    daylight_date: SYSTEMTIME,
-}
-
 #[repr(C)]
 #[derive(Clone, Copy, Default)]
 struct UnicodeString3264 {
     length: u16,
     maximum_length: u16,
+    /// 显式的填充，保证按字节复制时不会读到未初始化的内存
+    _padding: u32,
     buffer: u64,
 }
 
@@ -83,6 +70,7 @@ struct RegistryEntry64 {
     sub_key: UnicodeString3264,
     value_name: UnicodeString3264,
     data_type: u32,
+    _padding: u32,
     data: u64,
     data_size: u64,
 }
@@ -94,6 +82,7 @@ struct RegistryRedirectionEntry64 {
     redirected: RegistryEntry64,
 }
 
+/// 与 LE 的`LEB`对应，`registry_replacement`实际是变长数组，见`build_environment_block`
 #[repr(C)]
 #[derive(Clone, Copy, Default)]
 struct LocaleEmulatorEnvironmentBlock {
@@ -108,6 +97,54 @@ struct LocaleEmulatorEnvironmentBlock {
     registry_replacement: [RegistryRedirectionEntry64; 1],
 }
 
+/// 与 LE 中 C 结构的布局保持一致，LoaderDll 直接按偏移读取
+const fn check_layout() {
+    assert!(size_of::<TimeFields>() == 16);
+    assert!(size_of::<RtlTimeZoneInformation>() == 172);
+    assert!(offset_of!(RtlTimeZoneInformation, standard_start) == 68);
+    assert!(offset_of!(RtlTimeZoneInformation, daylight_name) == 88);
+    assert!(offset_of!(RtlTimeZoneInformation, daylight_bias) == 168);
+
+    assert!(size_of::<UnicodeString3264>() == 16);
+    assert!(offset_of!(UnicodeString3264, buffer) == 8);
+
+    assert!(size_of::<RegistryEntry64>() == 64);
+    assert!(offset_of!(RegistryEntry64, value_name) == 24);
+    assert!(offset_of!(RegistryEntry64, data_type) == 40);
+    assert!(offset_of!(RegistryEntry64, data) == 48);
+    assert!(offset_of!(RegistryEntry64, data_size) == 56);
+    assert!(size_of::<RegistryRedirectionEntry64>() == 128);
+
+    assert!(offset_of!(LocaleEmulatorEnvironmentBlock, default_face_name) == 20);
+    assert!(offset_of!(LocaleEmulatorEnvironmentBlock, timezone) == 84);
+    assert!(
+        offset_of!(
+            LocaleEmulatorEnvironmentBlock,
+            number_of_registry_redirection_entries
+        ) == 256
+    );
+    assert!(offset_of!(LocaleEmulatorEnvironmentBlock, registry_replacement) == 264);
+    assert!(size_of::<LocaleEmulatorEnvironmentBlock>() == 392);
+}
+
+const _: () = check_layout();
+
+/// 配置中的一个注册表重定向项，子进程读取该值时会得到这里的数据
+#[derive(Debug, Clone, Copy)]
+struct RegistryRedirection {
+    /// 预定义根键句柄的值，如`HKEY_LOCAL_MACHINE`为`0x80000002`
+    root: u32,
+    sub_key: &'static str,
+    /// 为空时表示默认值
+    value_name: &'static str,
+    data_type: u32,
+    /// 编码后的原始数据，字符串为以 null 结尾的 UTF-16LE
+    data: &'static [u8],
+}
+
+static REGISTRY_REDIRECTIONS: &[RegistryRedirection] =
+    translate_macros::generate_locale_emulator_registry!("assets/config.json");
+
 #[repr(C)]
 #[derive(Clone, Copy, Default)]
 pub(crate) struct MlProcessInformation {
@@ -129,74 +166,131 @@ type LeCreateProcessFn = unsafe extern "system" fn(
     token: isize,
 ) -> u32;
 
-fn query_reg_value_into<T: Copy>(
-    hkey: HKEY,
-    value_name: PCWSTR,
-    expected_type: u32,
-) -> crate::Result<T> {
-    let mut value: T = unsafe { core::mem::zeroed() };
-    let mut data_type: u32 = 0;
-    let mut data_size = core::mem::size_of::<T>() as u32;
+fn load_timezone_info(
+    timezone: &str,
+    leb: &mut LocaleEmulatorEnvironmentBlock,
+) -> crate::Result<()> {
+    let tzi = crate::utils::time_zone::load_time_zone_information(timezone)?;
 
-    let ret = unsafe {
-        RegQueryValueExW(
-            hkey,
-            value_name,
-            core::ptr::null_mut(),
-            &mut data_type,
-            &mut value as *mut T as *mut u8,
-            &mut data_size,
-        )
-    };
+    leb.timezone.standard_name = tzi.StandardName;
+    leb.timezone.daylight_name = tzi.DaylightName;
+    leb.timezone.bias = tzi.Bias;
+    leb.timezone.standard_bias = tzi.StandardBias;
+    // 与上游 C++ 保持一致：固定写 0
+    leb.timezone.daylight_bias = 0;
+    leb.timezone.standard_start = TimeFields::from_system_time(tzi.StandardDate);
+    leb.timezone.daylight_start = TimeFields::from_system_time(tzi.DaylightDate);
 
-    if ret != 0 {
-        crate::bail!(
-            "RegQueryValueExW failed, value={:?}, code={ret}",
-            value_name
-        );
-    }
+    Ok(())
+}
 
-    if data_type != expected_type {
-        crate::bail!(
-            "Unexpected registry value type, expected={expected_type}, actual={data_type}"
-        );
+/// 注册表重定向数据的构建器
+///
+/// 重定向数据紧跟在`LEB`的固定部分之后，依次为项数、各项以及字符串与值的数据，
+/// `UnicodeString3264::buffer`与`RegistryEntry64::data`保存的是相对于项数字段的偏移，
+/// 由 LoaderDll 在子进程中重定位
+struct RegistryBlob {
+    bytes: Vec<u8>,
+}
+
+impl RegistryBlob {
+    /// 项数与各项之后是数据区
+    fn new(count: usize) -> Self {
+        Self {
+            bytes: vec![0; size_of::<u64>() + count * size_of::<RegistryRedirectionEntry64>()],
+        }
     }
 
-    Ok(value)
-}
+    /// 追加数据并返回其偏移，按 8 字节对齐
+    fn push(&mut self, data: &[u8]) -> u64 {
+        self.bytes.resize(self.bytes.len().next_multiple_of(8), 0);
+        let offset = self.bytes.len() as u64;
+        self.bytes.extend_from_slice(data);
+        offset
+    }
 
-fn load_timezone_info(
-    timezone: &str,
-    leb: &mut LocaleEmulatorEnvironmentBlock,
-) -> crate::Result<()> {
-    let key = format!("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Time Zones\\{timezone}");
-    let key_w = key.as_bytes().to_wide_null_utf8();
-
-    let mut hkey: HKEY = core::ptr::null_mut();
-    let open_ret =
-        unsafe { RegOpenKeyExW(HKEY_LOCAL_MACHINE, key_w.as_ptr(), 0, KEY_READ, &mut hkey) };
-    if open_ret != 0 {
-        crate::bail!("RegOpenKeyExW failed for timezone '{timezone}', code={open_ret}");
+    /// 追加以 null 结尾的宽字符串
+    fn push_string(&mut self, s: &str) -> UnicodeString3264 {
+        let bytes: Vec<u8> = s
+            .encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let length = (bytes.len() - 2) as u16;
+
+        UnicodeString3264 {
+            length,
+            maximum_length: length + 2,
+            buffer: self.push(&bytes),
+            ..Default::default()
+        }
     }
 
-    scopeguard::defer!(unsafe {
-        RegCloseKey(hkey);
-    });
+    fn build(entries: &[RegistryRedirection]) -> Vec<u8> {
+        let mut blob = Self::new(entries.len());
+
+        let records: Vec<RegistryRedirectionEntry64> = entries
+            .iter()
+            .map(|entry| {
+                let original = RegistryEntry64 {
+                    root: entry.root as u64,
+                    sub_key: blob.push_string(entry.sub_key),
+                    value_name: blob.push_string(entry.value_name),
+                    ..Default::default()
+                };
+                let redirected = RegistryEntry64 {
+                    data_type: entry.data_type,
+                    data: blob.push(entry.data),
+                    data_size: entry.data.len() as u64,
+                    ..original
+                };
+                RegistryRedirectionEntry64 {
+                    original,
+                    redirected,
+                }
+            })
+            .collect();
+
+        blob.bytes[..size_of::<u64>()].copy_from_slice(&(entries.len() as u64).to_le_bytes());
+        for (i, record) in records.iter().enumerate() {
+            let offset = size_of::<u64>() + i * size_of::<RegistryRedirectionEntry64>();
+            let record = unsafe {
+                core::slice::from_raw_parts(
+                    record as *const RegistryRedirectionEntry64 as *const u8,
+                    size_of::<RegistryRedirectionEntry64>(),
+                )
+            };
+            blob.bytes[offset..offset + record.len()].copy_from_slice(record);
+        }
 
-    let std_name: [u16; 32] = query_reg_value_into(hkey, w!("Std"), REG_SZ)?;
-    let dlt_name: [u16; 32] = query_reg_value_into(hkey, w!("Dlt"), REG_SZ)?;
-    let tzi: RegTziFormat = query_reg_value_into(hkey, w!("TZI"), REG_BINARY)?;
+        blob.bytes
+    }
+}
 
-    leb.timezone.standard_name = std_name;
-    leb.timezone.daylight_name = dlt_name;
-    leb.timezone.bias = tzi.bias;
-    leb.timezone.standard_bias = tzi.standard_bias;
-    // 与上游 C++ 保持一致：固定写 0
-    leb.timezone.daylight_bias = 0;
-    leb.timezone.standard_start = TimeFields::from_system_time(tzi.standard_date);
-    leb.timezone.daylight_start = TimeFields::from_system_time(tzi.daylight_date);
+/// 构建完整的`LEB`，返回以`u64`对齐的缓冲区
+fn build_environment_block(
+    leb: &LocaleEmulatorEnvironmentBlock,
+    entries: &[RegistryRedirection],
+) -> Vec<u64> {
+    let header_len = offset_of!(
+        LocaleEmulatorEnvironmentBlock,
+        number_of_registry_redirection_entries
+    );
+    let registry = RegistryBlob::build(entries);
+    let total_len = (header_len + registry.len()).max(size_of::<LocaleEmulatorEnvironmentBlock>());
+
+    let mut buffer = vec![0u64; total_len.div_ceil(size_of::<u64>())];
+    let bytes = buffer.as_mut_ptr() as *mut u8;
+    unsafe {
+        core::ptr::copy_nonoverlapping(
+            leb as *const LocaleEmulatorEnvironmentBlock as *const u8,
+            bytes,
+            header_len,
+        );
+        core::ptr::copy_nonoverlapping(registry.as_ptr(), bytes.add(header_len), registry.len());
+    }
 
-    Ok(())
+    buffer
 }
 
 unsafe fn relaunch(process_info: *mut MlProcessInformation) -> crate::Result<()> {
@@ -212,6 +306,9 @@ unsafe fn relaunch(process_info: *mut MlProcessInformation) -> crate::Result<()>
         debug!("Failed to load timezone info: {_e:?}");
     }
 
+    debug!("Registry redirections: {REGISTRY_REDIRECTIONS:?}");
+    let mut leb = build_environment_block(&leb, REGISTRY_REDIRECTIONS);
+
     let exe_path = crate::utils::win32::get_module_file_name(core::ptr::null_mut(), true)?;
     let current_directory = crate::utils::win32::get_current_dir(true)?;
 
@@ -232,7 +329,7 @@ unsafe fn relaunch(process_info: *mut MlProcessInformation) -> crate::Result<()>
 
     let ret = unsafe {
         le_create_process(
-            &mut leb,
+            leb.as_mut_ptr() as *mut LocaleEmulatorEnvironmentBlock,
             exe_path.as_ptr(),
             GetCommandLineW(),
             current_directory.as_ptr(),
@@ -311,3 +408,124 @@ pub fn relaunch_with_locale_emulator() -> crate::Result<()> {
 
     unsafe { ExitProcess(0) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+    }
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+    }
+
+    /// 解码`entry`处`UnicodeString3264`指向的字符串，并检查结尾的 null
+    fn read_string(bytes: &[u8], entry: usize) -> String {
+        let length = read_u16(bytes, entry) as usize;
+        assert_eq!(read_u16(bytes, entry + 2) as usize, length + 2);
+        let buffer = read_u64(bytes, entry + 8) as usize;
+        assert_eq!(buffer % 8, 0);
+        assert_eq!(read_u16(bytes, buffer + length), 0);
+
+        let units: Vec<u16> = bytes[buffer..buffer + length]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16(&units).unwrap()
+    }
+
+    #[test]
+    fn layout() {
+        check_layout();
+    }
+
+    #[test]
+    fn registry_blob() {
+        let entries = [
+            RegistryRedirection {
+                root: 0x8000_0002,
+                sub_key: "Software\\游戏",
+                value_name: "",
+                data_type: 1,
+                data: &[b'a', 0, 0, 0],
+            },
+            RegistryRedirection {
+                root: 0x8000_0001,
+                sub_key: "Software\\Game",
+                value_name: "Volume",
+                data_type: 4,
+                data: &[3, 0, 0, 0, 0],
+            },
+        ];
+        let bytes = RegistryBlob::build(&entries);
+        let record_size = size_of::<RegistryRedirectionEntry64>();
+
+        assert_eq!(read_u64(&bytes, 0), 2);
+        for (i, entry) in entries.iter().enumerate() {
+            let original = 8 + i * record_size;
+            let redirected = original + size_of::<RegistryEntry64>();
+
+            for record in [original, redirected] {
+                assert_eq!(read_u64(&bytes, record), entry.root as u64);
+                assert_eq!(read_string(&bytes, record + 8), entry.sub_key);
+                assert_eq!(read_string(&bytes, record + 24), entry.value_name);
+            }
+
+            assert_eq!(read_u32(&bytes, original + 40), 0);
+            assert_eq!(read_u64(&bytes, original + 48), 0);
+            assert_eq!(read_u64(&bytes, original + 56), 0);
+
+            assert_eq!(read_u32(&bytes, redirected + 40), entry.data_type);
+            let data = read_u64(&bytes, redirected + 48) as usize;
+            let data_size = read_u64(&bytes, redirected + 56) as usize;
+            assert_eq!(data % 8, 0);
+            assert!(data >= 8 + entries.len() * record_size);
+            assert_eq!(&bytes[data..data + data_size], entry.data);
+        }
+    }
+
+    #[test]
+    fn environment_block() {
+        let leb = LocaleEmulatorEnvironmentBlock {
+            ansi_code_page: 932,
+            locale_id: 0x411,
+            ..Default::default()
+        };
+        let entry = RegistryRedirection {
+            root: 0x8000_0002,
+            sub_key: "Software\\Game",
+            value_name: "Path",
+            data_type: 1,
+            data: &[0, 0],
+        };
+
+        let buffer = build_environment_block(&leb, &[entry]);
+        let bytes: Vec<u8> = buffer.iter().flat_map(|n| n.to_le_bytes()).collect();
+        let header_len = offset_of!(
+            LocaleEmulatorEnvironmentBlock,
+            number_of_registry_redirection_entries
+        );
+
+        assert!(bytes.len() >= size_of::<LocaleEmulatorEnvironmentBlock>());
+        assert_eq!(read_u32(&bytes, 0), 932);
+        assert_eq!(read_u32(&bytes, 8), 0x411);
+        assert_eq!(
+            bytes[header_len..header_len + RegistryBlob::build(&[entry]).len()],
+            RegistryBlob::build(&[entry])
+        );
+
+        // 没有重定向项时也保留完整的固定部分
+        let buffer = build_environment_block(&leb, &[]);
+        assert_eq!(
+            buffer.len() * size_of::<u64>(),
+            size_of::<LocaleEmulatorEnvironmentBlock>()
+        );
+        assert_eq!(buffer[header_len / size_of::<u64>()], 0);
+    }
+}
@@ -0,0 +1,130 @@
+//! 进程内的轻量转区
+//!
+//! 与`locale_emulator`不同，不需要 Locale Emulator 的 DLL，也不会重新启动进程，
+//! 而是通过钩子让`GetACP`、`GetLocaleInfo`等 NLS API 返回`EMULATE_LOCALE_*`中的代码页、区域与时区，
+//! 并将使用`CP_ACP`、`CP_OEMCP`、`CP_THREAD_ACP`的转码重定向到模拟的代码页。
+//!
+//! 只会影响通过这些 API 获取区域信息的代码，直接读取 PEB 中 NLS 表的代码以及 GDI 的默认字符集不受影响
+
+use std::sync::LazyLock;
+
+use windows_sys::Win32::Foundation::{FILETIME, SYSTEMTIME};
+use windows_sys::Win32::Globalization::{CP_ACP, CP_OEMCP, CP_THREAD_ACP};
+use windows_sys::Win32::System::SystemInformation::GetSystemTime;
+use windows_sys::Win32::System::SystemServices::{
+    LOCALE_NAME_MAX_LENGTH, LOCALE_NEUTRAL, LOCALE_SYSTEM_DEFAULT, LOCALE_USER_DEFAULT,
+    TIME_ZONE_ID_DAYLIGHT, TIME_ZONE_ID_STANDARD, TIME_ZONE_ID_UNKNOWN,
+};
+use windows_sys::Win32::System::Time::{
+    SystemTimeToFileTime, SystemTimeToTzSpecificLocalTime, TIME_ZONE_INFORMATION,
+};
+use windows_sys::core::PCWSTR;
+
+use crate::constant::{EMULATE_LOCALE_CODEPAGE, EMULATE_LOCALE_LOCALE, EMULATE_LOCALE_TIMEZONE};
+use crate::debug;
+use crate::utils::exts::ptr_ext::PtrExt;
+
+/// `LOCALE_NAME_SYSTEM_DEFAULT`
+const LOCALE_NAME_SYSTEM_DEFAULT: &str = "!x-sys-default-locale";
+
+/// 模拟的时区，读取失败时为`None`，此时使用真实的时区
+static TIME_ZONE: LazyLock<Option<TIME_ZONE_INFORMATION>> = LazyLock::new(|| {
+    match crate::utils::time_zone::load_time_zone_information(EMULATE_LOCALE_TIMEZONE) {
+        Ok(tzi) => Some(tzi),
+        Err(_e) => {
+            debug!("Failed to load timezone {EMULATE_LOCALE_TIMEZONE}: {_e:?}");
+            None
+        }
+    }
+});
+
+/// 模拟的语言 ID，即区域 ID 的低 16 位
+pub fn lang_id() -> u16 {
+    (EMULATE_LOCALE_LOCALE & 0xFFFF) as u16
+}
+
+/// 将`CP_ACP`、`CP_OEMCP`、`CP_THREAD_ACP`映射到模拟的代码页，其他代码页保持不变
+pub fn map_code_page(code_page: u32) -> u32 {
+    match code_page {
+        CP_ACP | CP_OEMCP | CP_THREAD_ACP => EMULATE_LOCALE_CODEPAGE,
+        _ => code_page,
+    }
+}
+
+/// 将默认的区域 ID 映射到模拟的区域，其他区域保持不变
+pub fn map_locale(locale: u32) -> u32 {
+    match locale {
+        LOCALE_NEUTRAL | LOCALE_USER_DEFAULT | LOCALE_SYSTEM_DEFAULT => EMULATE_LOCALE_LOCALE,
+        _ => locale,
+    }
+}
+
+/// 区域名称是否表示默认区域，即`LOCALE_NAME_USER_DEFAULT`（空指针）或`LOCALE_NAME_SYSTEM_DEFAULT`
+///
+/// 空字符串表示固定区域（`LOCALE_NAME_INVARIANT`），不会被映射
+///
+/// # Safety
+/// `name`不为空时必须以 null 结尾
+pub unsafe fn is_default_locale_name(name: PCWSTR) -> bool {
+    if name.is_null() {
+        return true;
+    }
+
+    let name = unsafe { name.to_slice_until_null(LOCALE_NAME_MAX_LENGTH as usize) };
+    name.iter()
+        .copied()
+        .eq(LOCALE_NAME_SYSTEM_DEFAULT.encode_utf16())
+}
+
+/// 将`SYSTEMTIME`转换为以 100 纳秒为单位的整数
+fn system_time_to_u64(time: &SYSTEMTIME) -> Option<u64> {
+    let mut file_time = FILETIME {
+        dwLowDateTime: 0,
+        dwHighDateTime: 0,
+    };
+    if unsafe { SystemTimeToFileTime(time, &mut file_time) } == 0 {
+        return None;
+    }
+    Some(((file_time.dwHighDateTime as u64) << 32) | file_time.dwLowDateTime as u64)
+}
+
+/// 根据当前时间判断模拟的时区是否处于夏令时
+fn time_zone_id(tzi: &TIME_ZONE_INFORMATION) -> u32 {
+    // 没有夏令时规则
+    if tzi.DaylightDate.wMonth == 0 {
+        return TIME_ZONE_ID_UNKNOWN;
+    }
+
+    let mut utc: SYSTEMTIME = unsafe { core::mem::zeroed() };
+    let mut local: SYSTEMTIME = unsafe { core::mem::zeroed() };
+    unsafe { GetSystemTime(&mut utc) };
+    if unsafe { SystemTimeToTzSpecificLocalTime(tzi, &utc, &mut local) } == 0 {
+        return TIME_ZONE_ID_STANDARD;
+    }
+
+    // UTC = 本地时间 + 偏差，偏差以分钟为单位
+    let (Some(utc), Some(local)) = (system_time_to_u64(&utc), system_time_to_u64(&local)) else {
+        return TIME_ZONE_ID_STANDARD;
+    };
+    let bias = (utc as i64 - local as i64) / 600_000_000;
+
+    if tzi.DaylightBias != tzi.StandardBias && bias == (tzi.Bias + tzi.DaylightBias) as i64 {
+        TIME_ZONE_ID_DAYLIGHT
+    } else {
+        TIME_ZONE_ID_STANDARD
+    }
+}
+
+/// 处理`GetTimeZoneInformation`，返回`None`时应调用原函数
+///
+/// # Safety
+/// `tzi`必须可以写入
+pub unsafe fn get_time_zone_information(tzi: *mut TIME_ZONE_INFORMATION) -> Option<u32> {
+    let emulated = TIME_ZONE.as_ref()?;
+    if tzi.is_null() {
+        return None;
+    }
+
+    unsafe { tzi.write(*emulated) };
+    Some(time_zone_id(emulated))
+}
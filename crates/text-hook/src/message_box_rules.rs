@@ -0,0 +1,380 @@
+//! MessageBox 自动应答
+//!
+//! 规则来自用户配置中的`MESSAGE_BOX_RULES`，按顺序匹配，使用第一个命中的规则。
+//! 匹配对象为解码后的标题与正文（A版本按当前的ANSI代码页解码），
+//! 命中后可以直接返回指定的按钮、不显示对话框并返回默认按钮，或者显示翻译后的对话框。
+
+use std::cell::Cell;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    IDABORT, IDCANCEL, IDCONTINUE, IDIGNORE, IDNO, IDOK, IDRETRY, IDTRYAGAIN, IDYES,
+    MB_ABORTRETRYIGNORE, MB_CANCELTRYCONTINUE, MB_DEFMASK, MB_OKCANCEL, MB_RETRYCANCEL,
+    MB_TYPEMASK, MB_YESNO, MB_YESNOCANCEL,
+};
+
+use crate::debug;
+use crate::utils::exts::ptr_ext::PtrExt;
+use crate::utils::exts::slice_ext::{ByteSliceExt, WideSliceExt};
+
+/// 标题与正文的最大长度
+const MAX_TEXT_LENGTH: usize = 65536;
+
+/// 匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// 完全相等
+    Exact,
+    /// 正则表达式，须匹配整个字符串时请自行加上`^`与`$`
+    Regex,
+}
+
+/// 命中规则后的处理方式
+#[derive(Debug, Clone, Copy)]
+pub enum MessageBoxAction {
+    /// 不显示对话框，直接返回指定的值
+    Result(i32),
+    /// 不显示对话框，返回默认按钮对应的值
+    Suppress,
+    /// 显示翻译后的对话框，未指定的部分会通过`text_patch`查找译文（若开启），否则保持原样
+    Translate {
+        caption: Option<&'static str>,
+        text: Option<&'static str>,
+    },
+}
+
+/// 配置中的一条规则
+#[derive(Debug, Clone, Copy)]
+pub struct RuleSpec {
+    pub kind: MatchKind,
+    /// 标题的匹配模式，为`None`时匹配任意标题
+    pub caption: Option<&'static str>,
+    /// 正文的匹配模式，为`None`时匹配任意正文
+    pub text: Option<&'static str>,
+    pub action: MessageBoxAction,
+}
+
+/// 编译后的规则
+struct Rule {
+    spec: RuleSpec,
+    caption: Option<Regex>,
+    text: Option<Regex>,
+}
+
+static RULE_SPECS: &[RuleSpec] =
+    translate_macros::generate_message_box_rules!("assets/config.json");
+
+/// 所有编译成功的规则，编译失败的规则会被跳过
+static RULES: LazyLock<Vec<Rule>> = LazyLock::new(|| {
+    RULE_SPECS
+        .iter()
+        .filter_map(|spec| match Rule::new(*spec) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                debug!("Invalid message box rule {spec:?}: {e}");
+                None
+            }
+        })
+        .collect()
+});
+
+thread_local! {
+    /// 当前线程是否正在显示由规则替换的对话框
+    ///
+    /// user32 内部的 A 版本会转发到 W 版本，`MessageBox`也会转发到`MessageBoxEx`等，
+    /// 显示替换后的对话框时不再重复匹配规则
+    static SHOWING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// 单个模式的匹配结果
+enum Matched<'a> {
+    /// 没有指定模式
+    Any,
+    /// 完全相等
+    Exact,
+    /// 正则表达式的捕获组
+    Regex(Captures<'a>),
+}
+
+impl Rule {
+    fn new(spec: RuleSpec) -> Result<Self, regex::Error> {
+        let compile = |pattern: Option<&str>| match (spec.kind, pattern) {
+            (MatchKind::Regex, Some(pattern)) => Regex::new(pattern).map(Some),
+            _ => Ok(None),
+        };
+
+        Ok(Self {
+            spec,
+            caption: compile(spec.caption)?,
+            text: compile(spec.text)?,
+        })
+    }
+
+    fn match_one<'a>(
+        pattern: Option<&str>,
+        regex: Option<&Regex>,
+        subject: &'a str,
+    ) -> Option<Matched<'a>> {
+        match (pattern, regex) {
+            (None, _) => Some(Matched::Any),
+            (Some(_), Some(regex)) => regex.captures(subject).map(Matched::Regex),
+            (Some(pattern), None) => (pattern == subject).then_some(Matched::Exact),
+        }
+    }
+}
+
+/// 查找第一个标题与正文都匹配的规则，返回规则与两者的匹配结果
+fn find_rule<'r, 's>(
+    rules: &'r [Rule],
+    caption: &'s str,
+    text: &'s str,
+) -> Option<(&'r Rule, Matched<'s>, Matched<'s>)> {
+    rules.iter().find_map(|rule| {
+        let caption_matched = Rule::match_one(rule.spec.caption, rule.caption.as_ref(), caption)?;
+        let text_matched = Rule::match_one(rule.spec.text, rule.text.as_ref(), text)?;
+        Some((rule, caption_matched, text_matched))
+    })
+}
+
+/// 展开翻译模板，正则表达式规则中可以使用`$N`与`${name}`引用捕获组
+fn expand(template: &str, matched: &Matched) -> String {
+    match matched {
+        Matched::Regex(captures) => {
+            let mut out = String::new();
+            captures.expand(template, &mut out);
+            out
+        }
+        Matched::Any | Matched::Exact => template.to_string(),
+    }
+}
+
+/// 获取翻译后的字符串，`template`为`None`时通过`text_patch`查找
+fn translate(template: Option<&str>, matched: &Matched, original: &str) -> String {
+    if let Some(template) = template {
+        return expand(template, matched);
+    }
+
+    #[cfg(feature = "text_patch")]
+    if !original.is_empty()
+        && let Ok(translated) = crate::text_patch::lookup_or_add_item(original)
+    {
+        return translated.to_string();
+    }
+
+    original.to_string()
+}
+
+/// 根据`uType`返回默认按钮对应的值
+pub fn default_result(u_type: u32) -> i32 {
+    let buttons: &[i32] = match u_type & MB_TYPEMASK {
+        MB_OKCANCEL => &[IDOK, IDCANCEL],
+        MB_ABORTRETRYIGNORE => &[IDABORT, IDRETRY, IDIGNORE],
+        MB_YESNOCANCEL => &[IDYES, IDNO, IDCANCEL],
+        MB_YESNO => &[IDYES, IDNO],
+        MB_RETRYCANCEL => &[IDRETRY, IDCANCEL],
+        MB_CANCELTRYCONTINUE => &[IDCANCEL, IDTRYAGAIN, IDCONTINUE],
+        _ => &[IDOK],
+    };
+
+    // 默认按钮超出按钮数量时，与 Win32 一样使用第一个按钮
+    let index = ((u_type & MB_DEFMASK) >> 8) as usize;
+    buttons.get(index).copied().unwrap_or(buttons[0])
+}
+
+/// 解码 ANSI 字符串，空指针与资源 ID（`MessageBoxIndirect`）视为空字符串
+///
+/// # Safety
+/// `ptr`为字符串时必须以 null 结尾
+pub unsafe fn decode_a(ptr: *const u8) -> String {
+    if (ptr as usize) <= u16::MAX as usize {
+        return String::new();
+    }
+    unsafe { ptr.to_slice_until_null(MAX_TEXT_LENGTH) }
+        .to_wide_ansi()
+        .to_string_lossy()
+}
+
+/// 解码宽字符串，空指针与资源 ID（`MessageBoxIndirect`）视为空字符串
+///
+/// # Safety
+/// `ptr`为字符串时必须以 null 结尾
+pub unsafe fn decode_w(ptr: *const u16) -> String {
+    if (ptr as usize) <= u16::MAX as usize {
+        return String::new();
+    }
+    unsafe { ptr.to_slice_until_null(MAX_TEXT_LENGTH) }.to_string_lossy()
+}
+
+/// 根据规则处理对话框，返回`None`时应调用原函数
+///
+/// 命中`translate`时，`show`会以翻译后的以 null 结尾的正文与标题被调用，
+/// 应使用原始的 W 版本函数显示对话框
+pub fn handle(
+    text: &str,
+    caption: &str,
+    u_type: u32,
+    show: impl FnOnce(*const u16, *const u16) -> i32,
+) -> Option<i32> {
+    if RULES.is_empty() || SHOWING.get() {
+        return None;
+    }
+
+    let (rule, caption_matched, text_matched) = find_rule(&RULES, caption, text)?;
+
+    debug!(
+        "MessageBox '{caption}': '{text}' matched rule {:?}",
+        rule.spec
+    );
+    match rule.spec.action {
+        MessageBoxAction::Result(result) => Some(result),
+        MessageBoxAction::Suppress => Some(default_result(u_type)),
+        MessageBoxAction::Translate {
+            caption: caption_template,
+            text: text_template,
+        } => {
+            let caption = translate(caption_template, &caption_matched, caption);
+            let text = translate(text_template, &text_matched, text);
+            let caption: Vec<u16> = caption.encode_utf16().chain([0]).collect();
+            let text: Vec<u16> = text.encode_utf16().chain([0]).collect();
+
+            SHOWING.set(true);
+            let result = show(text.as_ptr(), caption.as_ptr());
+            SHOWING.set(false);
+            Some(result)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        MB_DEFBUTTON2, MB_DEFBUTTON3, MB_DEFBUTTON4, MB_ICONWARNING, MB_OK,
+    };
+
+    use super::*;
+
+    fn rule(
+        kind: MatchKind,
+        caption: Option<&'static str>,
+        text: Option<&'static str>,
+        result: i32,
+    ) -> Rule {
+        Rule::new(RuleSpec {
+            kind,
+            caption,
+            text,
+            action: MessageBoxAction::Result(result),
+        })
+        .unwrap()
+    }
+
+    /// 命中规则的返回值，以及展开后的标题模板与正文模板
+    fn find(
+        rules: &[Rule],
+        caption: &str,
+        text: &str,
+        templates: (&str, &str),
+    ) -> Option<(i32, String, String)> {
+        let (rule, caption_matched, text_matched) = find_rule(rules, caption, text)?;
+        let MessageBoxAction::Result(result) = rule.spec.action else {
+            unreachable!()
+        };
+        Some((
+            result,
+            expand(templates.0, &caption_matched),
+            expand(templates.1, &text_matched),
+        ))
+    }
+
+    #[test]
+    fn default_button() {
+        assert_eq!(default_result(MB_OK), IDOK);
+        assert_eq!(default_result(MB_YESNO), IDYES);
+        assert_eq!(default_result(MB_YESNO | MB_DEFBUTTON2), IDNO);
+        assert_eq!(default_result(MB_YESNOCANCEL | MB_DEFBUTTON3), IDCANCEL);
+        assert_eq!(
+            default_result(MB_CANCELTRYCONTINUE | MB_DEFBUTTON2),
+            IDTRYAGAIN
+        );
+        assert_eq!(
+            default_result(MB_OKCANCEL | MB_ICONWARNING | MB_DEFBUTTON2),
+            IDCANCEL
+        );
+    }
+
+    #[test]
+    fn default_button_beyond_count() {
+        assert_eq!(default_result(MB_OK | MB_DEFBUTTON2), IDOK);
+        assert_eq!(default_result(MB_YESNO | MB_DEFBUTTON3), IDYES);
+        assert_eq!(default_result(MB_RETRYCANCEL | MB_DEFBUTTON4), IDRETRY);
+        assert_eq!(default_result(MB_ABORTRETRYIGNORE | MB_DEFMASK), IDABORT);
+    }
+
+    #[test]
+    fn exact_match() {
+        let rules = [rule(
+            MatchKind::Exact,
+            Some("日本語版Windows判定"),
+            None,
+            IDNO,
+        )];
+        assert_eq!(
+            find(&rules, "日本語版Windows判定", "任意", ("$1", "$1")),
+            Some((IDNO, "$1".to_string(), "$1".to_string()))
+        );
+        assert!(find(&rules, "日本語版Windows判定 ", "", ("", "")).is_none());
+        assert!(find(&rules, "日本語版windows判定", "", ("", "")).is_none());
+    }
+
+    #[test]
+    fn regex_captures() {
+        let rules = [rule(
+            MatchKind::Regex,
+            Some("^Error (?<code>\\d+)$"),
+            Some("ファイル (.+) が見つかりません"),
+            IDOK,
+        )];
+        assert_eq!(
+            find(
+                &rules,
+                "Error 42",
+                "ファイル data.pak が見つかりません。",
+                ("错误 ${code}", "找不到文件 $1")
+            ),
+            Some((
+                IDOK,
+                "错误 42".to_string(),
+                "找不到文件 data.pak".to_string()
+            ))
+        );
+        assert!(find(&rules, "Error 42!", "ファイル a が見つかりません", ("", "")).is_none());
+        assert!(find(&rules, "Error 42", "他のエラー", ("", "")).is_none());
+    }
+
+    #[test]
+    fn first_match_wins() {
+        let rules = [
+            rule(MatchKind::Exact, Some("Confirm"), Some("Quit?"), IDYES),
+            rule(MatchKind::Regex, Some("^Confirm"), None, IDNO),
+            rule(MatchKind::Exact, None, None, IDCANCEL),
+        ];
+        let result = |caption, text| find(&rules, caption, text, ("", "")).map(|found| found.0);
+
+        assert_eq!(result("Confirm", "Quit?"), Some(IDYES));
+        assert_eq!(result("Confirm", "Save?"), Some(IDNO));
+        assert_eq!(result("Confirmation", ""), Some(IDNO));
+        assert_eq!(result("Other", "Quit?"), Some(IDCANCEL));
+    }
+
+    #[test]
+    fn invalid_regex_is_rejected() {
+        let spec = RuleSpec {
+            kind: MatchKind::Regex,
+            caption: Some("("),
+            text: None,
+            action: MessageBoxAction::Suppress,
+        };
+        assert!(Rule::new(spec).is_err());
+    }
+}
@@ -19,6 +19,27 @@ pub fn get_patch(src: &[u8]) -> Option<&'static [u8]> {
     Some(data)
 }
 
+/// 根据原始文件名（不区分大小写）获取补丁数据，`len` 为目标数据的长度
+///
+/// 不会校验目标数据的哈希，仅用于无法（或不便）读取目标数据的场合，比如按路径匹配文件映射
+pub fn get_patch_by_name(file_name: &str, len: usize) -> Option<&'static [u8]> {
+    if !is_patch_len(len) {
+        return None;
+    }
+
+    let hash = patch_data::NAMES.get(file_name.to_lowercase().as_str())?;
+    let data = patch_data::PATCHES.get(*hash)?.as_slice();
+    if data.len() != len {
+        debug!(
+            "Error: Patch {file_name} has a different length ({} != {len})",
+            data.len()
+        );
+        return None;
+    }
+
+    Some(data)
+}
+
 /// 是否是需要进行处理的补丁的长度？
 pub fn is_patch_len(len: usize) -> bool {
     patch_data::LEN_FILTER.contains(&len)
@@ -68,15 +89,251 @@ pub unsafe fn try_patching(ptr: *mut u8, len: usize) -> bool {
     }
 }
 
+/// 提取清单`raw/manifest.json`中的单个条目
+#[cfg(feature = "patch_extracting")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ManifestEntry {
+    /// 写入 raw 目录的文件名
+    file: String,
+    /// 原始数据的 sha256（十六进制）
+    hash: String,
+    /// 原始数据的字节长度
+    len: usize,
+    /// 调用者提供的来源名称（如脚本名），未知时为`null`
+    source: Option<String>,
+    /// 首次出现的顺序，从 1 开始
+    order: u64,
+    /// 捕获该数据的钩子位置
+    hook: String,
+}
+
+#[cfg(feature = "patch_extracting")]
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    entries: Vec<ManifestEntry>,
+}
+
+/// 提取状态，启动后只会从磁盘加载一次，之后所有查重都在内存中完成
+#[cfg(feature = "patch_extracting")]
+struct ExtractState {
+    raw_dir: std::path::PathBuf,
+    manifest: Manifest,
+    hashes: std::collections::HashSet<[u8; 32]>,
+    /// 已占用的文件名（小写）
+    files: std::collections::HashSet<String>,
+    /// 尚未写入清单的条目数
+    unsaved: usize,
+}
+
+#[cfg(feature = "patch_extracting")]
+static EXTRACT_STATE: std::sync::LazyLock<std::sync::Mutex<ExtractState>> =
+    std::sync::LazyLock::new(|| std::sync::Mutex::new(ExtractState::load()));
+
+#[cfg(feature = "patch_extracting")]
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// 每新增多少个条目写入一次清单，其余的在`flush_manifest`时写入
+#[cfg(feature = "patch_extracting")]
+const MANIFEST_SAVE_INTERVAL: usize = 64;
+
+#[cfg(feature = "patch_extracting")]
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(feature = "patch_extracting")]
+fn from_hex(s: &str) -> Option<[u8; 32]> {
+    if s.len() != 64 {
+        return None;
+    }
+
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(s.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(out)
+}
+
+/// 将来源名称转换为可用的文件名，去掉目录部分并替换非法字符
+#[cfg(feature = "patch_extracting")]
+fn sanitize_file_name(name: &str) -> String {
+    name.rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| match c {
+            ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[cfg(feature = "patch_extracting")]
+impl ExtractState {
+    fn load() -> Self {
+        let raw_dir = crate::utils::get_executable_dir().join("raw");
+
+        let mut state = ExtractState {
+            raw_dir,
+            manifest: Manifest::default(),
+            hashes: Default::default(),
+            files: Default::default(),
+            unsaved: 0,
+        };
+
+        let manifest_path = state.raw_dir.join(MANIFEST_FILE_NAME);
+        if let Ok(contents) = std::fs::read_to_string(&manifest_path) {
+            match serde_json::from_str::<Manifest>(&contents) {
+                Ok(manifest) => state.manifest = manifest,
+                Err(e) => debug!("Failed to parse {:?}: {e}", manifest_path),
+            }
+        }
+
+        for entry in &state.manifest.entries {
+            if let Some(hash) = from_hex(&entry.hash) {
+                state.hashes.insert(hash);
+            }
+            state.files.insert(entry.file.to_lowercase());
+        }
+
+        // 清单缺失、损坏或没来得及写入时，从目录中补全未记录的文件，避免重复提取
+        state.import_unlisted_files();
+
+        debug!(
+            "Loaded {} extracted entries from {:?}",
+            state.manifest.entries.len(),
+            state.raw_dir
+        );
+
+        state
+    }
+
+    fn import_unlisted_files(&mut self) {
+        let Ok(entries) = std::fs::read_dir(&self.raw_dir) else {
+            return;
+        };
+
+        let mut paths: Vec<_> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let Some(file) = path.file_name().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if file.eq_ignore_ascii_case(MANIFEST_FILE_NAME)
+                || self.files.contains(&file.to_lowercase())
+            {
+                continue;
+            }
+
+            match std::fs::read(&path) {
+                Ok(bytes) => {
+                    let hash = sha256_of_bytes(&bytes);
+                    let order = self.manifest.entries.len() as u64 + 1;
+                    self.hashes.insert(hash);
+                    self.files.insert(file.to_lowercase());
+                    self.manifest.entries.push(ManifestEntry {
+                        file: file.to_string(),
+                        hash: to_hex(&hash),
+                        len: bytes.len(),
+                        source: None,
+                        order,
+                        hook: "unknown".to_string(),
+                    });
+                    self.unsaved += 1;
+                }
+                Err(e) => debug!("Failed to read existing file {:?}: {:?}", path, e),
+            }
+        }
+    }
+
+    /// 选择输出文件名：优先使用来源名称，冲突时附加哈希前缀；无来源名称时使用哈希
+    fn choose_file_name(&self, source: Option<&str>, hash: &[u8; 32]) -> String {
+        let short = to_hex(&hash[..8]);
+        let name = source.map(sanitize_file_name).unwrap_or_default();
+
+        if name.is_empty() || name.eq_ignore_ascii_case(MANIFEST_FILE_NAME) {
+            return format!("{short}.bin");
+        }
+
+        if !self.files.contains(&name.to_lowercase()) {
+            return name;
+        }
+
+        // 同名但内容不同（比如同一脚本的不同版本）
+        match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => format!("{stem}.{short}.{ext}"),
+            _ => format!("{name}.{short}"),
+        }
+    }
+
+    fn save_manifest(&mut self) -> crate::Result<()> {
+        let contents = serde_json::to_string_pretty(&self.manifest)?;
+        std::fs::write(self.raw_dir.join(MANIFEST_FILE_NAME), contents)?;
+        self.unsaved = 0;
+        Ok(())
+    }
+}
+
+/// 把尚未写入的提取条目写入`raw/manifest.json`
+///
+/// 清单只会每隔若干条目写入一次，应在清理时调用这个函数。
+/// 即使没有调用，下次启动时也会从 raw 目录补全未记录的文件
+#[cfg(feature = "patch_extracting")]
+pub fn flush_manifest() -> crate::Result<()> {
+    let Ok(mut state) = EXTRACT_STATE.lock() else {
+        crate::bail!("Extract state is poisoned");
+    };
+
+    if state.unsaved == 0 {
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(&state.raw_dir)?;
+    state.save_manifest()
+}
+
 /// 尝试提取传入数据，若为新数据，将会写入 raw 目录。
 /// 返回`true`表示提取成功
 ///
+/// 等价于来源名称为`None`的`try_extracting_with_source`，钩子位置记录为调用处
+///
 /// # Safety
 /// - `ptr` 必须指向长度至少为 `len` 的可读有效内存。
 /// - 调用者需保证该内存在本次调用期间保持有效且不被并发修改。
-#[allow(dead_code, unused_variables)]
+#[allow(dead_code)]
+#[track_caller]
 #[cfg(feature = "patch_extracting")]
 pub unsafe fn try_extracting(ptr: *mut u8, len: usize) -> bool {
+    let caller = core::panic::Location::caller();
+    let hook = format!("{}:{}", caller.file(), caller.line());
+    unsafe { try_extracting_with_source(ptr, len, None, &hook) }
+}
+
+/// 尝试提取传入数据，若为新数据，将会写入 raw 目录，并记录到`raw/manifest.json`。
+/// 返回`true`表示提取成功
+///
+/// - `source`: 数据的来源名称（如脚本文件名），若提供则用作输出文件名，
+///   这样 raw 目录可以直接作为`assets/raw_patch`使用
+/// - `hook`: 捕获该数据的钩子，仅用于记录
+///
+/// # Safety
+/// - `ptr` 必须指向长度至少为 `len` 的可读有效内存。
+/// - 调用者需保证该内存在本次调用期间保持有效且不被并发修改。
+#[cfg(feature = "patch_extracting")]
+pub unsafe fn try_extracting_with_source(
+    ptr: *mut u8,
+    len: usize,
+    source: Option<&str>,
+    hook: &str,
+) -> bool {
     if !crate::utils::mem::quick_memory_check(ptr, len) {
         return false;
     }
@@ -84,72 +341,50 @@ pub unsafe fn try_extracting(ptr: *mut u8, len: usize) -> bool {
     let slice = unsafe { core::slice::from_raw_parts(ptr, len) };
     let new_hash = sha256_of_bytes(slice);
 
-    let exe_dir = crate::utils::get_executable_dir();
-
-    let raw_dir = exe_dir.join("raw");
+    let Ok(mut state) = EXTRACT_STATE.lock() else {
+        return false;
+    };
 
-    if let Err(e) = std::fs::create_dir_all(&raw_dir) {
-        debug!("Failed to create raw dir {:?}: {:?}", raw_dir, e);
+    if state.hashes.contains(&new_hash) {
         return false;
     }
 
-    let mut max_index: u64 = 0;
-
-    // 遍历 raw 目录，查找是否已有完全相同的文件（长度相同且 sha 相同）
-    if let Ok(entries) = std::fs::read_dir(&raw_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_file() {
-                continue;
-            }
+    if let Err(e) = std::fs::create_dir_all(&state.raw_dir) {
+        debug!("Failed to create raw dir {:?}: {:?}", state.raw_dir, e);
+        return false;
+    }
 
-            // --- 步骤1: 尝试更新 max_index ---
-            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                if let Ok(n) = stem.parse::<u64>() {
-                    max_index = max_index.max(n);
-                } else {
-                    // 如果文件名不是纯数字，则跳过后续的哈希检查
-                    continue;
-                }
-            } else {
-                continue;
-            }
+    let file = state.choose_file_name(source, &new_hash);
+    let out_path = state.raw_dir.join(&file);
 
-            // --- 步骤2: 检查文件内容是否重复 ---
-            match std::fs::read(&path) {
-                Ok(existing_bytes) => {
-                    if existing_bytes.len() == slice.len() {
-                        let existing_hash = sha256_of_bytes(&existing_bytes);
-                        if existing_hash == new_hash {
-                            debug!("Identical file already exists, skipping write: {:?}", path);
-                            return false;
-                        }
-                    }
-                }
-                Err(e) => {
-                    debug!("Failed to read existing file {:?}: {:?}", path, e);
-                }
-            }
-        }
-    } else {
-        debug!("Failed to read raw dir {:?}", raw_dir);
+    if let Err(e) = std::fs::write(&out_path, slice) {
+        debug!("Failed to write file {:?}: {:?}", out_path, e);
+        return false;
     }
 
-    // --- 如果循环正常结束，说明没有找到任何重复的文件 ---
-    // 此时的 max_index 就是目录中最大的索引值。
-    let next = max_index + 1;
-    let out_path = raw_dir.join(format!("{next}.snr"));
+    debug!("Wrote raw file {:?} (len={})", out_path, slice.len());
 
-    match std::fs::write(&out_path, slice) {
-        Ok(_) => {
-            debug!("Wrote raw file {:?} (len={})", out_path, slice.len());
-            true
-        }
-        Err(e) => {
-            debug!("Failed to write file {:?}: {:?}", out_path, e);
-            false
-        }
+    let order = state.manifest.entries.len() as u64 + 1;
+    state.hashes.insert(new_hash);
+    state.files.insert(file.to_lowercase());
+    state.manifest.entries.push(ManifestEntry {
+        file,
+        hash: to_hex(&new_hash),
+        len,
+        source: source.map(str::to_string),
+        order,
+        hook: hook.to_string(),
+    });
+    state.unsaved += 1;
+
+    // 每次都重写整个清单会使提取的 I/O 随条目数平方增长，这里只定期写入
+    if state.unsaved >= MANIFEST_SAVE_INTERVAL
+        && let Err(e) = state.save_manifest()
+    {
+        debug!("Failed to save manifest: {e:?}");
     }
+
+    true
 }
 
 /// 处理传入的缓冲区，进行修补或提取。
@@ -157,6 +392,7 @@ pub unsafe fn try_extracting(ptr: *mut u8, len: usize) -> bool {
 ///
 /// 仅限RUST内部使用，若要用于外部代码，请使用`process_buffer_ffi`
 #[inline]
+#[track_caller]
 pub fn process_buffer(ptr: *mut u8, len: usize) -> bool {
     unsafe {
         #[cfg(not(feature = "patch_extracting"))]
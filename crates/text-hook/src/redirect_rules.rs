@@ -0,0 +1,473 @@
+//! 路径重定向规则
+//!
+//! 规则来自用户配置中的`REDIRECTION_RULES`，按顺序匹配，使用第一个命中的规则。
+//! 匹配对象为路径相对于exe目录的部分（使用`/`分隔），若路径不在exe目录下则为完整的绝对路径。
+//! 所有匹配方式均为大小写不敏感（Unicode）。
+
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::debug;
+
+/// 匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// 完全相等，`$0` 为整个路径
+    Exact,
+    /// 以模式结尾，`$0` 为匹配的部分，`$1` 为之前的部分
+    Suffix,
+    /// glob 模式，`*`不跨越`/`，`**`可跨越`/`，`?`匹配单个字符，`[...]`匹配字符集，
+    /// 每个通配符依次对应`$1`、`$2`...（编译期转换为正则表达式）
+    Glob,
+    /// 正则表达式，须匹配整个路径时请自行加上`^`与`$`
+    Regex,
+}
+
+/// 目标路径的基准
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetBase {
+    /// 相对于exe目录，目标为绝对路径时保持不变
+    Exe,
+    /// 相对于原路径所在的目录，可用`../`重定向到同级目录
+    Source,
+    /// 资源包中的相对路径，只有资源包中存在该文件时规则才会生效
+    ResourcePack,
+}
+
+/// 配置中的一条规则
+#[derive(Debug, Clone, Copy)]
+pub struct RuleSpec {
+    pub kind: MatchKind,
+    pub pattern: &'static str,
+    /// glob 与正则表达式规则的正则表达式，已在编译期检查
+    pub regex: Option<&'static str>,
+    pub target: &'static str,
+    pub base: TargetBase,
+}
+
+/// 编译后的规则
+pub struct Rule {
+    pub spec: RuleSpec,
+    /// glob 与正则表达式规则编译后的正则表达式
+    regex: Option<Regex>,
+}
+
+static RULE_SPECS: &[RuleSpec] = translate_macros::generate_redirect_rules!("assets/config.json");
+
+/// `create_file_redirect`的旧配置，等价于一条后缀规则
+#[cfg(feature = "create_file_redirect")]
+const LEGACY_RULE: RuleSpec = RuleSpec {
+    kind: MatchKind::Suffix,
+    pattern: crate::constant::REDIRECTION_SRC_PATH,
+    regex: None,
+    target: const_str::concat!("${1}", crate::constant::REDIRECTION_TARGET_PATH),
+    base: TargetBase::Exe,
+};
+
+/// 所有编译成功的规则，正则表达式已在编译期检查，编译失败的规则会被跳过
+static RULES: LazyLock<Vec<Rule>> = LazyLock::new(|| {
+    #[cfg(feature = "create_file_redirect")]
+    let specs = RULE_SPECS.iter().chain([&LEGACY_RULE]);
+    #[cfg(not(feature = "create_file_redirect"))]
+    let specs = RULE_SPECS.iter();
+
+    specs
+        .filter_map(|spec| match Rule::new(*spec) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                debug!("Invalid redirection rule {}: {e}", spec.pattern);
+                None
+            }
+        })
+        .collect()
+});
+
+/// 大小写不敏感地比较两个字符
+fn char_eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// 大小写不敏感地判断`s`是否以`suffix`结尾，返回匹配部分在`s`中的起始位置
+pub fn strip_suffix_ignore_case(s: &str, suffix: &str) -> Option<usize> {
+    let mut start = s.len();
+    let mut chars = s.chars().rev();
+
+    for p in suffix.chars().rev() {
+        let c = chars.next()?;
+        if !char_eq_ignore_case(c, p) {
+            return None;
+        }
+        start -= c.len_utf8();
+    }
+
+    Some(start)
+}
+
+/// 大小写不敏感地判断两个字符串是否相等
+pub fn eq_ignore_case(a: &str, b: &str) -> bool {
+    let mut a = a.chars();
+    let mut b = b.chars();
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return true,
+            (Some(x), Some(y)) if char_eq_ignore_case(x, y) => continue,
+            _ => return false,
+        }
+    }
+}
+
+/// 展开目标路径模板中的`$N`、`${N}`与`${name}`，`$$`表示`$`本身
+///
+/// `get` 根据组号或组名返回捕获的内容，不存在的组展开为空字符串
+pub fn expand_target(template: &str, get: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        if let Some(after) = rest.strip_prefix('$') {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{')
+            && let Some(end) = after.find('}')
+        {
+            out.push_str(&get(&after[..end]).unwrap_or_default());
+            rest = &after[end + 1..];
+        } else {
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits == 0 {
+                out.push('$');
+            } else {
+                out.push_str(&get(&rest[..digits]).unwrap_or_default());
+                rest = &rest[digits..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+impl Rule {
+    /// 编译规则，正则表达式无效时返回错误信息
+    pub fn new(spec: RuleSpec) -> Result<Self, String> {
+        let regex = spec
+            .regex
+            .map(|source| {
+                RegexBuilder::new(source)
+                    .case_insensitive(true)
+                    .unicode(true)
+                    .build()
+                    .map_err(|e| e.to_string())
+            })
+            .transpose()?;
+
+        Ok(Self { spec, regex })
+    }
+
+    /// 若规则匹配`subject`，返回展开后的目标路径
+    pub fn apply(&self, subject: &str) -> Option<String> {
+        match self.spec.kind {
+            MatchKind::Exact => {
+                if !eq_ignore_case(subject, self.spec.pattern) {
+                    return None;
+                }
+                Some(expand_target(self.spec.target, |group| {
+                    (group == "0").then(|| subject.to_string())
+                }))
+            }
+            MatchKind::Suffix => {
+                let start = strip_suffix_ignore_case(subject, self.spec.pattern)?;
+                Some(expand_target(self.spec.target, |group| match group {
+                    "0" => Some(subject[start..].to_string()),
+                    "1" => Some(subject[..start].to_string()),
+                    _ => None,
+                }))
+            }
+            MatchKind::Glob | MatchKind::Regex => {
+                let captures = self.regex.as_ref()?.captures(subject)?;
+                Some(expand_target(self.spec.target, |group| {
+                    let m = match group.parse::<usize>() {
+                        Ok(index) => captures.get(index),
+                        Err(_) => captures.name(group),
+                    };
+                    m.map(|m| m.as_str().to_string())
+                }))
+            }
+        }
+    }
+}
+
+/// 按顺序应用规则，返回第一个匹配且被`accept`接受的规则及其目标路径
+pub fn apply_rules<'a>(
+    rules: &'a [Rule],
+    subject: &str,
+    accept: impl Fn(&Rule, &str) -> bool,
+) -> Option<(&'a Rule, String)> {
+    rules.iter().find_map(|rule| {
+        let target = rule.apply(subject)?;
+        accept(rule, &target).then_some((rule, target))
+    })
+}
+
+/// 计算匹配对象，返回`(匹配对象, 清理后的绝对路径)`
+fn to_subject(path: &str) -> Option<(String, PathBuf)> {
+    let normalized = path.replace('\\', "/");
+    let normalized = normalized
+        .strip_prefix("//?/")
+        .or_else(|| normalized.strip_prefix("//./"))
+        .unwrap_or(&normalized);
+
+    let path = Path::new(normalized);
+    let abs_path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        use crate::utils::exts::slice_ext::WideSliceExt;
+
+        crate::utils::win32::get_current_dir(false)
+            .ok()?
+            .to_path_buf()
+            .join(path)
+    };
+    let abs_path = path_clean::clean(&abs_path);
+
+    let abs_str = abs_path.to_string_lossy().replace('\\', "/");
+    let exec_dir = crate::utils::get_executable_dir()
+        .to_string_lossy()
+        .replace('\\', "/");
+    let exec_dir = exec_dir.trim_end_matches('/');
+
+    let subject = abs_str
+        .get(..exec_dir.len())
+        .filter(|prefix| eq_ignore_case(prefix, exec_dir))
+        .and_then(|_| abs_str[exec_dir.len()..].strip_prefix('/'))
+        .unwrap_or(&abs_str)
+        .to_string();
+
+    Some((subject, abs_path))
+}
+
+/// `ResourcePack`规则只在资源包中存在目标文件时生效
+fn accept(rule: &Rule, target: &str) -> bool {
+    if rule.spec.base != TargetBase::ResourcePack {
+        return true;
+    }
+
+    #[cfg(feature = "resource_pack")]
+    {
+        let relative = target.to_lowercase().replace('\\', "/");
+        crate::resource_pack::find_layer(relative.trim_start_matches('/')).is_some()
+    }
+
+    #[cfg(not(feature = "resource_pack"))]
+    {
+        debug!(
+            "Rule {} targets the resource pack, but resource_pack is disabled",
+            rule.spec.pattern
+        );
+        false
+    }
+}
+
+/// 按规则计算重定向后的路径
+fn apply_path(original: &str, subject: &str, abs_path: &Path) -> Option<PathBuf> {
+    let (rule, target) = apply_rules(&RULES, subject, accept)?;
+
+    let target_path = Path::new(&target);
+    let resolved = match rule.spec.base {
+        TargetBase::Exe if target_path.is_absolute() => target_path.to_path_buf(),
+        TargetBase::Exe | TargetBase::ResourcePack => {
+            crate::utils::get_executable_dir().join(target_path)
+        }
+        TargetBase::Source => abs_path.parent()?.join(target_path),
+    };
+
+    debug!(
+        "Redirect {original} -> {} by rule '{}'",
+        resolved.display(),
+        rule.spec.pattern
+    );
+    Some(resolved)
+}
+
+/// 若路径命中某条规则，返回重定向后的绝对路径（以 null 结尾的宽字符串）
+///
+/// 开启`save_redirect`时，未命中规则的路径会再交给存档目录重定向处理
+pub fn redirect_path(path: &[u16]) -> Option<Vec<u16>> {
+    if RULES.is_empty() && !cfg!(feature = "save_redirect") {
+        return None;
+    }
+
+    let original = String::from_utf16_lossy(path);
+    let (subject, abs_path) = to_subject(&original)?;
+
+    let resolved = apply_path(&original, &subject, &abs_path);
+
+    #[cfg(feature = "save_redirect")]
+    let resolved = resolved.or_else(|| {
+        let target = crate::save_redirect::redirect(&abs_path)?;
+        debug!("Redirect {original} -> {} to save dir", target.display());
+        Some(target)
+    });
+
+    let resolved = path_clean::clean(resolved?)
+        .to_string_lossy()
+        .replace('/', "\\");
+
+    Some(resolved.encode_utf16().chain([0]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(kind: MatchKind, pattern: &'static str, regex: Option<&'static str>) -> Rule {
+        Rule::new(RuleSpec {
+            kind,
+            pattern,
+            regex,
+            target: "out/$1|$0",
+            base: TargetBase::Exe,
+        })
+        .unwrap()
+    }
+
+    fn groups(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let pairs: Vec<(String, String)> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |group| {
+            pairs
+                .iter()
+                .find(|(k, _)| k == group)
+                .map(|(_, v)| v.clone())
+        }
+    }
+
+    #[test]
+    fn expand_target_groups() {
+        let get = groups(&[("0", "all"), ("1", "one"), ("12", "twelve"), ("name", "n")]);
+
+        assert_eq!(expand_target("a/$1/b", &get), "a/one/b");
+        assert_eq!(expand_target("$12", &get), "twelve");
+        assert_eq!(expand_target("${1}2", &get), "one2");
+        assert_eq!(expand_target("${name}.txt", &get), "n.txt");
+        assert_eq!(expand_target("$0", &get), "all");
+    }
+
+    #[test]
+    fn expand_target_literals() {
+        let get = groups(&[("1", "one")]);
+
+        assert_eq!(expand_target("$$1", &get), "$1");
+        assert_eq!(expand_target("price$", &get), "price$");
+        assert_eq!(expand_target("$x", &get), "$x");
+        assert_eq!(expand_target("${1", &get), "${1");
+        assert_eq!(expand_target("$2${missing}", &get), "");
+        assert_eq!(expand_target("no groups", &get), "no groups");
+    }
+
+    #[test]
+    fn strip_suffix_ignore_case_positions() {
+        assert_eq!(
+            strip_suffix_ignore_case("data/DATA2.TCD", "data2.tcd"),
+            Some(5)
+        );
+        assert_eq!(strip_suffix_ignore_case("data2.tcd", "DATA2.TCD"), Some(0));
+        assert_eq!(strip_suffix_ignore_case("a.tcd", "data2.tcd"), None);
+        assert_eq!(strip_suffix_ignore_case("data2.tcx", "tcd"), None);
+        assert_eq!(strip_suffix_ignore_case("abc", ""), Some(3));
+        // 多字节字符的起始位置按字节计算
+        assert_eq!(strip_suffix_ignore_case("セーブ/Ä.dat", "ä.DAT"), Some(10));
+    }
+
+    #[test]
+    fn eq_ignore_case_unicode() {
+        assert!(eq_ignore_case("Save/ÄÖ.DAT", "save/äö.dat"));
+        assert!(!eq_ignore_case("save", "saves"));
+        assert!(!eq_ignore_case("", "a"));
+    }
+
+    #[test]
+    fn apply_exact_and_suffix() {
+        let exact = rule(MatchKind::Exact, "Data.pak", None);
+        assert_eq!(exact.apply("data.PAK").as_deref(), Some("out/|data.PAK"));
+        assert_eq!(exact.apply("sub/data.pak"), None);
+
+        let suffix = rule(MatchKind::Suffix, "data.pak", None);
+        assert_eq!(
+            suffix.apply("sub/DATA.pak").as_deref(),
+            Some("out/sub/|DATA.pak")
+        );
+        assert_eq!(suffix.apply("data.pak.bak"), None);
+    }
+
+    #[test]
+    fn apply_regex_captures() {
+        let regex = rule(
+            MatchKind::Regex,
+            r"^save/(\d+)\.dat$",
+            Some(r"^save/(\d+)\.dat$"),
+        );
+        assert_eq!(
+            regex.apply("SAVE/12.dat").as_deref(),
+            Some("out/12|SAVE/12.dat")
+        );
+        assert_eq!(regex.apply("save/ab.dat"), None);
+
+        let glob = rule(
+            MatchKind::Glob,
+            "voice/*.ogg",
+            Some(r"^voice/([^/]*)\.ogg$"),
+        );
+        assert_eq!(
+            glob.apply("voice/a01.ogg").as_deref(),
+            Some("out/a01|voice/a01.ogg")
+        );
+    }
+
+    #[test]
+    fn apply_rules_uses_first_accepted_match() {
+        let rules = [
+            rule(MatchKind::Suffix, ".ogg", None),
+            rule(
+                MatchKind::Glob,
+                "voice/*.ogg",
+                Some(r"^voice/([^/]*)\.ogg$"),
+            ),
+            rule(MatchKind::Exact, "bgm.ogg", None),
+        ];
+
+        let (matched, target) = apply_rules(&rules, "voice/a.ogg", |_, _| true).unwrap();
+        assert_eq!(matched.spec.kind, MatchKind::Suffix);
+        assert_eq!(target, "out/voice/a|.ogg");
+
+        // 被拒绝的规则会跳过，继续尝试后面的规则
+        let (matched, target) = apply_rules(&rules, "voice/a.ogg", |rule, _| {
+            rule.spec.kind != MatchKind::Suffix
+        })
+        .unwrap();
+        assert_eq!(matched.spec.kind, MatchKind::Glob);
+        assert_eq!(target, "out/a|voice/a.ogg");
+
+        assert!(apply_rules(&rules, "voice/a.wav", |_, _| true).is_none());
+        assert!(apply_rules(&[], "bgm.ogg", |_, _| true).is_none());
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        let spec = RuleSpec {
+            kind: MatchKind::Regex,
+            pattern: "(",
+            regex: Some("("),
+            target: "",
+            base: TargetBase::Exe,
+        };
+        assert!(Rule::new(spec).is_err());
+    }
+}
@@ -0,0 +1,582 @@
+//! 注册表虚拟化
+//!
+//! 根据`assets/registry.json`中声明的键，接管`RegOpenKeyEx`、`RegCreateKeyEx`、`RegQueryValueEx`、
+//! `RegSetValueEx`、`RegCloseKey`与`RegEnumKey(Ex)`：
+//!
+//! - `redirect`：键及其子键被重定向到另一个真实的键（默认为 HKCU 下的同名路径），之后的操作都作用于真实的键
+//! - `store`：键及其子键保存在exe目录下的 JSON 存储中，配置中的值作为默认值，每次写入后立即保存
+//! - `fake`：键只包含配置中的常量值，写入会被忽略
+//!
+//! `store`与`fake`的键使用虚拟句柄（见`crate::utils::win32::create_virtual_handle`），不会访问真实的注册表。
+//! 若虚拟键的祖先在真实注册表中不存在，打开祖先时也会返回虚拟句柄，以便游戏逐级打开
+
+mod rules;
+mod store;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+
+use windows_sys::Win32::Foundation::{
+    CloseHandle, ERROR_ACCESS_DENIED, ERROR_FILE_NOT_FOUND, ERROR_INVALID_PARAMETER,
+    ERROR_MORE_DATA, ERROR_NO_MORE_ITEMS, ERROR_SUCCESS, WIN32_ERROR,
+};
+use windows_sys::Win32::Security::SECURITY_ATTRIBUTES;
+use windows_sys::Win32::System::Registry::{
+    HKEY, HKEY_CLASSES_ROOT, HKEY_CURRENT_CONFIG, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE,
+    HKEY_USERS, REG_CREATED_NEW_KEY, REG_OPENED_EXISTING_KEY,
+};
+use windows_sys::core::PCWSTR;
+use windows_sys::w;
+
+use crate::debug;
+use crate::hook::traits::registry_hook::{HOOK_REG_CREATE_KEY_EX_W, HOOK_REG_OPEN_KEY_EX_W};
+use crate::utils::exts::slice_ext::{ByteSliceExt, WideSliceExt};
+
+use rules::{
+    CONFIG, KeyMode, KeySpec, RegRoot, ValueSpec, child_names, is_ancestor, join_path, match_key,
+};
+use store::{RegData, RegStore, write_value};
+
+/// 虚拟句柄对应的键
+#[derive(Debug, Clone)]
+struct VirtualKey {
+    root: RegRoot,
+    /// 规范化后的子键路径
+    path: String,
+    /// 命中的键在`CONFIG.keys`中的下标，为`None`时表示虚拟键的祖先
+    index: Option<usize>,
+}
+
+impl VirtualKey {
+    fn spec(&self) -> Option<&'static KeySpec> {
+        self.index.map(|index| &CONFIG.keys[index])
+    }
+
+    /// 在存储中的完整路径
+    fn store_path(&self) -> String {
+        join_path(self.root.name(), &self.path)
+    }
+}
+
+struct State {
+    store: RegStore,
+    /// 虚拟句柄 -> 键
+    handles: HashMap<usize, VirtualKey>,
+    /// 通向虚拟键的真实句柄 -> (根键, 子键路径)，用于解析相对于它们打开的子键
+    tracked: HashMap<usize, (RegRoot, String)>,
+}
+
+static STATE: LazyLock<Mutex<State>> = LazyLock::new(|| {
+    Mutex::new(State {
+        store: load_store(),
+        handles: HashMap::new(),
+        tracked: HashMap::new(),
+    })
+});
+
+/// 路径的处理方式
+enum Target {
+    /// 被重定向到的真实键
+    Redirect(RegRoot, String),
+    /// `store`或`fake`中的键
+    Virtual(VirtualKey),
+    /// 虚拟键的祖先，优先打开真实的键
+    Ancestor(RegRoot, String),
+    /// 与虚拟化无关的真实键
+    Real(RegRoot, String),
+}
+
+/// 加载`advapi32.dll`，否则游戏未导入它时无法创建`registry_hook`中的钩子
+///
+/// 模块在进程结束前不再释放，保证钩子地址始终有效
+pub fn init() -> crate::Result<()> {
+    let module = crate::utils::win32::load_library(w!("advapi32.dll"))?;
+    core::mem::forget(module);
+    Ok(())
+}
+
+fn store_file() -> PathBuf {
+    crate::utils::get_executable_dir().join(CONFIG.store_file)
+}
+
+fn exe_dir() -> String {
+    crate::utils::get_executable_dir()
+        .to_string_lossy()
+        .trim_end_matches('\\')
+        .to_string()
+}
+
+/// 加载存储，并写入`store`键的默认值
+fn load_store() -> RegStore {
+    let path = store_file();
+    let mut store = match std::fs::read_to_string(&path) {
+        Ok(json) => RegStore::from_json(&json).unwrap_or_else(|e| {
+            debug!("Failed to parse registry store {}: {e}", path.display());
+            RegStore::default()
+        }),
+        Err(_) => RegStore::default(),
+    };
+
+    let exe_dir = exe_dir();
+    for key in CONFIG.keys {
+        if let KeyMode::Store(values) = key.mode {
+            let path = join_path(key.root.name(), key.path);
+            store.ensure_key(&path);
+            for value in values {
+                store.set_default(&path, value.name, RegData::from_spec(&value.data, &exe_dir));
+            }
+        }
+    }
+
+    store
+}
+
+/// 将存储写入临时文件后再重命名，失败时只输出调试信息
+fn save_store(store: &RegStore) {
+    let save = || -> crate::Result<()> {
+        let path = store_file();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&temp_path, store.to_json()?)?;
+        if let Err(e) = std::fs::rename(&temp_path, &path) {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
+        Ok(())
+    };
+
+    if save().is_err() {
+        debug!("Failed to save registry store {}", store_file().display());
+    }
+}
+
+const ROOTS: [(HKEY, RegRoot); 5] = [
+    (HKEY_CLASSES_ROOT, RegRoot::ClassesRoot),
+    (HKEY_CURRENT_USER, RegRoot::CurrentUser),
+    (HKEY_LOCAL_MACHINE, RegRoot::LocalMachine),
+    (HKEY_USERS, RegRoot::Users),
+    (HKEY_CURRENT_CONFIG, RegRoot::CurrentConfig),
+];
+
+fn root_hkey(root: RegRoot) -> HKEY {
+    ROOTS.iter().find(|(_, r)| *r == root).unwrap().0
+}
+
+/// 解析`hkey`与`sub_key`对应的完整路径，`hkey`未知时返回`None`
+fn locate(state: &State, hkey: HKEY, sub_key: &str) -> Option<(RegRoot, String)> {
+    if let Some((_, root)) = ROOTS.iter().find(|(h, _)| *h == hkey) {
+        return Some((*root, join_path("", sub_key)));
+    }
+    if let Some(key) = state.handles.get(&(hkey as usize)) {
+        return Some((key.root, join_path(&key.path, sub_key)));
+    }
+    let (root, path) = state.tracked.get(&(hkey as usize))?;
+    Some((*root, join_path(path, sub_key)))
+}
+
+fn classify(root: RegRoot, path: String) -> Target {
+    let Some((index, rest)) = match_key(CONFIG.keys, root, &path) else {
+        return match is_ancestor(CONFIG.keys, root, &path) {
+            true => Target::Ancestor(root, path),
+            false => Target::Real(root, path),
+        };
+    };
+
+    match CONFIG.keys[index].mode {
+        KeyMode::Redirect {
+            root: target_root,
+            path: target_path,
+        } => Target::Redirect(target_root, join_path(target_path, rest)),
+        KeyMode::Store(_) | KeyMode::Fake(_) => Target::Virtual(VirtualKey {
+            root,
+            path,
+            index: Some(index),
+        }),
+    }
+}
+
+/// 键是否存在，`fake`的键没有子键
+fn virtual_key_exists(state: &State, key: &VirtualKey) -> bool {
+    match key.spec().map(|spec| spec.mode) {
+        Some(KeyMode::Store(_)) => state.store.key_exists(&key.store_path()),
+        Some(KeyMode::Fake(_)) => key.path.eq_ignore_ascii_case(key.spec().unwrap().path),
+        _ => true,
+    }
+}
+
+/// 为虚拟键创建句柄并写入`result`
+unsafe fn open_virtual(state: &mut State, key: VirtualKey, result: *mut HKEY) -> WIN32_ERROR {
+    let Some(handle) = crate::utils::win32::create_virtual_handle() else {
+        return ERROR_ACCESS_DENIED;
+    };
+
+    debug!("Open virtual registry key {}", key.store_path());
+    state.handles.insert(handle as usize, key);
+    unsafe { *result = handle as HKEY };
+    ERROR_SUCCESS
+}
+
+/// 记录通向虚拟键的真实句柄
+fn track(root: RegRoot, path: String, hkey: HKEY) {
+    STATE
+        .lock()
+        .unwrap()
+        .tracked
+        .insert(hkey as usize, (root, path));
+}
+
+/// 调用原始的`RegOpenKeyExW`
+unsafe fn open_real(
+    root: RegRoot,
+    path: &str,
+    options: u32,
+    sam: u32,
+    result: *mut HKEY,
+) -> WIN32_ERROR {
+    let path: Vec<u16> = path.encode_utf16().chain([0]).collect();
+    unsafe {
+        crate::call!(
+            HOOK_REG_OPEN_KEY_EX_W,
+            root_hkey(root),
+            path.as_ptr(),
+            options,
+            sam,
+            result,
+        )
+    }
+}
+
+/// 处理`RegOpenKeyEx`，返回`None`时应调用原函数
+///
+/// # Safety
+/// `result`必须可写
+pub unsafe fn open_key(
+    hkey: HKEY,
+    sub_key: &[u16],
+    options: u32,
+    sam: u32,
+    result: *mut HKEY,
+) -> Option<WIN32_ERROR> {
+    if CONFIG.keys.is_empty() || result.is_null() {
+        return None;
+    }
+
+    let mut state = STATE.lock().unwrap();
+    let is_virtual = state.handles.contains_key(&(hkey as usize));
+    let (root, path) = locate(&state, hkey, &String::from_utf16_lossy(sub_key))?;
+
+    match classify(root, path) {
+        Target::Redirect(root, path) => {
+            drop(state);
+            debug!("Redirect registry key to {}\\{path}", root.name());
+            Some(unsafe { open_real(root, &path, options, sam, result) })
+        }
+        Target::Virtual(key) => Some(match virtual_key_exists(&state, &key) {
+            true => unsafe { open_virtual(&mut state, key, result) },
+            false => ERROR_FILE_NOT_FOUND,
+        }),
+        Target::Ancestor(root, path) => {
+            drop(state);
+            let code = unsafe { open_real(root, &path, options, sam, result) };
+            if code == ERROR_SUCCESS {
+                track(root, path, unsafe { *result });
+                return Some(code);
+            }
+
+            let key = VirtualKey {
+                root,
+                path,
+                index: None,
+            };
+            Some(unsafe { open_virtual(&mut STATE.lock().unwrap(), key, result) })
+        }
+        // 虚拟句柄不能传给原函数，改用完整路径打开
+        Target::Real(root, path) if is_virtual => {
+            drop(state);
+            Some(unsafe { open_real(root, &path, options, sam, result) })
+        }
+        Target::Real(..) => None,
+    }
+}
+
+/// 处理`RegCreateKeyEx`，返回`None`时应调用原函数
+///
+/// # Safety
+/// `result`必须可写，`disposition`可以为空
+pub unsafe fn create_key(
+    hkey: HKEY,
+    sub_key: &[u16],
+    class: PCWSTR,
+    options: u32,
+    sam: u32,
+    security_attributes: *const SECURITY_ATTRIBUTES,
+    result: *mut HKEY,
+    disposition: *mut u32,
+) -> Option<WIN32_ERROR> {
+    if CONFIG.keys.is_empty() || result.is_null() {
+        return None;
+    }
+
+    let create_real = |root: RegRoot, path: &str| {
+        let path: Vec<u16> = path.encode_utf16().chain([0]).collect();
+        unsafe {
+            crate::call!(
+                HOOK_REG_CREATE_KEY_EX_W,
+                root_hkey(root),
+                path.as_ptr(),
+                0,
+                class,
+                options,
+                sam,
+                security_attributes,
+                result,
+                disposition,
+            )
+        }
+    };
+    let set_disposition = |value: u32| {
+        if !disposition.is_null() {
+            unsafe { *disposition = value };
+        }
+    };
+
+    let mut state = STATE.lock().unwrap();
+    let is_virtual = state.handles.contains_key(&(hkey as usize));
+    let (root, path) = locate(&state, hkey, &String::from_utf16_lossy(sub_key))?;
+
+    match classify(root, path) {
+        Target::Redirect(root, path) => {
+            drop(state);
+            debug!("Redirect registry key to {}\\{path}", root.name());
+            Some(create_real(root, &path))
+        }
+        Target::Virtual(key) => {
+            let created = match key.spec().map(|spec| spec.mode) {
+                Some(KeyMode::Store(_)) => {
+                    let created = state.store.ensure_key(&key.store_path());
+                    if created {
+                        save_store(&state.store);
+                    }
+                    created
+                }
+                _ if virtual_key_exists(&state, &key) => false,
+                // `fake`的键是只读的，不能创建子键
+                _ => return Some(ERROR_ACCESS_DENIED),
+            };
+
+            let code = unsafe { open_virtual(&mut state, key, result) };
+            if code == ERROR_SUCCESS {
+                set_disposition(match created {
+                    true => REG_CREATED_NEW_KEY,
+                    false => REG_OPENED_EXISTING_KEY,
+                });
+            }
+            Some(code)
+        }
+        // 祖先通常位于 HKLM 下，创建真实的键需要管理员权限，因此只尝试打开
+        Target::Ancestor(root, path) => {
+            drop(state);
+            let code = unsafe { open_real(root, &path, 0, sam, result) };
+            if code == ERROR_SUCCESS {
+                track(root, path, unsafe { *result });
+                set_disposition(REG_OPENED_EXISTING_KEY);
+                return Some(code);
+            }
+
+            let key = VirtualKey {
+                root,
+                path,
+                index: None,
+            };
+            let code = unsafe { open_virtual(&mut STATE.lock().unwrap(), key, result) };
+            if code == ERROR_SUCCESS {
+                set_disposition(REG_OPENED_EXISTING_KEY);
+            }
+            Some(code)
+        }
+        Target::Real(root, path) if is_virtual => {
+            drop(state);
+            Some(create_real(root, &path))
+        }
+        Target::Real(..) => None,
+    }
+}
+
+/// 使用 ANSI 代码页编码字符串
+fn encode_ansi(s: &str) -> Vec<u8> {
+    s.encode_utf16().collect::<Vec<_>>().to_ansi()
+}
+
+/// 使用 ANSI 代码页解码字符串
+fn decode_ansi(bytes: &[u8]) -> String {
+    String::from_utf16_lossy(&bytes.to_wide_ansi())
+}
+
+/// 查找`fake`键中的值
+fn find_fake_value(values: &[ValueSpec], name: &str) -> Option<RegData> {
+    values
+        .iter()
+        .find(|value| value.name.eq_ignore_ascii_case(name))
+        .map(|value| RegData::from_spec(&value.data, &exe_dir()))
+}
+
+/// 处理虚拟句柄上的`RegQueryValueEx`，返回`None`时应调用原函数
+///
+/// `wide`为`false`时字符串使用 ANSI 代码页，返回的大小与缓冲区的处理与 Win32 一致：
+/// `data`为空时只返回所需的大小，缓冲区不足时返回`ERROR_MORE_DATA`并写入所需的大小
+///
+/// # Safety
+/// `kind`、`data`、`size`可以为空，`data`非空时必须可以写入`*size`字节
+pub unsafe fn query_value(
+    hkey: HKEY,
+    name: &[u16],
+    wide: bool,
+    kind: *mut u32,
+    data: *mut u8,
+    size: *mut u32,
+) -> Option<WIN32_ERROR> {
+    if CONFIG.keys.is_empty() {
+        return None;
+    }
+
+    let state = STATE.lock().unwrap();
+    let key = state.handles.get(&(hkey as usize))?;
+    let name = String::from_utf16_lossy(name);
+
+    let value = match key.spec().map(|spec| spec.mode) {
+        Some(KeyMode::Store(_)) => state.store.get_value(&key.store_path(), &name).cloned(),
+        Some(KeyMode::Fake(values)) => find_fake_value(values, &name),
+        _ => None,
+    };
+    drop(state);
+
+    let Some(value) = value else {
+        return Some(ERROR_FILE_NOT_FOUND);
+    };
+
+    let bytes = value.to_bytes(wide, encode_ansi);
+    if !kind.is_null() {
+        unsafe { *kind = value.kind() };
+    }
+
+    let size = unsafe { size.as_mut() };
+    let data = (!data.is_null()).then(|| {
+        let len = size.as_deref().map_or(0, |size| *size as usize);
+        unsafe { core::slice::from_raw_parts_mut(data, len) }
+    });
+    Some(write_value(&bytes, data, size))
+}
+
+/// 处理虚拟句柄上的`RegSetValueEx`，返回`None`时应调用原函数
+pub fn set_value(
+    hkey: HKEY,
+    name: &[u16],
+    kind: u32,
+    data: &[u8],
+    wide: bool,
+) -> Option<WIN32_ERROR> {
+    if CONFIG.keys.is_empty() {
+        return None;
+    }
+
+    let mut state = STATE.lock().unwrap();
+    let key = state.handles.get(&(hkey as usize))?.clone();
+    let name = String::from_utf16_lossy(name);
+
+    Some(match key.spec().map(|spec| spec.mode) {
+        Some(KeyMode::Store(_)) => {
+            let value = RegData::from_bytes(kind, data, wide, decode_ansi);
+            debug!(
+                "Set registry value {}\\{name} = {value:?}",
+                key.store_path()
+            );
+            state.store.set_value(&key.store_path(), &name, value);
+            save_store(&state.store);
+            ERROR_SUCCESS
+        }
+        Some(KeyMode::Fake(_)) => {
+            debug!(
+                "Ignore write to fake registry value {}\\{name}",
+                key.store_path()
+            );
+            ERROR_SUCCESS
+        }
+        _ => ERROR_ACCESS_DENIED,
+    })
+}
+
+/// 处理`RegCloseKey`，返回`None`时应调用原函数
+pub fn close_key(hkey: HKEY) -> Option<WIN32_ERROR> {
+    if CONFIG.keys.is_empty() {
+        return None;
+    }
+
+    let mut state = STATE.lock().unwrap();
+    if state.handles.remove(&(hkey as usize)).is_some() {
+        unsafe { CloseHandle(hkey as _) };
+        return Some(ERROR_SUCCESS);
+    }
+
+    state.tracked.remove(&(hkey as usize));
+    None
+}
+
+/// 处理虚拟句柄上的`RegEnumKey(Ex)`，返回`None`时应调用原函数
+///
+/// `capacity`为缓冲区的字符数（包括结尾的 null），成功时返回写入的字符数（不包括 null），
+/// 缓冲区不足时返回`ERROR_MORE_DATA`
+///
+/// # Safety
+/// `name`必须可以写入`capacity`个字符（`wide`为`true`时为宽字符，否则为字节）
+pub unsafe fn enum_key(
+    hkey: HKEY,
+    index: u32,
+    wide: bool,
+    name: *mut u8,
+    capacity: u32,
+) -> Option<Result<u32, WIN32_ERROR>> {
+    if CONFIG.keys.is_empty() {
+        return None;
+    }
+
+    let state = STATE.lock().unwrap();
+    let key = state.handles.get(&(hkey as usize))?;
+    let names = match key.spec().map(|spec| spec.mode) {
+        Some(KeyMode::Store(_)) => state.store.subkeys(&key.store_path()),
+        Some(_) => Vec::new(),
+        None => child_names(CONFIG.keys, key.root, &key.path),
+    };
+    drop(state);
+
+    let Some(found) = names.get(index as usize) else {
+        return Some(Err(ERROR_NO_MORE_ITEMS));
+    };
+    if name.is_null() {
+        return Some(Err(ERROR_INVALID_PARAMETER));
+    }
+
+    unsafe {
+        if wide {
+            let found: Vec<u16> = found.encode_utf16().collect();
+            if found.len() >= capacity as usize {
+                return Some(Err(ERROR_MORE_DATA));
+            }
+            let name = name as *mut u16;
+            core::ptr::copy_nonoverlapping(found.as_ptr(), name, found.len());
+            *name.add(found.len()) = 0;
+            Some(Ok(found.len() as u32))
+        } else {
+            let found = encode_ansi(found);
+            if found.len() >= capacity as usize {
+                return Some(Err(ERROR_MORE_DATA));
+            }
+            core::ptr::copy_nonoverlapping(found.as_ptr(), name, found.len());
+            *name.add(found.len()) = 0;
+            Some(Ok(found.len() as u32))
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! `assets/registry.json` 生成的键规则，以及键路径的匹配
+
+/// 根键
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegRoot {
+    ClassesRoot,
+    CurrentUser,
+    LocalMachine,
+    Users,
+    CurrentConfig,
+}
+
+impl RegRoot {
+    /// 根键的完整名称，如`HKEY_LOCAL_MACHINE`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ClassesRoot => "HKEY_CLASSES_ROOT",
+            Self::CurrentUser => "HKEY_CURRENT_USER",
+            Self::LocalMachine => "HKEY_LOCAL_MACHINE",
+            Self::Users => "HKEY_USERS",
+            Self::CurrentConfig => "HKEY_CURRENT_CONFIG",
+        }
+    }
+}
+
+/// 配置中的值数据，字符串中的`{exe_dir}`会被替换为exe所在目录
+#[derive(Debug, Clone, Copy)]
+pub enum DataSpec {
+    Sz(&'static str),
+    ExpandSz(&'static str),
+    MultiSz(&'static [&'static str]),
+    Dword(u32),
+    Qword(u64),
+    Binary(&'static [u8]),
+}
+
+/// 配置中的一个值
+#[derive(Debug, Clone, Copy)]
+pub struct ValueSpec {
+    pub name: &'static str,
+    pub data: DataSpec,
+}
+
+/// 键的虚拟化方式
+#[derive(Debug, Clone, Copy)]
+pub enum KeyMode {
+    /// 重定向到另一个真实的键，子键也会一并重定向
+    Redirect { root: RegRoot, path: &'static str },
+    /// 保存在exe目录下的 JSON 存储中，给出的值仅作为默认值
+    Store(&'static [ValueSpec]),
+    /// 只读的常量值，写入会被忽略
+    Fake(&'static [ValueSpec]),
+}
+
+/// 配置中的一个键
+#[derive(Debug, Clone, Copy)]
+pub struct KeySpec {
+    pub root: RegRoot,
+    /// 以`\`分隔的子键路径
+    pub path: &'static str,
+    pub mode: KeyMode,
+}
+
+/// `assets/registry.json` 的内容
+#[derive(Debug, Clone, Copy)]
+pub struct RegistryConfig {
+    /// 外置存储的文件路径，相对于exe目录
+    pub store_file: &'static str,
+    pub keys: &'static [KeySpec],
+}
+
+pub static CONFIG: RegistryConfig =
+    translate_macros::generate_registry_rules!("assets/registry.json");
+
+/// 拼接并规范化子键路径，去掉多余的分隔符，`/`不视为分隔符
+pub fn join_path(base: &str, sub: &str) -> String {
+    base.split('\\')
+        .chain(sub.split('\\'))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\\")
+}
+
+/// 若`path`等于`prefix`或位于其下，返回剩余的部分（不区分 ASCII 大小写）
+pub fn strip_key_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    if prefix.is_empty() {
+        return Some(path);
+    }
+
+    let head = path.get(..prefix.len())?;
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+
+    match &path[prefix.len()..] {
+        "" => Some(""),
+        rest => rest.strip_prefix('\\'),
+    }
+}
+
+/// 查找规范化后的路径命中的键，路径越长优先级越高，返回键的下标与剩余的子键路径
+pub fn match_key<'a>(keys: &[KeySpec], root: RegRoot, path: &'a str) -> Option<(usize, &'a str)> {
+    keys.iter()
+        .enumerate()
+        .filter(|(_, key)| key.root == root)
+        .filter_map(|(index, key)| Some((index, strip_key_prefix(path, key.path)?)))
+        .max_by_key(|(index, _)| keys[*index].path.len())
+}
+
+/// 路径是否为某个键的祖先（不包括键本身）
+pub fn is_ancestor(keys: &[KeySpec], root: RegRoot, path: &str) -> bool {
+    keys.iter().any(|key| {
+        key.root == root && strip_key_prefix(key.path, path).is_some_and(|rest| !rest.is_empty())
+    })
+}
+
+/// 位于`path`下一级、通向某个键的子键名，按名称排序并去重（不区分 ASCII 大小写）
+pub fn child_names(keys: &[KeySpec], root: RegRoot, path: &str) -> Vec<String> {
+    let mut names: Vec<String> = keys
+        .iter()
+        .filter(|key| key.root == root)
+        .filter_map(|key| strip_key_prefix(key.path, path))
+        .filter_map(|rest| rest.split('\\').next())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    names.sort_by_key(|name| name.to_ascii_lowercase());
+    names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn key(root: RegRoot, path: &'static str) -> KeySpec {
+        KeySpec {
+            root,
+            path,
+            mode: KeyMode::Fake(&[]),
+        }
+    }
+
+    const KEYS: &[KeySpec] = &[
+        key(RegRoot::LocalMachine, "Software\\Vendor\\Game"),
+        key(RegRoot::LocalMachine, "Software\\Vendor\\Game\\Settings"),
+        key(RegRoot::LocalMachine, "Software\\Vendor\\Tool"),
+        key(RegRoot::CurrentUser, "Software\\Vendor\\Game"),
+    ];
+
+    #[test]
+    fn join_path_normalizes_separators() {
+        assert_eq!(
+            join_path("Software\\", "\\Vendor\\\\Game\\"),
+            "Software\\Vendor\\Game"
+        );
+        assert_eq!(join_path("", ""), "");
+        assert_eq!(join_path("", "a/b"), "a/b");
+    }
+
+    #[test]
+    fn strip_key_prefix_on_boundaries() {
+        assert_eq!(
+            strip_key_prefix("Software\\Vendor", ""),
+            Some("Software\\Vendor")
+        );
+        assert_eq!(
+            strip_key_prefix("SOFTWARE\\Vendor", "software"),
+            Some("Vendor")
+        );
+        assert_eq!(strip_key_prefix("Software", "software"), Some(""));
+        assert_eq!(strip_key_prefix("SoftwareX", "software"), None);
+        assert_eq!(strip_key_prefix("Soft", "software"), None);
+    }
+
+    #[test]
+    fn match_key_prefers_longest() {
+        let root = RegRoot::LocalMachine;
+        assert_eq!(
+            match_key(KEYS, root, "software\\vendor\\game\\settings\\video"),
+            Some((1, "video"))
+        );
+        assert_eq!(
+            match_key(KEYS, root, "Software\\Vendor\\Game\\Save"),
+            Some((0, "Save"))
+        );
+        assert_eq!(
+            match_key(KEYS, root, "Software\\Vendor\\Game"),
+            Some((0, ""))
+        );
+        assert_eq!(match_key(KEYS, root, "Software\\Vendor\\GameX"), None);
+        assert_eq!(match_key(KEYS, root, "Software\\Vendor"), None);
+        assert_eq!(
+            match_key(KEYS, RegRoot::CurrentUser, "Software\\Vendor\\Game"),
+            Some((3, ""))
+        );
+        assert_eq!(
+            match_key(KEYS, RegRoot::Users, "Software\\Vendor\\Game"),
+            None
+        );
+    }
+
+    #[test]
+    fn ancestors_and_children() {
+        let root = RegRoot::LocalMachine;
+        assert!(is_ancestor(KEYS, root, ""));
+        assert!(is_ancestor(KEYS, root, "SOFTWARE\\vendor"));
+        assert!(!is_ancestor(KEYS, root, "Software\\Vendor\\Tool"));
+        assert!(!is_ancestor(KEYS, root, "Software\\Other"));
+        assert!(!is_ancestor(KEYS, RegRoot::Users, "Software"));
+
+        assert_eq!(child_names(KEYS, root, ""), ["Software"]);
+        assert_eq!(
+            child_names(KEYS, root, "software\\vendor"),
+            ["Game", "Tool"]
+        );
+        assert_eq!(
+            child_names(KEYS, root, "Software\\Vendor\\Game"),
+            ["Settings"]
+        );
+        assert!(child_names(KEYS, root, "Software\\Vendor\\Tool").is_empty());
+    }
+}
@@ -0,0 +1,457 @@
+//! 外置的注册表存储，以及注册表值与原始字节之间的转换
+//!
+//! 存储文件的格式为`{ "HKEY_LOCAL_MACHINE\\Software\\...": { "值名": { "type": "sz", "data": "..." } } }`，
+//! 键名与值名均不区分 ASCII 大小写，但会保留第一次写入时的大小写
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use windows_sys::Win32::Foundation::{
+    ERROR_INVALID_PARAMETER, ERROR_MORE_DATA, ERROR_SUCCESS, WIN32_ERROR,
+};
+
+use crate::registry_virtualization::rules::DataSpec;
+
+pub const REG_SZ: u32 = 1;
+pub const REG_EXPAND_SZ: u32 = 2;
+pub const REG_BINARY: u32 = 3;
+pub const REG_DWORD: u32 = 4;
+pub const REG_MULTI_SZ: u32 = 7;
+pub const REG_QWORD: u32 = 11;
+
+/// 注册表值
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum RegData {
+    Sz(String),
+    ExpandSz(String),
+    MultiSz(Vec<String>),
+    Dword(u32),
+    Qword(u64),
+    Binary(Vec<u8>),
+    /// 其他类型，按原始字节保存
+    Raw {
+        kind: u32,
+        bytes: Vec<u8>,
+    },
+}
+
+impl RegData {
+    /// 由配置中的值生成，字符串中的`{exe_dir}`会被替换为`exe_dir`
+    pub fn from_spec(spec: &DataSpec, exe_dir: &str) -> Self {
+        let expand = |s: &str| s.replace("{exe_dir}", exe_dir);
+        match *spec {
+            DataSpec::Sz(s) => Self::Sz(expand(s)),
+            DataSpec::ExpandSz(s) => Self::ExpandSz(expand(s)),
+            DataSpec::MultiSz(items) => Self::MultiSz(items.iter().map(|s| expand(s)).collect()),
+            DataSpec::Dword(n) => Self::Dword(n),
+            DataSpec::Qword(n) => Self::Qword(n),
+            DataSpec::Binary(bytes) => Self::Binary(bytes.to_vec()),
+        }
+    }
+
+    /// 对应的`REG_*`类型
+    pub fn kind(&self) -> u32 {
+        match self {
+            Self::Sz(_) => REG_SZ,
+            Self::ExpandSz(_) => REG_EXPAND_SZ,
+            Self::MultiSz(_) => REG_MULTI_SZ,
+            Self::Dword(_) => REG_DWORD,
+            Self::Qword(_) => REG_QWORD,
+            Self::Binary(_) => REG_BINARY,
+            Self::Raw { kind, .. } => *kind,
+        }
+    }
+
+    /// 转换为`RegQueryValueEx`返回的字节
+    ///
+    /// 字符串包含结尾的 null，`multi_sz`以两个 null 结尾。
+    /// `wide`为`true`时字符串使用 UTF-16LE，否则使用`encode`编码
+    pub fn to_bytes(&self, wide: bool, encode: impl Fn(&str) -> Vec<u8>) -> Vec<u8> {
+        let string = |s: &str| -> Vec<u8> {
+            if wide {
+                s.encode_utf16()
+                    .chain([0])
+                    .flat_map(u16::to_le_bytes)
+                    .collect()
+            } else {
+                let mut bytes = encode(s);
+                bytes.push(0);
+                bytes
+            }
+        };
+
+        match self {
+            Self::Sz(s) | Self::ExpandSz(s) => string(s),
+            Self::MultiSz(items) => {
+                let mut bytes: Vec<u8> = items.iter().flat_map(|s| string(s)).collect();
+                bytes.extend(string(""));
+                bytes
+            }
+            Self::Dword(n) => n.to_le_bytes().to_vec(),
+            Self::Qword(n) => n.to_le_bytes().to_vec(),
+            Self::Binary(bytes) | Self::Raw { bytes, .. } => bytes.clone(),
+        }
+    }
+
+    /// 由`RegSetValueEx`传入的类型与字节生成
+    ///
+    /// 字符串允许缺少结尾的 null，`wide`为`true`时按 UTF-16LE 解析，否则使用`decode`解码。
+    /// 长度与类型不符的`dword`与`qword`按原始字节保存
+    pub fn from_bytes(
+        kind: u32,
+        bytes: &[u8],
+        wide: bool,
+        decode: impl Fn(&[u8]) -> String,
+    ) -> Self {
+        let strings = || -> Vec<String> {
+            if wide {
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect();
+                units
+                    .split(|c| *c == 0)
+                    .map(String::from_utf16_lossy)
+                    .collect()
+            } else {
+                bytes.split(|c| *c == 0).map(&decode).collect()
+            }
+        };
+        let first = || strings().into_iter().next().unwrap_or_default();
+
+        match kind {
+            REG_SZ => Self::Sz(first()),
+            REG_EXPAND_SZ => Self::ExpandSz(first()),
+            REG_MULTI_SZ => Self::MultiSz(
+                strings()
+                    .into_iter()
+                    .take_while(|s| !s.is_empty())
+                    .collect(),
+            ),
+            REG_DWORD if bytes.len() == 4 => {
+                Self::Dword(u32::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            REG_QWORD if bytes.len() == 8 => {
+                Self::Qword(u64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            REG_BINARY => Self::Binary(bytes.to_vec()),
+            _ => Self::Raw {
+                kind,
+                bytes: bytes.to_vec(),
+            },
+        }
+    }
+}
+
+/// 按`RegQueryValueEx`的规则将值的字节写入缓冲区
+///
+/// `size`为空时`data`也必须为空；`size`非空时总是写入所需的大小，
+/// `data`为空时只返回所需的大小，缓冲区不足时返回`ERROR_MORE_DATA`
+pub fn write_value(bytes: &[u8], data: Option<&mut [u8]>, size: Option<&mut u32>) -> WIN32_ERROR {
+    let Some(size) = size else {
+        return match data {
+            Some(_) => ERROR_INVALID_PARAMETER,
+            None => ERROR_SUCCESS,
+        };
+    };
+
+    *size = bytes.len() as u32;
+    let Some(data) = data else {
+        return ERROR_SUCCESS;
+    };
+    let Some(data) = data.get_mut(..bytes.len()) else {
+        return ERROR_MORE_DATA;
+    };
+    data.copy_from_slice(bytes);
+    ERROR_SUCCESS
+}
+
+/// 存储中的一个键
+#[derive(Debug, Clone, Default)]
+struct StoredKey {
+    /// 保留大小写的完整路径
+    path: String,
+    /// 小写值名 -> (保留大小写的值名, 值)
+    values: BTreeMap<String, (String, RegData)>,
+}
+
+/// 外置的注册表存储
+#[derive(Debug, Clone, Default)]
+pub struct RegStore {
+    /// 小写的完整路径 -> 键
+    keys: BTreeMap<String, StoredKey>,
+}
+
+impl RegStore {
+    /// 解析存储文件
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let raw: BTreeMap<String, BTreeMap<String, RegData>> = serde_json::from_str(json)?;
+
+        let mut store = Self::default();
+        for (path, values) in raw {
+            store.ensure_key(&path);
+            for (name, data) in values {
+                store.set_value(&path, &name, data);
+            }
+        }
+        Ok(store)
+    }
+
+    /// 序列化为存储文件
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let raw: BTreeMap<&str, BTreeMap<&str, &RegData>> = self
+            .keys
+            .values()
+            .map(|key| {
+                let values = key
+                    .values
+                    .values()
+                    .map(|(name, data)| (name.as_str(), data))
+                    .collect();
+                (key.path.as_str(), values)
+            })
+            .collect();
+
+        serde_json::to_string_pretty(&raw)
+    }
+
+    /// 确保键存在，返回是否为新建的键
+    pub fn ensure_key(&mut self, path: &str) -> bool {
+        let lower = path.to_ascii_lowercase();
+        if self.keys.contains_key(&lower) {
+            return false;
+        }
+
+        let created = !self.key_exists(path);
+        self.keys.insert(
+            lower,
+            StoredKey {
+                path: path.to_string(),
+                values: BTreeMap::new(),
+            },
+        );
+        created
+    }
+
+    /// 键是否存在，键的祖先也视为存在
+    pub fn key_exists(&self, path: &str) -> bool {
+        let lower = path.to_ascii_lowercase();
+        let prefix = format!("{lower}\\");
+        self.keys.contains_key(&lower)
+            || self
+                .keys
+                .range(prefix.clone()..)
+                .next()
+                .is_some_and(|(key, _)| key.starts_with(&prefix))
+    }
+
+    /// 获取值，`name`为空时表示默认值
+    pub fn get_value(&self, path: &str, name: &str) -> Option<&RegData> {
+        let key = self.keys.get(&path.to_ascii_lowercase())?;
+        key.values
+            .get(&name.to_ascii_lowercase())
+            .map(|(_, data)| data)
+    }
+
+    /// 设置值，键不存在时会创建
+    pub fn set_value(&mut self, path: &str, name: &str, data: RegData) {
+        self.ensure_key(path);
+        let key = self.keys.get_mut(&path.to_ascii_lowercase()).unwrap();
+        let lower = name.to_ascii_lowercase();
+        match key.values.get_mut(&lower) {
+            Some((_, old)) => *old = data,
+            None => {
+                key.values.insert(lower, (name.to_string(), data));
+            }
+        }
+    }
+
+    /// 值不存在时才设置，用于写入默认值
+    pub fn set_default(&mut self, path: &str, name: &str, data: RegData) {
+        if self.get_value(path, name).is_none() {
+            self.set_value(path, name, data);
+        }
+    }
+
+    /// 直接子键名，按名称排序并去重（不区分 ASCII 大小写）
+    pub fn subkeys(&self, path: &str) -> Vec<String> {
+        let prefix = format!("{}\\", path.to_ascii_lowercase());
+        let mut names: Vec<String> = self
+            .keys
+            .range(prefix.clone()..)
+            .take_while(|(lower, _)| lower.starts_with(&prefix))
+            .filter_map(|(_, key)| key.path[prefix.len()..].split('\\').next())
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        names.sort_by_key(|name| name.to_ascii_lowercase());
+        names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ascii(s: &str) -> Vec<u8> {
+        s.as_bytes().to_vec()
+    }
+
+    #[test]
+    fn kind_matches_variant() {
+        assert_eq!(RegData::Sz(String::new()).kind(), REG_SZ);
+        assert_eq!(RegData::ExpandSz(String::new()).kind(), REG_EXPAND_SZ);
+        assert_eq!(RegData::MultiSz(Vec::new()).kind(), REG_MULTI_SZ);
+        assert_eq!(RegData::Dword(0).kind(), REG_DWORD);
+        assert_eq!(RegData::Qword(0).kind(), REG_QWORD);
+        assert_eq!(RegData::Binary(Vec::new()).kind(), REG_BINARY);
+        let raw = RegData::Raw {
+            kind: 9,
+            bytes: Vec::new(),
+        };
+        assert_eq!(raw.kind(), 9);
+    }
+
+    #[test]
+    fn to_bytes_includes_terminators() {
+        let sz = RegData::Sz("ab".to_string());
+        assert_eq!(sz.to_bytes(true, ascii), [b'a', 0, b'b', 0, 0, 0]);
+        assert_eq!(sz.to_bytes(false, ascii), b"ab\0");
+
+        let multi = RegData::MultiSz(vec!["a".to_string(), "bc".to_string()]);
+        assert_eq!(multi.to_bytes(false, ascii), b"a\0bc\0\0");
+        assert_eq!(RegData::MultiSz(Vec::new()).to_bytes(false, ascii), b"\0");
+
+        assert_eq!(
+            RegData::Dword(0x1234_5678).to_bytes(true, ascii),
+            [0x78, 0x56, 0x34, 0x12]
+        );
+        assert_eq!(
+            RegData::Qword(1).to_bytes(true, ascii),
+            [1, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn from_bytes_round_trip() {
+        let decode = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+        let values = [
+            RegData::Sz("path".to_string()),
+            RegData::ExpandSz("%TEMP%".to_string()),
+            RegData::MultiSz(vec!["a".to_string(), "b".to_string()]),
+            RegData::Dword(7),
+            RegData::Qword(u64::MAX),
+            RegData::Binary(vec![0, 1, 2]),
+        ];
+        for value in values {
+            for wide in [true, false] {
+                let bytes = value.to_bytes(wide, ascii);
+                assert_eq!(
+                    RegData::from_bytes(value.kind(), &bytes, wide, decode),
+                    value
+                );
+            }
+        }
+
+        // 缺少结尾 null 的字符串与长度不符的 dword
+        assert_eq!(
+            RegData::from_bytes(REG_SZ, b"abc", false, decode),
+            RegData::Sz("abc".to_string())
+        );
+        assert_eq!(
+            RegData::from_bytes(REG_DWORD, &[1, 2], false, decode),
+            RegData::Raw {
+                kind: REG_DWORD,
+                bytes: vec![1, 2]
+            }
+        );
+    }
+
+    #[test]
+    fn write_value_size_only() {
+        let mut size = 0;
+        assert_eq!(write_value(b"abcd", None, Some(&mut size)), ERROR_SUCCESS);
+        assert_eq!(size, 4);
+
+        assert_eq!(write_value(b"abcd", None, None), ERROR_SUCCESS);
+        assert_eq!(
+            write_value(b"abcd", Some(&mut []), None),
+            ERROR_INVALID_PARAMETER
+        );
+    }
+
+    #[test]
+    fn write_value_more_data() {
+        let mut buffer = [0xAA; 3];
+        let mut size = buffer.len() as u32;
+        assert_eq!(
+            write_value(b"abcd", Some(&mut buffer), Some(&mut size)),
+            ERROR_MORE_DATA
+        );
+        assert_eq!(size, 4);
+        assert_eq!(buffer, [0xAA; 3]);
+
+        let mut size = 0;
+        assert_eq!(
+            write_value(b"abcd", Some(&mut []), Some(&mut size)),
+            ERROR_MORE_DATA
+        );
+        assert_eq!(size, 4);
+    }
+
+    #[test]
+    fn write_value_fits() {
+        let mut buffer = [0xAA; 6];
+        let mut size = 4;
+        assert_eq!(
+            write_value(b"abcd", Some(&mut buffer[..4]), Some(&mut size)),
+            ERROR_SUCCESS
+        );
+        assert_eq!(size, 4);
+        assert_eq!(buffer, [b'a', b'b', b'c', b'd', 0xAA, 0xAA]);
+
+        let mut size = 6;
+        assert_eq!(
+            write_value(b"ab", Some(&mut buffer), Some(&mut size)),
+            ERROR_SUCCESS
+        );
+        assert_eq!(size, 2);
+        assert_eq!(buffer[..3], [b'a', b'b', b'c']);
+    }
+
+    #[test]
+    fn store_keys_ignore_case() {
+        let mut store = RegStore::default();
+        assert!(store.ensure_key("HKEY_CURRENT_USER\\Software\\Game"));
+        assert!(!store.ensure_key("hkey_current_user\\software\\game"));
+        assert!(store.key_exists("HKEY_CURRENT_USER\\Software"));
+        assert!(!store.key_exists("HKEY_CURRENT_USER\\Soft"));
+
+        store.set_value(
+            "HKEY_CURRENT_USER\\Software\\Game",
+            "Volume",
+            RegData::Dword(3),
+        );
+        store.set_default(
+            "hkey_current_user\\software\\game",
+            "VOLUME",
+            RegData::Dword(9),
+        );
+        assert_eq!(
+            store.get_value("HKEY_CURRENT_USER\\SOFTWARE\\GAME", "volume"),
+            Some(&RegData::Dword(3))
+        );
+        assert_eq!(store.subkeys("HKEY_CURRENT_USER"), ["Software"]);
+
+        let json = store.to_json().unwrap();
+        assert!(json.contains("\"Volume\""));
+        let loaded = RegStore::from_json(&json).unwrap();
+        assert_eq!(
+            loaded.get_value("HKEY_CURRENT_USER\\Software\\Game", "Volume"),
+            Some(&RegData::Dword(3))
+        );
+    }
+}
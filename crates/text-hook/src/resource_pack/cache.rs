@@ -0,0 +1,210 @@
+//! 资源包解压缓存
+//!
+//! 每一层解压到`%TEMP%`（按用户隔离）下各自的目录中，并写入记录资源包哈希的戳文件，
+//! 下次启动时若戳文件一致则跳过解压，退出时也不会删除该目录。
+//!
+//! 解压时先写入以进程ID命名的兄弟目录，完成后再重命名为正式目录，
+//! 若正式目录正被其他实例使用而无法替换，则当前进程直接使用自己的目录，并在退出时删除。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+
+use crate::debug;
+use crate::resource_pack::PackLayer;
+
+/// 戳文件名，位于每一层的解压目录中
+const STAMP_FILE_NAME: &str = ".text_hook_stamp";
+
+/// 每一层实际使用的解压目录
+struct ExtractedDir {
+    path: PathBuf,
+    /// 是否为当前进程独占的目录，独占目录在`clean_up`时删除
+    owned: bool,
+}
+
+/// 资源包名称 -> 实际使用的解压目录
+static EXTRACTED: LazyLock<RwLock<HashMap<&'static str, ExtractedDir>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// 生成戳文件内容
+///
+/// 戳文件包含解压模式：完整解压的目录与按需解压（`resource_pack_lazy`）的目录戳文件不同，
+/// 避免完整解压时误用只解压了部分文件的目录
+///
+/// - `layer_hash` 为该层资源包的哈希
+/// - `shadowing_hashes` 为优先级更高的可用层的哈希，这些层会决定该层需要解压哪些文件
+/// - `external_meta` 为外置资源包的大小与修改时间，内嵌时为`None`
+pub fn make_stamp<'a>(
+    layer_hash: &str,
+    shadowing_hashes: impl IntoIterator<Item = &'a str>,
+    external_meta: Option<(u64, u64)>,
+) -> String {
+    let mut stamp = format!(
+        "version={}\nformat={}\nmode={}\nhash={layer_hash}\n",
+        env!("CARGO_PKG_VERSION"),
+        if cfg!(feature = "resource_pack_v2") {
+            2
+        } else {
+            1
+        },
+        if cfg!(feature = "resource_pack_lazy") {
+            "lazy"
+        } else {
+            "full"
+        }
+    );
+
+    for hash in shadowing_hashes {
+        stamp.push_str(&format!("shadowed_by={hash}\n"));
+    }
+
+    if let Some((size, modified)) = external_meta {
+        stamp.push_str(&format!("external={size},{modified}\n"));
+    }
+
+    stamp
+}
+
+/// 在`dir`的同级目录中生成带后缀的目录路径，如`foo` -> `foo.1234.tmp`
+pub fn sibling_dir(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    dir.with_file_name(name)
+}
+
+/// 读取目录中的戳文件
+fn read_stamp(dir: &Path) -> Option<String> {
+    std::fs::read_to_string(dir.join(STAMP_FILE_NAME)).ok()
+}
+
+/// 获取外置资源包的大小与修改时间
+pub fn external_meta(layer: &PackLayer) -> Option<(u64, u64)> {
+    let file_name = layer.file_name?;
+    let metadata = std::fs::metadata(crate::utils::get_executable_dir().join(file_name)).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some((metadata.len(), modified))
+}
+
+fn record(layer: &'static PackLayer, path: PathBuf, owned: bool) {
+    EXTRACTED
+        .write()
+        .unwrap()
+        .insert(layer.name, ExtractedDir { path, owned });
+}
+
+/// 若该层的缓存已是最新则直接使用，否则调用`write`将文件写入新目录后原子地替换
+pub fn extract_layer(
+    layer: &'static PackLayer,
+    stamp: &str,
+    write: impl FnOnce(&Path) -> crate::Result<()>,
+) -> crate::Result<()> {
+    let dir = layer.temp_dir();
+
+    if read_stamp(dir).as_deref() == Some(stamp) {
+        debug!(
+            "Resource pack {} is up to date in {}, skip extraction",
+            layer.name,
+            dir.display()
+        );
+        record(layer, dir.to_path_buf(), false);
+        return Ok(());
+    }
+
+    let pid = std::process::id();
+    let staging = sibling_dir(dir, &format!("{pid}.tmp"));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    std::fs::create_dir_all(&staging)?;
+
+    if let Err(e) = write(&staging) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    // 戳文件最后写入，只有完整解压的目录才会带有戳文件
+    std::fs::write(staging.join(STAMP_FILE_NAME), stamp)?;
+
+    // 其他实例可能已经完成了相同的解压
+    let adopt_existing = |staging: &Path| {
+        if read_stamp(dir).as_deref() == Some(stamp) {
+            let _ = std::fs::remove_dir_all(staging);
+            record(layer, dir.to_path_buf(), false);
+            true
+        } else {
+            false
+        }
+    };
+
+    if dir.exists() {
+        let old = sibling_dir(dir, &format!("{pid}.old"));
+        if let Err(e) = std::fs::rename(dir, &old) {
+            if adopt_existing(&staging) {
+                return Ok(());
+            }
+
+            debug!(
+                "Resource pack dir {} is in use ({e}), use {} for this process",
+                dir.display(),
+                staging.display()
+            );
+            record(layer, staging, true);
+            return Ok(());
+        }
+
+        if let Err(e) = std::fs::remove_dir_all(&old) {
+            debug!("Failed to remove old dir {}: {e}", old.display());
+        }
+    }
+
+    if let Err(e) = std::fs::rename(&staging, dir) {
+        if adopt_existing(&staging) {
+            return Ok(());
+        }
+
+        debug!(
+            "Failed to move {} to {} ({e}), use it for this process",
+            staging.display(),
+            dir.display()
+        );
+        record(layer, staging, true);
+        return Ok(());
+    }
+
+    debug!(
+        "Resource pack {} extracted to {}",
+        layer.name,
+        dir.display()
+    );
+    record(layer, dir.to_path_buf(), false);
+
+    Ok(())
+}
+
+/// 获取该层实际使用的解压目录
+pub fn extracted_dir(layer: &PackLayer) -> PathBuf {
+    EXTRACTED
+        .read()
+        .ok()
+        .and_then(|dirs| dirs.get(layer.name).map(|dir| dir.path.clone()))
+        .unwrap_or_else(|| layer.temp_dir().to_path_buf())
+}
+
+/// 删除当前进程独占的解压目录，共享的缓存目录会保留给下次启动或其他实例使用
+pub fn clean_up() -> crate::Result<()> {
+    let mut dirs = EXTRACTED.write().unwrap();
+    for (name, dir) in dirs.drain() {
+        if dir.owned && dir.path.exists() {
+            debug!("Remove resource pack {name} dir {}", dir.path.display());
+            std::fs::remove_dir_all(&dir.path)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,294 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+
+use windows_sys::Win32::{
+    Foundation::{
+        CloseHandle, ERROR_NO_MORE_FILES, FALSE, GetLastError, HANDLE, INVALID_HANDLE_VALUE,
+        SetLastError, TRUE,
+    },
+    Storage::FileSystem::{
+        FILE_ATTRIBUTE_ARCHIVE, FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_READONLY, WIN32_FIND_DATAW,
+    },
+};
+use windows_sys::core::{BOOL, PCWSTR};
+
+use crate::debug;
+use crate::hook::traits::file_hook::{
+    HOOK_FIND_CLOSE, HOOK_FIND_FIRST_FILE_W, HOOK_FIND_NEXT_FILE_W,
+};
+use crate::utils::exts::slice_ext::WideSliceExt;
+
+/// 虚拟搜索句柄 -> 搜索状态
+static SEARCHES: LazyLock<Mutex<HashMap<usize, Search>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 合并后的搜索结果，`next` 为下一次`FindNextFile`返回的位置
+struct Search {
+    entries: Vec<WIN32_FIND_DATAW>,
+    next: usize,
+}
+
+/// 大小写不敏感的通配符匹配，支持`*`与`?`
+///
+/// 与`FindFirstFile`一致，`*.*`匹配所有名称（包括不含`.`的名称）
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    if pattern == "*.*" {
+        return true;
+    }
+
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let name: Vec<char> = name.chars().flat_map(char::to_lowercase).collect();
+
+    let (mut p, mut n) = (0, 0);
+    // 上一个`*`在模式中的位置，以及当时对应的名称位置，用于回溯
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// 列出资源包中`dir`下的直接子项，返回`(名称, 是否为目录)`，按名称排序
+///
+/// - `dir` 为资源包中的相对路径（小写，`/`分隔），空字符串表示根目录
+/// - `paths` 为资源包中所有文件的相对路径
+pub fn list_children<'a>(
+    dir: &str,
+    paths: impl IntoIterator<Item = &'a str>,
+) -> Vec<(&'a str, bool)> {
+    let mut children = HashMap::new();
+
+    for path in paths {
+        let rest = if dir.is_empty() {
+            path
+        } else {
+            match path
+                .strip_prefix(dir)
+                .and_then(|rest| rest.strip_prefix('/'))
+            {
+                Some(rest) => rest,
+                None => continue,
+            }
+        };
+
+        match rest.split_once('/') {
+            Some((name, _)) => {
+                children.insert(name, true);
+            }
+            None => {
+                children.entry(rest).or_insert(false);
+            }
+        }
+    }
+
+    let mut children: Vec<_> = children.into_iter().collect();
+    children.sort_unstable();
+    children
+}
+
+/// 拼接资源包中的相对路径
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{dir}/{name}")
+    }
+}
+
+/// 构造资源包中文件或目录的查找结果
+fn make_find_data(name: &str, attributes: u32, size: u64) -> WIN32_FIND_DATAW {
+    let time = crate::resource_pack::file_time();
+    let mut data: WIN32_FIND_DATAW = unsafe { core::mem::zeroed() };
+
+    data.dwFileAttributes = attributes;
+    data.ftCreationTime = time;
+    data.ftLastAccessTime = time;
+    data.ftLastWriteTime = time;
+    data.nFileSizeHigh = (size >> 32) as u32;
+    data.nFileSizeLow = size as u32;
+
+    let wide: Vec<u16> = name.encode_utf16().collect();
+    let len = wide.len().min(data.cFileName.len() - 1);
+    data.cFileName[..len].copy_from_slice(&wide[..len]);
+
+    data
+}
+
+/// 获取查找结果中的文件名
+fn file_name(data: &WIN32_FIND_DATAW) -> String {
+    let len = data
+        .cFileName
+        .iter()
+        .position(|c| *c == 0)
+        .unwrap_or(data.cFileName.len());
+    data.cFileName[..len].to_string_lossy()
+}
+
+/// 调用原函数枚举真实目录中的所有项，失败时返回`Err(LastError)`
+unsafe fn enumerate_real(pattern: PCWSTR) -> Result<Vec<WIN32_FIND_DATAW>, u32> {
+    let mut data: WIN32_FIND_DATAW = unsafe { core::mem::zeroed() };
+    let handle = unsafe { crate::call!(HOOK_FIND_FIRST_FILE_W, pattern, &mut data) };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(unsafe { GetLastError() });
+    }
+
+    let mut entries = vec![data];
+    while unsafe { crate::call!(HOOK_FIND_NEXT_FILE_W, handle, &mut data) } != FALSE {
+        entries.push(data);
+    }
+    unsafe { crate::call!(HOOK_FIND_CLOSE, handle) };
+
+    Ok(entries)
+}
+
+/// 对应`FindFirstFileW`，若查找的目录下存在资源包中的项，则返回合并后的虚拟搜索句柄
+///
+/// 返回`None`表示目录下没有匹配的资源，应调用原函数
+///
+/// # Safety
+/// - `file_name` 须为有效的以 null 结尾的宽字符串
+/// - `find_file_data` 须指向有效的`WIN32_FIND_DATAW`
+pub unsafe fn find_first_file(
+    file_name: PCWSTR,
+    find_file_data: *mut WIN32_FIND_DATAW,
+) -> Option<HANDLE> {
+    use crate::utils::exts::ptr_ext::PtrExt;
+
+    if file_name.is_null() || find_file_data.is_null() {
+        return None;
+    }
+
+    let pattern = unsafe { file_name.to_slice_until_null(4096) }.to_string_lossy();
+    let (dir, name_pattern) = match pattern.rfind(['\\', '/']) {
+        Some(pos) => (&pattern[..pos], &pattern[pos + 1..]),
+        None => (".", pattern.as_str()),
+    };
+
+    let relative_dir = crate::resource_pack::get_relative_path(Path::new(dir)).ok()??;
+    if !relative_dir.is_empty() && !crate::resource_pack::is_resource_dir(&relative_dir) {
+        return None;
+    }
+
+    let pack_entries: Vec<_> = list_children(&relative_dir, crate::resource_pack::resource_paths())
+        .into_iter()
+        .filter(|(name, _)| wildcard_match(name_pattern, name))
+        .collect();
+    if pack_entries.is_empty() {
+        return None;
+    }
+
+    let real_entries = unsafe { enumerate_real(file_name) }.unwrap_or_else(|e| {
+        debug!("FindFirstFileW {pattern} has no real entries (error {e})");
+        Vec::new()
+    });
+
+    let mut entries = Vec::with_capacity(real_entries.len() + pack_entries.len());
+    let mut seen = HashSet::new();
+
+    if real_entries.is_empty() && !relative_dir.is_empty() {
+        // 目录只存在于资源包中时补上`.`与`..`
+        for name in [".", ".."] {
+            if wildcard_match(name_pattern, name) {
+                entries.push(make_find_data(name, FILE_ATTRIBUTE_DIRECTORY, 0));
+            }
+        }
+    }
+
+    for mut data in real_entries {
+        let name = file_name(&data).to_lowercase();
+
+        // 同名文件以资源包中的内容为准
+        if data.dwFileAttributes & FILE_ATTRIBUTE_DIRECTORY == 0
+            && let Some(size) = crate::resource_pack::resource_size(&join(&relative_dir, &name))
+        {
+            data.nFileSizeHigh = (size >> 32) as u32;
+            data.nFileSizeLow = size as u32;
+        }
+
+        seen.insert(name);
+        entries.push(data);
+    }
+
+    for (name, is_dir) in pack_entries {
+        if !seen.insert(name.to_lowercase()) {
+            continue;
+        }
+
+        let data = if is_dir {
+            make_find_data(name, FILE_ATTRIBUTE_DIRECTORY, 0)
+        } else {
+            make_find_data(
+                name,
+                FILE_ATTRIBUTE_READONLY | FILE_ATTRIBUTE_ARCHIVE,
+                crate::resource_pack::resource_size(&join(&relative_dir, name)).unwrap_or(0),
+            )
+        };
+        entries.push(data);
+    }
+
+    let handle = crate::utils::win32::create_virtual_handle()?;
+
+    debug!(
+        "FindFirstFileW {pattern} merged {} entries as {handle:p}",
+        entries.len()
+    );
+
+    unsafe { *find_file_data = entries[0] };
+    SEARCHES
+        .lock()
+        .unwrap()
+        .insert(handle as usize, Search { entries, next: 1 });
+
+    Some(handle)
+}
+
+/// 对应`FindNextFileW`，若句柄不是虚拟搜索句柄则返回`None`
+///
+/// # Safety
+/// - `find_file_data` 须指向有效的`WIN32_FIND_DATAW`
+pub unsafe fn find_next_file(
+    handle: HANDLE,
+    find_file_data: *mut WIN32_FIND_DATAW,
+) -> Option<BOOL> {
+    let mut searches = SEARCHES.lock().ok()?;
+    let search = searches.get_mut(&(handle as usize))?;
+
+    let Some(data) = search.entries.get(search.next) else {
+        unsafe { SetLastError(ERROR_NO_MORE_FILES) };
+        return Some(FALSE);
+    };
+
+    unsafe { *find_file_data = *data };
+    search.next += 1;
+
+    Some(TRUE)
+}
+
+/// 对应`FindClose`，若句柄不是虚拟搜索句柄则返回`None`
+pub fn find_close(handle: HANDLE) -> Option<BOOL> {
+    SEARCHES.lock().ok()?.remove(&(handle as usize))?;
+
+    debug!("Virtual search {handle:p} closed");
+
+    Some(unsafe { CloseHandle(handle) })
+}
@@ -0,0 +1,116 @@
+//! 按需解压资源包
+//!
+//! attach时只在内存中加载资源包并建立索引（v1 格式在内存中解析出索引，v2 格式使用编译期索引），
+//! 每个文件在第一次被打开时才解压到临时目录，同一文件在进程内只会解压一次。
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::debug;
+use crate::resource_pack::{LoadedPack, PackLayer};
+
+/// 已加载的资源包，以及每个文件的解压状态
+struct LazyPack {
+    pack: LoadedPack,
+    /// 相对路径 -> 是否已成功解压
+    extracted: HashMap<&'static str, OnceLock<bool>>,
+}
+
+/// 按优先级从高到低排列，在`init`时加载
+static PACKS: OnceLock<Vec<LazyPack>> = OnceLock::new();
+
+/// 准备每一层的解压目录，并将资源包加载到内存中
+pub fn init() -> crate::Result<()> {
+    if PACKS.get().is_some() {
+        return Ok(());
+    }
+
+    let mut packs = Vec::new();
+    for (i, layer) in crate::resource_pack::layers().iter().enumerate() {
+        // 目录中已有的文件来自相同的资源包时可以直接使用，否则换成只含戳文件的新目录
+        // 按需解压的戳文件与完整解压不同（见`cache::make_stamp`），完整解压不会误用这个目录
+        let stamp = crate::resource_pack::layer_stamp(i);
+        super::cache::extract_layer(layer, &stamp, |_| Ok(()))?;
+
+        let extracted = layer
+            .paths
+            .iter()
+            .map(|path| (*path, OnceLock::new()))
+            .collect();
+        packs.push(LazyPack {
+            pack: LoadedPack::load(layer)?,
+            extracted,
+        });
+    }
+
+    let _ = PACKS.set(packs);
+
+    Ok(())
+}
+
+/// 将文件写入临时文件后再重命名，防止其他进程读到不完整的文件
+fn write_atomically(file_path: &Path, content: &[u8]) -> crate::Result<()> {
+    if let Some(parent) = file_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let temp_path = super::cache::sibling_dir(file_path, &format!("{}.tmp", std::process::id()));
+    std::fs::write(&temp_path, content)?;
+
+    if let Err(e) = std::fs::rename(&temp_path, file_path) {
+        let _ = std::fs::remove_file(&temp_path);
+        // 其他进程可能已经写入了相同的文件
+        if !file_path.is_file() {
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// 确保该层中相对路径对应的文件已解压到`dir`中，返回是否可用
+///
+/// 多个线程同时打开同一文件时，只有一个线程会执行解压，其余线程会等待其完成
+pub fn ensure_extracted(layer: &'static PackLayer, dir: &Path, relative_path: &str) -> bool {
+    let Some(lazy) = PACKS
+        .get()
+        .and_then(|packs| packs.iter().find(|p| core::ptr::eq(p.pack.layer(), layer)))
+    else {
+        debug!("Resource pack {} is not loaded", layer.name);
+        return false;
+    };
+
+    let Some(state) = lazy.extracted.get(relative_path) else {
+        return false;
+    };
+
+    *state.get_or_init(|| {
+        let file_path = dir.join(relative_path);
+        if file_path.is_file() {
+            return true;
+        }
+
+        let Some(content) = lazy.pack.get(relative_path) else {
+            debug!(
+                "Failed to read {relative_path} from resource pack {}",
+                layer.name
+            );
+            return false;
+        };
+
+        match write_atomically(&file_path, content) {
+            Ok(()) => {
+                debug!(
+                    "Extracted {relative_path} from resource pack {}",
+                    layer.name
+                );
+                true
+            }
+            Err(e) => {
+                debug!("Failed to extract {relative_path}: {e:?}");
+                false
+            }
+        }
+    })
+}
@@ -0,0 +1,599 @@
+use std::borrow::Cow;
+#[cfg(not(feature = "resource_pack_v2"))]
+use std::collections::HashMap;
+use std::collections::HashSet;
+#[cfg(not(feature = "resource_pack_v2"))]
+use std::ops::Range;
+use std::path::Path;
+use std::sync::LazyLock;
+#[cfg(feature = "resource_pack_v2")]
+use std::sync::OnceLock;
+
+#[cfg(feature = "resource_pack_v2")]
+use resource_pack_format::PakReader;
+
+#[cfg(any(feature = "resource_pack_find", feature = "resource_pack_vfs"))]
+use windows_sys::Win32::{
+    Foundation::FILETIME,
+    Storage::FileSystem::{GetFileAttributesExW, GetFileExInfoStandard, WIN32_FILE_ATTRIBUTE_DATA},
+    System::SystemInformation::GetSystemTimeAsFileTime,
+};
+
+use crate::utils::exts::slice_ext::WideSliceExt;
+
+#[cfg(not(feature = "resource_pack_vfs"))]
+mod cache;
+#[cfg(feature = "resource_pack_find")]
+pub mod find;
+#[cfg(all(feature = "resource_pack_lazy", not(feature = "resource_pack_vfs")))]
+pub mod lazy;
+#[cfg(feature = "resource_pack_vfs")]
+pub mod vfs;
+
+mod pack {
+    #[cfg(all(
+        not(feature = "resource_pack_embedding"),
+        not(feature = "resource_pack_v2")
+    ))]
+    translate_macros::generate_resource_pack!(
+        "assets/resource_pack",
+        "assets/config.json",
+        "assets/dist"
+    );
+
+    #[cfg(all(feature = "resource_pack_embedding", not(feature = "resource_pack_v2")))]
+    translate_macros::generate_resource_pack!("assets/resource_pack", "assets/config.json");
+
+    #[cfg(all(not(feature = "resource_pack_embedding"), feature = "resource_pack_v2"))]
+    translate_macros::generate_resource_pack!(
+        "assets/resource_pack",
+        "assets/config.json",
+        "assets/dist",
+        version = 2
+    );
+
+    #[cfg(all(feature = "resource_pack_embedding", feature = "resource_pack_v2"))]
+    translate_macros::generate_resource_pack!(
+        "assets/resource_pack",
+        "assets/config.json",
+        version = 2
+    );
+}
+
+/// 资源包中的一层，由`generate_resource_pack!`生成
+///
+/// 多个层按优先级从高到低排列，查找文件时使用第一个包含该文件的层
+pub struct PackLayer {
+    /// 资源包名称，`resource_pack_vfs`下只用于调试输出
+    #[cfg_attr(
+        all(feature = "resource_pack_vfs", not(feature = "debug_output")),
+        allow(dead_code)
+    )]
+    pub name: &'static str,
+    /// 外置资源包的文件名，内嵌时为`None`
+    pub file_name: Option<&'static str>,
+    /// 外置资源包不存在时跳过该层
+    pub optional: bool,
+    /// 资源包数据的 SHA-256
+    #[cfg(not(feature = "resource_pack_vfs"))]
+    pub hash: &'static str,
+    paths: &'static phf::Set<&'static str>,
+    #[cfg(feature = "resource_pack_find")]
+    sizes: &'static phf::Map<&'static str, u64>,
+    #[cfg(feature = "resource_pack_v2")]
+    index: &'static phf::Map<&'static str, u32>,
+    load: fn() -> crate::Result<Cow<'static, [u8]>>,
+    #[cfg(not(feature = "resource_pack_vfs"))]
+    temp_dir: fn() -> &'static Path,
+}
+
+impl PackLayer {
+    /// 该层是否包含相对路径对应的文件
+    pub fn contains(&self, relative_path: &str) -> bool {
+        self.paths.contains(relative_path)
+    }
+
+    /// 该层默认的解压目录，实际使用的目录见`cache::extracted_dir`
+    #[cfg(not(feature = "resource_pack_vfs"))]
+    pub fn temp_dir(&self) -> &'static Path {
+        (self.temp_dir)()
+    }
+
+    /// 可选的外置资源包是否缺失
+    fn is_missing(&self) -> bool {
+        self.optional
+            && self
+                .file_name
+                .is_some_and(|name| !crate::utils::get_executable_dir().join(name).is_file())
+    }
+}
+
+/// 可用的资源包层，按优先级从高到低排列，已排除缺失的可选资源包
+static LAYERS: LazyLock<Vec<&'static PackLayer>> = LazyLock::new(|| {
+    pack::LAYERS
+        .iter()
+        .filter(|layer| {
+            if layer.is_missing() {
+                crate::debug!("Optional resource pack {} is missing, skipped", layer.name);
+                return false;
+            }
+            true
+        })
+        .collect()
+});
+
+/// 获取可用的资源包层
+pub fn layers() -> &'static [&'static PackLayer] {
+    &LAYERS
+}
+
+/// 查找提供相对路径对应文件的资源包层
+pub fn find_layer(relative_path: &str) -> Option<&'static PackLayer> {
+    LAYERS
+        .iter()
+        .copied()
+        .find(|layer| layer.contains(relative_path))
+}
+
+/// 输出每个文件由哪一层提供，用于调试
+pub fn dump_layers() {
+    for layer in pack::LAYERS {
+        crate::debug!(
+            "Resource pack layer {}: {} files, {}{}",
+            layer.name,
+            layer.paths.len(),
+            layer.file_name.unwrap_or("<embedded>"),
+            if layer.is_missing() { " (missing)" } else { "" }
+        );
+    }
+
+    let mut paths: Vec<_> = resource_paths().collect();
+    paths.sort_unstable();
+    for path in paths {
+        if let Some(layer) = find_layer(path) {
+            crate::debug!("  {path} <- {}", layer.name);
+        }
+    }
+}
+
+/// 已加载到内存中的资源包
+pub struct LoadedPack {
+    layer: &'static PackLayer,
+    data: Cow<'static, [u8]>,
+    /// v1 格式：相对路径 -> 文件内容在数据中的范围
+    #[cfg(not(feature = "resource_pack_v2"))]
+    entries: HashMap<String, Range<usize>>,
+    /// v2 格式：压缩文件的解压缓存，按索引号存放
+    #[cfg(feature = "resource_pack_v2")]
+    decoded: Vec<OnceLock<Option<Vec<u8>>>>,
+    /// v2 格式：未压缩文件的 CRC32 是否正确，按索引号存放
+    #[cfg(feature = "resource_pack_v2")]
+    verified: Vec<OnceLock<bool>>,
+}
+
+impl LoadedPack {
+    /// 加载并解析资源包
+    #[cfg(not(feature = "resource_pack_v2"))]
+    pub fn load(layer: &'static PackLayer) -> crate::Result<Self> {
+        let data = (layer.load)()?;
+        let entries = parse_entries(&data)?;
+        Ok(Self {
+            layer,
+            data,
+            entries,
+        })
+    }
+
+    /// 加载资源包并检查索引，文件内容会在首次访问时解压
+    ///
+    /// 外置资源包可能被重新打包过，编译期索引中的每个路径都必须与资源包中同一索引号的路径一致
+    #[cfg(feature = "resource_pack_v2")]
+    pub fn load(layer: &'static PackLayer) -> crate::Result<Self> {
+        let data = (layer.load)()?;
+        let reader = PakReader::new(&data)?;
+        if reader.len() != layer.index.len() {
+            crate::bail!(
+                "Resource pack {} index mismatch: {} entries, expected {}",
+                layer.name,
+                reader.len(),
+                layer.index.len()
+            );
+        }
+
+        for (path, &index) in layer.index.entries() {
+            let entry = reader.entry(index as usize)?;
+            let actual = reader.path(index as usize, &entry)?;
+            if actual != *path {
+                crate::bail!(
+                    "Resource pack {} index mismatch: entry {index} is {actual}, expected {path}",
+                    layer.name
+                );
+            }
+        }
+
+        let decoded = (0..reader.len()).map(|_| OnceLock::new()).collect();
+        let verified = (0..reader.len()).map(|_| OnceLock::new()).collect();
+        Ok(Self {
+            layer,
+            data,
+            decoded,
+            verified,
+        })
+    }
+
+    /// 资源包所属的层
+    #[cfg(any(feature = "resource_pack_lazy", feature = "resource_pack_vfs"))]
+    pub fn layer(&self) -> &'static PackLayer {
+        self.layer
+    }
+
+    /// 获取资源包内相对路径对应的文件内容
+    #[cfg(all(
+        not(feature = "resource_pack_v2"),
+        any(feature = "resource_pack_lazy", feature = "resource_pack_vfs")
+    ))]
+    pub fn get(&self, relative_path: &str) -> Option<&[u8]> {
+        self.entries
+            .get(relative_path)
+            .map(|range| &self.data[range.clone()])
+    }
+
+    /// 获取资源包内相对路径对应的文件内容，通过编译期生成的索引号 O(1) 定位
+    ///
+    /// 首次访问时检查大小与 CRC32，不一致时返回`None`
+    #[cfg(feature = "resource_pack_v2")]
+    pub fn get(&self, relative_path: &str) -> Option<&[u8]> {
+        let index = *self.layer.index.get(relative_path)? as usize;
+        let reader = PakReader::new(&self.data).ok()?;
+        let entry = reader.entry(index).ok()?;
+        let stored = reader.stored_data(&entry).ok()?;
+
+        if !entry.is_compressed() {
+            let valid = *self.verified[index]
+                .get_or_init(|| verify_entry(relative_path, &entry, stored).is_ok());
+            return valid.then_some(stored);
+        }
+
+        self.decoded[index]
+            .get_or_init(|| decode_entry(relative_path, &entry, stored).ok())
+            .as_deref()
+    }
+
+    /// 遍历资源包中的所有文件
+    #[cfg(all(
+        not(feature = "resource_pack_v2"),
+        not(any(feature = "resource_pack_lazy", feature = "resource_pack_vfs"))
+    ))]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.entries
+            .iter()
+            .map(|(path, range)| (path.as_str(), &self.data[range.clone()]))
+    }
+
+    /// 遍历资源包中的所有文件
+    #[cfg(all(
+        feature = "resource_pack_v2",
+        not(any(feature = "resource_pack_lazy", feature = "resource_pack_vfs"))
+    ))]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.layer
+            .index
+            .keys()
+            .filter_map(|path| Some((*path, self.get(path)?)))
+    }
+}
+
+/// 解压 v2 格式中的单个文件，并检查大小与 CRC32
+#[cfg(feature = "resource_pack_v2")]
+fn decode_entry(
+    path: &str,
+    entry: &resource_pack_format::Entry,
+    stored: &[u8],
+) -> crate::Result<Vec<u8>> {
+    let content = crate::utils::decompress(stored, entry.size as usize)?;
+    verify_entry(path, entry, &content)?;
+    Ok(content)
+}
+
+/// 检查 v2 格式中单个文件的原始数据的大小与 CRC32
+#[cfg(feature = "resource_pack_v2")]
+fn verify_entry(
+    path: &str,
+    entry: &resource_pack_format::Entry,
+    content: &[u8],
+) -> crate::Result<()> {
+    if content.len() as u64 != entry.size {
+        crate::bail!(
+            "Resource {path} size mismatch: {} != {}",
+            content.len(),
+            entry.size
+        );
+    }
+
+    if resource_pack_format::crc32(content) != entry.crc32 {
+        crate::bail!("Resource {path} CRC32 mismatch");
+    }
+
+    Ok(())
+}
+
+/// 解析 cat 格式: [u32:路径长度][u8:路径][u64:内容长度][u8:内容]...
+#[cfg(not(feature = "resource_pack_v2"))]
+fn parse_entries(data: &[u8]) -> crate::Result<HashMap<String, Range<usize>>> {
+    fn take<'a>(data: &'a [u8], offset: &mut usize, len: usize) -> crate::Result<&'a [u8]> {
+        let Some(slice) = offset
+            .checked_add(len)
+            .and_then(|end| data.get(*offset..end))
+        else {
+            crate::bail!("Resource pack is truncated at offset {offset}");
+        };
+        *offset += len;
+        Ok(slice)
+    }
+
+    let mut entries = HashMap::new();
+    let mut offset = 0usize;
+    while offset < data.len() {
+        let path_len = u32::from_le_bytes(take(data, &mut offset, 4)?.try_into()?) as usize;
+        let path = std::str::from_utf8(take(data, &mut offset, path_len)?)?;
+        let content_len = u64::from_le_bytes(take(data, &mut offset, 8)?.try_into()?) as usize;
+        let start = offset;
+        take(data, &mut offset, content_len)?;
+
+        entries.insert(path.to_string(), start..offset);
+    }
+
+    Ok(entries)
+}
+
+/// 所有可用层中文件的相对路径（去重）
+static RESOURCE_PATHS: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
+    let mut seen = HashSet::new();
+    LAYERS
+        .iter()
+        .flat_map(|layer| layer.paths.iter().copied())
+        .filter(|path| seen.insert(*path))
+        .collect()
+});
+
+/// 资源包中所有文件的上级目录（相对路径，不含尾部斜杠）
+#[cfg(any(feature = "resource_pack_find", feature = "resource_pack_vfs"))]
+static RESOURCE_DIRS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    let mut dirs = HashSet::new();
+    for path in RESOURCE_PATHS.iter() {
+        let mut path: &'static str = path;
+        while let Some(pos) = path.rfind('/') {
+            path = &path[..pos];
+            if !dirs.insert(path) {
+                break;
+            }
+        }
+    }
+    dirs
+});
+
+/// 判断相对路径是否为资源包中的目录
+#[cfg(any(feature = "resource_pack_find", feature = "resource_pack_vfs"))]
+pub fn is_resource_dir(relative_path: &str) -> bool {
+    RESOURCE_DIRS.contains(relative_path)
+}
+
+/// 遍历所有可用层中文件的相对路径
+pub fn resource_paths() -> impl Iterator<Item = &'static str> {
+    RESOURCE_PATHS.iter().copied()
+}
+
+/// 获取资源包中文件的原始大小，以提供该文件的层为准
+#[cfg(feature = "resource_pack_find")]
+pub fn resource_size(relative_path: &str) -> Option<u64> {
+    find_layer(relative_path)?.sizes.get(relative_path).copied()
+}
+
+/// 资源包中文件的时间戳，统一使用可执行文件的修改时间
+#[cfg(any(feature = "resource_pack_find", feature = "resource_pack_vfs"))]
+pub fn file_time() -> FILETIME {
+    static FILE_TIME: LazyLock<FILETIME> = LazyLock::new(|| unsafe {
+        use crate::utils::exts::path_ext::PathExt;
+
+        let mut time = FILETIME {
+            dwLowDateTime: 0,
+            dwHighDateTime: 0,
+        };
+
+        let mut data: WIN32_FILE_ATTRIBUTE_DATA = core::mem::zeroed();
+        let exe_path = crate::utils::win32::get_module_file_name(core::ptr::null_mut(), false)
+            .map(|p| p.to_path_buf());
+
+        if let Ok(exe_path) = exe_path
+            && GetFileAttributesExW(
+                exe_path.to_wide_null().as_ptr(),
+                GetFileExInfoStandard,
+                &mut data as *mut _ as *mut core::ffi::c_void,
+            ) != 0
+        {
+            time = data.ftLastWriteTime;
+        } else {
+            GetSystemTimeAsFileTime(&mut time);
+        }
+
+        time
+    });
+
+    *FILE_TIME
+}
+
+/// 解压资源包到临时目录
+///
+/// 每一层解压到各自的临时目录，被更高优先级的层覆盖的文件不会被解压。
+/// 若临时目录中的戳文件与当前资源包一致，则跳过解压（见`cache`）
+///
+/// 开启`resource_pack_lazy`时，只会将资源包加载到内存中，文件在第一次打开时才解压（见`lazy`）
+///
+/// 开启`resource_pack_vfs`时，只会将资源包加载到内存中，不会写入磁盘
+pub fn extract() -> crate::Result<()> {
+    dump_layers();
+
+    #[cfg(feature = "resource_pack_vfs")]
+    {
+        vfs::init()
+    }
+
+    #[cfg(all(feature = "resource_pack_lazy", not(feature = "resource_pack_vfs")))]
+    {
+        lazy::init()
+    }
+
+    #[cfg(not(any(feature = "resource_pack_vfs", feature = "resource_pack_lazy")))]
+    {
+        for (i, layer) in layers().iter().enumerate() {
+            cache::extract_layer(layer, &layer_stamp(i), |dir| {
+                let pack = LoadedPack::load(layer)?;
+                for (path, content) in pack.iter() {
+                    if !find_layer(path).is_some_and(|served| core::ptr::eq(served, *layer)) {
+                        continue;
+                    }
+
+                    let file_path = dir.join(path);
+                    if let Some(parent) = file_path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&file_path, content)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// 生成第`index`个可用层的戳文件内容
+#[cfg(not(feature = "resource_pack_vfs"))]
+fn layer_stamp(index: usize) -> String {
+    let layers = layers();
+    cache::make_stamp(
+        layers[index].hash,
+        layers[..index].iter().map(|layer| layer.hash),
+        cache::external_meta(layers[index]),
+    )
+}
+
+/// 清理资源包解压产生的临时文件
+///
+/// 只会删除当前进程独占的目录，共享的缓存目录会保留，避免影响同时运行的其他实例
+pub fn clean_up() -> crate::Result<()> {
+    #[cfg(not(feature = "resource_pack_vfs"))]
+    cache::clean_up()?;
+
+    Ok(())
+}
+
+fn to_unix_clean_path(path: &Path) -> String {
+    let s = path.to_string_lossy();
+
+    let mut s = s.to_lowercase().replace('\\', "/");
+
+    // 1. 剥离前缀（因为已经全小写且换了斜杠，所以匹配 //?/）
+    if let Some(stripped) = s.strip_prefix("//?/") {
+        s = stripped.to_string()
+    } else if let Some(stripped) = s.strip_prefix("//./") {
+        s = stripped.to_string()
+    }
+
+    // 2. 强制加尾部斜杠用于前缀匹配，防止 MyGame 和 MyGameLauncher 混淆
+    if !s.ends_with('/') && !s.is_empty() {
+        s.push('/');
+    }
+
+    s
+}
+
+#[cfg(not(feature = "resource_pack_vfs"))]
+fn to_windows_path(path: &Path) -> String {
+    // 确保使用反斜杠
+    let mut s = path.to_string_lossy().replace('/', "\\");
+
+    // 加上 \\?\ 前缀给 CreateFileW 使用
+    if !s.starts_with(r"\\") {
+        s = format!(r"\\?\{}", s)
+    }
+
+    s
+}
+
+/// 将可执行目录下的路径映射为资源包中的相对路径
+///
+/// 输入路径会被转为绝对路径，与可执行目录进行大小写不敏感的前缀匹配，
+/// 返回小写且使用`/`分隔的相对路径，若不在可执行目录下则返回`None`
+pub fn get_relative_path(path: &Path) -> crate::Result<Option<String>> {
+    let exec_dir = crate::utils::get_executable_dir();
+
+    let abs_path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        crate::utils::win32::get_current_dir(false)?
+            .to_path_buf()
+            .join(path)
+    };
+
+    let abs_path = path_clean::clean(&abs_path);
+
+    crate::debug!(
+        "Trying to get resource path for {}, abs_path={}, exec_dir={}",
+        path.display(),
+        abs_path.display(),
+        exec_dir.display()
+    );
+
+    let clean_abs = to_unix_clean_path(&abs_path);
+    let clean_exec = to_unix_clean_path(exec_dir);
+
+    let Some(mut relative_str) = clean_abs.strip_prefix(&clean_exec) else {
+        return Ok(None);
+    };
+
+    if relative_str.starts_with('/') {
+        relative_str = &relative_str[1..];
+    }
+
+    if relative_str.ends_with('/') {
+        relative_str = &relative_str[..relative_str.len() - 1];
+    }
+
+    Ok(Some(relative_str.to_string()))
+}
+
+/// 将可执行目录下的路径映射到资源包中的相对路径，若该路径不是资源包中的文件则返回`None`
+#[cfg(feature = "resource_pack_vfs")]
+pub fn get_resource_relative_path(path: &Path) -> crate::Result<Option<String>> {
+    Ok(get_relative_path(path)?.filter(|relative| find_layer(relative).is_some()))
+}
+
+/// 将可执行目录下的路径映射到资源包临时目录路径
+///
+/// 按优先级从高到低依次在各层中查找对应资源，返回第一个匹配层中资源的绝对路径
+#[cfg(not(feature = "resource_pack_vfs"))]
+pub fn get_resource_path(path: &Path) -> crate::Result<Option<std::path::PathBuf>> {
+    let Some(relative_str) = get_relative_path(path)? else {
+        return Ok(None);
+    };
+
+    if let Some(layer) = find_layer(&relative_str) {
+        crate::debug!(
+            "Redirection to resource pack {}: {}",
+            layer.name,
+            relative_str
+        );
+        let dir = cache::extracted_dir(layer);
+
+        #[cfg(feature = "resource_pack_lazy")]
+        if !lazy::ensure_extracted(layer, &dir, &relative_str) {
+            return Ok(None);
+        }
+
+        let final_path = dir.join(relative_str);
+        return Ok(Some(to_windows_path(&final_path).into()));
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex, OnceLock};
+
+use windows_sys::Win32::{
+    Foundation::{
+        ERROR_INVALID_PARAMETER, ERROR_NEGATIVE_SEEK, FALSE, GENERIC_WRITE, HANDLE, SetLastError,
+        TRUE,
+    },
+    Storage::FileSystem::{
+        BY_HANDLE_FILE_INFORMATION, CREATE_ALWAYS, CREATE_NEW, FILE_APPEND_DATA,
+        FILE_ATTRIBUTE_ARCHIVE, FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_READONLY, FILE_BEGIN,
+        FILE_CURRENT, FILE_END, FILE_TYPE_DISK, FILE_WRITE_DATA, GET_FILEEX_INFO_LEVELS,
+        GetFileExInfoStandard, INVALID_SET_FILE_POINTER, TRUNCATE_EXISTING,
+        WIN32_FILE_ATTRIBUTE_DATA,
+    },
+    System::{IO::OVERLAPPED, Threading::SetEvent},
+};
+use windows_sys::core::BOOL;
+
+use crate::debug;
+use crate::resource_pack::LoadedPack;
+
+/// 内存中的资源包，按优先级从高到低排列，在`init`时加载
+static PACKS: OnceLock<Vec<LoadedPack>> = OnceLock::new();
+
+/// 虚拟文件句柄 -> 文件状态
+static FILES: LazyLock<Mutex<HashMap<usize, VirtualFile>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+struct VirtualFile {
+    data: &'static [u8],
+    pos: u64,
+}
+
+/// 将资源包加载到内存中
+pub fn init() -> crate::Result<()> {
+    if PACKS.get().is_some() {
+        return Ok(());
+    }
+
+    let packs = crate::resource_pack::layers()
+        .iter()
+        .map(|layer| LoadedPack::load(layer))
+        .collect::<crate::Result<Vec<_>>>()?;
+    let _ = PACKS.set(packs);
+
+    Ok(())
+}
+
+/// 获取资源包内的文件内容，使用第一个包含该文件的层
+fn get_content(relative_path: &str) -> Option<&'static [u8]> {
+    PACKS
+        .get()?
+        .iter()
+        .find(|pack| pack.layer().contains(relative_path))?
+        .get(relative_path)
+}
+
+/// 尝试将路径作为虚拟文件打开，成功时返回虚拟句柄（见`crate::utils::win32::create_virtual_handle`）
+///
+/// 需要写入或创建文件时返回`None`，交给原函数处理
+pub fn open(path: &Path, desired_access: u32, creation_disposition: u32) -> Option<HANDLE> {
+    if desired_access & (GENERIC_WRITE | FILE_WRITE_DATA | FILE_APPEND_DATA) != 0
+        || matches!(
+            creation_disposition,
+            CREATE_NEW | CREATE_ALWAYS | TRUNCATE_EXISTING
+        )
+    {
+        return None;
+    }
+
+    let relative_path = match crate::resource_pack::get_resource_relative_path(path) {
+        Ok(Some(relative_path)) => relative_path,
+        Ok(None) => return None,
+        Err(e) => {
+            debug!(
+                "Failed to get resource path for {}: {e:?}",
+                path.to_string_lossy()
+            );
+            return None;
+        }
+    };
+
+    let Some(data) = get_content(&relative_path) else {
+        debug!("Resource pack is not loaded, skip {relative_path}");
+        return None;
+    };
+
+    let handle = crate::utils::win32::create_virtual_handle()?;
+
+    debug!("Open virtual file {relative_path} as {handle:p}");
+
+    FILES
+        .lock()
+        .unwrap()
+        .insert(handle as usize, VirtualFile { data, pos: 0 });
+
+    Some(handle)
+}
+
+/// 在句柄关闭前调用，移除虚拟文件记录
+pub fn on_handle_closed(handle: HANDLE) {
+    if let Ok(mut files) = FILES.lock()
+        && files.remove(&(handle as usize)).is_some()
+    {
+        debug!("Virtual file {handle:p} closed");
+    }
+}
+
+/// 对虚拟文件执行操作，若句柄不是虚拟文件则返回`None`
+fn with_file<R>(handle: HANDLE, f: impl FnOnce(&mut VirtualFile) -> R) -> Option<R> {
+    let mut files = FILES.lock().ok()?;
+    files.get_mut(&(handle as usize)).map(f)
+}
+
+/// 对应`ReadFile`
+///
+/// 若提供了`OVERLAPPED`，则从其中的偏移读取，并按同步完成处理
+///
+/// # Safety
+/// - 参数须与`ReadFile`一致
+pub unsafe fn read_file(
+    handle: HANDLE,
+    buffer: *mut u8,
+    number_of_bytes_to_read: u32,
+    number_of_bytes_read: *mut u32,
+    overlapped: *mut OVERLAPPED,
+) -> Option<BOOL> {
+    with_file(handle, |file| unsafe {
+        if !overlapped.is_null() {
+            let offset = (*overlapped).Anonymous.Anonymous;
+            file.pos = (offset.OffsetHigh as u64) << 32 | offset.Offset as u64;
+        }
+
+        let start = (file.pos as usize).min(file.data.len());
+        let len = (number_of_bytes_to_read as usize).min(file.data.len() - start);
+
+        core::ptr::copy_nonoverlapping(file.data.as_ptr().add(start), buffer, len);
+        file.pos = (start + len) as u64;
+
+        if !number_of_bytes_read.is_null() {
+            *number_of_bytes_read = len as u32;
+        }
+
+        if !overlapped.is_null() {
+            (*overlapped).Internal = 0;
+            (*overlapped).InternalHigh = len;
+            if !(*overlapped).hEvent.is_null() {
+                SetEvent((*overlapped).hEvent);
+            }
+        }
+
+        TRUE
+    })
+}
+
+/// 按移动方式计算新的文件位置，失败时设置LastError并返回`None`
+fn seek(file: &mut VirtualFile, distance: i64, move_method: u32) -> Option<u64> {
+    let base = match move_method {
+        FILE_BEGIN => 0,
+        FILE_CURRENT => file.pos as i64,
+        FILE_END => file.data.len() as i64,
+        _ => {
+            unsafe { SetLastError(ERROR_INVALID_PARAMETER) };
+            return None;
+        }
+    };
+
+    let Some(pos) = base.checked_add(distance).filter(|pos| *pos >= 0) else {
+        unsafe { SetLastError(ERROR_NEGATIVE_SEEK) };
+        return None;
+    };
+
+    file.pos = pos as u64;
+    Some(file.pos)
+}
+
+/// 对应`SetFilePointer`
+///
+/// # Safety
+/// - `distance_to_move_high` 为空或指向有效的`i32`
+pub unsafe fn set_file_pointer(
+    handle: HANDLE,
+    distance_to_move: i32,
+    distance_to_move_high: *mut i32,
+    move_method: u32,
+) -> Option<u32> {
+    with_file(handle, |file| unsafe {
+        let distance = if distance_to_move_high.is_null() {
+            distance_to_move as i64
+        } else {
+            ((*distance_to_move_high as i64) << 32) | (distance_to_move as u32 as i64)
+        };
+
+        let Some(pos) = seek(file, distance, move_method) else {
+            return INVALID_SET_FILE_POINTER;
+        };
+
+        if !distance_to_move_high.is_null() {
+            *distance_to_move_high = (pos >> 32) as i32;
+        }
+
+        // 低位可能恰好等于 INVALID_SET_FILE_POINTER，调用者需要通过 LastError 判断
+        SetLastError(0);
+        pos as u32
+    })
+}
+
+/// 对应`SetFilePointerEx`
+///
+/// # Safety
+/// - `new_file_pointer` 为空或指向有效的`i64`
+pub unsafe fn set_file_pointer_ex(
+    handle: HANDLE,
+    distance_to_move: i64,
+    new_file_pointer: *mut i64,
+    move_method: u32,
+) -> Option<BOOL> {
+    with_file(handle, |file| unsafe {
+        let Some(pos) = seek(file, distance_to_move, move_method) else {
+            return FALSE;
+        };
+
+        if !new_file_pointer.is_null() {
+            *new_file_pointer = pos as i64;
+        }
+
+        TRUE
+    })
+}
+
+/// 对应`GetFileSize`
+///
+/// # Safety
+/// - `file_size_high` 为空或指向有效的`u32`
+pub unsafe fn get_file_size(handle: HANDLE, file_size_high: *mut u32) -> Option<u32> {
+    with_file(handle, |file| unsafe {
+        let size = file.data.len() as u64;
+        if !file_size_high.is_null() {
+            *file_size_high = (size >> 32) as u32;
+        }
+        SetLastError(0);
+        size as u32
+    })
+}
+
+/// 对应`GetFileSizeEx`
+///
+/// # Safety
+/// - `file_size` 须指向有效的`i64`
+pub unsafe fn get_file_size_ex(handle: HANDLE, file_size: *mut i64) -> Option<BOOL> {
+    with_file(handle, |file| unsafe {
+        if file_size.is_null() {
+            SetLastError(ERROR_INVALID_PARAMETER);
+            return FALSE;
+        }
+        *file_size = file.data.len() as i64;
+        TRUE
+    })
+}
+
+/// 对应`GetFileType`，虚拟文件总是报告为磁盘文件
+///
+/// CRT 的`fopen`/`_open`在`CreateFile`之后会用它检查句柄类型，失败时直接关闭文件
+pub fn get_file_type(handle: HANDLE) -> Option<u32> {
+    with_file(handle, |_| FILE_TYPE_DISK)
+}
+
+/// 对应`GetFileInformationByHandle`，CRT 的`_fstat`等会用到
+///
+/// # Safety
+/// - `file_information` 为空或指向有效的`BY_HANDLE_FILE_INFORMATION`
+pub unsafe fn get_file_information_by_handle(
+    handle: HANDLE,
+    file_information: *mut BY_HANDLE_FILE_INFORMATION,
+) -> Option<BOOL> {
+    with_file(handle, |file| unsafe {
+        if file_information.is_null() {
+            SetLastError(ERROR_INVALID_PARAMETER);
+            return FALSE;
+        }
+
+        let time = crate::resource_pack::file_time();
+        let size = file.data.len() as u64;
+        // 同一文件的数据地址不变，用作文件索引
+        let index = file.data.as_ptr() as u64;
+
+        *file_information = BY_HANDLE_FILE_INFORMATION {
+            dwFileAttributes: FILE_ATTRIBUTE_READONLY | FILE_ATTRIBUTE_ARCHIVE,
+            ftCreationTime: time,
+            ftLastAccessTime: time,
+            ftLastWriteTime: time,
+            dwVolumeSerialNumber: 0,
+            nFileSizeHigh: (size >> 32) as u32,
+            nFileSizeLow: size as u32,
+            nNumberOfLinks: 1,
+            nFileIndexHigh: (index >> 32) as u32,
+            nFileIndexLow: index as u32,
+        };
+
+        TRUE
+    })
+}
+
+/// 获取资源包内文件或目录的属性以及文件大小
+fn get_attributes_and_size(path: &Path) -> Option<(u32, u64)> {
+    let relative_path = crate::resource_pack::get_relative_path(path).ok()??;
+
+    if let Some(data) = get_content(&relative_path) {
+        Some((
+            FILE_ATTRIBUTE_READONLY | FILE_ATTRIBUTE_ARCHIVE,
+            data.len() as u64,
+        ))
+    } else if crate::resource_pack::is_resource_dir(&relative_path) {
+        Some((FILE_ATTRIBUTE_DIRECTORY, 0))
+    } else {
+        None
+    }
+}
+
+/// 对应`GetFileAttributes`，若路径不在资源包中则返回`None`
+pub fn get_file_attributes(path: &Path) -> Option<u32> {
+    get_attributes_and_size(path).map(|(attributes, _)| attributes)
+}
+
+/// 对应`GetFileAttributesEx`，若路径不在资源包中则返回`None`
+///
+/// # Safety
+/// - `file_information` 须指向与`info_level_id`对应的有效结构体
+pub unsafe fn get_file_attributes_ex(
+    path: &Path,
+    info_level_id: GET_FILEEX_INFO_LEVELS,
+    file_information: *mut core::ffi::c_void,
+) -> Option<BOOL> {
+    if info_level_id != GetFileExInfoStandard || file_information.is_null() {
+        return None;
+    }
+
+    let (attributes, size) = get_attributes_and_size(path)?;
+    let time = crate::resource_pack::file_time();
+
+    unsafe {
+        *(file_information as *mut WIN32_FILE_ATTRIBUTE_DATA) = WIN32_FILE_ATTRIBUTE_DATA {
+            dwFileAttributes: attributes,
+            ftCreationTime: time,
+            ftLastAccessTime: time,
+            ftLastWriteTime: time,
+            nFileSizeHigh: (size >> 32) as u32,
+            nFileSizeLow: size as u32,
+        };
+    }
+
+    Some(TRUE)
+}
@@ -0,0 +1,376 @@
+//! 存档路径虚拟化
+//!
+//! 将`SAVE_REDIRECT_DIRS`中配置的目录映射到exe目录下的`SAVE_REDIRECT_DIR`（默认为`save`）中，
+//! 使游戏的存档与设置跟随游戏目录，而不是散落在`%APPDATA%`、`我的文档`或者`VirtualStore`中。
+//!
+//! - 已知文件夹会通过`SHGetFolderPath`、`SHGetSpecialFolderPath`、`SHGetKnownFolderPath`直接返回存档目录
+//! - 位于这些目录（包括已知文件夹的真实路径）下的文件路径，会在`redirect_rules`之后由文件钩子重定向
+//! - 存档目录及其中的子目录都在需要时才创建
+
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use windows_sys::Win32::System::Com::{CoTaskMemAlloc, CoTaskMemFree};
+use windows_sys::Win32::UI::Shell::{
+    FOLDERID_Documents, FOLDERID_LocalAppData, FOLDERID_LocalAppDataLow, FOLDERID_ProgramData,
+    FOLDERID_RoamingAppData, FOLDERID_SavedGames,
+};
+use windows_sys::core::{GUID, PWSTR};
+use windows_sys::w;
+
+use crate::constant::SAVE_REDIRECT_DIR;
+use crate::debug;
+use crate::hook::traits::shell_hook::HOOK_SH_GET_KNOWN_FOLDER_PATH;
+use crate::utils::exts::path_ext::PathExt;
+use crate::utils::exts::slice_ext::WideSliceExt;
+
+/// 支持重定向的已知文件夹
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KnownFolder {
+    /// `%APPDATA%`
+    AppData,
+    /// `%LOCALAPPDATA%`
+    LocalAppData,
+    /// `%USERPROFILE%\AppData\LocalLow`
+    LocalAppDataLow,
+    /// `%PROGRAMDATA%`
+    ProgramData,
+    /// 我的文档
+    Documents,
+    /// `%USERPROFILE%\Saved Games`
+    SavedGames,
+}
+
+impl KnownFolder {
+    const ALL: [Self; 6] = [
+        Self::AppData,
+        Self::LocalAppData,
+        Self::LocalAppDataLow,
+        Self::ProgramData,
+        Self::Documents,
+        Self::SavedGames,
+    ];
+
+    /// 对应的`KNOWNFOLDERID`
+    pub fn id(self) -> GUID {
+        match self {
+            Self::AppData => FOLDERID_RoamingAppData,
+            Self::LocalAppData => FOLDERID_LocalAppData,
+            Self::LocalAppDataLow => FOLDERID_LocalAppDataLow,
+            Self::ProgramData => FOLDERID_ProgramData,
+            Self::Documents => FOLDERID_Documents,
+            Self::SavedGames => FOLDERID_SavedGames,
+        }
+    }
+
+    /// 对应的`CSIDL`，没有对应`CSIDL`的文件夹返回`None`
+    pub fn csidl(self) -> Option<i32> {
+        match self {
+            Self::AppData => Some(0x001a),      // CSIDL_APPDATA
+            Self::LocalAppData => Some(0x001c), // CSIDL_LOCAL_APPDATA
+            Self::ProgramData => Some(0x0023),  // CSIDL_COMMON_APPDATA
+            Self::Documents => Some(0x0005),    // CSIDL_PERSONAL
+            Self::LocalAppDataLow | Self::SavedGames => None,
+        }
+    }
+
+    /// 根据`KNOWNFOLDERID`查找
+    pub fn from_id(id: &GUID) -> Option<Self> {
+        Self::ALL.into_iter().find(|folder| {
+            let known = folder.id();
+            known.data1 == id.data1
+                && known.data2 == id.data2
+                && known.data3 == id.data3
+                && known.data4 == id.data4
+        })
+    }
+
+    /// 根据`CSIDL`查找，会忽略`CSIDL_FLAG_CREATE`等标志位
+    pub fn from_csidl(csidl: i32) -> Option<Self> {
+        // CSIDL_FLAG_MASK
+        let csidl = csidl & !0xff00;
+        Self::ALL
+            .into_iter()
+            .find(|folder| folder.csidl() == Some(csidl))
+    }
+}
+
+/// 需要重定向的目录的来源
+#[derive(Debug, Clone, Copy)]
+pub enum SaveSource {
+    /// 已知文件夹
+    Folder(KnownFolder),
+    /// 目录路径，可包含`%VAR%`环境变量，相对路径相对于exe目录
+    Path(&'static str),
+}
+
+/// 配置中的一项
+#[derive(Debug, Clone, Copy)]
+pub struct SaveDir {
+    pub source: SaveSource,
+    /// 存档目录下的子目录名
+    pub target: &'static str,
+}
+
+static SAVE_DIRS: &[SaveDir] = translate_macros::generate_save_redirect_dirs!("assets/config.json");
+
+/// 解析出真实路径后的目录
+struct ResolvedDir {
+    /// 小写且使用`/`分隔、不以`/`结尾的真实路径
+    prefix: String,
+    /// 存档目录中对应的目录
+    target: PathBuf,
+}
+
+/// 所有可以解析出真实路径的目录，在第一次重定向文件路径时解析
+static RESOLVED: LazyLock<Vec<ResolvedDir>> = LazyLock::new(|| {
+    SAVE_DIRS
+        .iter()
+        .filter_map(|dir| {
+            let real = match dir.source {
+                SaveSource::Folder(folder) => real_folder_path(folder),
+                SaveSource::Path(path) => {
+                    let path = expand_env(path, |name| std::env::var(name).ok());
+                    Some(crate::utils::get_executable_dir().join(path))
+                }
+            };
+
+            let Some(real) = real else {
+                debug!("Failed to resolve save dir {:?}", dir.source);
+                return None;
+            };
+
+            let prefix = normalize(&path_clean::clean(&real));
+            debug!("Save dir {prefix} -> {}", dir.target);
+            Some(ResolvedDir {
+                prefix,
+                target: save_root().join(dir.target),
+            })
+        })
+        .collect()
+});
+
+/// 加载`shell32.dll`，否则游戏未导入它时无法创建`shell_hook`中的钩子
+///
+/// 模块在进程结束前不再释放，保证钩子地址始终有效
+pub fn init() -> crate::Result<()> {
+    let module = crate::utils::win32::load_library(w!("shell32.dll"))?;
+    core::mem::forget(module);
+    Ok(())
+}
+
+/// 存档根目录
+pub fn save_root() -> PathBuf {
+    crate::utils::get_executable_dir().join(SAVE_REDIRECT_DIR)
+}
+
+/// 小写且使用`/`分隔、不以`/`结尾的路径，用于不区分大小写的前缀比较
+fn normalize(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "/")
+        .trim_end_matches('/')
+        .to_lowercase()
+}
+
+/// 去掉`path`中小写后等于`prefix`的前缀，返回剩余部分
+///
+/// 部分字符小写前后的字节长度不同，因此需要在原路径上逐字符确定前缀的结束位置
+fn strip_prefix_lowercase<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    let mut lowered = String::with_capacity(prefix.len());
+    for (i, c) in path.char_indices() {
+        if lowered.len() >= prefix.len() {
+            return (lowered == prefix).then(|| &path[i..]);
+        }
+        lowered.extend(c.to_lowercase());
+    }
+    (lowered == prefix).then_some("")
+}
+
+/// 展开路径中的`%VAR%`，`%%`表示`%`本身，不存在的变量保持原样
+pub fn expand_env(path: &str, get: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(path.len());
+    let mut rest = path;
+
+    while let Some(start) = rest.find('%') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let Some(end) = after.find('%') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+
+        let name = &after[..end];
+        match name {
+            "" => out.push('%'),
+            _ => match get(name) {
+                Some(value) => out.push_str(&value),
+                None => {
+                    out.push('%');
+                    out.push_str(name);
+                    out.push('%');
+                }
+            },
+        }
+        rest = &after[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// 通过原始的`SHGetKnownFolderPath`获取已知文件夹的真实路径
+fn real_folder_path(folder: KnownFolder) -> Option<PathBuf> {
+    unsafe {
+        let mut path: PWSTR = core::ptr::null_mut();
+        let result = crate::call!(
+            HOOK_SH_GET_KNOWN_FOLDER_PATH,
+            &folder.id(),
+            0,
+            core::ptr::null_mut(),
+            &mut path,
+        );
+        scopeguard::defer! {
+            CoTaskMemFree(path as _);
+        }
+
+        if result < 0 || path.is_null() {
+            return None;
+        }
+
+        use crate::utils::exts::ptr_ext::PtrExt;
+        Some(path.to_slice_until_null(32768).to_path_buf())
+    }
+}
+
+/// 创建目录，失败时只输出调试信息
+fn ensure_dir(dir: &Path) {
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        debug!("Failed to create save dir {}: {e}", dir.display());
+    }
+}
+
+/// 若该已知文件夹需要重定向，返回存档目录中对应的目录（会在需要时创建）
+pub fn folder_target(folder: KnownFolder) -> Option<PathBuf> {
+    let dir = SAVE_DIRS
+        .iter()
+        .find(|dir| matches!(dir.source, SaveSource::Folder(f) if f == folder))?;
+
+    let target = save_root().join(dir.target);
+    ensure_dir(&target);
+
+    debug!("Known folder {folder:?} -> {}", target.display());
+    Some(target)
+}
+
+/// 若绝对路径位于需要重定向的目录下，返回存档目录中对应的路径，并确保其所在的目录存在
+pub fn redirect(abs_path: &Path) -> Option<PathBuf> {
+    let path = normalize(abs_path);
+
+    // 已经位于存档目录中的路径不再重定向
+    let root = normalize(&save_root());
+    if path == root || path.starts_with(&format!("{root}/")) {
+        return None;
+    }
+
+    let dir = RESOLVED.iter().find(|dir| {
+        path == dir.prefix
+            || path
+                .strip_prefix(&dir.prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    })?;
+
+    // 前缀比较使用小写路径，拼接时使用原路径以保留大小写
+    let original = abs_path.to_string_lossy().replace('\\', "/");
+    let rest = strip_prefix_lowercase(&original, &dir.prefix)?.trim_start_matches('/');
+    let target = dir.target.join(rest);
+
+    if let Some(parent) = target.parent() {
+        ensure_dir(parent);
+    }
+
+    Some(target)
+}
+
+/// 将路径以 ANSI 写入调用者提供的`MAX_PATH`缓冲区，路径过长时返回`false`
+///
+/// # Safety
+/// `buffer`必须可以写入`MAX_PATH`字节
+pub unsafe fn write_path_a(path: &Path, buffer: *mut u8) -> bool {
+    let path = path.to_wide().to_multi_byte_null(0);
+    if path.len() > 260 {
+        return false;
+    }
+    unsafe { core::ptr::copy_nonoverlapping(path.as_ptr(), buffer, path.len()) };
+    true
+}
+
+/// 将路径写入调用者提供的`MAX_PATH`宽字符缓冲区，路径过长时返回`false`
+///
+/// # Safety
+/// `buffer`必须可以写入`MAX_PATH`个宽字符
+pub unsafe fn write_path_w(path: &Path, buffer: *mut u16) -> bool {
+    let path = path.to_wide_null();
+    if path.len() > 260 {
+        return false;
+    }
+    unsafe { core::ptr::copy_nonoverlapping(path.as_ptr(), buffer, path.len()) };
+    true
+}
+
+/// 使用`CoTaskMemAlloc`分配路径字符串，调用者需要使用`CoTaskMemFree`释放
+pub fn alloc_path_w(path: &Path) -> PWSTR {
+    let path = path.to_wide_null();
+    unsafe {
+        let buffer = CoTaskMemAlloc(path.len() * 2) as PWSTR;
+        if !buffer.is_null() {
+            core::ptr::copy_nonoverlapping(path.as_ptr(), buffer, path.len());
+        }
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_prefix_keeps_original_case() {
+        assert_eq!(
+            strip_prefix_lowercase(
+                "C:/Users/Foo/AppData/Game/Save1.dat",
+                "c:/users/foo/appdata"
+            ),
+            Some("/Game/Save1.dat")
+        );
+        assert_eq!(
+            strip_prefix_lowercase("C:/Users/Foo/", "c:/users/foo"),
+            Some("/")
+        );
+        assert_eq!(
+            strip_prefix_lowercase("C:/Users/Foo", "c:/users/foo"),
+            Some("")
+        );
+    }
+
+    #[test]
+    fn strip_prefix_with_length_changing_lowercase() {
+        // `İ`（2 字节）小写后为`i̇`（3 字节）
+        assert_eq!(
+            strip_prefix_lowercase("C:/İ/Save.dat", "c:/i\u{307}"),
+            Some("/Save.dat")
+        );
+        // `K`（开尔文符号，3 字节）小写后为`k`（1 字节）
+        assert_eq!(
+            strip_prefix_lowercase("C:/\u{212A}ey/Save.dat", "c:/key"),
+            Some("/Save.dat")
+        );
+    }
+
+    #[test]
+    fn strip_prefix_mismatch() {
+        assert_eq!(strip_prefix_lowercase("C:/Other", "c:/users"), None);
+        assert_eq!(strip_prefix_lowercase("C:/Us", "c:/users"), None);
+        assert_eq!(strip_prefix_lowercase("C:/İ", "c:/i"), None);
+    }
+}
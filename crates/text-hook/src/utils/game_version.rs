@@ -0,0 +1,366 @@
+//! 游戏版本检测：根据主模块与指定 DLL 的特征选择补丁集
+//!
+//! 同一引擎的不同游戏（或同一游戏的不同版本）只有偏移不同时，可以把每个版本注册为一个[`PatchSet`]，
+//! 运行时检测后执行匹配的那一个，而不需要为每个版本单独编译 DLL：
+//!
+//! ```rust
+//! static PATCH_SETS: &[PatchSet] = &[
+//!     PatchSet {
+//!         name: "うたかな",
+//!         checks: &[Check::main(Fingerprint::Timestamp(0x4A3B2C1D))],
+//!         apply: patch_utakana,
+//!     },
+//!     PatchSet {
+//!         name: "天巫女姫",
+//!         checks: &[
+//!             Check::main(Fingerprint::SizeOfImage(0x1A6000)),
+//!             Check::module("sc.dll", Fingerprint::Pattern(pattern!("8B 44 24 ?? 50 E8"))),
+//!         ],
+//!         apply: patch_amamiko,
+//!     },
+//! ];
+//!
+//! crate::utils::game_version::apply_detected(PATCH_SETS).unwrap();
+//! ```
+//!
+//! 一个补丁集的所有检查都通过才算匹配，没有匹配或匹配多个时拒绝执行。
+//! 配置中的`GAME_VERSION`不为空时跳过检测，直接使用同名的补丁集。
+//! 开启`debug_output`时会输出每一项检查的结果
+
+use std::collections::HashMap;
+
+use windows_sys::Win32::System::{
+    Diagnostics::Debug::{IMAGE_FILE_HEADER, IMAGE_SECTION_HEADER},
+    SystemServices::{IMAGE_DOS_HEADER, IMAGE_DOS_SIGNATURE, IMAGE_NT_SIGNATURE},
+};
+
+use crate::debug;
+use crate::utils::exts::slice_ext::WideSliceExt;
+use crate::utils::mem::patch::get_dos_and_nt_headers;
+use crate::utils::mem::scan::{Pattern, scan_module};
+
+/// 模块的一项特征
+#[derive(Debug, Clone, Copy)]
+pub enum Fingerprint {
+    /// PE 文件头中的`TimeDateStamp`
+    Timestamp(u32),
+    /// 可选头中的`SizeOfImage`
+    SizeOfImage(u32),
+    /// 磁盘上模块文件`.text`节的 sha256（十六进制，不区分大小写），不受重定位与内存补丁影响
+    TextSha256(&'static str),
+    /// 模块中至少有一处匹配的特征码
+    Pattern(Pattern),
+}
+
+/// 对某个模块的一项检查
+#[derive(Debug, Clone, Copy)]
+pub struct Check {
+    /// 模块名，`None`为主模块
+    pub module: Option<&'static str>,
+    pub fingerprint: Fingerprint,
+}
+
+impl Check {
+    /// 检查主模块
+    pub const fn main(fingerprint: Fingerprint) -> Self {
+        Self {
+            module: None,
+            fingerprint,
+        }
+    }
+
+    /// 检查指定的模块（如`"sc.dll"`），模块未加载时检查失败
+    pub const fn module(module: &'static str, fingerprint: Fingerprint) -> Self {
+        Self {
+            module: Some(module),
+            fingerprint,
+        }
+    }
+}
+
+/// 一个版本对应的补丁
+#[derive(Debug, Clone, Copy)]
+pub struct PatchSet {
+    pub name: &'static str,
+    /// 所有检查都通过时匹配；为空时永远不会被自动选中，只能通过`GAME_VERSION`指定
+    pub checks: &'static [Check],
+    pub apply: fn(),
+}
+
+/// 检测当前游戏的版本，返回唯一匹配的补丁集
+pub fn detect(sets: &'static [PatchSet]) -> crate::Result<&'static PatchSet> {
+    if let Some(name) = crate::constant::GAME_VERSION.filter(|name| !name.is_empty()) {
+        return match sets.iter().find(|set| set.name == name) {
+            Some(set) => {
+                debug!("Game version: {name} (forced by GAME_VERSION)");
+                Ok(set)
+            }
+            None => crate::bail!(
+                "GAME_VERSION {name} is not a registered patch set, available: {:?}",
+                sets.iter().map(|set| set.name).collect::<Vec<_>>()
+            ),
+        };
+    }
+
+    // .text 的哈希需要读取整个文件，同一模块只计算一次
+    let mut text_hashes = HashMap::new();
+    let mut matched = Vec::new();
+
+    for set in sets {
+        let mut ok = !set.checks.is_empty();
+
+        for check in set.checks {
+            let _module = check.module.unwrap_or("<main>");
+            match evaluate(check, &mut text_hashes) {
+                Ok(None) => debug!(
+                    "Game version [{}] {_module} {:?}: ok",
+                    set.name, check.fingerprint
+                ),
+                Ok(Some(_reason)) => {
+                    debug!("Game version [{}] {_module}: {_reason}", set.name);
+                    ok = false;
+                }
+                // 具体原因在产生错误时已经输出
+                Err(_) => {
+                    debug!("Game version [{}] {_module}: check failed", set.name);
+                    ok = false;
+                }
+            }
+        }
+
+        if ok {
+            matched.push(set);
+        }
+    }
+
+    match matched.as_slice() {
+        [set] => {
+            debug!(
+                "Game version: {} (all {} checks passed)",
+                set.name,
+                set.checks.len()
+            );
+            Ok(set)
+        }
+        [] => crate::bail!("No patch set matches the current game, refusing to patch"),
+        _ => crate::bail!(
+            "Multiple patch sets match the current game: {:?}, refusing to patch",
+            matched.iter().map(|set| set.name).collect::<Vec<_>>()
+        ),
+    }
+}
+
+/// 检测版本并执行对应的补丁，返回补丁集的名称
+pub fn apply_detected(sets: &'static [PatchSet]) -> crate::Result<&'static str> {
+    let set = detect(sets)?;
+    (set.apply)();
+    Ok(set.name)
+}
+
+/// 执行一项检查，通过时返回`None`，否则返回不匹配的原因
+fn evaluate(
+    check: &Check,
+    text_hashes: &mut HashMap<Option<&'static str>, String>,
+) -> crate::Result<Option<String>> {
+    let base = module_base(check.module)?;
+    let (_, nt) = unsafe { get_dos_and_nt_headers(base)? };
+
+    let mismatch = match check.fingerprint {
+        Fingerprint::Timestamp(expected) => {
+            let actual = nt.FileHeader.TimeDateStamp;
+            (actual != expected)
+                .then(|| format!("timestamp expected {expected:#010x}, found {actual:#010x}"))
+        }
+        Fingerprint::SizeOfImage(expected) => {
+            let actual = nt.OptionalHeader.SizeOfImage;
+            (actual != expected)
+                .then(|| format!("SizeOfImage expected {expected:#x}, found {actual:#x}"))
+        }
+        Fingerprint::TextSha256(expected) => {
+            let actual = match text_hashes.get(&check.module) {
+                Some(hash) => hash.clone(),
+                None => {
+                    let hash = text_sha256(base)?;
+                    text_hashes.insert(check.module, hash.clone());
+                    hash
+                }
+            };
+            (!actual.eq_ignore_ascii_case(expected))
+                .then(|| format!(".text sha256 expected {expected}, found {actual}"))
+        }
+        Fingerprint::Pattern(pattern) => {
+            let matches = unsafe { scan_module(base, &[], &pattern)? };
+            matches
+                .is_empty()
+                .then(|| format!("pattern {:02X?} not found", pattern.bytes))
+        }
+    };
+
+    Ok(mismatch)
+}
+
+fn module_base(module: Option<&str>) -> crate::Result<usize> {
+    let handle = match module {
+        Some(name) => {
+            let wide: Vec<u16> = name.encode_utf16().chain(core::iter::once(0)).collect();
+            crate::utils::win32::get_module_handle(wide.as_ptr())?
+        }
+        None => crate::utils::win32::get_module_handle(core::ptr::null())?,
+    };
+
+    Ok(handle as usize)
+}
+
+/// 读取模块在磁盘上的文件，计算`.text`节原始数据的 sha256
+fn text_sha256(base: usize) -> crate::Result<String> {
+    let path = crate::utils::win32::get_module_file_name(base as _, false)?;
+    let file = std::fs::read(path.to_path_buf())?;
+    let data = text_section_data(&file)?;
+
+    Ok(crate::utils::sha256_of_bytes(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+/// 在 PE 文件数据中查找`.text`节的原始数据
+///
+/// 文件可能被截断或损坏，所有头都先检查是否在文件范围内，再按未对齐的方式读取
+fn text_section_data(file: &[u8]) -> crate::Result<&[u8]> {
+    fn read<T>(file: &[u8], offset: usize) -> crate::Result<T> {
+        let bytes = offset
+            .checked_add(core::mem::size_of::<T>())
+            .and_then(|end| file.get(offset..end))
+            .ok_or_else(|| crate::anyhow!("PE header at {offset:#x} is out of file bounds"))?;
+        Ok(unsafe { bytes.as_ptr().cast::<T>().read_unaligned() })
+    }
+
+    let dos: IMAGE_DOS_HEADER = read(file, 0)?;
+    if dos.e_magic != IMAGE_DOS_SIGNATURE {
+        crate::bail!("Invalid DOS signature: {:#x}", dos.e_magic);
+    }
+
+    let nt_offset = dos.e_lfanew as u32 as usize;
+    let signature: u32 = read(file, nt_offset)?;
+    if signature != IMAGE_NT_SIGNATURE {
+        crate::bail!("Invalid NT signature: {signature:#x}");
+    }
+
+    // 节表紧跟在可选头之后，文件头的布局与位数无关
+    let file_header_offset = nt_offset + core::mem::size_of::<u32>();
+    let file_header: IMAGE_FILE_HEADER = read(file, file_header_offset)?;
+    let first = file_header_offset
+        + core::mem::size_of::<IMAGE_FILE_HEADER>()
+        + file_header.SizeOfOptionalHeader as usize;
+
+    for i in 0..file_header.NumberOfSections as usize {
+        let header: IMAGE_SECTION_HEADER = read(
+            file,
+            first + i * core::mem::size_of::<IMAGE_SECTION_HEADER>(),
+        )?;
+        if header.Name == *b".text\0\0\0" {
+            let start = header.PointerToRawData as usize;
+            return start
+                .checked_add(header.SizeOfRawData as usize)
+                .and_then(|end| file.get(start..end))
+                .ok_or_else(|| crate::anyhow!("Section .text is out of file bounds"));
+        }
+    }
+
+    crate::bail!("Section .text not found")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NT_OFFSET: usize = 0x40;
+    const OPTIONAL_HEADER_SIZE: usize = 0xE0;
+    const SECTIONS: usize = NT_OFFSET + 24 + OPTIONAL_HEADER_SIZE;
+    const TEXT: &[u8] = b"\x55\x8B\xEC\x83\xEC\x08\xC3\xCC";
+
+    fn put(file: &mut [u8], offset: usize, bytes: &[u8]) {
+        file[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// 只有`.data`与`.text`两个节的最小 PE 文件，`.text`的数据位于文件末尾
+    fn pe_file() -> Vec<u8> {
+        let mut file = vec![0; 0x200];
+        put(&mut file, 0, b"MZ");
+        put(&mut file, 0x3C, &(NT_OFFSET as u32).to_le_bytes());
+        put(&mut file, NT_OFFSET, b"PE\0\0");
+        put(&mut file, NT_OFFSET + 6, &2u16.to_le_bytes());
+        put(
+            &mut file,
+            NT_OFFSET + 20,
+            &(OPTIONAL_HEADER_SIZE as u16).to_le_bytes(),
+        );
+
+        for (i, (name, offset)) in [(b".data\0\0\0", 0x1F0u32), (b".text\0\0\0", 0x200)]
+            .into_iter()
+            .enumerate()
+        {
+            let header = SECTIONS + i * 40;
+            put(&mut file, header, name);
+            put(&mut file, header + 16, &(TEXT.len() as u32).to_le_bytes());
+            put(&mut file, header + 20, &offset.to_le_bytes());
+        }
+
+        file.extend_from_slice(TEXT);
+        file
+    }
+
+    #[test]
+    fn finds_text_section() {
+        let file = pe_file();
+        assert_eq!(text_section_data(&file).unwrap(), TEXT);
+    }
+
+    #[test]
+    fn truncated_file() {
+        let file = pe_file();
+        for len in 0..file.len() {
+            assert!(text_section_data(&file[..len]).is_err(), "len {len:#x}");
+        }
+    }
+
+    #[test]
+    fn invalid_headers() {
+        let corrupt = |patches: &[(usize, &[u8])]| {
+            let mut file = pe_file();
+            for &(offset, bytes) in patches {
+                put(&mut file, offset, bytes);
+            }
+            text_section_data(&file).is_err()
+        };
+        let no_text = (SECTIONS + 40, b".code\0\0\0".as_slice());
+
+        assert!(corrupt(&[(0, b"ZM")]));
+        assert!(corrupt(&[(NT_OFFSET, b"NE")]));
+        // e_lfanew 指向文件之外或为负数
+        assert!(corrupt(&[(0x3C, &0x1000u32.to_le_bytes())]));
+        assert!(corrupt(&[(0x3C, &(-4i32).to_le_bytes())]));
+        // 节表超出文件
+        assert!(corrupt(&[
+            (NT_OFFSET + 6, &u16::MAX.to_le_bytes()),
+            no_text
+        ]));
+        assert!(corrupt(&[(NT_OFFSET + 20, &0xFFF0u16.to_le_bytes())]));
+        // .text 的原始数据超出文件
+        assert!(corrupt(&[(SECTIONS + 40 + 16, &0x100u32.to_le_bytes())]));
+        assert!(corrupt(&[(SECTIONS + 40 + 20, &u32::MAX.to_le_bytes())]));
+        // 没有 .text 节
+        assert!(corrupt(&[no_text]));
+    }
+
+    #[test]
+    fn unaligned_headers() {
+        // NT 头位于奇数偏移处
+        let mut file = pe_file();
+        file.insert(NT_OFFSET, 0);
+        put(&mut file, 0x3C, &(NT_OFFSET as u32 + 1).to_le_bytes());
+        let header = SECTIONS + 1 + 40;
+        put(&mut file, header + 20, &0x201u32.to_le_bytes());
+        assert_eq!(text_section_data(&file).unwrap(), TEXT);
+    }
+}
@@ -0,0 +1,250 @@
+//! 代码洞：把 hook 点被覆盖的指令搬到可执行内存中执行，再跳回原处
+//!
+//! 例如在`0xE490`写入 jmp 前，用[`build_code_cave`]搬走那里的`sub esp, 0x8; push ebx; push ebp`，
+//! 跳板执行完后跳到[`CodeCave::entry`]即可继续执行原函数，无需手动复制指令与计算返回地址
+//!
+//! x64 下 hook 点的`jmp rel32`只能到达 ±2GB，代码洞与跳板都会分配在目标附近：
+//!
+//! ```rust
+//! let cave = build_code_cave(addr, 5)?;
+//! let mut post = Vec::new();
+//! emit_abs_jmp(&mut post, cave.entry);
+//! let stub = generate_trampoline_stub_64(handler as usize, &pre, &post);
+//! let stub_addr = place_code_near(addr, &stub)?;
+//! write_jmp_instruction(addr as *mut u8, stub_addr as *const u8)?;
+//! ```
+
+use std::sync::Mutex;
+
+use windows_sys::Win32::System::{
+    Memory::{
+        MEM_COMMIT, MEM_FREE, MEM_RESERVE, MEMORY_BASIC_INFORMATION, PAGE_EXECUTE_READWRITE,
+        VirtualAlloc, VirtualQuery,
+    },
+    SystemInformation::{GetSystemInfo, SYSTEM_INFO},
+};
+
+use crate::utils::mem::align_up;
+use crate::utils::mem::decode::{MAX_INSTRUCTION_LEN, emit_jmp, relocate};
+use crate::utils::mem::patch::flush_icache;
+
+/// 每次向系统申请的可执行内存大小
+const ARENA_SIZE: usize = 0x10000;
+
+/// rel32 可以到达的距离，留出一些余量
+const MAX_REL32_DISTANCE: usize = 0x7FF0_0000;
+
+/// 已申请的可执行内存`(下一个可分配地址, 结束)`
+static ARENAS: Mutex<Vec<(usize, usize)>> = Mutex::new(Vec::new());
+
+/// 分配`size`字节的可执行内存，分配的内存在进程结束前不会释放
+pub fn alloc_code(size: usize) -> crate::Result<*mut u8> {
+    alloc_code_in(
+        size,
+        |_, _| true,
+        |alloc_size| unsafe {
+            VirtualAlloc(
+                core::ptr::null(),
+                alloc_size,
+                MEM_COMMIT | MEM_RESERVE,
+                PAGE_EXECUTE_READWRITE,
+            ) as usize
+        },
+    )
+}
+
+/// 在`target`的 ±2GB 范围内分配`size`字节的可执行内存，使两者之间可以用 rel32 跳转
+///
+/// 32 位下 rel32 可以到达任意地址，等同于[`alloc_code`]
+pub fn alloc_code_near(target: usize, size: usize) -> crate::Result<*mut u8> {
+    if cfg!(target_arch = "x86") {
+        return alloc_code(size);
+    }
+
+    let reachable = |start: usize, end: usize| {
+        start.abs_diff(target) <= MAX_REL32_DISTANCE && end.abs_diff(target) <= MAX_REL32_DISTANCE
+    };
+    alloc_code_in(size, reachable, |alloc_size| unsafe {
+        alloc_near(target, alloc_size)
+    })
+}
+
+/// 从满足`accept`的已有内存中分配，都不满足时用`alloc`申请新的内存（失败时返回 0）
+fn alloc_code_in(
+    size: usize,
+    accept: impl Fn(usize, usize) -> bool,
+    alloc: impl FnOnce(usize) -> usize,
+) -> crate::Result<*mut u8> {
+    let size = align_up(size, 16);
+    let mut arenas = ARENAS.lock().unwrap();
+
+    let index = match arenas
+        .iter()
+        .position(|&(next, end)| end - next >= size && accept(next, next + size))
+    {
+        Some(index) => index,
+        None => {
+            let alloc_size = align_up(size, ARENA_SIZE);
+            let ptr = alloc(alloc_size);
+            if ptr == 0 {
+                crate::print_last_error_message!();
+                crate::bail!("Failed to allocate {alloc_size:#x} bytes of executable memory");
+            }
+            arenas.push((ptr, ptr + alloc_size));
+            arenas.len() - 1
+        }
+    };
+
+    let arena = &mut arenas[index];
+    let ptr = arena.0;
+    arena.0 += size;
+
+    Ok(ptr as *mut u8)
+}
+
+/// 在`target`附近查找空闲区域并申请内存，先向低地址搜索再向高地址搜索，失败时返回 0
+unsafe fn alloc_near(target: usize, size: usize) -> usize {
+    let (granularity, min_app, max_app) = unsafe {
+        let mut sys: SYSTEM_INFO = core::mem::zeroed();
+        GetSystemInfo(&mut sys as _);
+        (
+            sys.dwAllocationGranularity as usize,
+            sys.lpMinimumApplicationAddress as usize,
+            sys.lpMaximumApplicationAddress as usize,
+        )
+    };
+    let align_down = |addr: usize| addr / granularity * granularity;
+
+    let min_addr = target.saturating_sub(MAX_REL32_DISTANCE).max(min_app);
+    let max_addr = target
+        .saturating_add(MAX_REL32_DISTANCE)
+        .min(max_app)
+        .saturating_sub(size);
+
+    let query = |addr: usize| unsafe {
+        let mut mbi: MEMORY_BASIC_INFORMATION = core::mem::zeroed();
+        let len = VirtualQuery(
+            addr as _,
+            &mut mbi,
+            core::mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+        );
+        (len != 0).then_some(mbi)
+    };
+    let try_alloc = |addr: usize| unsafe {
+        VirtualAlloc(
+            addr as _,
+            size,
+            MEM_COMMIT | MEM_RESERVE,
+            PAGE_EXECUTE_READWRITE,
+        ) as usize
+    };
+
+    // 向低地址搜索
+    let mut addr = align_down(target);
+    while addr >= min_addr {
+        let Some(mbi) = query(addr) else { break };
+        let base = mbi.BaseAddress as usize;
+        let end = base + mbi.RegionSize;
+
+        if mbi.State == MEM_FREE {
+            let candidate = align_down(addr.min(end.saturating_sub(size)));
+            if candidate >= base.max(min_addr) {
+                let ptr = try_alloc(candidate);
+                if ptr != 0 {
+                    return ptr;
+                }
+            }
+        }
+
+        match align_down(base).checked_sub(granularity) {
+            Some(prev) => addr = prev,
+            None => break,
+        }
+    }
+
+    // 向高地址搜索
+    let mut addr = align_up(target, granularity);
+    while addr <= max_addr {
+        let Some(mbi) = query(addr) else { break };
+        let base = mbi.BaseAddress as usize;
+        let end = base + mbi.RegionSize;
+
+        if mbi.State == MEM_FREE {
+            let candidate = align_up(addr.max(base), granularity);
+            if candidate <= max_addr && candidate + size <= end {
+                let ptr = try_alloc(candidate);
+                if ptr != 0 {
+                    return ptr;
+                }
+            }
+        }
+
+        addr = align_up(end, granularity);
+    }
+
+    0
+}
+
+/// 把`code`写入`near`附近（±2GB）的可执行内存，返回写入的地址
+///
+/// 代码必须与位置无关，例如 [`generate_trampoline_stub_64`](crate::utils::mem::patch::generate_trampoline_stub_64) 生成的跳板
+pub fn place_code_near(near: usize, code: &[u8]) -> crate::Result<usize> {
+    let dest = alloc_code_near(near, code.len())?;
+
+    unsafe {
+        core::ptr::copy_nonoverlapping(code.as_ptr(), dest, code.len());
+    }
+    flush_icache(dest, code.len());
+
+    Ok(dest as usize)
+}
+
+/// 搬走的指令以及跳回原处的 jmp
+#[derive(Debug, Clone, Copy)]
+pub struct CodeCave {
+    /// 代码洞的入口，跳到这里即执行被覆盖的指令并回到原函数
+    pub entry: usize,
+    /// 被覆盖的原地址
+    pub patch_addr: usize,
+    /// 从原地址搬走的字节数，写入 hook 后剩余的字节应填充为 nop
+    pub stolen_len: usize,
+}
+
+impl CodeCave {
+    /// 被搬走的指令之后的地址
+    pub fn resume_addr(&self) -> usize {
+        self.patch_addr + self.stolen_len
+    }
+}
+
+/// 为`patch_addr`构建代码洞，搬走至少`min_len`字节的完整指令（写入 jmp 时为 5）
+///
+/// # Safety
+/// - `patch_addr` 必须指向当前进程中可读的有效指令内存。
+/// - 构建代码洞后、写入 hook 前，原地址的指令不能被修改。
+pub unsafe fn build_code_cave(patch_addr: usize, min_len: usize) -> crate::Result<CodeCave> {
+    // 最坏情况下每条指令都是 jcc rel8，在 x64 下展开为 16 字节
+    let max_instructions = min_len + MAX_INSTRUCTION_LEN - 1;
+    let entry = alloc_code_near(patch_addr, max_instructions * 16 + 14)? as usize;
+
+    let relocated = unsafe { relocate(patch_addr, min_len, entry)? };
+    let mut code = relocated.code;
+    let resume = patch_addr + relocated.stolen_len;
+    emit_jmp(&mut code, entry + code.len(), resume);
+
+    unsafe {
+        core::ptr::copy_nonoverlapping(code.as_ptr(), entry as *mut u8, code.len());
+    }
+    flush_icache(entry as *const u8, code.len());
+
+    crate::debug!(
+        "Code cave for {patch_addr:#x} at {entry:#x}, stolen {} bytes",
+        relocated.stolen_len
+    );
+
+    Ok(CodeCave {
+        entry,
+        patch_addr,
+        stolen_len: relocated.stolen_len,
+    })
+}
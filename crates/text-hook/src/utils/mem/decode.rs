@@ -0,0 +1,830 @@
+//! 最小的 x86/x64 指令长度解码与重定位
+//!
+//! 只解析长度以及与位置相关的部分（相对跳转、相对调用与 RIP 相对寻址），
+//! 用于把 inline hook 覆盖掉的指令搬到其他位置执行。
+//! VEX/EVEX/XOP 编码与 3DNow! 指令不支持，遇到时返回错误
+
+/// 当前进程是否为 x64
+const X64: bool = cfg!(target_arch = "x86_64");
+
+/// x86 指令的最大长度
+pub const MAX_INSTRUCTION_LEN: usize = 15;
+
+/// 指令对控制流的影响
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// 顺序执行下一条指令
+    Next,
+    /// `jmp rel8/rel32`
+    Jmp(usize),
+    /// `jcc rel8/rel32`，`cond`为条件码（0x0-0xF）
+    Jcc { cond: u8, target: usize },
+    /// `call rel32`
+    Call(usize),
+    /// `loop/loope/loopne/jecxz`，只有 rel8 形式，无法重定位
+    Loop(usize),
+    /// `ret`/`retf`/`iret`
+    Return,
+    /// 间接跳转`jmp r/m`
+    IndirectJmp,
+}
+
+/// 解码后的指令
+#[derive(Debug, Clone, Copy)]
+pub struct Instruction {
+    /// 指令长度
+    pub len: usize,
+    pub flow: Flow,
+    /// RIP 相对寻址时 disp32 在指令中的偏移（仅 x64）
+    pub rip_disp: Option<usize>,
+}
+
+impl Instruction {
+    /// 执行后不会顺序执行到下一条指令
+    pub fn is_terminator(&self) -> bool {
+        matches!(self.flow, Flow::Jmp(_) | Flow::Return | Flow::IndirectJmp)
+    }
+
+    /// RIP 相对寻址的目标地址，`address`为指令所在地址
+    pub fn rip_target(&self, code: &[u8], address: usize) -> Option<usize> {
+        let offset = self.rip_disp?;
+        let disp = i32::from_le_bytes(code[offset..offset + 4].try_into().ok()?);
+        Some((address + self.len).wrapping_add_signed(disp as isize))
+    }
+}
+
+/// 解码`code`开头的一条指令，`address`为这条指令所在的地址（用于计算跳转目标）
+pub fn decode(code: &[u8], address: usize) -> crate::Result<Instruction> {
+    decode_with_mode(code, address, X64)
+}
+
+/// 解码`address`处的一条指令
+///
+/// # Safety
+/// `address`起的 [`MAX_INSTRUCTION_LEN`] 字节必须可读
+pub unsafe fn decode_at(address: usize) -> crate::Result<Instruction> {
+    let code = unsafe { core::slice::from_raw_parts(address as *const u8, MAX_INSTRUCTION_LEN) };
+    decode(code, address)
+}
+
+/// 操作数的立即数部分
+#[derive(Clone, Copy)]
+enum Imm {
+    None,
+    Fixed(usize),
+    /// imm16/imm32，由操作数大小决定
+    Word,
+    /// 相对偏移
+    Rel8,
+    Rel32,
+}
+
+fn decode_with_mode(code: &[u8], address: usize, x64: bool) -> crate::Result<Instruction> {
+    let byte = |i: usize| -> crate::Result<u8> {
+        match code.get(i) {
+            Some(&b) if i < MAX_INSTRUCTION_LEN => Ok(b),
+            _ => crate::bail!("Truncated instruction at {address:#x}"),
+        }
+    };
+
+    // 前缀
+    let mut pos = 0;
+    let mut opsize16 = false;
+    let mut addrsize_override = false;
+    let mut rex_w = false;
+    loop {
+        let b = byte(pos)?;
+        match b {
+            0x66 => opsize16 = true,
+            0x67 => addrsize_override = true,
+            0xF0 | 0xF2 | 0xF3 | 0x26 | 0x2E | 0x36 | 0x3E | 0x64 | 0x65 => {}
+            // REX 只有紧挨着操作码时才生效
+            0x40..=0x4F if x64 => {
+                rex_w = b & 0x08 != 0;
+                pos += 1;
+                continue;
+            }
+            _ => break,
+        }
+        rex_w = false;
+        pos += 1;
+    }
+
+    let opcode = byte(pos)?;
+    pos += 1;
+
+    let unsupported = || crate::anyhow!("Unsupported opcode {opcode:#04x} at {address:#x}");
+
+    let mut has_modrm = false;
+    let mut imm = Imm::None;
+    let mut flow = Flow::Next;
+    let mut jcc_cond = None;
+
+    if opcode == 0x0F {
+        let op2 = byte(pos)?;
+        pos += 1;
+        match op2 {
+            0x38 => {
+                pos += 1;
+                has_modrm = true;
+            }
+            0x3A => {
+                pos += 1;
+                has_modrm = true;
+                imm = Imm::Fixed(1);
+            }
+            // mov cr/dr 的 ModRM 总是寄存器形式
+            0x20..=0x23 => pos += 1,
+            0x80..=0x8F => {
+                imm = Imm::Rel32;
+                jcc_cond = Some(op2 & 0x0F);
+            }
+            0x05..=0x09
+            | 0x0B
+            | 0x0E
+            | 0x30..=0x37
+            | 0x77
+            | 0xA0..=0xA2
+            | 0xA8..=0xAA
+            | 0xC8..=0xCF => {}
+            0x70..=0x73 | 0xA4 | 0xAC | 0xBA | 0xC2 | 0xC4..=0xC6 => {
+                has_modrm = true;
+                imm = Imm::Fixed(1);
+            }
+            0x04 | 0x0A | 0x0C | 0x0F | 0x24..=0x27 | 0x39 | 0x3B..=0x3F | 0xA6 | 0xA7 => {
+                return Err(unsupported());
+            }
+            _ => has_modrm = true,
+        }
+    } else {
+        match opcode {
+            // 算术指令：r/m, reg / al, imm8 / eax, imm32
+            0x00..=0x3F if opcode & 0x07 < 4 => has_modrm = true,
+            0x00..=0x3F if opcode & 0x07 == 4 => imm = Imm::Fixed(1),
+            0x00..=0x3F if opcode & 0x07 == 5 => imm = Imm::Word,
+            0x00..=0x3F => {}
+            0x40..=0x61 => {}
+            0x62 if x64 => return Err(unsupported()),
+            0x62 | 0x63 => has_modrm = true,
+            0x68 => imm = Imm::Word,
+            0x69 => {
+                has_modrm = true;
+                imm = Imm::Word;
+            }
+            0x6A => imm = Imm::Fixed(1),
+            0x6B => {
+                has_modrm = true;
+                imm = Imm::Fixed(1);
+            }
+            0x6C..=0x6F => {}
+            0x70..=0x7F => {
+                imm = Imm::Rel8;
+                jcc_cond = Some(opcode & 0x0F);
+            }
+            0x80 | 0x82 | 0x83 => {
+                has_modrm = true;
+                imm = Imm::Fixed(1);
+            }
+            0x81 => {
+                has_modrm = true;
+                imm = Imm::Word;
+            }
+            0x84..=0x8F => has_modrm = true,
+            0x9A => imm = Imm::Fixed(if opsize16 { 4 } else { 6 }),
+            0x90..=0x9F => {}
+            0xA0..=0xA3 => {
+                imm = Imm::Fixed(match (x64, addrsize_override) {
+                    (true, false) => 8,
+                    (true, true) | (false, false) => 4,
+                    (false, true) => 2,
+                })
+            }
+            0xA8 => imm = Imm::Fixed(1),
+            0xA9 => imm = Imm::Word,
+            0xA4..=0xAF => {}
+            0xB0..=0xB7 => imm = Imm::Fixed(1),
+            0xB8..=0xBF if rex_w => imm = Imm::Fixed(8),
+            0xB8..=0xBF => imm = Imm::Word,
+            0xC0 | 0xC1 | 0xC6 => {
+                has_modrm = true;
+                imm = Imm::Fixed(1);
+            }
+            0xC2 | 0xCA => {
+                imm = Imm::Fixed(2);
+                flow = Flow::Return;
+            }
+            0xC3 | 0xCB | 0xCF => flow = Flow::Return,
+            0xC4 | 0xC5 => {
+                // x64 下或 mod == 11 时为 VEX 前缀
+                if x64 || byte(pos)? >> 6 == 0b11 {
+                    return Err(unsupported());
+                }
+                has_modrm = true;
+            }
+            0xC7 => {
+                has_modrm = true;
+                imm = Imm::Word;
+            }
+            0xC8 => imm = Imm::Fixed(3),
+            0xCD | 0xD4 | 0xD5 => imm = Imm::Fixed(1),
+            0xC9 | 0xCC | 0xCE | 0xD6 | 0xD7 => {}
+            0xD0..=0xD3 | 0xD8..=0xDF => has_modrm = true,
+            0xE0..=0xE3 => imm = Imm::Rel8,
+            0xE4..=0xE7 => imm = Imm::Fixed(1),
+            0xE8 | 0xE9 => imm = Imm::Rel32,
+            0xEA => imm = Imm::Fixed(if opsize16 { 4 } else { 6 }),
+            0xEB => imm = Imm::Rel8,
+            0xEC..=0xEF | 0xF1 | 0xF4 | 0xF5 | 0xF8..=0xFD => {}
+            0xF6 | 0xF7 => {
+                has_modrm = true;
+                // 只有 test r/m, imm 带立即数
+                if (byte(pos)? >> 3) & 0x07 < 2 {
+                    imm = if opcode == 0xF6 {
+                        Imm::Fixed(1)
+                    } else {
+                        Imm::Word
+                    };
+                }
+            }
+            0xFE => has_modrm = true,
+            0xFF => {
+                has_modrm = true;
+                if matches!((byte(pos)? >> 3) & 0x07, 4 | 5) {
+                    flow = Flow::IndirectJmp;
+                }
+            }
+            _ => return Err(unsupported()),
+        }
+    }
+
+    // ModRM/SIB/disp
+    let mut rip_disp = None;
+    if has_modrm {
+        let modrm = byte(pos)?;
+        pos += 1;
+        let md = modrm >> 6;
+        let rm = modrm & 0x07;
+
+        if md != 0b11 {
+            if !x64 && addrsize_override {
+                // 16 位寻址
+                pos += match (md, rm) {
+                    (0b00, 0b110) => 2,
+                    (0b00, _) => 0,
+                    (0b01, _) => 1,
+                    _ => 2,
+                };
+            } else {
+                let mut base = rm;
+                if rm == 0b100 {
+                    base = byte(pos)? & 0x07;
+                    pos += 1;
+                }
+                match md {
+                    0b00 if rm == 0b101 => {
+                        if x64 {
+                            if addrsize_override {
+                                crate::bail!(
+                                    "EIP-relative addressing at {address:#x} is not supported"
+                                );
+                            }
+                            rip_disp = Some(pos);
+                        }
+                        pos += 4;
+                    }
+                    0b00 if base == 0b101 => pos += 4,
+                    0b00 => {}
+                    0b01 => pos += 1,
+                    _ => pos += 4,
+                }
+            }
+        }
+    }
+
+    let rel = match imm {
+        Imm::None => None,
+        Imm::Fixed(size) => {
+            pos += size;
+            None
+        }
+        Imm::Word => {
+            // REX.W 优先于 0x66
+            pos += if opsize16 && !rex_w { 2 } else { 4 };
+            None
+        }
+        Imm::Rel8 | Imm::Rel32 if opsize16 => {
+            crate::bail!("16-bit relative branch at {address:#x} is not supported");
+        }
+        Imm::Rel8 => {
+            let rel = byte(pos)? as i8 as isize;
+            pos += 1;
+            Some(rel)
+        }
+        Imm::Rel32 => {
+            byte(pos + 3)?;
+            let rel = i32::from_le_bytes(code[pos..pos + 4].try_into().unwrap()) as isize;
+            pos += 4;
+            Some(rel)
+        }
+    };
+
+    // 确认整条指令都在缓冲区内
+    byte(pos - 1)?;
+
+    if let Some(rel) = rel {
+        let target = (address + pos).wrapping_add_signed(rel);
+        flow = match (opcode, jcc_cond) {
+            (_, Some(cond)) => Flow::Jcc { cond, target },
+            (0xE8, _) => Flow::Call(target),
+            (0xE9 | 0xEB, _) => Flow::Jmp(target),
+            _ => Flow::Loop(target),
+        };
+    }
+
+    Ok(Instruction {
+        len: pos,
+        flow,
+        rip_disp,
+    })
+}
+
+/// 重定位后的指令
+#[derive(Debug)]
+pub struct Relocated {
+    /// 可以在新位置执行的指令
+    pub code: Vec<u8>,
+    /// 从原地址搬走的字节数（完整指令）
+    pub stolen_len: usize,
+}
+
+/// 把`src`处至少`min_len`字节的完整指令重定位到`dest`
+///
+/// 相对跳转与调用会改写为 rel32 形式（x64 下超出 ±2GB 时改为绝对跳转），
+/// RIP 相对寻址会修正 disp32。返回的代码不包含跳回原处的 jmp
+///
+/// # Safety
+/// `src`起的指令必须可读
+pub unsafe fn relocate(src: usize, min_len: usize, dest: usize) -> crate::Result<Relocated> {
+    // 最后一条指令从`min_len - 1`开始，最多再读取一条最长的指令
+    let len = min_len + MAX_INSTRUCTION_LEN - 1;
+    let code = unsafe { core::slice::from_raw_parts(src as *const u8, len) };
+    relocate_with_mode(code, src, min_len, dest, X64)
+}
+
+/// 重定位`code`开头至少`min_len`字节的指令，`src`为`code`所在的地址
+fn relocate_with_mode(
+    src_code: &[u8],
+    src: usize,
+    min_len: usize,
+    dest: usize,
+    x64: bool,
+) -> crate::Result<Relocated> {
+    let mut code = Vec::with_capacity(min_len * 2);
+    let mut stolen_len = 0;
+    let mut branch_targets = Vec::new();
+
+    while stolen_len < min_len {
+        let address = src + stolen_len;
+        let rest = src_code.get(stolen_len..).unwrap_or_default();
+        let instr = decode_with_mode(rest, address, x64)?;
+        let bytes = &rest[..instr.len];
+        let at = dest + code.len();
+
+        match instr.flow {
+            Flow::Jmp(target) => emit_jmp(&mut code, at, target),
+            Flow::Call(target) => emit_call(&mut code, at, target),
+            Flow::Jcc { cond, target } => emit_jcc(&mut code, at, cond, target),
+            Flow::Loop(_) => crate::bail!("Cannot relocate loop/jecxz at {address:#x}"),
+            _ => {
+                let start = code.len();
+                code.extend_from_slice(bytes);
+
+                if let Some(offset) = instr.rip_disp {
+                    let target = instr.rip_target(bytes, address).unwrap();
+                    let disp = rel32(at + instr.len, target).ok_or_else(|| {
+                        crate::anyhow!("RIP-relative target {target:#x} out of range from {at:#x}")
+                    })?;
+                    code[start + offset..start + offset + 4].copy_from_slice(&disp.to_le_bytes());
+                }
+            }
+        }
+
+        if let Flow::Jmp(target) | Flow::Jcc { target, .. } = instr.flow {
+            branch_targets.push((address, target));
+        }
+
+        stolen_len += instr.len;
+
+        if stolen_len < min_len && instr.is_terminator() {
+            crate::bail!("Function ends at {address:#x} before {min_len} bytes could be relocated");
+        }
+    }
+
+    // 跳回被搬走范围内部的指令无法重定位
+    if let Some((address, _)) = branch_targets
+        .iter()
+        .find(|&&(_, target)| target > src && target < src + stolen_len)
+    {
+        crate::bail!("Branch at {address:#x} targets the relocated range");
+    }
+
+    Ok(Relocated { code, stolen_len })
+}
+
+/// `next`到`target`的 rel32，超出范围时返回`None`
+fn rel32(next: usize, target: usize) -> Option<i32> {
+    i32::try_from(target.wrapping_sub(next) as isize).ok()
+}
+
+/// 写入跳转到`target`的 jmp，`at`为这条指令将要所在的地址
+pub(crate) fn emit_jmp(code: &mut Vec<u8>, at: usize, target: usize) {
+    match rel32(at + 5, target) {
+        Some(rel) => {
+            code.push(0xE9);
+            code.extend_from_slice(&rel.to_le_bytes());
+        }
+        None => emit_abs_jmp(code, target),
+    }
+}
+
+/// jmp [rip+0]; dq target（仅 x64）
+pub(crate) fn emit_abs_jmp(code: &mut Vec<u8>, target: usize) {
+    code.extend_from_slice(&[0xFF, 0x25, 0, 0, 0, 0]);
+    code.extend_from_slice(&(target as u64).to_le_bytes());
+}
+
+fn emit_call(code: &mut Vec<u8>, at: usize, target: usize) {
+    match rel32(at + 5, target) {
+        Some(rel) => {
+            code.push(0xE8);
+            code.extend_from_slice(&rel.to_le_bytes());
+        }
+        None => {
+            // call [rip+2]; jmp +8; dq target
+            code.extend_from_slice(&[0xFF, 0x15, 0x02, 0, 0, 0, 0xEB, 0x08]);
+            code.extend_from_slice(&(target as u64).to_le_bytes());
+        }
+    }
+}
+
+fn emit_jcc(code: &mut Vec<u8>, at: usize, cond: u8, target: usize) {
+    match rel32(at + 6, target) {
+        Some(rel) => {
+            code.extend_from_slice(&[0x0F, 0x80 | cond]);
+            code.extend_from_slice(&rel.to_le_bytes());
+        }
+        None => {
+            // 反转条件跳过绝对跳转：j!cc +14; jmp [rip+0]; dq target
+            code.extend_from_slice(&[0x70 | (cond ^ 1), 14]);
+            emit_abs_jmp(code, target);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDR: usize = 0x40_1000;
+
+    /// 每项为`(字节, 长度)`，解码时在后面补上足够的 nop
+    fn assert_lengths(x64: bool, table: &[(&[u8], usize)]) {
+        for &(bytes, len) in table {
+            let mut code = bytes.to_vec();
+            code.resize(MAX_INSTRUCTION_LEN, 0x90);
+            let instr = decode_with_mode(&code, ADDR, x64)
+                .unwrap_or_else(|_| panic!("{bytes:02X?} 解码失败"));
+            assert_eq!(instr.len, len, "{bytes:02X?}");
+            assert_eq!(instr.flow, Flow::Next, "{bytes:02X?}");
+        }
+    }
+
+    fn flow(x64: bool, bytes: &[u8]) -> Flow {
+        decode_with_mode(bytes, ADDR, x64).unwrap().flow
+    }
+
+    #[test]
+    fn prefixes() {
+        assert_lengths(
+            false,
+            &[
+                (&[0x90], 1),
+                (&[0xF3, 0xA5], 2),                   // rep movsd
+                (&[0xF0, 0x0F, 0xB1, 0x0A], 4),       // lock cmpxchg [edx], ecx
+                (&[0x64, 0xA1, 0, 0, 0, 0], 6),       // mov eax, fs:[0]
+                (&[0x2E, 0x3E, 0x26, 0x8B, 0xC0], 5), // 多个段前缀
+                (&[0x40], 1),                         // x86 下为 inc eax
+            ],
+        );
+        assert_lengths(
+            true,
+            &[
+                (&[0x40, 0x90], 2),
+                (&[0x41, 0xB0, 0x01], 3), // mov r8b, 1
+                (&[0x65, 0x48, 0x8B, 0x04, 0x25, 0x30, 0, 0, 0], 9), // mov rax, gs:[0x30]
+                (&[0xF3, 0x48, 0xAB], 3), // rep stosq
+            ],
+        );
+    }
+
+    #[test]
+    fn modrm_sib_disp() {
+        assert_lengths(
+            false,
+            &[
+                (&[0x8B, 0xEC], 2),                      // mov ebp, esp
+                (&[0x8B, 0x45, 0x00], 3),                // mov eax, [ebp]
+                (&[0x8B, 0x45, 0x08], 3),                // mov eax, [ebp+8]
+                (&[0x8B, 0x85, 0, 1, 0, 0], 6),          // mov eax, [ebp+0x100]
+                (&[0x8B, 0x05, 0, 0x10, 0, 0], 6),       // mov eax, [0x1000]
+                (&[0x8B, 0x04, 0x24], 3),                // mov eax, [esp]
+                (&[0x8B, 0x44, 0x24, 0x08], 4),          // mov eax, [esp+8]
+                (&[0x8B, 0x84, 0x24, 0, 1, 0, 0], 7),    // mov eax, [esp+0x100]
+                (&[0x8B, 0x04, 0x85, 0, 0x10, 0, 0], 7), // mov eax, [eax*4+0x1000]
+                (&[0x8B, 0x44, 0x88, 0x10], 4),          // mov eax, [eax+ecx*4+0x10]
+                (&[0xA1, 0, 0x10, 0, 0], 5),             // mov eax, [0x1000]
+                (&[0x67, 0x8B, 0x46, 0x02], 4),          // mov eax, [bp+2]
+                (&[0x67, 0x8B, 0x06, 0x34, 0x12], 5),    // mov eax, [0x1234]
+                (&[0x67, 0x8B, 0x00], 3),                // mov eax, [bx+si]
+                (&[0x67, 0xA1, 0x34, 0x12], 4),          // mov eax, [0x1234]
+                (&[0xC5, 0x45, 0x08], 3),                // lds eax, [ebp+8]
+                (&[0xD9, 0x44, 0x24, 0x04], 4),          // fld dword [esp+4]
+            ],
+        );
+        assert_lengths(
+            true,
+            &[
+                (&[0x48, 0x89, 0x5C, 0x24, 0x08], 5), // mov [rsp+8], rbx
+                (&[0x4C, 0x8B, 0x44, 0x24, 0x28], 5), // mov r8, [rsp+0x28]
+                (&[0x42, 0x8B, 0x04, 0xA0], 4),       // mov eax, [rax+r12*4]
+                (&[0x41, 0x8B, 0x45, 0x00], 4),       // mov eax, [r13]
+                (&[0xA1, 0, 0, 0, 0, 0, 0, 0, 0], 9), // mov eax, [moffs64]
+                (&[0x67, 0xA1, 0, 0, 0, 0], 6),       // mov eax, [moffs32]
+            ],
+        );
+    }
+
+    #[test]
+    fn opcode_maps() {
+        assert_lengths(
+            false,
+            &[
+                (&[0x0F, 0x0B], 2),                   // ud2
+                (&[0x0F, 0xA2], 2),                   // cpuid
+                (&[0x0F, 0xB6, 0xC0], 3),             // movzx eax, al
+                (&[0x0F, 0x1F, 0x44, 0x00, 0x00], 5), // nop dword [eax+eax]
+                (&[0x0F, 0x10, 0x44, 0x24, 0x10], 5), // movups xmm0, [esp+0x10]
+                (&[0x0F, 0xBA, 0xE0, 0x03], 4),       // bt eax, 3
+                (&[0x0F, 0xA4, 0xC2, 0x04], 4),       // shld edx, eax, 4
+                (&[0x0F, 0x20, 0xC0], 3),             // mov eax, cr0
+                (&[0x66, 0x0F, 0x38, 0x00, 0xC1], 5), // pshufb xmm0, xmm1
+                (&[0x66, 0x0F, 0x38, 0x00, 0x44, 0x24, 0x08], 7),
+                (&[0x66, 0x0F, 0x3A, 0x0F, 0xC1, 0x08], 6), // palignr xmm0, xmm1, 8
+                (&[0x66, 0x0F, 0x3A, 0x16, 0x04, 0x24, 0x01], 7), // pextrd [esp], xmm0, 1
+            ],
+        );
+        assert_lengths(true, &[(&[0x0F, 0x05], 2)]); // syscall
+    }
+
+    #[test]
+    fn immediate_sizes() {
+        assert_lengths(
+            false,
+            &[
+                (&[0x04, 0x01], 2),                         // add al, 1
+                (&[0x05, 1, 0, 0, 0], 5),                   // add eax, 1
+                (&[0x66, 0x05, 1, 0], 4),                   // add ax, 1
+                (&[0x83, 0xEC, 0x08], 3),                   // sub esp, 8
+                (&[0x81, 0xEC, 0, 1, 0, 0], 6),             // sub esp, 0x100
+                (&[0x66, 0x81, 0xEC, 0, 1], 5),             // sub sp, 0x100
+                (&[0xB8, 0x78, 0x56, 0x34, 0x12], 5),       // mov eax, imm32
+                (&[0x66, 0xB8, 0x34, 0x12], 4),             // mov ax, imm16
+                (&[0xC7, 0x44, 0x24, 0x04, 1, 0, 0, 0], 8), // mov dword [esp+4], 1
+                (&[0x66, 0xC7, 0x00, 1, 0], 5),             // mov word [eax], 1
+                (&[0x68, 0, 0x10, 0, 0], 5),                // push imm32
+                (&[0x66, 0x68, 0, 0x10], 4),                // push imm16
+                (&[0x6A, 0x01], 2),                         // push 1
+                (&[0x69, 0xC0, 0x10, 0, 0, 0], 6),          // imul eax, eax, 0x10
+                (&[0x6B, 0xC0, 0x10], 3),                   // imul eax, eax, 0x10
+                (&[0xF6, 0xC1, 0x01], 3),                   // test cl, 1
+                (&[0xF7, 0xC1, 1, 0, 0, 0], 6),             // test ecx, 1
+                (&[0xF7, 0xD8], 2),                         // neg eax
+                (&[0xC8, 0x10, 0x00, 0x00], 4),             // enter 0x10, 0
+                (&[0x9A, 0, 0, 0, 0, 0x08, 0], 7),          // call far ptr16:32
+                (&[0xFF, 0xD0], 2),                         // call eax
+            ],
+        );
+        assert_lengths(
+            true,
+            &[
+                (&[0x48, 0x83, 0xEC, 0x28], 4),              // sub rsp, 0x28
+                (&[0x48, 0x81, 0xEC, 0, 1, 0, 0], 7),        // sub rsp, 0x100
+                (&[0x66, 0x48, 0x81, 0xEC, 0, 1, 0, 0], 8),  // REX.W 优先于 0x66
+                (&[0x48, 0xB8, 1, 2, 3, 4, 5, 6, 7, 8], 10), // mov rax, imm64
+                (&[0x49, 0xBB, 1, 2, 3, 4, 5, 6, 7, 8], 10), // mov r11, imm64
+                (&[0x66, 0x48, 0xB8, 1, 2, 3, 4, 5, 6, 7, 8], 11),
+                (&[0x48, 0x66, 0xB8, 0x34, 0x12], 5), // REX 不紧挨操作码时无效
+                (&[0x48, 0xC7, 0xC0, 1, 0, 0, 0], 7), // mov rax, imm32
+                (&[0x48, 0xF7, 0xC1, 1, 0, 0, 0], 7), // test rcx, 1
+            ],
+        );
+    }
+
+    #[test]
+    fn control_flow() {
+        let next = ADDR + 2;
+        assert_eq!(flow(false, &[0xEB, 0x10]), Flow::Jmp(next + 0x10));
+        assert_eq!(flow(false, &[0xEB, 0xFE]), Flow::Jmp(ADDR));
+        assert_eq!(
+            flow(false, &[0x74, 0xF0]),
+            Flow::Jcc {
+                cond: 4,
+                target: next - 0x10
+            }
+        );
+        assert_eq!(
+            flow(false, &[0x0F, 0x85, 0, 1, 0, 0]),
+            Flow::Jcc {
+                cond: 5,
+                target: ADDR + 6 + 0x100
+            }
+        );
+        assert_eq!(
+            flow(false, &[0xE8, 0xFB, 0xFF, 0xFF, 0xFF]),
+            Flow::Call(ADDR)
+        );
+        assert_eq!(flow(true, &[0xE9, 0, 0, 0, 0]), Flow::Jmp(ADDR + 5));
+        assert_eq!(flow(false, &[0xE2, 0xFE]), Flow::Loop(ADDR));
+        assert_eq!(flow(false, &[0xE3, 0x05]), Flow::Loop(next + 5));
+        assert_eq!(flow(false, &[0xC3]), Flow::Return);
+        assert_eq!(flow(false, &[0xC2, 0x08, 0x00]), Flow::Return);
+        assert_eq!(flow(false, &[0xFF, 0xE0]), Flow::IndirectJmp);
+        assert_eq!(flow(false, &[0xFF, 0x25, 0, 0x10, 0, 0]), Flow::IndirectJmp);
+        assert!(
+            decode_with_mode(&[0xC3], ADDR, false)
+                .unwrap()
+                .is_terminator()
+        );
+        assert!(
+            !decode_with_mode(&[0xE8, 0, 0, 0, 0], ADDR, false)
+                .unwrap()
+                .is_terminator()
+        );
+    }
+
+    #[test]
+    fn rip_relative() {
+        // mov eax, [rip+0x10]
+        let code = [0x8B, 0x05, 0x10, 0, 0, 0];
+        let instr = decode_with_mode(&code, ADDR, true).unwrap();
+        assert_eq!((instr.len, instr.rip_disp), (6, Some(2)));
+        assert_eq!(instr.rip_target(&code, ADDR), Some(ADDR + 6 + 0x10));
+
+        // lea rcx, [rip-0x10]
+        let code = [0x48, 0x8D, 0x0D, 0xF0, 0xFF, 0xFF, 0xFF];
+        let instr = decode_with_mode(&code, ADDR, true).unwrap();
+        assert_eq!((instr.len, instr.rip_disp), (7, Some(3)));
+        assert_eq!(instr.rip_target(&code, ADDR), Some(ADDR + 7 - 0x10));
+
+        // mov dword [rip+0], 1：disp32 之后还有立即数
+        let code = [0xC7, 0x05, 0, 0, 0, 0, 1, 0, 0, 0];
+        let instr = decode_with_mode(&code, ADDR, true).unwrap();
+        assert_eq!((instr.len, instr.rip_disp), (10, Some(2)));
+        assert_eq!(instr.rip_target(&code, ADDR), Some(ADDR + 10));
+
+        // call [rip]
+        let instr = decode_with_mode(&[0xFF, 0x15, 0, 0, 0, 0], ADDR, true).unwrap();
+        assert_eq!((instr.flow, instr.rip_disp), (Flow::Next, Some(2)));
+
+        // x86 下同样的编码是绝对地址
+        let instr = decode_with_mode(&[0x8B, 0x05, 0x10, 0, 0, 0], ADDR, false).unwrap();
+        assert_eq!(instr.rip_disp, None);
+        // SIB 的 base == 101 不是 RIP 相对寻址
+        let code = [0x8B, 0x04, 0x25, 0x10, 0, 0, 0];
+        assert_eq!(decode_with_mode(&code, ADDR, true).unwrap().rip_disp, None);
+    }
+
+    #[test]
+    fn unsupported() {
+        let cases: &[(bool, &[u8])] = &[
+            (false, &[0x8B]),                              // 缺少 ModRM
+            (false, &[0xE8, 0, 0]),                        // rel32 不完整
+            (false, &[0x66; MAX_INSTRUCTION_LEN + 1]),     // 超过最大长度
+            (false, &[0x66, 0xE9, 0, 0]),                  // 16 位相对跳转
+            (false, &[0xC5, 0xF8, 0x77]),                  // vzeroupper
+            (true, &[0xC5, 0x45, 0x08]),                   // x64 下为 VEX
+            (true, &[0x62, 0xF1, 0x7C, 0x48, 0x10, 0xC1]), // EVEX
+            (false, &[0x0F, 0x0F, 0xC1, 0xB4]),            // 3DNow!
+            (true, &[0x67, 0x8B, 0x05, 0, 0, 0, 0]),       // EIP 相对寻址
+        ];
+        for &(x64, bytes) in cases {
+            assert!(decode_with_mode(bytes, ADDR, x64).is_err(), "{bytes:02X?}");
+        }
+    }
+
+    fn relocate_32(code: &[u8], min_len: usize, dest: usize) -> crate::Result<Relocated> {
+        relocate_with_mode(code, ADDR, min_len, dest, false)
+    }
+
+    #[test]
+    fn relocate_plain_instructions() {
+        // push ebp; mov ebp, esp; sub esp, 8
+        let code = [0x55, 0x8B, 0xEC, 0x83, 0xEC, 0x08, 0xCC];
+        let relocated = relocate_32(&code, 5, 0x50_0000).unwrap();
+        assert_eq!(relocated.stolen_len, 6);
+        assert_eq!(relocated.code, code[..6]);
+    }
+
+    #[test]
+    fn relocate_rel8_to_rel32() {
+        let dest = 0x50_0000;
+        let rel = |next: usize, target: usize| (target.wrapping_sub(next) as u32).to_le_bytes();
+
+        // jz +0x10; nop x3
+        let relocated = relocate_32(&[0x74, 0x10, 0x90, 0x90, 0x90], 5, dest).unwrap();
+        let mut expected = vec![0x0F, 0x84];
+        expected.extend(rel(dest + 6, ADDR + 0x12));
+        expected.extend([0x90, 0x90, 0x90]);
+        assert_eq!(relocated.code, expected);
+        assert_eq!(relocated.stolen_len, 5);
+
+        // jmp 作为最后一条指令时可以重定位
+        let relocated = relocate_32(&[0x90, 0x90, 0x90, 0xEB, 0xF0], 5, dest).unwrap();
+        let mut expected = vec![0x90, 0x90, 0x90, 0xE9];
+        expected.extend(rel(dest + 8, ADDR + 5 - 0x10));
+        assert_eq!(relocated.code, expected);
+
+        // call rel32
+        let relocated = relocate_32(&[0xE8, 0, 0, 0, 0], 5, dest).unwrap();
+        let mut expected = vec![0xE8];
+        expected.extend(rel(dest + 5, ADDR + 5));
+        assert_eq!(relocated.code, expected);
+    }
+
+    #[test]
+    fn relocate_x64_absolute_fallbacks() {
+        let src = 0x7FF6_0000_1000;
+        // 距离超过 ±2GB
+        let dest = src + 0x1_0000_0000;
+        let target = src + 2 + 0x10;
+        let relocate_64 =
+            |code: &[u8], min_len| relocate_with_mode(code, src, min_len, dest, true).unwrap();
+
+        // jmp +0x10 -> jmp [rip+0]; dq target
+        let relocated = relocate_64(&[0xEB, 0x10], 2);
+        let mut expected = vec![0xFF, 0x25, 0, 0, 0, 0];
+        expected.extend((target as u64).to_le_bytes());
+        assert_eq!(relocated.code, expected);
+
+        // jz +0x10 -> jnz +14; jmp [rip+0]; dq target
+        let relocated = relocate_64(&[0x74, 0x10], 2);
+        let mut expected = vec![0x75, 14, 0xFF, 0x25, 0, 0, 0, 0];
+        expected.extend((target as u64).to_le_bytes());
+        assert_eq!(relocated.code, expected);
+
+        // call rel32 -> call [rip+2]; jmp +8; dq target
+        let relocated = relocate_64(&[0xE8, 0x10, 0, 0, 0], 5);
+        let mut expected = vec![0xFF, 0x15, 0x02, 0, 0, 0, 0xEB, 0x08];
+        expected.extend(((src + 5 + 0x10) as u64).to_le_bytes());
+        assert_eq!(relocated.code, expected);
+    }
+
+    #[test]
+    fn relocate_rip_relative() {
+        let src = 0x7FF6_0000_1000;
+        // mov rax, [rip+0x10]
+        let code = [0x48, 0x8B, 0x05, 0x10, 0, 0, 0];
+        let target = src + 7 + 0x10;
+
+        let dest = src + 0x1000_0000;
+        let relocated = relocate_with_mode(&code, src, 5, dest, true).unwrap();
+        let disp = (target.wrapping_sub(dest + 7) as u32).to_le_bytes();
+        assert_eq!(relocated.code[..3], code[..3]);
+        assert_eq!(relocated.code[3..], disp);
+
+        let dest = src + 0x1_0000_0000;
+        assert!(relocate_with_mode(&code, src, 5, dest, true).is_err());
+    }
+
+    #[test]
+    fn relocate_bails() {
+        let dest = 0x50_0000;
+        let cases: &[&[u8]] = &[
+            &[0xE2, 0xFE, 0x90, 0x90, 0x90],       // loop
+            &[0x90, 0xE3, 0x10, 0x90, 0x90],       // jecxz
+            &[0x90, 0xC3, 0xCC, 0xCC, 0xCC],       // 在 min_len 之前 ret
+            &[0xEB, 0x10, 0xCC, 0xCC, 0xCC],       // 在 min_len 之前 jmp
+            &[0x74, 0x01, 0x90, 0x90, 0x90, 0x90], // 跳回被搬走的范围内
+            &[0x90, 0x90, 0x90, 0x75, 0xFC],       // 向后跳到范围内
+        ];
+        for code in cases {
+            assert!(relocate_32(code, 5, dest).is_err(), "{code:02X?}");
+        }
+
+        // 刚好在 min_len 处结束的 ret 可以重定位
+        let relocated = relocate_32(&[0x90, 0x90, 0x90, 0x90, 0xC3], 5, dest).unwrap();
+        assert_eq!(relocated.stolen_len, 5);
+        // 跳到范围开头（即 hook 点本身）不受影响
+        assert!(relocate_32(&[0x90, 0x90, 0x90, 0x75, 0xFB], 5, dest).is_ok());
+    }
+}
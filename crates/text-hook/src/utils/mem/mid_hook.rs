@@ -0,0 +1,58 @@
+//! mid hook 的寄存器上下文
+//!
+//! 由`#[translate_macros::mid_hook(...)]`生成的跳板在 hook 点执行`pushfd; pushad`，
+//! 然后把栈上的寄存器作为[`Registers`]交给回调，回调返回后再用`popad; popfd`恢复，
+//! 因此回调中对寄存器的修改会在恢复执行时生效
+
+/// hook 点的寄存器，字段顺序与`pushad; pushfd`在栈上的布局一致
+#[repr(C)]
+#[derive(Debug)]
+pub struct Registers {
+    pub edi: usize,
+    pub esi: usize,
+    pub ebp: usize,
+    /// hook 点的 esp，只读，修改不会生效（`popad`会忽略这个值）
+    pub esp: usize,
+    pub ebx: usize,
+    pub edx: usize,
+    pub ecx: usize,
+    pub eax: usize,
+    pub eflags: usize,
+}
+
+impl Registers {
+    /// 读取`[esp + offset]`
+    ///
+    /// # Safety
+    /// `esp + offset`必须在 hook 点的栈上
+    pub unsafe fn stack(&self, offset: usize) -> usize {
+        unsafe { *((self.esp + offset) as *const usize) }
+    }
+
+    /// 写入`[esp + offset]`
+    ///
+    /// # Safety
+    /// `esp + offset`必须在 hook 点的栈上，且修改不能破坏原函数的栈帧
+    pub unsafe fn set_stack(&mut self, offset: usize, value: usize) {
+        unsafe { *((self.esp + offset) as *mut usize) = value }
+    }
+}
+
+/// mid hook 回调的返回值，决定回调结束后从哪里继续执行
+///
+/// 返回`0`表示执行被覆盖的指令后回到原地址，否则跳转到返回的地址
+pub trait IntoResume {
+    fn into_resume(self) -> usize;
+}
+
+impl IntoResume for () {
+    fn into_resume(self) -> usize {
+        0
+    }
+}
+
+impl IntoResume for Option<usize> {
+    fn into_resume(self) -> usize {
+        self.unwrap_or(0)
+    }
+}
@@ -1,6 +1,11 @@
+pub(crate) mod code_cave;
+pub(crate) mod decode;
 pub(crate) mod iat;
+#[cfg(target_arch = "x86")]
+pub(crate) mod mid_hook;
 pub(crate) mod patch;
 pub(crate) mod protect_guard;
+pub(crate) mod scan;
 
 /// 从 `*const T` 开始搜索第一个值为 0 的元素，返回 `&[T]`（长度 ≤ max_len）。
 ///
@@ -1,11 +1,12 @@
 use translate_macros::byte_slice;
 use windows_sys::Win32::System::{
-    Diagnostics::Debug::FlushInstructionCache,
+    Diagnostics::Debug::{FlushInstructionCache, IMAGE_SECTION_HEADER},
     Memory::{PAGE_EXECUTE_READWRITE, PAGE_READWRITE},
     SystemServices::{IMAGE_DOS_HEADER, IMAGE_DOS_SIGNATURE, IMAGE_NT_SIGNATURE},
     Threading::GetCurrentProcess,
 };
 
+use crate::utils::mem::decode::{Flow, decode_at};
 use crate::utils::mem::protect_guard::ProtectGuard;
 
 /// 刷新指令缓存（在修改代码段字节后必须调用）
@@ -51,6 +52,141 @@ pub fn write_bytes(address: *mut u8, data: &[u8]) -> crate::Result<()> {
     Ok(())
 }
 
+/// 一段连续的字节补丁，`rva`相对于模块基址
+#[derive(Debug, Clone, Copy)]
+pub struct BytePatch {
+    pub rva: usize,
+    pub original: &'static [u8],
+    pub patched: &'static [u8],
+}
+
+/// 一个模块中的所有字节补丁
+#[derive(Debug, Clone, Copy)]
+pub struct ModulePatches {
+    pub module: &'static str,
+    pub base: usize,
+    pub patches: &'static [BytePatch],
+}
+
+/// 校验后写入字节补丁，`revert`为`true`时将补丁后的字节恢复为原始字节
+///
+/// 会先校验所有补丁，只要有一处既不是预期的字节也不是目标字节，就不写入任何字节并返回错误，
+/// 每处差异都会通过`debug!`输出；已经是目标字节的补丁会被跳过，因此可以重复调用
+pub fn write_verified_patches(modules: &[ModulePatches], revert: bool) -> crate::Result<()> {
+    let mut pending = Vec::new();
+    let mut mismatches = 0usize;
+
+    for module in modules {
+        for patch in module.patches {
+            let (expected, target) = if revert {
+                (patch.patched, patch.original)
+            } else {
+                (patch.original, patch.patched)
+            };
+
+            let address = module.base.wrapping_add(patch.rva) as *mut u8;
+            let current = unsafe { core::slice::from_raw_parts(address, expected.len()) };
+
+            if current == target {
+                continue;
+            }
+            if current == expected {
+                pending.push((address, target));
+                continue;
+            }
+
+            mismatches += 1;
+            crate::debug!(
+                "{}+{:#x}: expected {expected:02X?}, found {current:02X?}, target {target:02X?}",
+                module.module,
+                patch.rva
+            );
+            for (_i, (_expected, _found)) in expected
+                .iter()
+                .zip(current)
+                .enumerate()
+                .filter(|(_, (expected, found))| expected != found)
+            {
+                crate::debug!(
+                    "  {}+{:#x}: expected {_expected:02X}, found {_found:02X}",
+                    module.module,
+                    patch.rva + _i
+                );
+            }
+        }
+    }
+
+    if mismatches > 0 {
+        crate::bail!("{mismatches} patch(es) do not match the expected bytes, nothing was written");
+    }
+
+    for (address, data) in pending {
+        write_asm(address, data)?;
+    }
+
+    Ok(())
+}
+
+/// `asm_bytes!`中占位符的修正项，偏移均相对于代码开头
+#[derive(Debug, Clone, Copy)]
+pub enum AsmFixup {
+    /// 相对于下一条指令地址的 rel32（jmp/call/jcc 的目标）
+    Rel32 {
+        offset: usize,
+        next: usize,
+        target: usize,
+    },
+    /// 绝对值（立即数或位移），`size`为 4 或 8
+    Abs {
+        offset: usize,
+        size: usize,
+        value: usize,
+    },
+}
+
+/// 将占位符的值写入`asm_bytes!`汇编出的代码，`address`为代码的写入地址
+///
+/// rel32 超出范围，或 32 位的立即数无法容纳该值时返回错误
+pub fn apply_asm_fixups<const N: usize>(
+    mut code: [u8; N],
+    address: usize,
+    fixups: &[AsmFixup],
+) -> crate::Result<[u8; N]> {
+    for fixup in fixups {
+        match *fixup {
+            AsmFixup::Rel32 {
+                offset,
+                next,
+                target,
+            } => {
+                let next = address.wrapping_add(next);
+                let diff = (target as isize).wrapping_sub(next as isize);
+                let rel32 = i32::try_from(diff).map_err(|_| {
+                    crate::anyhow!("rel32 out of range: target={target:#x}, next={next:#x}")
+                })?;
+                code[offset..offset + 4].copy_from_slice(&rel32.to_le_bytes());
+            }
+            AsmFixup::Abs {
+                offset,
+                size: 8,
+                value,
+            } => {
+                code[offset..offset + 8].copy_from_slice(&(value as u64).to_le_bytes());
+            }
+            AsmFixup::Abs { offset, value, .. } => {
+                // 零扩展或符号扩展后等于原值都可以
+                let imm32 = u32::try_from(value)
+                    .ok()
+                    .or_else(|| i32::try_from(value as isize).ok().map(|v| v as u32))
+                    .ok_or_else(|| crate::anyhow!("imm32 out of range: {value:#x}"))?;
+                code[offset..offset + 4].copy_from_slice(&imm32.to_le_bytes());
+            }
+        }
+    }
+
+    Ok(code)
+}
+
 /// 生成一个32位的汇编跳板代码的缓冲区
 ///
 /// # 参数
@@ -84,9 +220,78 @@ pub fn generate_trampoline_stub_32(
     code_buf
 }
 
-/// 解析可修补的地址，处理短跳转指令链(最多8次跳转)
+/// 生成一个64位的汇编跳板代码的缓冲区
+///
+/// x64 没有`pushad`，跳板依次保存`rax, rcx, rdx, rbx, rbp, rsi, rdi, r8-r15`与`rflags`，
+/// 执行`pre_asm`时栈布局为：`[rsp]`为 rflags，`[rsp+0x08]`为 r15 …… `[rsp+0x78]`为 rax，
+/// `[rsp+0x80]`开始为 hook 点的栈。`pre_asm`只应设置参数寄存器（rcx/rdx/r8/r9），不能改变 rsp。
+///
+/// 调用目标函数前会把栈对齐到 16 字节，在影子空间之上保存易失的`xmm0-xmm5`（浮点参数与返回值可能位于其中），
+/// 并预留 0x20 字节的影子空间，目标函数应为`extern "system"`。
+/// 调用后恢复所有寄存器、`xmm0-xmm5`与标志（返回值被丢弃），然后执行`post_asm`
+///
+/// # 参数
+/// - `target_fn_addr`: 目标函数地址
+/// - `pre_asm`: 调用目标函数前执行的汇编字节
+/// - `post_asm`: 调用目标函数后执行的汇编字节
+///
+/// # 返回
+/// - 返回包含跳板代码的字节向量
+pub fn generate_trampoline_stub_64(
+    target_fn_addr: usize,
+    pre_asm: &[u8],
+    post_asm: &[u8],
+) -> Vec<u8> {
+    let mut code_buf: Vec<u8> = Vec::with_capacity(160);
+
+    // push rax; push rcx; push rdx; push rbx; push rbp; push rsi; push rdi;
+    // push r8; push r9; push r10; push r11; push r12; push r13; push r14; push r15;
+    // pushfq; cld;
+    code_buf.extend_from_slice(&byte_slice!(
+        "50 51 52 53 55 56 57 41 50 41 51 41 52 41 53 41 54 41 55 41 56 41 57 9C FC"
+    ));
+
+    code_buf.extend_from_slice(pre_asm);
+
+    // mov rbx, rsp; and rsp, -0x10; sub rsp, 0x80;
+    code_buf.extend_from_slice(&byte_slice!("48 89 E3 48 83 E4 F0 48 81 EC 80 00 00 00"));
+
+    // movdqu [rsp+0x20], xmm0; movdqu [rsp+0x30], xmm1; movdqu [rsp+0x40], xmm2;
+    // movdqu [rsp+0x50], xmm3; movdqu [rsp+0x60], xmm4; movdqu [rsp+0x70], xmm5;
+    code_buf.extend_from_slice(&byte_slice!(
+        "F3 0F 7F 44 24 20 F3 0F 7F 4C 24 30 F3 0F 7F 54 24 40 F3 0F 7F 5C 24 50 F3 0F 7F 64 24 60 F3 0F 7F 6C 24 70"
+    ));
+
+    // mov rax, imm64
+    code_buf.extend_from_slice(&byte_slice!("48 B8"));
+    code_buf.extend_from_slice(&(target_fn_addr as u64).to_le_bytes());
+
+    // call rax;
+    code_buf.extend_from_slice(&byte_slice!("FF D0"));
+
+    // movdqu xmm0, [rsp+0x20]; movdqu xmm1, [rsp+0x30]; movdqu xmm2, [rsp+0x40];
+    // movdqu xmm3, [rsp+0x50]; movdqu xmm4, [rsp+0x60]; movdqu xmm5, [rsp+0x70];
+    code_buf.extend_from_slice(&byte_slice!(
+        "F3 0F 6F 44 24 20 F3 0F 6F 4C 24 30 F3 0F 6F 54 24 40 F3 0F 6F 5C 24 50 F3 0F 6F 64 24 60 F3 0F 6F 6C 24 70"
+    ));
+
+    // mov rsp, rbx; popfq;
+    code_buf.extend_from_slice(&byte_slice!("48 89 DC 9D"));
+
+    // pop r15; pop r14; pop r13; pop r12; pop r11; pop r10; pop r9; pop r8;
+    // pop rdi; pop rsi; pop rbp; pop rbx; pop rdx; pop rcx; pop rax;
+    code_buf.extend_from_slice(&byte_slice!(
+        "41 5F 41 5E 41 5D 41 5C 41 5B 41 5A 41 59 41 58 5F 5E 5D 5B 5A 59 58"
+    ));
+
+    code_buf.extend_from_slice(post_asm);
+
+    code_buf
+}
+
+/// 解析可修补的地址，处理跳转指令链(最多8次跳转)
 ///
-/// 这个函数用于解析可能包含跳转指令的地址，通过跟随相对短跳转(0xEB)指令，
+/// 这个函数用于解析可能包含跳转指令的地址，通过跟随相对跳转(`jmp rel8`/`jmp rel32`)指令，
 /// 找到最终的跳转目标地址。这在inline hook中特别有用，
 /// 因为短跳转的字节长度太小会导致inline hook失败
 ///
@@ -98,13 +303,10 @@ pub unsafe fn resolve_patchable_addr(mut addr: usize) -> crate::Result<usize> {
     const MAX_FOLLOW: usize = 8;
 
     for _ in 0..MAX_FOLLOW {
-        let opcode = unsafe { *(addr as *const u8) };
-
-        match opcode {
-            0xEB => {
-                let rel = unsafe { *((addr + 1) as *const i8) } as isize;
-                let next = (addr + 2) as isize;
-                addr = (next + rel) as usize;
+        // 无法解码的指令不是跳转，直接返回
+        match unsafe { decode_at(addr) }.map(|instr| instr.flow) {
+            Ok(Flow::Jmp(target)) => {
+                addr = target;
                 continue;
             }
             _ => {
@@ -238,6 +440,29 @@ pub unsafe fn get_dos_and_nt_headers(
     Ok((dos, nt))
 }
 
+/// 获取目标模块的节表
+///
+/// # Safety
+/// - `module_base` 必须是有效 PE 映像基址，且头部与节表在当前进程中可读。
+/// - 返回的引用依赖于该映像生命周期，调用者必须保证模块不被卸载。
+pub unsafe fn get_section_headers(
+    module_base: usize,
+) -> crate::Result<&'static [IMAGE_SECTION_HEADER]> {
+    unsafe {
+        let (_, nt) = get_dos_and_nt_headers(module_base)?;
+
+        // 节表紧跟在可选头之后
+        let first = (nt as *const IMAGE_NT_HEADERS as usize)
+            + core::mem::offset_of!(IMAGE_NT_HEADERS, OptionalHeader)
+            + nt.FileHeader.SizeOfOptionalHeader as usize;
+
+        Ok(core::slice::from_raw_parts(
+            first as *const IMAGE_SECTION_HEADER,
+            nt.FileHeader.NumberOfSections as usize,
+        ))
+    }
+}
+
 /// 获取当前模块（可执行文件）的入口点地址（Entry Point）
 ///
 /// # Safety
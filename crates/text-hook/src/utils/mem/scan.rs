@@ -0,0 +1,236 @@
+//! 特征码（AOB）扫描
+//!
+//! 特征码通过`translate_macros::pattern!`在编译时生成，例如`pattern!("8B 44 24 ?? 50 E8 ?? ?? ?? ??")`。
+//! 扫描时先用`memchr::memmem`查找特征码中最长的一段确定字节，再比较其余字节，
+//! 因此即使扫描整个`.text`节也足够快。[`scan`]只依赖字节切片，可以直接在普通缓冲区上使用
+
+use memchr::memmem::Finder;
+
+use crate::utils::mem::patch::get_section_headers;
+
+/// 带通配符的特征码，`mask`中为`false`的位置可以匹配任意字节
+#[derive(Debug, Clone, Copy)]
+pub struct Pattern {
+    pub bytes: &'static [u8],
+    pub mask: &'static [bool],
+}
+
+impl Pattern {
+    /// 特征码的字节数
+    pub const fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// 特征码是否为空
+    pub const fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// 判断`data`的开头是否与特征码匹配
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.len() >= self.len()
+            && self
+                .bytes
+                .iter()
+                .zip(self.mask)
+                .zip(data)
+                .all(|((byte, &exact), actual)| !exact || byte == actual)
+    }
+
+    /// 最长的一段连续确定字节，返回`(偏移, 长度)`
+    fn anchor(&self) -> (usize, usize) {
+        let mut best = (0, 0);
+        let mut start = 0;
+
+        for (i, &exact) in self.mask.iter().enumerate() {
+            if !exact {
+                start = i + 1;
+            } else if i + 1 - start > best.1 {
+                best = (start, i + 1 - start);
+            }
+        }
+
+        best
+    }
+}
+
+/// 在`haystack`中查找特征码的所有匹配，返回各匹配的起始偏移（包括相互重叠的匹配）
+pub fn scan(haystack: &[u8], pattern: &Pattern) -> Vec<usize> {
+    let mut matches = Vec::new();
+    if pattern.is_empty() || haystack.len() < pattern.len() {
+        return matches;
+    }
+
+    let (anchor_offset, anchor_len) = pattern.anchor();
+    let finder = Finder::new(&pattern.bytes[anchor_offset..anchor_offset + anchor_len]);
+
+    // 锚点只需要在能容纳整个特征码的范围内查找
+    let last_start = haystack.len() - pattern.len();
+    let window = &haystack[anchor_offset..last_start + anchor_offset + anchor_len];
+
+    // 全是通配符时锚点为空，最后一次匹配后`pos`会越过`window`的结尾
+    let mut pos = 0;
+    while let Some(found) = window.get(pos..).and_then(|rest| finder.find(rest)) {
+        let start = pos + found;
+        if pattern.matches(&haystack[start..]) {
+            matches.push(start);
+        }
+        pos = start + 1;
+    }
+
+    matches
+}
+
+/// 在模块的指定节中扫描特征码，返回所有匹配的绝对地址
+///
+/// `sections`为节名（如`.text`），为空时扫描所有节；指定的节不存在时返回错误
+///
+/// # Safety
+/// - `module_base` 必须是有效 PE 映像基址，且被扫描的节在当前进程中可读。
+/// - 调用者必须保证扫描期间模块不被卸载。
+pub unsafe fn scan_module(
+    module_base: usize,
+    sections: &[&str],
+    pattern: &Pattern,
+) -> crate::Result<Vec<usize>> {
+    let headers = unsafe { get_section_headers(module_base)? };
+
+    for &name in sections {
+        if !headers
+            .iter()
+            .any(|h| section_name(&h.Name) == name.as_bytes())
+        {
+            crate::bail!("Section {name} not found in module {module_base:#x}");
+        }
+    }
+
+    let mut matches = Vec::new();
+    for header in headers {
+        let name = section_name(&header.Name);
+        if !sections.is_empty() && !sections.iter().any(|s| s.as_bytes() == name) {
+            continue;
+        }
+
+        // VirtualSize 为 0 时（部分链接器）使用 SizeOfRawData
+        let size = match unsafe { header.Misc.VirtualSize } {
+            0 => header.SizeOfRawData,
+            size => size,
+        } as usize;
+        let start = module_base + header.VirtualAddress as usize;
+        let data = unsafe { core::slice::from_raw_parts(start as *const u8, size) };
+
+        matches.extend(scan(data, pattern).into_iter().map(|offset| start + offset));
+    }
+
+    Ok(matches)
+}
+
+/// 与[`scan_module`]相同，但要求特征码恰好匹配一次，返回匹配的绝对地址
+///
+/// # Safety
+/// 同[`scan_module`]
+pub unsafe fn scan_module_unique(
+    module_base: usize,
+    sections: &[&str],
+    pattern: &Pattern,
+) -> crate::Result<usize> {
+    let matches = unsafe { scan_module(module_base, sections, pattern)? };
+
+    match matches.as_slice() {
+        [addr] => Ok(*addr),
+        [] => crate::bail!("Pattern {:02X?} not found", pattern.bytes),
+        _ => crate::bail!(
+            "Pattern {:02X?} matched {} times: {matches:#x?}",
+            pattern.bytes,
+            matches.len()
+        ),
+    }
+}
+
+/// 节名去掉结尾的 null
+fn section_name(name: &[u8; 8]) -> &[u8] {
+    let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+    &name[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn pattern(bytes: &'static [u8], mask: &'static [bool]) -> Pattern {
+        Pattern { bytes, mask }
+    }
+
+    #[test]
+    fn single_exact_byte() {
+        let p = pattern(&[0x90], &[true]);
+        assert_eq!(scan(&[0x90, 0x00, 0x90, 0x90], &p), [0, 2, 3]);
+        assert_eq!(scan(&[0x90], &p), [0]);
+        assert!(scan(&[0x00], &p).is_empty());
+        assert!(scan(&[], &p).is_empty());
+    }
+
+    #[test]
+    fn overlapping_matches() {
+        let p = pattern(&[0xAA, 0xAA], &[true, true]);
+        assert_eq!(scan(&[0xAA; 4], &p), [0, 1, 2]);
+
+        let p = pattern(&[0xAB, 0, 0xAB], &[true, false, true]);
+        assert_eq!(scan(&[0xAB, 1, 0xAB, 2, 0xAB], &p), [0, 2]);
+    }
+
+    #[test]
+    fn match_at_last_offset() {
+        let p = pattern(&[0x12, 0x34, 0x56], &[true, true, true]);
+        assert_eq!(scan(&[0, 0, 0, 0x12, 0x34, 0x56], &p), [3]);
+        // 只有部分字节位于结尾时不匹配
+        assert!(scan(&[0, 0, 0, 0, 0x12, 0x34], &p).is_empty());
+    }
+
+    #[test]
+    fn wildcard_leading_pattern() {
+        let p = pattern(&[0, 0, 0x55, 0x8B], &[false, false, true, true]);
+        assert_eq!(scan(&[0x55, 0x8B, 0x55, 0x8B, 0x55, 0x8B], &p), [0, 2]);
+        // 锚点位于开头时，前面没有足够的字节容纳通配符
+        assert!(scan(&[0x55, 0x8B, 0, 0], &p).is_empty());
+
+        let p = pattern(&[0, 0xC3], &[false, true]);
+        assert_eq!(scan(&[0xC3, 0xC3, 1, 0xC3], &p), [0, 2]);
+    }
+
+    #[test]
+    fn wildcard_trailing_pattern() {
+        let p = pattern(&[0xE8, 0, 0, 0, 0], &[true, false, false, false, false]);
+        assert_eq!(scan(&[0xE8, 1, 2, 3, 4, 0xE8, 1, 2], &p), [0]);
+    }
+
+    #[test]
+    fn haystack_shorter_than_pattern() {
+        let p = pattern(&[1, 2, 3], &[true, true, true]);
+        assert!(scan(&[1, 2], &p).is_empty());
+        assert!(scan(&[], &p).is_empty());
+    }
+
+    #[test]
+    fn all_wildcards() {
+        let p = pattern(&[0, 0], &[false, false]);
+        assert_eq!(scan(&[1, 2, 3], &p), [0, 1]);
+        assert!(scan(&[1], &p).is_empty());
+    }
+
+    #[test]
+    fn empty_pattern() {
+        assert!(scan(&[1, 2, 3], &pattern(&[], &[])).is_empty());
+    }
+
+    #[test]
+    fn anchor_picks_longest_exact_run() {
+        let p = pattern(
+            &[1, 0, 2, 3, 4, 0, 5, 6],
+            &[true, false, true, true, true, false, true, true],
+        );
+        assert_eq!(p.anchor(), (2, 3));
+        assert_eq!(pattern(&[0, 1], &[false, true]).anchor(), (1, 1));
+        assert_eq!(pattern(&[0], &[false]).anchor(), (0, 0));
+    }
+}
@@ -1,10 +1,13 @@
 pub(crate) mod error_handling;
 pub(crate) mod exts;
+pub(crate) mod game_version;
 pub(crate) mod hwbp;
 pub(crate) mod mem;
 pub(crate) mod nt;
 pub(crate) mod panic;
+pub(crate) mod profile;
 pub(crate) mod raii_wrapper;
+pub(crate) mod time_zone;
 pub(crate) mod win32;
 
 use sha2::{Digest, Sha256};
@@ -0,0 +1,178 @@
+//! 与`GetPrivateProfile*`系列函数语义一致的缓冲区写入与整数解析
+
+/// 按`GetPrivateProfileString`（节名与键名都不为 NULL 时）的语义将字符串写入缓冲区
+///
+/// 字符串总是以 null 结尾，缓冲区不足时会被截断，此时返回`nSize - 1`；
+/// 否则返回写入的字符数（不包括 null），缓冲区为空时返回 0
+pub fn write_profile_string<T: Copy + Default>(value: &[T], buffer: &mut [T]) -> u32 {
+    let Some(capacity) = buffer.len().checked_sub(1) else {
+        return 0;
+    };
+
+    let len = value.len().min(capacity);
+    buffer[..len].copy_from_slice(&value[..len]);
+    buffer[len] = T::default();
+    len as u32
+}
+
+/// 按`GetPrivateProfileString`（节名或键名为 NULL 时）与`GetPrivateProfileSection`的语义
+/// 将字符串列表写入缓冲区
+///
+/// 每个字符串以 null 结尾，整个列表再以一个 null 结尾，空字符串会被忽略。
+/// 缓冲区不足时最后一个字符串会被截断，并以两个 null 结尾，此时返回`nSize - 2`；
+/// 否则返回写入的字符数（不包括最后的 null）
+pub fn write_profile_list<T: Copy + Default, S: AsRef<[T]>>(items: &[S], buffer: &mut [T]) -> u32 {
+    let mut data: Vec<T> = Vec::new();
+    for item in items
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|item| !item.is_empty())
+    {
+        data.extend_from_slice(item);
+        data.push(T::default());
+    }
+    // 列表结尾的 null，空列表只包含这一个 null
+    data.push(T::default());
+
+    if data.len() <= buffer.len() {
+        buffer[..data.len()].copy_from_slice(&data);
+        return (data.len() - 1) as u32;
+    }
+
+    match buffer.len() {
+        0 => 0,
+        1 => {
+            buffer[0] = T::default();
+            0
+        }
+        len => {
+            buffer[..len - 2].copy_from_slice(&data[..len - 2]);
+            buffer[len - 2] = T::default();
+            buffer[len - 1] = T::default();
+            (len - 2) as u32
+        }
+    }
+}
+
+/// 按`GetPrivateProfileInt`的语义解析整数，值为空时返回`None`（应使用默认值）
+///
+/// 与`RtlUnicodeStringToInteger`一致：跳过开头的空白，可以带`+`或`-`，
+/// 支持`0x`、`0o`、`0b`前缀，遇到第一个无效字符时停止，没有有效数字时为 0，负数按补码返回
+pub fn parse_profile_int(value: &str) -> Option<u32> {
+    if value.is_empty() {
+        return None;
+    }
+
+    let mut rest = value.trim_start_matches(|c: char| c <= ' ');
+    let negative = match rest.as_bytes().first() {
+        Some(b'-') => {
+            rest = &rest[1..];
+            true
+        }
+        Some(b'+') => {
+            rest = &rest[1..];
+            false
+        }
+        _ => false,
+    };
+
+    let (radix, digits) = match rest.get(..2) {
+        Some("0x" | "0X") => (16, &rest[2..]),
+        Some("0o" | "0O") => (8, &rest[2..]),
+        Some("0b" | "0B") => (2, &rest[2..]),
+        _ => (10, rest),
+    };
+
+    let result = digits
+        .chars()
+        .map_while(|c| c.to_digit(radix))
+        .fold(0u32, |acc, digit| {
+            acc.wrapping_mul(radix).wrapping_add(digit)
+        });
+
+    Some(if negative {
+        result.wrapping_neg()
+    } else {
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(value: &str, size: usize) -> (u32, Vec<u8>) {
+        let mut buffer = vec![0xFF; size];
+        let len = write_profile_string(value.as_bytes(), &mut buffer);
+        (len, buffer)
+    }
+
+    fn list(items: &[&str], size: usize) -> (u32, Vec<u8>) {
+        let items: Vec<&[u8]> = items.iter().map(|item| item.as_bytes()).collect();
+        let mut buffer = vec![0xFF; size];
+        let len = write_profile_list(&items, &mut buffer);
+        (len, buffer)
+    }
+
+    #[test]
+    fn string_small_buffers() {
+        assert_eq!(string("abc", 0), (0, vec![]));
+        assert_eq!(string("abc", 1), (0, vec![0]));
+        assert_eq!(string("abc", 2), (1, vec![b'a', 0]));
+    }
+
+    #[test]
+    fn string_fit_and_truncate() {
+        assert_eq!(string("abc", 3), (2, b"ab\0".to_vec()));
+        assert_eq!(string("abc", 4), (3, b"abc\0".to_vec()));
+        assert_eq!(string("abc", 5), (3, b"abc\0\xFF".to_vec()));
+        assert_eq!(string("", 2), (0, vec![0, 0xFF]));
+    }
+
+    #[test]
+    fn list_small_buffers() {
+        assert_eq!(list(&["ab", "cd"], 0), (0, vec![]));
+        assert_eq!(list(&["ab", "cd"], 1), (0, vec![0]));
+        assert_eq!(list(&["ab", "cd"], 2), (0, vec![0, 0]));
+        assert_eq!(list(&["ab", "cd"], 3), (1, vec![b'a', 0, 0]));
+    }
+
+    #[test]
+    fn list_exact_fit() {
+        // `ab\0cd\0\0`共 7 个字符
+        assert_eq!(list(&["ab", "cd"], 7), (6, b"ab\0cd\0\0".to_vec()));
+        assert_eq!(list(&["ab", "cd"], 8), (6, b"ab\0cd\0\0\xFF".to_vec()));
+        assert_eq!(list(&["ab", "cd"], 6), (4, b"ab\0c\0\0".to_vec()));
+        assert_eq!(list(&["ab", "cd"], 5), (3, b"ab\0\0\0".to_vec()));
+    }
+
+    #[test]
+    fn list_skips_empty_items() {
+        assert_eq!(list(&[], 1), (0, vec![0]));
+        assert_eq!(list(&[], 2), (0, vec![0, 0xFF]));
+        assert_eq!(list(&["", "a", ""], 4), (2, b"a\0\0\xFF".to_vec()));
+    }
+
+    #[test]
+    fn list_wide() {
+        let items = [[0x4E2Du16].as_slice(), [0x6587, 0x5B57].as_slice()];
+        let mut buffer = [0xFFFFu16; 6];
+        assert_eq!(write_profile_list(&items, &mut buffer), 5);
+        assert_eq!(buffer, [0x4E2D, 0, 0x6587, 0x5B57, 0, 0]);
+    }
+
+    #[test]
+    fn parse_int() {
+        assert_eq!(parse_profile_int(""), None);
+        assert_eq!(parse_profile_int("42"), Some(42));
+        assert_eq!(parse_profile_int("  \t+42abc"), Some(42));
+        assert_eq!(parse_profile_int("-1"), Some(u32::MAX));
+        assert_eq!(parse_profile_int("0x1F"), Some(31));
+        assert_eq!(parse_profile_int("0X1g"), Some(1));
+        assert_eq!(parse_profile_int("0o17"), Some(15));
+        assert_eq!(parse_profile_int("0b101"), Some(5));
+        assert_eq!(parse_profile_int("abc"), Some(0));
+        assert_eq!(parse_profile_int(" "), Some(0));
+        assert_eq!(parse_profile_int("4294967297"), Some(1));
+    }
+}
@@ -0,0 +1,329 @@
+//! 时区信息
+//!
+//! 优先从注册表读取，注册表中不存在对应的键时（精简版系统、Wine 等）使用内置的常用时区表。
+//! 内置表的数据取自 Windows 10 注册表中的`TZI`，只包含当前的夏令时规则，不包含历史规则
+
+use windows_sys::Win32::Foundation::SYSTEMTIME;
+use windows_sys::Win32::System::Time::TIME_ZONE_INFORMATION;
+
+use crate::debug;
+
+/// 内置的时区
+struct TimeZoneEntry {
+    /// 注册表中的键名，如`Tokyo Standard Time`
+    key: &'static str,
+    bias: i32,
+    standard_name: &'static str,
+    daylight_name: &'static str,
+    daylight_bias: i32,
+    standard_date: SYSTEMTIME,
+    daylight_date: SYSTEMTIME,
+}
+
+/// 没有夏令时
+const NO_TRANSITION: SYSTEMTIME = transition(0, 0, 0, 0);
+
+/// 每年`month`月第`week`个（5 表示最后一个）星期`day_of_week`的`hour`时
+const fn transition(month: u16, day_of_week: u16, week: u16, hour: u16) -> SYSTEMTIME {
+    SYSTEMTIME {
+        wYear: 0,
+        wMonth: month,
+        wDayOfWeek: day_of_week,
+        wDay: week,
+        wHour: hour,
+        wMinute: 0,
+        wSecond: 0,
+        wMilliseconds: 0,
+    }
+}
+
+/// 没有夏令时的时区
+const fn fixed(
+    key: &'static str,
+    bias: i32,
+    standard_name: &'static str,
+    daylight_name: &'static str,
+) -> TimeZoneEntry {
+    TimeZoneEntry {
+        key,
+        bias,
+        standard_name,
+        daylight_name,
+        daylight_bias: -60,
+        standard_date: NO_TRANSITION,
+        daylight_date: NO_TRANSITION,
+    }
+}
+
+/// 有夏令时的时区，夏令时偏差为 -60 分钟
+const fn seasonal(
+    key: &'static str,
+    bias: i32,
+    standard_name: &'static str,
+    daylight_name: &'static str,
+    standard_date: SYSTEMTIME,
+    daylight_date: SYSTEMTIME,
+) -> TimeZoneEntry {
+    TimeZoneEntry {
+        key,
+        bias,
+        standard_name,
+        daylight_name,
+        daylight_bias: -60,
+        standard_date,
+        daylight_date,
+    }
+}
+
+/// 欧盟：10 月最后一个星期日结束，3 月最后一个星期日开始
+const EU_STANDARD: SYSTEMTIME = transition(10, 0, 5, 3);
+const EU_DAYLIGHT: SYSTEMTIME = transition(3, 0, 5, 2);
+/// 北美：11 月第一个星期日结束，3 月第二个星期日开始
+const NA_STANDARD: SYSTEMTIME = transition(11, 0, 1, 2);
+const NA_DAYLIGHT: SYSTEMTIME = transition(3, 0, 2, 2);
+
+static TIME_ZONES: &[TimeZoneEntry] = &[
+    fixed(
+        "Tokyo Standard Time",
+        -540,
+        "Tokyo Standard Time",
+        "Tokyo Daylight Time",
+    ),
+    fixed(
+        "Korea Standard Time",
+        -540,
+        "Korea Standard Time",
+        "Korea Daylight Time",
+    ),
+    fixed(
+        "China Standard Time",
+        -480,
+        "China Standard Time",
+        "China Daylight Time",
+    ),
+    fixed(
+        "Taipei Standard Time",
+        -480,
+        "Taipei Standard Time",
+        "Taipei Daylight Time",
+    ),
+    fixed(
+        "Singapore Standard Time",
+        -480,
+        "Malay Peninsula Standard Time",
+        "Malay Peninsula Daylight Time",
+    ),
+    fixed(
+        "SE Asia Standard Time",
+        -420,
+        "SE Asia Standard Time",
+        "SE Asia Daylight Time",
+    ),
+    fixed(
+        "India Standard Time",
+        -330,
+        "India Standard Time",
+        "India Daylight Time",
+    ),
+    fixed(
+        "Russian Standard Time",
+        -180,
+        "Russia TZ 2 Standard Time",
+        "Russia TZ 2 Daylight Time",
+    ),
+    TimeZoneEntry {
+        daylight_bias: 0,
+        ..fixed(
+            "UTC",
+            0,
+            "Coordinated Universal Time",
+            "Coordinated Universal Time",
+        )
+    },
+    seasonal(
+        "GMT Standard Time",
+        0,
+        "GMT Standard Time",
+        "GMT Daylight Time",
+        transition(10, 0, 5, 2),
+        transition(3, 0, 5, 1),
+    ),
+    seasonal(
+        "W. Europe Standard Time",
+        -60,
+        "W. Europe Standard Time",
+        "W. Europe Daylight Time",
+        EU_STANDARD,
+        EU_DAYLIGHT,
+    ),
+    seasonal(
+        "Romance Standard Time",
+        -60,
+        "Romance Standard Time",
+        "Romance Daylight Time",
+        EU_STANDARD,
+        EU_DAYLIGHT,
+    ),
+    seasonal(
+        "Eastern Standard Time",
+        300,
+        "Eastern Standard Time",
+        "Eastern Daylight Time",
+        NA_STANDARD,
+        NA_DAYLIGHT,
+    ),
+    seasonal(
+        "Central Standard Time",
+        360,
+        "Central Standard Time",
+        "Central Daylight Time",
+        NA_STANDARD,
+        NA_DAYLIGHT,
+    ),
+    seasonal(
+        "Mountain Standard Time",
+        420,
+        "Mountain Standard Time",
+        "Mountain Daylight Time",
+        NA_STANDARD,
+        NA_DAYLIGHT,
+    ),
+    seasonal(
+        "Pacific Standard Time",
+        480,
+        "Pacific Standard Time",
+        "Pacific Daylight Time",
+        NA_STANDARD,
+        NA_DAYLIGHT,
+    ),
+    seasonal(
+        "AUS Eastern Standard Time",
+        -600,
+        "AUS Eastern Standard Time",
+        "AUS Eastern Daylight Time",
+        transition(4, 0, 1, 3),
+        transition(10, 0, 1, 2),
+    ),
+];
+
+/// 将名称编码为`TIME_ZONE_INFORMATION`中的定长字符串，超出部分会被截断
+fn encode_name(name: &str) -> [u16; 32] {
+    let mut buf = [0u16; 32];
+    // 保留结尾的 null
+    for (dst, src) in buf[..31].iter_mut().zip(name.encode_utf16()) {
+        *dst = src;
+    }
+    buf
+}
+
+/// 从内置的时区表中查找时区（不区分 ASCII 大小写）
+pub fn builtin_time_zone_information(timezone: &str) -> Option<TIME_ZONE_INFORMATION> {
+    let entry = TIME_ZONES
+        .iter()
+        .find(|entry| entry.key.eq_ignore_ascii_case(timezone))?;
+
+    Some(TIME_ZONE_INFORMATION {
+        Bias: entry.bias,
+        StandardName: encode_name(entry.standard_name),
+        StandardDate: entry.standard_date,
+        StandardBias: 0,
+        DaylightName: encode_name(entry.daylight_name),
+        DaylightDate: entry.daylight_date,
+        DaylightBias: entry.daylight_bias,
+    })
+}
+
+/// 获取时区信息，注册表读取失败时使用内置的时区表
+pub fn load_time_zone_information(timezone: &str) -> crate::Result<TIME_ZONE_INFORMATION> {
+    match crate::utils::win32::query_time_zone_information(timezone) {
+        Ok(tzi) => Ok(tzi),
+        Err(e) => match builtin_time_zone_information(timezone) {
+            Some(tzi) => {
+                debug!("Using builtin timezone {timezone}: {e:?}");
+                Ok(tzi)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_name(name: &[u16; 32]) -> String {
+        let len = name.iter().position(|c| *c == 0).unwrap();
+        String::from_utf16(&name[..len]).unwrap()
+    }
+
+    #[test]
+    fn builtin_lookup() {
+        let tzi = builtin_time_zone_information("tokyo standard time").unwrap();
+        assert_eq!(tzi.Bias, -540);
+        assert_eq!(tzi.StandardBias, 0);
+        assert_eq!(decode_name(&tzi.StandardName), "Tokyo Standard Time");
+        assert_eq!(decode_name(&tzi.DaylightName), "Tokyo Daylight Time");
+        assert_eq!(tzi.StandardDate.wMonth, 0);
+        assert_eq!(tzi.DaylightDate.wMonth, 0);
+
+        let tzi = builtin_time_zone_information("Pacific Standard Time").unwrap();
+        assert_eq!(tzi.Bias, 480);
+        assert_eq!(tzi.DaylightBias, -60);
+        let (standard, daylight) = (tzi.StandardDate, tzi.DaylightDate);
+        assert_eq!(
+            (
+                standard.wMonth,
+                standard.wDayOfWeek,
+                standard.wDay,
+                standard.wHour
+            ),
+            (11, 0, 1, 2)
+        );
+        assert_eq!(
+            (
+                daylight.wMonth,
+                daylight.wDayOfWeek,
+                daylight.wDay,
+                daylight.wHour
+            ),
+            (3, 0, 2, 2)
+        );
+
+        assert!(builtin_time_zone_information("Mars Standard Time").is_none());
+    }
+
+    #[test]
+    fn encode_name_truncates() {
+        let long = "a".repeat(40);
+        let name = encode_name(&long);
+        assert_eq!(decode_name(&name), "a".repeat(31));
+        assert_eq!(name[31], 0);
+    }
+
+    #[test]
+    fn table_is_consistent() {
+        for (i, entry) in TIME_ZONES.iter().enumerate() {
+            assert!(
+                TIME_ZONES[..i]
+                    .iter()
+                    .all(|other| !other.key.eq_ignore_ascii_case(entry.key)),
+                "{} 重复",
+                entry.key
+            );
+            assert!(entry.standard_name.encode_utf16().count() < 32);
+            assert!(entry.daylight_name.encode_utf16().count() < 32);
+            assert_eq!(entry.bias % 15, 0);
+
+            // 夏令时的开始与结束要么都有，要么都没有
+            let (standard, daylight) = (entry.standard_date, entry.daylight_date);
+            assert_eq!(standard.wMonth == 0, daylight.wMonth == 0, "{}", entry.key);
+            for date in [standard, daylight] {
+                if date.wMonth != 0 {
+                    assert!((1..=12).contains(&date.wMonth));
+                    assert!((1..=5).contains(&date.wDay));
+                    assert!(date.wDayOfWeek <= 6);
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,21 @@
 use scopeguard::defer;
 use windows_sys::{
     Win32::{
-        Foundation::{GetLastError, HMODULE, HWND, SetLastError},
-        Storage::FileSystem::{Wow64DisableWow64FsRedirection, Wow64RevertWow64FsRedirection},
+        Foundation::{FALSE, GetLastError, HANDLE, HMODULE, HWND, SYSTEMTIME, SetLastError, TRUE},
+        Storage::FileSystem::{
+            WIN32_FIND_DATAA, WIN32_FIND_DATAW, Wow64DisableWow64FsRedirection,
+            Wow64RevertWow64FsRedirection,
+        },
         System::{
             Environment::GetCurrentDirectoryW,
             LibraryLoader::{GetModuleFileNameW, GetModuleHandleW, GetProcAddress, LoadLibraryW},
+            Registry::{
+                HKEY, HKEY_LOCAL_MACHINE, KEY_READ, REG_BINARY, REG_SZ, RegCloseKey, RegOpenKeyExW,
+                RegQueryValueExW,
+            },
             SystemInformation::GetSystemDirectoryW,
+            Threading::CreateEventW,
+            Time::TIME_ZONE_INFORMATION,
         },
         UI::WindowsAndMessaging::{
             CB_ADDSTRING, CB_FINDSTRING, CB_FINDSTRINGEXACT, CB_GETLBTEXT, CB_INSERTSTRING,
@@ -15,6 +24,7 @@ use windows_sys::{
         },
     },
     core::{PCSTR, PCWSTR},
+    w,
 };
 
 use crate::{
@@ -328,3 +338,120 @@ where
         }
     }
 }
+
+/// 将宽字符版本的查找结果转为`WIN32_FIND_DATAA`，文件名使用 ANSI 代码页
+pub fn to_find_data_a(data: &WIN32_FIND_DATAW) -> WIN32_FIND_DATAA {
+    let mut out: WIN32_FIND_DATAA = unsafe { core::mem::zeroed() };
+
+    out.dwFileAttributes = data.dwFileAttributes;
+    out.ftCreationTime = data.ftCreationTime;
+    out.ftLastAccessTime = data.ftLastAccessTime;
+    out.ftLastWriteTime = data.ftLastWriteTime;
+    out.nFileSizeHigh = data.nFileSizeHigh;
+    out.nFileSizeLow = data.nFileSizeLow;
+
+    let len = data
+        .cFileName
+        .iter()
+        .position(|c| *c == 0)
+        .unwrap_or(data.cFileName.len());
+    let name = data.cFileName[..len].to_multi_byte(0);
+    let len = name.len().min(out.cFileName.len() - 1);
+    for (dst, src) in out.cFileName.iter_mut().zip(&name[..len]) {
+        *dst = *src as _;
+    }
+
+    out
+}
+
+/// 创建一个虚拟句柄
+///
+/// 虚拟句柄是一个真实的事件对象句柄，用于保证句柄值不会与其他内核对象冲突，
+/// 若游戏将其传给未HOOK的API，也只会得到失败而不会访问到其他对象
+pub fn create_virtual_handle() -> Option<HANDLE> {
+    let handle = unsafe { CreateEventW(core::ptr::null(), TRUE, FALSE, core::ptr::null()) };
+    if handle.is_null() {
+        crate::print_last_error_message!();
+        return None;
+    }
+    Some(handle)
+}
+
+/// 注册表中`TZI`值的格式
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct RegTziFormat {
+    bias: i32,
+    standard_bias: i32,
+    daylight_bias: i32,
+    standard_date: SYSTEMTIME,
+    daylight_date: SYSTEMTIME,
+}
+
+/// 读取注册表值到定长的结构中，类型不一致时返回错误
+fn query_reg_value_into<T: Copy>(
+    hkey: HKEY,
+    value_name: PCWSTR,
+    expected_type: u32,
+) -> crate::Result<T> {
+    let mut value: T = unsafe { core::mem::zeroed() };
+    let mut data_type: u32 = 0;
+    let mut data_size = core::mem::size_of::<T>() as u32;
+
+    let ret = unsafe {
+        RegQueryValueExW(
+            hkey,
+            value_name,
+            core::ptr::null_mut(),
+            &mut data_type,
+            &mut value as *mut T as *mut u8,
+            &mut data_size,
+        )
+    };
+
+    if ret != 0 {
+        crate::bail!(
+            "RegQueryValueExW failed, value={:?}, code={ret}",
+            value_name
+        );
+    }
+
+    if data_type != expected_type {
+        crate::bail!(
+            "Unexpected registry value type, expected={expected_type}, actual={data_type}"
+        );
+    }
+
+    Ok(value)
+}
+
+/// 从注册表中读取时区（如`Tokyo Standard Time`）的信息
+pub fn query_time_zone_information(timezone: &str) -> crate::Result<TIME_ZONE_INFORMATION> {
+    let key = format!("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Time Zones\\{timezone}");
+    let key_w = key.as_bytes().to_wide_null_utf8();
+
+    let mut hkey: HKEY = core::ptr::null_mut();
+    let open_ret =
+        unsafe { RegOpenKeyExW(HKEY_LOCAL_MACHINE, key_w.as_ptr(), 0, KEY_READ, &mut hkey) };
+    if open_ret != 0 {
+        crate::bail!("RegOpenKeyExW failed for timezone '{timezone}', code={open_ret}");
+    }
+
+    defer!(unsafe {
+        RegCloseKey(hkey);
+    });
+
+    let standard_name: [u16; 32] = query_reg_value_into(hkey, w!("Std"), REG_SZ)?;
+    let daylight_name: [u16; 32] = query_reg_value_into(hkey, w!("Dlt"), REG_SZ)?;
+    let tzi: RegTziFormat = query_reg_value_into(hkey, w!("TZI"), REG_BINARY)?;
+
+    Ok(TIME_ZONE_INFORMATION {
+        Bias: tzi.bias,
+        StandardName: standard_name,
+        StandardDate: tzi.standard_date,
+        StandardBias: tzi.standard_bias,
+        DaylightName: daylight_name,
+        DaylightDate: tzi.daylight_date,
+        DaylightBias: tzi.daylight_bias,
+    })
+}
@@ -186,7 +186,9 @@ unsafe extern "system" fn veh_handler(exception_info: *mut EXCEPTION_POINTERS) -
                 }
 
                 #[cfg(feature = "apply_1337_patch_on_hwbp_hit")]
-                crate::x64dbg_1337_patch::apply();
+                if let Err(_e) = crate::x64dbg_1337_patch::apply() {
+                    crate::debug!("Apply 1337 patch failed with {_e:?}");
+                }
 
                 // 清除 DR6 命中标志
                 context.Dr6 &= !0b1111;
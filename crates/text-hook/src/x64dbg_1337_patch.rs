@@ -1,12 +1,20 @@
 use crate::debug;
 
 mod patch_from_1337 {
-    translate_macros::generate_patch_fn_from_1337!("assets/x64dbg_1337_patch" => pub fn apply);
+    translate_macros::generate_patch_fn_from_1337!(
+        "assets/x64dbg_1337_patch" => pub fn apply, pub fn revert
+    );
 }
 
 /// 应用1337文件的补丁数据，会对指定的模块进行写入字节；
-/// 目前还不支持恢复数据
+/// 写入前会校验原始字节，任何一处不匹配都不会写入
 pub fn apply() -> crate::Result<()> {
     debug!("Start 1337 patch...");
     patch_from_1337::apply()
 }
+
+/// 将1337文件中的补丁恢复为原始字节，同样会先校验当前的字节
+pub fn revert() -> crate::Result<()> {
+    debug!("Revert 1337 patch...");
+    patch_from_1337::revert()
+}
@@ -0,0 +1,1074 @@
+use std::collections::{BTreeSet, HashMap};
+
+use iced_x86::{
+    Code, Encoder, EncodingKind, Instruction, MemoryOperand, Mnemonic, OpCodeOperandKind, Register,
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Expr, Ident, LitInt, LitStr, Token};
+
+use crate::impls::byte_slice::byte_lit;
+
+/// 宏输入：`bits, "汇编", name = expr, ...`
+struct Input {
+    bits: LitInt,
+    asm: LitStr,
+    args: Vec<(Ident, Expr)>,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let bits = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let asm = input.parse()?;
+
+        let mut args = Vec::new();
+        if input.parse::<Option<Token![,]>>()?.is_some() {
+            let pairs =
+                Punctuated::<(Ident, Expr), Token![,]>::parse_terminated_with(input, |p| {
+                    let name = p.parse()?;
+                    p.parse::<Token![=]>()?;
+                    Ok((name, p.parse()?))
+                })?;
+            args.extend(pairs);
+        }
+
+        Ok(Self { bits, asm, args })
+    }
+}
+
+/// 指令操作数
+enum Operand {
+    Reg(Register),
+    Imm(i64),
+    Mem {
+        mem: MemoryOperand,
+        /// `byte ptr`等指定的字节数
+        size: Option<usize>,
+        /// 位移为占位符
+        placeholder: Option<String>,
+    },
+    /// 立即数或跳转目标为占位符
+    Placeholder(String),
+    /// 片段内的标签
+    Label(String),
+    /// `$+n`，相对于当前指令开头
+    Relative(i64),
+}
+
+/// 一条解析后的指令
+struct Line {
+    text: String,
+    mnemonic: Mnemonic,
+    operands: Vec<Operand>,
+}
+
+enum Item {
+    Label(String),
+    Instr(Line),
+}
+
+/// 占位符的修正方式
+#[derive(Clone)]
+enum FixupKind {
+    /// 相对于下一条指令地址的 rel32
+    Rel32 { next: usize },
+    /// 绝对值，`size`为 4 或 8
+    Abs { size: usize },
+}
+
+#[derive(Clone)]
+struct Fixup {
+    name: String,
+    offset: usize,
+    kind: FixupKind,
+}
+
+/// 一条指令的编码结果，偏移相对于指令开头
+struct Encoded {
+    bytes: Vec<u8>,
+    fixups: Vec<Fixup>,
+}
+
+/// 占位符使用的临时值，保证选出能容纳完整值的编码
+const PLACEHOLDER_IMM64: i64 = 0x1122_3344_5566_7788;
+const PLACEHOLDER_IMM32: i64 = 0x1234_5678;
+
+fn parse_number(s: &str) -> Option<i64> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest.trim()),
+        None => (false, s),
+    };
+
+    let value = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()?
+    } else if let Some(hex) = s.strip_suffix(['h', 'H'])
+        && s.starts_with(|c: char| c.is_ascii_digit())
+    {
+        u64::from_str_radix(hex, 16).ok()?
+    } else {
+        s.parse::<u64>().ok()?
+    } as i64;
+
+    Some(if negative {
+        value.wrapping_neg()
+    } else {
+        value
+    })
+}
+
+fn parse_register(s: &str) -> Option<Register> {
+    let s = match s.to_ascii_lowercase().as_str() {
+        // iced 中 r8b~r15b 的名字为 r8l~r15l
+        name if name.starts_with('r') && name.ends_with('b') && name.len() <= 4 => {
+            format!("{}l", &name[..name.len() - 1])
+        }
+        name => name.to_string(),
+    };
+
+    Register::values()
+        .filter(|&r| r != Register::None)
+        .find(|r| format!("{r:?}").eq_ignore_ascii_case(&s))
+}
+
+fn parse_mnemonic(s: &str) -> Option<Mnemonic> {
+    let s = s.to_ascii_lowercase();
+    let name = match s.as_str() {
+        "jz" => "je",
+        "jnz" => "jne",
+        "jc" | "jnae" => "jb",
+        "jnc" | "jnb" => "jae",
+        "jna" => "jbe",
+        "jnbe" => "ja",
+        "jnge" => "jl",
+        "jnl" => "jge",
+        "jng" => "jle",
+        "jnle" => "jg",
+        "jpe" => "jp",
+        "jpo" => "jnp",
+        "sal" => "shl",
+        "movabs" => "mov",
+        "retn" => "ret",
+        name => name,
+    };
+
+    Mnemonic::values()
+        .filter(|&m| m != Mnemonic::INVALID)
+        .find(|m| format!("{m:?}").eq_ignore_ascii_case(name))
+}
+
+/// 解析`{name}`形式的占位符
+fn parse_placeholder(s: &str) -> Option<String> {
+    let name = s.strip_prefix('{')?.strip_suffix('}')?.trim();
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then(|| name.to_string())
+}
+
+fn is_identifier(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '.')
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// 解析`[base+index*scale+disp]`中的内容
+fn parse_memory(inner: &str, segment: Register) -> Result<(MemoryOperand, Option<String>), String> {
+    let mut base = Register::None;
+    let mut index = Register::None;
+    let mut scale = 1;
+    let mut displacement = 0i64;
+    let mut has_displacement = false;
+    let mut placeholder = None;
+
+    // 按 + - 拆分，保留符号
+    let mut terms = Vec::new();
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        if (c == '+' || c == '-') && i > 0 {
+            terms.push(&inner[start..i]);
+            start = i;
+        }
+    }
+    terms.push(&inner[start..]);
+
+    for term in terms {
+        let term = term.trim();
+        let (negative, term) = match term.strip_prefix('-') {
+            Some(rest) => (true, rest.trim()),
+            None => (false, term.strip_prefix('+').unwrap_or(term).trim()),
+        };
+
+        if let Some(name) = parse_placeholder(term) {
+            if negative || placeholder.is_some() {
+                return Err(format!(
+                    "内存操作数中的占位符只能出现一次且不能取负：[{inner}]"
+                ));
+            }
+            placeholder = Some(name);
+            continue;
+        }
+
+        if let Some((a, b)) = term.split_once('*') {
+            let (reg, factor) = match (parse_register(a.trim()), parse_register(b.trim())) {
+                (Some(reg), None) => (reg, b.trim()),
+                (None, Some(reg)) => (reg, a.trim()),
+                _ => return Err(format!("无法解析内存操作数中的`{term}`")),
+            };
+            if negative || index != Register::None {
+                return Err(format!("内存操作数中的变址寄存器无效：[{inner}]"));
+            }
+            index = reg;
+            scale = match parse_number(factor) {
+                Some(n @ (1 | 2 | 4 | 8)) => n as u32,
+                _ => return Err(format!("比例因子必须为 1、2、4 或 8：`{term}`")),
+            };
+            continue;
+        }
+
+        if let Some(reg) = parse_register(term) {
+            if negative {
+                return Err(format!("内存操作数中的寄存器不能取负：[{inner}]"));
+            }
+            if base == Register::None {
+                base = reg;
+            } else if index == Register::None {
+                index = reg;
+            } else {
+                return Err(format!("内存操作数中的寄存器过多：[{inner}]"));
+            }
+            continue;
+        }
+
+        match parse_number(term) {
+            Some(n) => {
+                has_displacement = true;
+                displacement = if negative {
+                    displacement.wrapping_sub(n)
+                } else {
+                    displacement.wrapping_add(n)
+                };
+            }
+            None => return Err(format!("无法解析内存操作数中的`{term}`")),
+        }
+    }
+
+    // 片段总是在 IP 0 处汇编，iced 会把 RIP 相对位移当作绝对目标，编码出错误的位移
+    if [base, index]
+        .iter()
+        .any(|reg| matches!(reg, Register::RIP | Register::EIP))
+    {
+        return Err(format!("不支持 RIP 相对寻址：[{inner}]"));
+    }
+    if placeholder.is_some() && has_displacement {
+        return Err(format!("占位符不能与数值位移同时使用：[{inner}]"));
+    }
+    if placeholder.is_some() {
+        displacement = PLACEHOLDER_IMM32;
+    }
+
+    // 没有基址与变址时为 32 位绝对地址；占位符需要完整的 32 位位移
+    let displ_size = if (base == Register::None && index == Register::None) || placeholder.is_some()
+    {
+        4
+    } else if displacement != 0 {
+        1
+    } else {
+        0
+    };
+
+    let mem = MemoryOperand::new(base, index, scale, displacement, displ_size, false, segment);
+    Ok((mem, placeholder))
+}
+
+fn parse_operand(s: &str) -> Result<Operand, String> {
+    let s = s.trim();
+
+    if let Some(name) = parse_placeholder(s) {
+        return Ok(Operand::Placeholder(name));
+    }
+
+    // 内存操作数：[size ptr] [seg:][...]
+    if s.ends_with(']') {
+        let lower = s.to_ascii_lowercase();
+        let (size, rest) = match lower.split_once("ptr") {
+            Some((size, _)) => {
+                let size = match size.trim() {
+                    "byte" => 1,
+                    "word" => 2,
+                    "dword" => 4,
+                    "qword" => 8,
+                    other => return Err(format!("不支持的操作数大小`{other}`")),
+                };
+                (Some(size), s[lower.find("ptr").unwrap() + 3..].trim())
+            }
+            None => (None, s),
+        };
+
+        let (segment, bracket) = match rest.split_once('[') {
+            Some((prefix, inner)) => {
+                let prefix = prefix.trim();
+                let segment = if prefix.is_empty() {
+                    Register::None
+                } else {
+                    prefix
+                        .strip_suffix(':')
+                        .and_then(|seg| parse_register(seg.trim()))
+                        .filter(|seg| seg.is_segment_register())
+                        .ok_or_else(|| format!("无法解析段前缀`{prefix}`"))?
+                };
+                (segment, inner)
+            }
+            None => return Err(format!("无法解析内存操作数`{s}`")),
+        };
+
+        let (mem, placeholder) = parse_memory(bracket.trim_end_matches(']'), segment)?;
+        return Ok(Operand::Mem {
+            mem,
+            size,
+            placeholder,
+        });
+    }
+
+    if let Some(reg) = parse_register(s) {
+        return Ok(Operand::Reg(reg));
+    }
+
+    if let Some(rel) = s.strip_prefix('$') {
+        let rel = rel.replace(' ', "");
+        let offset = match rel.strip_prefix('+') {
+            _ if rel.is_empty() => Some(0),
+            Some(n) => parse_number(n),
+            None => parse_number(&rel),
+        };
+        return offset
+            .map(Operand::Relative)
+            .ok_or_else(|| format!("无法解析相对地址`{s}`"));
+    }
+
+    if let Some(n) = parse_number(s) {
+        return Ok(Operand::Imm(n));
+    }
+
+    if is_identifier(s) {
+        return Ok(Operand::Label(s.to_string()));
+    }
+
+    Err(format!("无法解析操作数`{s}`"))
+}
+
+/// 将汇编文本拆分为标签与指令，指令之间用换行或`;`分隔
+fn parse_asm(asm: &str) -> Result<Vec<Item>, String> {
+    let mut items = Vec::new();
+
+    for statement in asm.split(['\n', ';']) {
+        let mut statement = statement.trim();
+        if statement.is_empty() {
+            continue;
+        }
+
+        // 标签，可以与指令写在同一行
+        if let Some((label, rest)) = statement.split_once(':')
+            && is_identifier(label.trim())
+            && !label.trim().contains(' ')
+            && parse_register(label.trim()).is_none()
+        {
+            items.push(Item::Label(label.trim().to_string()));
+            statement = rest.trim();
+            if statement.is_empty() {
+                continue;
+            }
+        }
+
+        let (name, rest) = statement
+            .split_once(char::is_whitespace)
+            .unwrap_or((statement, ""));
+        let mnemonic =
+            parse_mnemonic(name).ok_or_else(|| format!("未知的指令`{name}`：{statement}"))?;
+        let operands = if rest.trim().is_empty() {
+            Vec::new()
+        } else {
+            rest.split(',')
+                .map(parse_operand)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("{e}：{statement}"))?
+        };
+
+        items.push(Item::Instr(Line {
+            text: statement.to_string(),
+            mnemonic,
+            operands,
+        }));
+    }
+
+    Ok(items)
+}
+
+/// 传给 iced 的操作数
+#[derive(Clone, Copy)]
+enum Arg {
+    Reg(Register),
+    Imm(i64),
+    Mem(MemoryOperand),
+}
+
+/// 根据操作数组合创建指令，iced 会检查立即数是否能用该指令编码
+fn build_instruction(code: Code, args: &[Arg]) -> Result<Instruction, String> {
+    use Arg::*;
+
+    // 除了“寄存器, 立即数”以外，iced 只接受 32 位立即数
+    macro_rules! imm32 {
+        ($imm:expr, |$v:ident| $body:expr) => {
+            if let Ok($v) = i32::try_from($imm) {
+                $body
+            } else if let Ok($v) = u32::try_from($imm) {
+                $body
+            } else {
+                return Err(format!("立即数 {:#x} 超出 32 位范围", $imm));
+            }
+        };
+    }
+
+    let result = match *args {
+        [] => Ok(Instruction::with(code)),
+        [Reg(a)] => Instruction::with1(code, a),
+        [Imm(a)] => imm32!(a, |a| Instruction::with1(code, a)),
+        [Mem(a)] => Instruction::with1(code, a),
+        [Reg(a), Reg(b)] => Instruction::with2(code, a, b),
+        [Reg(a), Imm(b)] => Instruction::with2(code, a, b),
+        [Reg(a), Mem(b)] => Instruction::with2(code, a, b),
+        [Mem(a), Reg(b)] => Instruction::with2(code, a, b),
+        [Mem(a), Imm(b)] => imm32!(b, |b| Instruction::with2(code, a, b)),
+        [Imm(a), Reg(b)] => imm32!(a, |a| Instruction::with2(code, a, b)),
+        // 两个立即数的类型必须相同（如 enter）
+        [Imm(a), Imm(b)] => match (i32::try_from(a), i32::try_from(b)) {
+            (Ok(a), Ok(b)) => Instruction::with2(code, a, b),
+            _ => imm32!(a, |a| imm32!(b, |b| Instruction::with2(
+                code, a as u32, b as u32
+            ))),
+        },
+        [Reg(a), Reg(b), Reg(c)] => Instruction::with3(code, a, b, c),
+        [Reg(a), Reg(b), Imm(c)] => imm32!(c, |c| Instruction::with3(code, a, b, c)),
+        [Reg(a), Reg(b), Mem(c)] => Instruction::with3(code, a, b, c),
+        [Reg(a), Mem(b), Reg(c)] => Instruction::with3(code, a, b, c),
+        [Reg(a), Mem(b), Imm(c)] => imm32!(c, |c| Instruction::with3(code, a, b, c)),
+        [Mem(a), Reg(b), Reg(c)] => Instruction::with3(code, a, b, c),
+        [Mem(a), Reg(b), Imm(c)] => imm32!(c, |c| Instruction::with3(code, a, b, c)),
+        _ => return Err("不支持的操作数组合".to_string()),
+    };
+
+    result.map_err(|e| e.to_string())
+}
+
+fn is_branch_kind(kind: OpCodeOperandKind, bits: u32, near_only: bool) -> bool {
+    use OpCodeOperandKind::*;
+    match (bits, kind) {
+        (32, br32_4) | (64, br64_4) => true,
+        (32, br32_1) | (64, br64_1) => !near_only,
+        _ => false,
+    }
+}
+
+/// 指令在当前位数下可用的编码
+fn candidate_codes(mnemonic: Mnemonic, bits: u32, op_count: usize) -> impl Iterator<Item = Code> {
+    Code::values().filter(move |code| {
+        let op_code = code.op_code();
+        code.mnemonic() == mnemonic
+            && op_code.is_instruction()
+            && op_code.encoding() == EncodingKind::Legacy
+            && op_code.op_count() as usize == op_count
+            && if bits == 64 {
+                op_code.mode64()
+            } else {
+                op_code.mode32()
+            }
+    })
+}
+
+fn encode_at(
+    instruction: &Instruction,
+    bits: u32,
+    ip: u64,
+) -> Result<(Vec<u8>, iced_x86::ConstantOffsets), String> {
+    let mut encoder = Encoder::new(bits);
+    encoder.encode(instruction, ip).map_err(|e| e.to_string())?;
+    let offsets = encoder.get_constant_offsets();
+    Ok((encoder.take_buffer(), offsets))
+}
+
+/// 编码跳转指令，目标为标签、`$+n`或占位符
+fn encode_branch(
+    line: &Line,
+    bits: u32,
+    ip: u64,
+    labels: &HashMap<String, u64>,
+    force_near: bool,
+) -> Result<Encoded, String> {
+    let has_near = candidate_codes(line.mnemonic, bits, 1)
+        .any(|code| is_branch_kind(code.op_code().op_kind(0), bits, true));
+    let (target, placeholder, near_only) = match &line.operands[0] {
+        Operand::Placeholder(name) => (ip, Some(name.clone()), true),
+        Operand::Relative(offset) => (ip.wrapping_add(*offset as u64), None, false),
+        Operand::Label(name) => match labels.get(name) {
+            // jecxz、loop 等只有 rel8 编码，第一轮标签地址未定，先按跳到自身计算长度
+            _ if force_near && !has_near => (ip, None, false),
+            Some(&target) => (target, None, force_near),
+            None if force_near => (ip, None, true),
+            None => return Err(format!("未定义的标签`{name}`")),
+        },
+        _ => unreachable!(),
+    };
+
+    let best = candidate_codes(line.mnemonic, bits, 1)
+        .filter(|code| is_branch_kind(code.op_code().op_kind(0), bits, near_only))
+        .filter_map(|code| {
+            let instruction = Instruction::with_branch(code, target).ok()?;
+            encode_at(&instruction, bits, ip).ok()
+        })
+        .min_by_key(|(bytes, _)| bytes.len())
+        .ok_or_else(|| format!("无法编码跳转指令：{}", line.text))?;
+
+    let bytes = best.0;
+    let fixups = placeholder
+        .map(|name| Fixup {
+            name,
+            // rel32 总是位于指令末尾
+            offset: bytes.len() - 4,
+            kind: FixupKind::Rel32 { next: bytes.len() },
+        })
+        .into_iter()
+        .collect();
+
+    Ok(Encoded { bytes, fixups })
+}
+
+/// 编码一条指令，在所有可用的编码中选择最短的一个
+fn encode_line(
+    line: &Line,
+    bits: u32,
+    ip: u64,
+    labels: &HashMap<String, u64>,
+    force_near: bool,
+) -> Result<Encoded, String> {
+    let is_branch_target = matches!(
+        line.operands.as_slice(),
+        [Operand::Placeholder(_) | Operand::Label(_) | Operand::Relative(_)]
+    ) && candidate_codes(line.mnemonic, bits, 1)
+        .any(|code| is_branch_kind(code.op_code().op_kind(0), bits, false));
+    if is_branch_target {
+        return encode_branch(line, bits, ip, labels, force_near);
+    }
+
+    let imm_placeholder = line.operands.iter().find_map(|op| match op {
+        Operand::Placeholder(name) => Some(name.clone()),
+        _ => None,
+    });
+    let mem_placeholder = line.operands.iter().find_map(|op| match op {
+        Operand::Mem { placeholder, .. } => placeholder.clone(),
+        _ => None,
+    });
+    let mem_size = line.operands.iter().find_map(|op| match op {
+        Operand::Mem { size, .. } => Some(*size),
+        _ => None,
+    });
+
+    // 64 位下优先尝试 64 位立即数（如 mov r64, imm64）
+    let dummies: &[i64] = match (&imm_placeholder, bits) {
+        (Some(_), 64) => &[PLACEHOLDER_IMM64, PLACEHOLDER_IMM32],
+        (Some(_), _) => &[PLACEHOLDER_IMM32],
+        (None, _) => &[0],
+    };
+
+    for &dummy in dummies {
+        let mut args = Vec::with_capacity(line.operands.len());
+        for op in &line.operands {
+            args.push(match op {
+                Operand::Reg(reg) => Arg::Reg(*reg),
+                Operand::Imm(imm) => Arg::Imm(*imm),
+                Operand::Placeholder(_) => Arg::Imm(dummy),
+                Operand::Mem { mem, .. } => Arg::Mem(*mem),
+                Operand::Label(name) => {
+                    return Err(format!("`{name}`不是寄存器，标签只能用作跳转目标"));
+                }
+                Operand::Relative(_) => return Err("`$`只能用作跳转目标".to_string()),
+            });
+        }
+
+        let mut encoded = Vec::new();
+        for code in candidate_codes(line.mnemonic, bits, args.len()) {
+            let op_code = code.op_code();
+            if (0..op_code.op_count()).any(|i| is_branch_kind(op_code.op_kind(i), bits, false)) {
+                continue;
+            }
+            if let Some(Some(size)) = mem_size
+                && op_code.memory_size().size() != size
+            {
+                continue;
+            }
+
+            let Ok(instruction) = build_instruction(code, &args) else {
+                continue;
+            };
+            if let Ok((bytes, offsets)) = encode_at(&instruction, bits, ip) {
+                encoded.push((op_code.memory_size().size(), bytes, offsets));
+            }
+        }
+
+        if encoded.is_empty() {
+            continue;
+        }
+
+        // 没有指定大小的内存操作数不能有歧义，例如`push [eax]`
+        if mem_size == Some(None) {
+            let sizes: BTreeSet<_> = encoded.iter().map(|e| e.0).filter(|&s| s != 0).collect();
+            if sizes.len() > 1 {
+                return Err(format!(
+                    "内存操作数大小不明确，请使用 byte/word/dword/qword ptr 指定：{}",
+                    line.text
+                ));
+            }
+        }
+
+        let (_, bytes, offsets) = encoded
+            .into_iter()
+            .min_by_key(|(_, bytes, _)| bytes.len())
+            .unwrap();
+
+        let mut fixups = Vec::new();
+        if let Some(name) = imm_placeholder {
+            let size = offsets.immediate_size();
+            if size != 4 && size != 8 {
+                return Err(format!("占位符立即数必须为 32 或 64 位：{}", line.text));
+            }
+            fixups.push(Fixup {
+                name,
+                offset: offsets.immediate_offset(),
+                kind: FixupKind::Abs { size },
+            });
+        }
+        if let Some(name) = mem_placeholder {
+            if offsets.displacement_size() != 4 {
+                return Err(format!("占位符位移必须为 32 位：{}", line.text));
+            }
+            fixups.push(Fixup {
+                name,
+                offset: offsets.displacement_offset(),
+                kind: FixupKind::Abs { size: 4 },
+            });
+        }
+
+        return Ok(Encoded { bytes, fixups });
+    }
+
+    Err(format!("无法编码指令：{}", line.text))
+}
+
+/// 汇编整个片段，返回字节与相对于片段开头的修正项
+///
+/// 先把所有跳转到标签的指令编码为 rel32，再逐轮尝试缩短为 rel8，直到长度不再变化
+fn assemble(items: &[Item], bits: u32) -> Result<(Vec<u8>, Vec<Fixup>), String> {
+    const MAX_PASSES: usize = 16;
+
+    let mut labels = HashMap::new();
+    for item in items {
+        if let Item::Label(name) = item
+            && labels.insert(name.clone(), 0u64).is_some()
+        {
+            return Err(format!("标签`{name}`重复定义"));
+        }
+    }
+
+    let mut previous_len = None;
+    for pass in 0..MAX_PASSES {
+        let mut bytes = Vec::new();
+        let mut fixups = Vec::new();
+        let mut new_labels = HashMap::new();
+
+        for item in items {
+            match item {
+                Item::Label(name) => {
+                    new_labels.insert(name.clone(), bytes.len() as u64);
+                }
+                Item::Instr(line) => {
+                    let ip = bytes.len() as u64;
+                    let encoded = encode_line(line, bits, ip, &labels, pass == 0)?;
+                    fixups.extend(encoded.fixups.into_iter().map(|mut fixup| {
+                        fixup.offset += ip as usize;
+                        if let FixupKind::Rel32 { next } = &mut fixup.kind {
+                            *next += ip as usize;
+                        }
+                        fixup
+                    }));
+                    bytes.extend(encoded.bytes);
+                }
+            }
+        }
+
+        if pass > 0 && previous_len == Some(bytes.len()) && new_labels == labels {
+            return Ok((bytes, fixups));
+        }
+        previous_len = Some(bytes.len());
+        labels = new_labels;
+    }
+
+    Err("跳转指令的长度无法收敛".to_string())
+}
+
+/// 汇编一段可以原样搬到其他位置执行的代码，如 mid hook 中被覆盖的指令
+///
+/// 不允许标签、占位符与相对跳转，因为它们在新位置的含义会改变
+pub(crate) fn assemble_relocatable(asm: &str, bits: u32) -> Result<Vec<u8>, String> {
+    let items = parse_asm(asm)?;
+
+    for item in &items {
+        match item {
+            Item::Label(name) => return Err(format!("不允许使用标签`{name}`")),
+            Item::Instr(line) => {
+                if line.operands.iter().any(|op| {
+                    matches!(
+                        op,
+                        Operand::Placeholder(_)
+                            | Operand::Label(_)
+                            | Operand::Relative(_)
+                            | Operand::Mem {
+                                placeholder: Some(_),
+                                ..
+                            }
+                    )
+                }) {
+                    return Err(format!("不允许使用占位符或相对跳转：{}", line.text));
+                }
+            }
+        }
+    }
+
+    assemble(&items, bits).map(|(bytes, _)| bytes)
+}
+
+pub fn asm_bytes(input: TokenStream) -> syn::Result<TokenStream> {
+    let input = syn::parse2::<Input>(input)?;
+
+    let bits = input.bits.base10_parse::<u32>()?;
+    if bits != 32 && bits != 64 {
+        syn_bail!(&input.bits, "位数只能为 32 或 64");
+    }
+
+    let items = parse_asm(&input.asm.value()).map_err(|e| syn_err!(&input.asm, "{e}"))?;
+    let (bytes, fixups) = assemble(&items, bits).map_err(|e| syn_err!(&input.asm, "{e}"))?;
+    let byte_lits = bytes.iter().map(|&b| byte_lit(b));
+
+    if fixups.is_empty() && input.args.is_empty() {
+        return Ok(quote! {
+            [ #(#byte_lits),* ]
+        });
+    }
+
+    // 检查参数：与 format! 一样，占位符必须有对应的参数，参数也必须被使用
+    let mut args = HashMap::new();
+    for (name, expr) in &input.args {
+        if args.insert(name.to_string(), (name, expr)).is_some() {
+            syn_bail!(name, "参数`{name}`重复");
+        }
+    }
+
+    let needs_at = fixups
+        .iter()
+        .any(|f| matches!(f.kind, FixupKind::Rel32 { .. }));
+    let at = match args.remove("at") {
+        Some((_, expr)) if needs_at => quote! { (#expr) as usize },
+        Some((name, _)) => syn_bail!(name, "没有相对跳转占位符，不需要`at`"),
+        None if needs_at => syn_bail!(
+            &input.asm,
+            "相对跳转占位符需要通过`at = 地址`指定代码的写入地址"
+        ),
+        None => quote! { 0usize },
+    };
+
+    let mut bindings = Vec::new();
+    let mut used = BTreeSet::new();
+    for fixup in &fixups {
+        if used.insert(fixup.name.clone()) {
+            let Some((_, expr)) = args.remove(&fixup.name) else {
+                syn_bail!(&input.asm, "占位符`{{{}}}`没有对应的参数", fixup.name);
+            };
+            let var = format_ident!("__asm_{}", fixup.name);
+            bindings.push(quote! { let #var: usize = (#expr) as usize; });
+        }
+    }
+    if let Some((name, _)) = args.into_values().next() {
+        syn_bail!(name, "参数`{name}`没有被使用");
+    }
+
+    let fixup_items = fixups.iter().map(|fixup| {
+        let var = format_ident!("__asm_{}", fixup.name);
+        let offset = fixup.offset;
+        match fixup.kind {
+            FixupKind::Rel32 { next } => quote! {
+                crate::utils::mem::patch::AsmFixup::Rel32 {
+                    offset: #offset,
+                    next: #next,
+                    target: #var,
+                }
+            },
+            FixupKind::Abs { size } => quote! {
+                crate::utils::mem::patch::AsmFixup::Abs {
+                    offset: #offset,
+                    size: #size,
+                    value: #var,
+                }
+            },
+        }
+    });
+
+    Ok(quote! {
+        {
+            let __asm_at: usize = #at;
+            #(#bindings)*
+            crate::utils::mem::patch::apply_asm_fixups(
+                [ #(#byte_lits),* ],
+                __asm_at,
+                &[ #(#fixup_items),* ],
+            )
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asm(bits: u32, text: &str) -> Result<(Vec<u8>, Vec<Fixup>), String> {
+        assemble(&parse_asm(text)?, bits)
+    }
+
+    fn error(bits: u32, text: &str) -> String {
+        match asm(bits, text) {
+            Ok(_) => panic!("`{text}`应当汇编失败"),
+            Err(e) => e,
+        }
+    }
+
+    fn bytes(bits: u32, text: &str) -> Vec<u8> {
+        asm(bits, text).unwrap().0
+    }
+
+    /// 占位符的名字、偏移与修正方式，`next`为 None 时表示绝对值的字节数
+    fn fixups(bits: u32, text: &str) -> Vec<(String, usize, usize, Option<usize>)> {
+        asm(bits, text)
+            .unwrap()
+            .1
+            .into_iter()
+            .map(|fixup| match fixup.kind {
+                FixupKind::Rel32 { next } => (fixup.name, fixup.offset, 4, Some(next)),
+                FixupKind::Abs { size } => (fixup.name, fixup.offset, size, None),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn forward_label_relaxed_to_rel8() {
+        assert_eq!(
+            bytes(32, "jmp done; nop; done: ret"),
+            [0xEB, 0x01, 0x90, 0xC3]
+        );
+
+        let text = format!("jz done\n{}done: ret", "nop\n".repeat(100));
+        let code = bytes(32, &text);
+        assert_eq!(code[..2], [0x74, 0x64]);
+        assert_eq!(code.len(), 2 + 100 + 1);
+    }
+
+    #[test]
+    fn forward_label_kept_as_rel32() {
+        let text = format!("jz done\n{}done: ret", "nop\n".repeat(128));
+        let code = bytes(32, &text);
+        assert_eq!(code[..6], [0x0F, 0x84, 0x80, 0x00, 0x00, 0x00]);
+        assert_eq!(code.len(), 6 + 128 + 1);
+
+        let text = format!("jmp done\n{}done: ret", "nop\n".repeat(200));
+        assert_eq!(bytes(64, &text)[..5], [0xE9, 0xC8, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn relaxation_cascades() {
+        // 第一轮 jz 的距离超出 rel8，jmp 缩短后才能缩短
+        let text = format!("jz b\njmp c\n{}b: nop\nc: ret", "nop\n".repeat(120));
+        let code = bytes(32, &text);
+        assert_eq!(code[..4], [0x74, 0x7A, 0xEB, 0x79]);
+        assert_eq!(code.len(), 2 + 2 + 120 + 2);
+    }
+
+    #[test]
+    fn backward_loop() {
+        assert_eq!(bytes(32, "top: dec ecx; jnz top"), [0x49, 0x75, 0xFD]);
+        assert_eq!(bytes(64, "top: dec ecx; jnz top"), [0xFF, 0xC9, 0x75, 0xFC]);
+        assert_eq!(bytes(32, "top:\nloop top"), [0xE2, 0xFE]);
+        assert_eq!(
+            bytes(32, "jecxz done; nop; done: ret"),
+            [0xE3, 0x01, 0x90, 0xC3]
+        );
+
+        // 只有 rel8 编码的跳转在第一轮不能按占位地址计算距离
+        let text = format!("{}top: dec ecx\nloop top", "nop\n".repeat(200));
+        assert_eq!(bytes(32, &text)[200..], [0x49, 0xE2, 0xFD]);
+    }
+
+    #[test]
+    fn relaxation_shifts_later_labels() {
+        // 第一个跳转缩短后，第二个跳转的目标随之前移
+        let text = "jmp a; jmp b; a: nop; b: ret";
+        assert_eq!(bytes(32, text), [0xEB, 0x02, 0xEB, 0x01, 0x90, 0xC3]);
+    }
+
+    #[test]
+    fn relative_target() {
+        assert_eq!(bytes(32, "jmp $"), [0xEB, 0xFE]);
+        assert_eq!(bytes(32, "nop; jmp $+7"), [0x90, 0xEB, 0x05]);
+    }
+
+    #[test]
+    fn branch_placeholder() {
+        assert_eq!(bytes(32, "call {target}"), [0xE8, 0xFB, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(
+            fixups(32, "call {target}"),
+            [("target".to_string(), 1, 4, Some(5))]
+        );
+        // 占位符跳转总是 rel32，偏移相对于片段开头
+        assert_eq!(
+            fixups(32, "nop; jz {target}; ret"),
+            [("target".to_string(), 3, 4, Some(7))]
+        );
+        assert_eq!(
+            fixups(64, "push rax; jmp {target}"),
+            [("target".to_string(), 2, 4, Some(6))]
+        );
+    }
+
+    #[test]
+    fn immediate_placeholder() {
+        assert_eq!(bytes(32, "push {imm32}")[..1], [0x68]);
+        assert_eq!(
+            fixups(32, "push {imm32}"),
+            [("imm32".to_string(), 1, 4, None)]
+        );
+        assert_eq!(
+            fixups(32, "nop; mov ecx, {imm32}"),
+            [("imm32".to_string(), 2, 4, None)]
+        );
+        // 64 位下优先使用 imm64
+        assert_eq!(bytes(64, "mov rax, {addr}")[..2], [0x48, 0xB8]);
+        assert_eq!(
+            fixups(64, "mov rax, {addr}"),
+            [("addr".to_string(), 2, 8, None)]
+        );
+    }
+
+    #[test]
+    fn memory_placeholder() {
+        assert_eq!(
+            fixups(32, "mov ecx, [{addr}]"),
+            [("addr".to_string(), 2, 4, None)]
+        );
+        assert_eq!(
+            fixups(32, "mov dword ptr [eax+{addr}], 1"),
+            [("addr".to_string(), 2, 4, None)]
+        );
+        assert_eq!(
+            fixups(32, "call {target}; push {imm32}"),
+            [
+                ("target".to_string(), 1, 4, Some(5)),
+                ("imm32".to_string(), 6, 4, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn memory_operands() {
+        assert_eq!(bytes(32, "mov eax, [esp+8]"), [0x8B, 0x44, 0x24, 0x08]);
+        assert_eq!(bytes(32, "mov eax, [esp]"), [0x8B, 0x04, 0x24]);
+        assert_eq!(bytes(32, "mov eax, [ebp]"), [0x8B, 0x45, 0x00]);
+        assert_eq!(
+            bytes(32, "mov eax, [eax+ecx*4+0x10]"),
+            [0x8B, 0x44, 0x88, 0x10]
+        );
+        assert_eq!(bytes(32, "lea eax, [4*ecx-8]")[..3], [0x8D, 0x04, 0x8D]);
+        assert_eq!(
+            bytes(32, "lea eax, [esp+0x80]"),
+            [0x8D, 0x84, 0x24, 0x80, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(
+            bytes(32, "mov eax, fs:[0]"),
+            [0x64, 0xA1, 0x00, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(
+            bytes(32, "mov dword ptr fs:[0], esp"),
+            [0x64, 0x89, 0x25, 0x00, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(
+            bytes(64, "mov rax, gs:[0x30]"),
+            [0x65, 0x67, 0x48, 0xA1, 0x30, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(
+            bytes(64, "mov qword ptr [rsp+0x28], r8"),
+            [0x4C, 0x89, 0x44, 0x24, 0x28]
+        );
+        assert_eq!(bytes(32, "push dword ptr [eax]"), [0xFF, 0x30]);
+    }
+
+    #[test]
+    fn rip_relative_is_rejected() {
+        for text in [
+            "mov eax, [rip+0x10]",
+            "lea rax, [rip]",
+            "jmp qword ptr [rip+2]",
+        ] {
+            let err = error(64, text);
+            assert!(err.contains("RIP"), "{err}");
+        }
+        assert!(assemble_relocatable("mov eax, [rip+0x10]", 64).is_err());
+    }
+
+    #[test]
+    fn ambiguous_operand_size() {
+        let err = error(32, "push [eax]");
+        assert!(err.contains("大小不明确"), "{err}");
+        assert!(asm(32, "inc [eax]").is_err());
+    }
+
+    #[test]
+    fn unknown_mnemonic() {
+        let err = error(32, "nop; frobnicate eax");
+        assert!(err.contains("frobnicate"), "{err}");
+    }
+
+    #[test]
+    fn rel8_out_of_range() {
+        // jecxz 与 loop 只有 rel8 编码
+        for mnemonic in ["jecxz", "loop"] {
+            let text = format!("{mnemonic} done\n{}done: ret", "nop\n".repeat(128));
+            let err = error(32, &text);
+            assert!(err.contains("无法编码跳转指令"), "{err}");
+        }
+        let text = format!("top:\n{}jecxz top", "nop\n".repeat(127));
+        assert!(asm(32, &text).is_err());
+    }
+
+    #[test]
+    fn label_errors() {
+        assert!(error(32, "jmp missing").contains("missing"));
+        assert!(error(32, "a: nop; a: ret").contains("重复"));
+        assert!(asm(32, "mov eax, a").is_err());
+    }
+
+    #[test]
+    fn relocatable_rejects_position_dependent_code() {
+        assert_eq!(
+            assemble_relocatable("mov eax, [esp+4]; push ebp", 32),
+            Ok(vec![0x8B, 0x44, 0x24, 0x04, 0x55])
+        );
+        for text in ["a: nop", "jmp $+2", "push {imm32}", "mov eax, [{addr}]"] {
+            assert!(assemble_relocatable(text, 32).is_err(), "{text}");
+        }
+    }
+}
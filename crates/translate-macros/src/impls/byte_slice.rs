@@ -2,9 +2,10 @@ use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::{LitInt, LitStr};
 
-pub fn byte_slice(input: TokenStream) -> syn::Result<TokenStream> {
-    // 只接受一个字符串字面量
-    let lit = syn::parse2::<LitStr>(input)?;
+/// 解析由单个空格分隔的十六进制字节字符串
+///
+/// `allow_wildcard`为`true`时允许`??`通配符，对应的字节为`None`
+pub(crate) fn parse_hex_bytes(lit: &LitStr, allow_wildcard: bool) -> syn::Result<Vec<Option<u8>>> {
     let s = lit.value();
 
     // 基本检查：不能为空
@@ -37,10 +38,15 @@ pub fn byte_slice(input: TokenStream) -> syn::Result<TokenStream> {
         );
     }
 
-    // 存放生成的字面量
-    let mut lits: Vec<LitInt> = Vec::with_capacity(parts.len());
+    let mut bytes = Vec::with_capacity(parts.len());
 
     for (i, part) in parts.iter().enumerate() {
+        // 通配符
+        if allow_wildcard && *part == "??" {
+            bytes.push(None);
+            continue;
+        }
+
         // 每个 part 必须恰好长度为 2
         if part.len() != 2 {
             syn_bail!(
@@ -61,9 +67,14 @@ pub fn byte_slice(input: TokenStream) -> syn::Result<TokenStream> {
         if !ok {
             syn_bail!(
                 lit,
-                "第 {} 个字节包含非十六进制字符：`{}`。只能包含 0-9 A-F a-f。",
+                "第 {} 个字节包含非十六进制字符：`{}`。只能包含 0-9 A-F a-f{}。",
                 i + 1,
-                part
+                part,
+                if allow_wildcard {
+                    " 或通配符 ??"
+                } else {
+                    ""
+                }
             );
         }
 
@@ -75,14 +86,29 @@ pub fn byte_slice(input: TokenStream) -> syn::Result<TokenStream> {
             }
         };
 
-        // 以十六进制带 u8 后缀的字面量形式创建 LitInt（例如 "0x0C_u8"）
-        // 使用 0x{:02X}u8 形式更直观
-        let lit_text = format!("0x{value:02X}u8");
-        let lit = LitInt::new(&lit_text, Span::call_site());
-        lits.push(lit);
+        bytes.push(Some(value));
     }
 
-    // 生成数组字面量，例如: [0x0C_u8, 0x00_u8, ...]
+    Ok(bytes)
+}
+
+/// 以十六进制带 u8 后缀的字面量形式创建 LitInt（例如 "0x0Cu8"）
+pub(crate) fn byte_lit(value: u8) -> LitInt {
+    LitInt::new(&format!("0x{value:02X}u8"), Span::call_site())
+}
+
+pub fn byte_slice(input: TokenStream) -> syn::Result<TokenStream> {
+    // 只接受一个字符串字面量
+    let lit = syn::parse2::<LitStr>(input)?;
+
+    // 不允许通配符，因此每个字节都有值
+    let lits: Vec<LitInt> = parse_hex_bytes(&lit, false)?
+        .into_iter()
+        .flatten()
+        .map(byte_lit)
+        .collect();
+
+    // 生成数组字面量，例如: [0x0Cu8, 0x00u8, ...]
     let output = quote! {
         [ #(#lits),* ]
     };
@@ -38,6 +38,9 @@ pub enum ConfigEntry {
         optional: bool,
         #[serde(default)]
         expr: bool,
+        /// 只供其他宏从配置中读取（如`RESOURCE_PACKS`），不生成常量
+        #[serde(default)]
+        skip: bool,
     },
     Simple(serde_json::Value),
 }
@@ -102,8 +105,13 @@ pub fn generate_constants_from_json(input: TokenStream) -> syn::Result<TokenStre
             encode_to_u16,
             optional,
             expr,
+            skip,
         } = entry
         {
+            if skip {
+                continue;
+            }
+
             let val_opt = value.as_ref();
 
             const_tokens.push(json_item_to_const_tokens(
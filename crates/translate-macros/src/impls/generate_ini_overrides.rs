@@ -0,0 +1,106 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde::Deserialize;
+use serde_json::Value;
+use syn::LitStr;
+
+use crate::impls::utils::get_full_path_by_manifest;
+
+/// `assets/ini_overrides.json` 中的一项
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IniRuleConfig {
+    /// INI 文件的匹配模式，不含路径分隔符时只匹配文件名
+    file: String,
+    /// 节名的匹配模式，不指定时匹配任意节
+    #[serde(default)]
+    section: Option<String>,
+    /// 键名的匹配模式，不指定时匹配任意键
+    #[serde(default)]
+    key: Option<String>,
+    /// 固定的值
+    #[serde(default)]
+    value: Option<Value>,
+    /// 重定向到的 INI 文件，相对于exe目录
+    #[serde(default)]
+    redirect: Option<String>,
+}
+
+/// 将 JSON 值转换为 INI 中的字符串
+fn value_to_string(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) if n.is_i64() || n.is_u64() => Ok(n.to_string()),
+        Value::Bool(b) => Ok(if *b { "1" } else { "0" }.to_string()),
+        _ => Err(format!(
+            "value 只能是字符串、整数或布尔值，但得到了 {value}"
+        )),
+    }
+}
+
+/// 将可选的模式转换为`Option<&str>`表达式
+fn optional(pattern: &Option<String>) -> TokenStream {
+    match pattern {
+        Some(pattern) => quote! { Some(#pattern) },
+        None => quote! { None },
+    }
+}
+
+pub fn generate_ini_overrides(input: TokenStream) -> syn::Result<TokenStream> {
+    let config_lit = syn::parse2::<LitStr>(input)?;
+    let config_path = get_full_path_by_manifest(config_lit.value())?;
+
+    // 文件不存在时视为没有任何规则
+    let rules: Vec<IniRuleConfig> = match std::fs::read_to_string(&config_path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| syn_err!(&config_lit, "解析 INI 覆盖配置失败: {e}"))?,
+        Err(_) => Vec::new(),
+    };
+
+    let mut items = Vec::with_capacity(rules.len());
+    for (i, rule) in rules.iter().enumerate() {
+        if rule.file.is_empty() {
+            syn_bail!(&config_lit, "规则 {i}: file 不能为空");
+        }
+
+        let action = match (&rule.value, &rule.redirect) {
+            (Some(value), None) => {
+                if rule.section.is_none() || rule.key.is_none() {
+                    syn_bail!(
+                        &config_lit,
+                        "规则 {i}: 指定 value 时必须同时指定 section 与 key"
+                    );
+                }
+                let value =
+                    value_to_string(value).map_err(|e| syn_err!(&config_lit, "规则 {i}: {e}"))?;
+                quote! { IniAction::Value(#value) }
+            }
+            (None, Some(redirect)) => {
+                if redirect.is_empty() {
+                    syn_bail!(&config_lit, "规则 {i}: redirect 不能为空");
+                }
+                quote! { IniAction::Redirect(#redirect) }
+            }
+            _ => syn_bail!(
+                &config_lit,
+                "规则 {i}: 必须且只能指定 value 或 redirect 中的一个"
+            ),
+        };
+
+        let file = &rule.file;
+        let section = optional(&rule.section);
+        let key = optional(&rule.key);
+        items.push(quote! {
+            IniRule {
+                file: #file,
+                section: #section,
+                key: #key,
+                action: #action,
+            }
+        });
+    }
+
+    Ok(quote! {
+        &[ #(#items),* ]
+    })
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use serde::Deserialize;
+use serde_json::Value;
+use syn::LitStr;
+
+use crate::impls::generate_registry_rules::{RegValue, parse_key, parse_reg_value};
+use crate::impls::utils::get_full_path_by_manifest;
+
+/// `EMULATE_LOCALE_REGISTRY` 中的一项
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EntryConfig {
+    /// 完整的键路径，如`HKLM\Software\Microsoft\Windows NT\CurrentVersion\FontSubstitutes`
+    key: String,
+    /// 值名，不指定时为默认值
+    #[serde(default)]
+    name: String,
+    /// 替换后的值，格式与`assets/registry.json`中的值相同
+    value: Value,
+}
+
+/// `RegRoot` 变体名对应的预定义根键句柄值（`HKEY_*`的低 32 位）
+fn root_value(root: &str) -> u32 {
+    match root {
+        "ClassesRoot" => 0x8000_0000,
+        "CurrentUser" => 0x8000_0001,
+        "LocalMachine" => 0x8000_0002,
+        "Users" => 0x8000_0003,
+        _ => 0x8000_0005,
+    }
+}
+
+/// 将字符串编码为以 null 结尾的 UTF-16LE 字节
+fn encode_wide_null(s: &str, out: &mut Vec<u8>) {
+    for unit in s.encode_utf16().chain([0]) {
+        out.extend_from_slice(&unit.to_le_bytes());
+    }
+}
+
+/// 将值编码为注册表中的类型名与原始数据
+fn encode_value(value: RegValue) -> (&'static str, Vec<u8>) {
+    let mut data = Vec::new();
+    let ty = match value {
+        RegValue::Sz(s) => {
+            encode_wide_null(&s, &mut data);
+            "REG_SZ"
+        }
+        RegValue::ExpandSz(s) => {
+            encode_wide_null(&s, &mut data);
+            "REG_EXPAND_SZ"
+        }
+        RegValue::MultiSz(items) => {
+            for item in &items {
+                encode_wide_null(item, &mut data);
+            }
+            data.extend_from_slice(&[0, 0]);
+            "REG_MULTI_SZ"
+        }
+        RegValue::Dword(n) => {
+            data.extend_from_slice(&n.to_le_bytes());
+            "REG_DWORD"
+        }
+        RegValue::Qword(n) => {
+            data.extend_from_slice(&n.to_le_bytes());
+            "REG_QWORD"
+        }
+        RegValue::Binary(bytes) => {
+            data = bytes;
+            "REG_BINARY"
+        }
+    };
+    (ty, data)
+}
+
+pub fn generate_locale_emulator_registry(input: TokenStream) -> syn::Result<TokenStream> {
+    let config_lit = syn::parse2::<LitStr>(input)?;
+    let config_path = get_full_path_by_manifest(config_lit.value())?;
+
+    // 用户配置不存在时视为没有重定向项
+    let config: HashMap<String, Value> =
+        serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap_or("{}".to_string()))
+            .map_err(|e| syn_err!(&config_lit, "解析配置 JSON 失败: {e}"))?;
+
+    let entries: Vec<EntryConfig> = match config.get("EMULATE_LOCALE_REGISTRY") {
+        Some(entries) => serde_json::from_value(entries.clone())
+            .map_err(|e| syn_err!(&config_lit, "解析'EMULATE_LOCALE_REGISTRY'失败: {e}"))?,
+        None => Vec::new(),
+    };
+
+    let mut items = Vec::with_capacity(entries.len());
+    for (i, entry) in entries.into_iter().enumerate() {
+        let (root, sub_key) =
+            parse_key(&entry.key).map_err(|e| syn_err!(&config_lit, "重定向项 {i}: {e}"))?;
+        if sub_key.is_empty() {
+            syn_bail!(&config_lit, "重定向项 {i}: 不能重定向根键 '{}'", entry.key);
+        }
+
+        let value = parse_reg_value(&entry.value)
+            .map_err(|e| syn_err!(&config_lit, "重定向项 {i}: {e}"))?;
+        let (ty, data) = encode_value(value);
+
+        let root = root_value(root);
+        let ty = Ident::new(ty, Span::call_site());
+        let value_name = entry.name;
+        items.push(quote! {
+            RegistryRedirection {
+                root: #root,
+                sub_key: #sub_key,
+                value_name: #value_name,
+                data_type: windows_sys::Win32::System::Registry::#ty,
+                data: &[ #(#data),* ],
+            }
+        });
+    }
+
+    Ok(quote! {
+        &[ #(#items),* ]
+    })
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde::Deserialize;
+use serde_json::Value;
+use syn::LitStr;
+
+use crate::impls::utils::get_full_path_by_manifest;
+
+/// `MESSAGE_BOX_RULES` 中的一项
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    /// 匹配方式: exact（默认）/ regex
+    #[serde(default, rename = "match")]
+    kind: Option<String>,
+    /// 标题的匹配模式，不指定时匹配任意标题
+    #[serde(default)]
+    caption: Option<String>,
+    /// 正文的匹配模式，不指定时匹配任意正文
+    #[serde(default)]
+    text: Option<String>,
+    /// 处理方式: result / suppress / translate
+    action: String,
+    /// `result` 时返回的按钮，可以是整数或按钮名
+    #[serde(default)]
+    result: Option<Value>,
+    /// `translate` 时使用的标题
+    #[serde(default)]
+    translated_caption: Option<String>,
+    /// `translate` 时使用的正文
+    #[serde(default)]
+    translated_text: Option<String>,
+}
+
+/// 将按钮名或整数转换为`MessageBox`的返回值
+fn result_to_id(value: &Value) -> Result<i32, String> {
+    if let Some(id) = value.as_i64() {
+        return i32::try_from(id).map_err(|_| format!("result {id} 超出范围"));
+    }
+
+    let id = match value.as_str().map(str::to_ascii_lowercase).as_deref() {
+        Some("ok") => 1,
+        Some("cancel") => 2,
+        Some("abort") => 3,
+        Some("retry") => 4,
+        Some("ignore") => 5,
+        Some("yes") => 6,
+        Some("no") => 7,
+        Some("try_again") => 10,
+        Some("continue") => 11,
+        _ => {
+            return Err(format!(
+                "未知的 result {value}，可选整数或 ok / cancel / abort / retry / ignore / yes / no / try_again / continue"
+            ));
+        }
+    };
+    Ok(id)
+}
+
+/// 将可选的字符串转换为`Option<&str>`表达式
+fn optional(s: &Option<String>) -> TokenStream {
+    match s {
+        Some(s) => quote! { Some(#s) },
+        None => quote! { None },
+    }
+}
+
+pub fn generate_message_box_rules(input: TokenStream) -> syn::Result<TokenStream> {
+    let config_lit = syn::parse2::<LitStr>(input)?;
+    let config_path = get_full_path_by_manifest(config_lit.value())?;
+
+    // 用户配置不存在时视为没有规则
+    let config: HashMap<String, Value> =
+        serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap_or("{}".to_string()))
+            .map_err(|e| syn_err!(&config_lit, "解析配置 JSON 失败: {e}"))?;
+
+    let rules: Vec<RuleConfig> = match config.get("MESSAGE_BOX_RULES") {
+        Some(rules) => serde_json::from_value(rules.clone())
+            .map_err(|e| syn_err!(&config_lit, "解析'MESSAGE_BOX_RULES'失败: {e}"))?,
+        None => Vec::new(),
+    };
+
+    let mut items = Vec::with_capacity(rules.len());
+    for (i, rule) in rules.iter().enumerate() {
+        let kind = match rule.kind.as_deref().unwrap_or("exact") {
+            "exact" => quote! { MatchKind::Exact },
+            "regex" => quote! { MatchKind::Regex },
+            other => syn_bail!(
+                &config_lit,
+                "规则 {i}: 未知的匹配方式 '{other}'，可选 exact / regex"
+            ),
+        };
+
+        if rule.caption.is_none() && rule.text.is_none() {
+            syn_bail!(&config_lit, "规则 {i}: caption 与 text 至少需要指定一个");
+        }
+
+        if rule.action != "translate"
+            && (rule.translated_caption.is_some() || rule.translated_text.is_some())
+        {
+            syn_bail!(
+                &config_lit,
+                "规则 {i}: translated_caption 与 translated_text 只能用于 translate"
+            );
+        }
+        if rule.action != "result" && rule.result.is_some() {
+            syn_bail!(
+                &config_lit,
+                "规则 {i}: result 只能用于 action 为 result 的规则"
+            );
+        }
+
+        let action = match rule.action.as_str() {
+            "result" => {
+                let Some(result) = &rule.result else {
+                    syn_bail!(&config_lit, "规则 {i}: action 为 result 时必须指定 result");
+                };
+                let id =
+                    result_to_id(result).map_err(|e| syn_err!(&config_lit, "规则 {i}: {e}"))?;
+                quote! { MessageBoxAction::Result(#id) }
+            }
+            "suppress" => quote! { MessageBoxAction::Suppress },
+            "translate" => {
+                let caption = optional(&rule.translated_caption);
+                let text = optional(&rule.translated_text);
+                quote! {
+                    MessageBoxAction::Translate {
+                        caption: #caption,
+                        text: #text,
+                    }
+                }
+            }
+            other => syn_bail!(
+                &config_lit,
+                "规则 {i}: 未知的处理方式 '{other}'，可选 result / suppress / translate"
+            ),
+        };
+
+        let caption = optional(&rule.caption);
+        let text = optional(&rule.text);
+        items.push(quote! {
+            RuleSpec {
+                kind: #kind,
+                caption: #caption,
+                text: #text,
+                action: #action,
+            }
+        });
+    }
+
+    Ok(quote! {
+        &[ #(#items),* ]
+    })
+}
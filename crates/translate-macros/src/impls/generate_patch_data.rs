@@ -32,6 +32,10 @@ pub fn generate_patch_data(input: TokenStream) -> syn::Result<TokenStream> {
         match std::fs::read_dir(&raw_dir) {
             Ok(rd) => {
                 for e in rd.filter_map(|r| r.ok()) {
+                    // `patch_extracting` 输出的清单，不是补丁文件
+                    if e.file_name() == "manifest.json" {
+                        continue;
+                    }
                     if e.file_type().map(|t| t.is_file()).unwrap_or(false) {
                         raw_files.push(e.path());
                     }
@@ -219,6 +223,29 @@ pub fn generate_patch_data(input: TokenStream) -> syn::Result<TokenStream> {
         };
     };
 
+    // NAMES map（小写文件名 -> hash），用于按路径匹配
+    let mut names_entries = Vec::new();
+    let mut seen_names: HashSet<String> = HashSet::new();
+    for item in files.iter() {
+        let name = item.raw_filename.to_lowercase();
+        if !seen_names.insert(name.clone()) {
+            syn_bail2!("发现仅大小写不同的重复文件名: {}", item.raw_filename);
+        }
+
+        let bytes_esc = bytes_to_escaped_literal(&item.hash);
+        let rhs_str = format!("b\"{}\"", bytes_esc);
+        let rhs_ts: TokenStream = rhs_str.parse().unwrap();
+        let name_lit = Literal::string(&name);
+        names_entries.push(quote! {
+            #name_lit => #rhs_ts,
+        });
+    }
+    let names_map = quote! {
+        pub(super) static NAMES: ::phf::Map<&'static str, &'static [u8;32]> = ::phf::phf_map! {
+            #(#names_entries)*
+        };
+    };
+
     let generated = quote! {
         #(#statics_tokens)*
 
@@ -227,6 +254,8 @@ pub fn generate_patch_data(input: TokenStream) -> syn::Result<TokenStream> {
         #len_filter
 
         #filenames_map
+
+        #names_map
     };
 
     Ok(generated)
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
+use std::path::Path;
 
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::{
     Ident, LitStr, Token, Visibility,
     parse::{Parse, ParseStream},
@@ -9,13 +10,32 @@ use syn::{
 
 use crate::impls::utils::get_full_path_by_manifest;
 
-/// Macro 输入解析器：`"1337 Directory" => <pub> fn <ident>`
+/// Macro 输入解析器：`"1337 Directory" => <pub> fn <ident> [, <pub> fn <revert_ident>]`
 struct Input {
     vis: Option<Visibility>,
     fn_ident: Ident,
+    revert: Option<(Option<Visibility>, Ident)>,
     path: LitStr,
 }
 
+/// 解析`<pub> fn <ident>`
+fn parse_fn_decl(input: ParseStream) -> syn::Result<(Option<Visibility>, Ident)> {
+    // 可选的 pub（或其它 Visibility，这里只接受 pub、pub(crate) 等 syn::Visibility）
+    let vis: Option<Visibility> = if input.peek(Token![pub]) {
+        Some(input.parse()?)
+    } else {
+        None
+    };
+
+    // 必须要有 `fn`
+    let _fn_token: Token![fn] = input.parse()?;
+
+    // 别名标识符
+    let fn_ident: Ident = input.parse()?;
+
+    Ok((vis, fn_ident))
+}
+
 impl Parse for Input {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         // 路径字符串
@@ -23,19 +43,16 @@ impl Parse for Input {
 
         let _token: Token![=>] = input.parse()?;
 
-        // 可选的 pub（或其它 Visibility，这里只接受 pub、pub(crate) 等 syn::Visibility）
-        let vis: Option<Visibility> = if input.peek(Token![pub]) {
-            Some(input.parse()?)
+        let (vis, fn_ident) = parse_fn_decl(input)?;
+
+        // 可选的恢复函数
+        let revert = if input.peek(Token![,]) {
+            let _comma: Token![,] = input.parse()?;
+            Some(parse_fn_decl(input)?)
         } else {
             None
         };
 
-        // 必须要有 `fn`
-        let _fn_token: Token![fn] = input.parse()?;
-
-        // 别名标识符
-        let fn_ident: Ident = input.parse()?;
-
         // 确保没有多余内容
         if !input.is_empty() {
             return Err(input.error("在路径字符串后发现非预期的token"));
@@ -44,24 +61,141 @@ impl Parse for Input {
         Ok(Input {
             vis,
             fn_ident,
+            revert,
             path,
         })
     }
 }
 
-pub fn generate_patch_fn_from_1337(input: TokenStream) -> syn::Result<TokenStream> {
-    let input = syn::parse2::<Input>(input)?;
+/// 模块的首选基址与映像大小
+#[derive(Clone, Copy)]
+struct ImageInfo {
+    base: u64,
+    /// 从 PE 文件读取时才有映像大小
+    size: Option<u64>,
+}
+
+/// 解析十六进制数，可带`0x`前缀
+fn parse_hex(s: &str) -> Result<u64, std::num::ParseIntError> {
+    let s = s.trim();
+    let digits = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    u64::from_str_radix(digits, 16)
+}
+
+/// 去掉行尾的`#`或`//`注释
+fn strip_comment(line: &str) -> &str {
+    let end = [line.find('#'), line.find("//")]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(line.len());
+    line[..end].trim()
+}
+
+/// 从1337目录中与模块同名的 PE 文件读取首选基址与映像大小，不存在时返回`None`
+fn read_image_info(dir: &Path, module: &str) -> Result<Option<ImageInfo>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("无法读取1337目录: {e}"))?;
+    let Some(path) = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.eq_ignore_ascii_case(module))
+        })
+    else {
+        return Ok(None);
+    };
 
-    let vis_ts = if let Some(vis) = input.vis {
-        quote! { #vis }
+    let bytes = std::fs::read(&path).map_err(|e| format!("读取 PE 文件 {path:?} 失败: {e}"))?;
+    let pe =
+        goblin::pe::PE::parse(&bytes).map_err(|e| format!("解析 PE 文件 {path:?} 失败: {e}"))?;
+    let windows_fields = pe
+        .header
+        .optional_header
+        .ok_or_else(|| format!("PE 文件 {path:?} 没有可选头"))?
+        .windows_fields;
+
+    Ok(Some(ImageInfo {
+        base: windows_fields.image_base,
+        size: Some(windows_fields.size_of_image as u64),
+    }))
+}
+
+/// 解析模块声明`>module`或`>module@0x400000`，没有指定基址时尝试读取同名的 PE 文件
+fn parse_module_header(header: &str, dir: &Path) -> Result<(String, Option<ImageInfo>), String> {
+    match header.split_once('@') {
+        Some((module, base)) => {
+            let base = parse_hex(base).map_err(|e| format!("解析模块基址 '{base}' 失败: {e}"))?;
+            Ok((
+                module.trim().to_string(),
+                Some(ImageInfo { base, size: None }),
+            ))
+        }
+        None => {
+            let module = header.trim();
+            Ok((module.to_string(), read_image_info(dir, module)?))
+        }
+    }
+}
+
+/// 未指定基址时，不小于该值的地址可能是 VA 也可能是 RVA
+///
+/// 取 exe 与 dll 常见首选基址中较小的一个（`0x400000`、`0x10000000`）
+fn ambiguous_address_threshold(module: &str) -> u64 {
+    if module.to_lowercase().ends_with(".exe") {
+        0x40_0000
     } else {
-        quote! {}
+        0x1000_0000
+    }
+}
+
+/// 将地址转换为 RVA
+///
+/// - 已知基址与映像大小时，位于映像范围内的地址视为 VA，小于映像大小的地址视为 RVA
+/// - 只知道基址时，不小于基址的地址视为 VA
+/// - 都不知道时只接受 RVA，可能是 VA 的地址会报错，需要通过`>module@base`指定基址
+fn to_rva(addr: u64, module: &str, image: Option<ImageInfo>) -> Result<u32, String> {
+    let rva = match image {
+        Some(ImageInfo {
+            base,
+            size: Some(size),
+        }) => {
+            if (base..base + size).contains(&addr) {
+                addr - base
+            } else if addr < size {
+                addr
+            } else {
+                return Err(format!(
+                    "地址 {addr:#X} 既不在模块 {module} 的映像范围 {base:#X}..{:#X} 内，也不是有效的 RVA",
+                    base + size
+                ));
+            }
+        }
+        Some(ImageInfo { base, size: None }) if addr >= base => addr - base,
+        Some(_) => addr,
+        None if addr >= ambiguous_address_threshold(module) => {
+            return Err(format!(
+                "无法确定地址 {addr:#X} 是 VA 还是 RVA，请使用 '>{module}@基址' 指定模块 {module} 的首选基址，\
+                 或者将同名的 PE 文件放到1337目录中"
+            ));
+        }
+        None => addr,
     };
 
+    u32::try_from(rva).map_err(|_| format!("RVA {rva:#X} 超出 32 位范围"))
+}
+
+pub fn generate_patch_fn_from_1337(input: TokenStream) -> syn::Result<TokenStream> {
+    let input = syn::parse2::<Input>(input)?;
+
     let dir_1337 = get_full_path_by_manifest(input.path.value())
         .map_err(|e| syn_err!(&input.path, "无法解析1337目录路径: {e}"))?;
 
-    let mut modules_patches = HashMap::<String, Vec<(u32, u8)>>::new();
+    let mut modules_patches = HashMap::<String, Vec<(u32, u8, u8)>>::new();
     let mut main_module_count = 0;
 
     // 遍历所有1337文件
@@ -78,28 +212,31 @@ pub fn generate_patch_fn_from_1337(input: TokenStream) -> syn::Result<TokenStrea
         let content = std::fs::read_to_string(&path)
             .map_err(|e| syn_err!(&input.path, "读取文件 {path:?} 失败: {e}"))?;
 
-        let mut current_module: Option<String> = None;
+        let mut current_module: Option<(String, Option<ImageInfo>)> = None;
 
         for (line_idx, line) in content.lines().enumerate() {
-            let line = line.trim();
+            let line = strip_comment(line);
 
-            if line.is_empty() || line.starts_with("//") || line.starts_with('#') {
+            if line.is_empty() {
                 continue;
             }
 
-            // 解析模块名
-            if let Some(module_name) = line.strip_prefix('>') {
+            // 解析模块名与可选的基址
+            if let Some(header) = line.strip_prefix('>') {
+                let (module_name, image) = parse_module_header(header, &dir_1337).map_err(|e| {
+                    syn_err!(&input.path, "文件 {:?} 第{}行: {}", path, line_idx + 1, e)
+                })?;
                 if module_name.to_lowercase().ends_with(".exe") {
                     main_module_count += 1;
                 }
-                current_module = Some(module_name.to_string());
+                current_module = Some((module_name, image));
                 continue;
             }
 
             // 解析补丁数据
             if let Some((addr_part, byte_part)) = line.split_once(':') {
-                if let Some((_, new_byte_str)) = byte_part.split_once("->") {
-                    let addr = u32::from_str_radix(addr_part.trim(), 16).map_err(|e| {
+                if let Some((old_byte_str, new_byte_str)) = byte_part.split_once("->") {
+                    let addr = parse_hex(addr_part).map_err(|e| {
                         syn_err!(
                             &input.path,
                             "文件 {:?} 第{}行: 解析地址失败: {}",
@@ -109,21 +246,31 @@ pub fn generate_patch_fn_from_1337(input: TokenStream) -> syn::Result<TokenStrea
                         )
                     })?;
 
-                    let new_byte = u8::from_str_radix(new_byte_str.trim(), 16).map_err(|e| {
-                        syn_err!(
-                            &input.path,
-                            "文件 {:?} 第{}行: 解析字节失败: {}",
-                            path,
-                            line_idx + 1,
-                            e
-                        )
-                    })?;
-
-                    if let Some(module) = &current_module {
+                    let parse_byte = |s: &str| {
+                        let byte = parse_hex(s).map_err(|e| e.to_string()).and_then(|byte| {
+                            u8::try_from(byte).map_err(|_| format!("{byte:#X} 超出字节范围"))
+                        });
+                        byte.map_err(|e| {
+                            syn_err!(
+                                &input.path,
+                                "文件 {:?} 第{}行: 解析字节失败: {}",
+                                path,
+                                line_idx + 1,
+                                e
+                            )
+                        })
+                    };
+                    let old_byte = parse_byte(old_byte_str)?;
+                    let new_byte = parse_byte(new_byte_str)?;
+
+                    if let Some((module, image)) = &current_module {
+                        let rva = to_rva(addr, module, *image).map_err(|e| {
+                            syn_err!(&input.path, "文件 {:?} 第{}行: {}", path, line_idx + 1, e)
+                        })?;
                         modules_patches
                             .entry(module.clone())
                             .or_default()
-                            .push((addr, new_byte));
+                            .push((rva, old_byte, new_byte));
                     } else {
                         syn_bail!(
                             &input.path,
@@ -159,7 +306,7 @@ pub fn generate_patch_fn_from_1337(input: TokenStream) -> syn::Result<TokenStrea
         );
     }
 
-    // 生成每个模块的patch代码
+    // 生成每个模块的patch数据
     let mut module_patches_ts = Vec::new();
 
     for (module, patches) in modules_patches {
@@ -170,55 +317,159 @@ pub fn generate_patch_fn_from_1337(input: TokenStream) -> syn::Result<TokenStrea
             quote! { crate::utils::win32::get_module_handle(::windows_sys::w!(#module)) }
         };
 
-        // 排序并合并连续地址
+        // 排序并检查重复的地址
         let mut sorted_patches = patches;
-        sorted_patches.sort_by_key(|&(addr, _)| addr);
-
-        let mut merged_patches: Vec<(u32, Vec<u8>)> = Vec::new();
+        sorted_patches.sort_by_key(|&(addr, _, _)| addr);
+
+        if let Some(pair) = sorted_patches
+            .windows(2)
+            .find(|pair| pair[0].0 == pair[1].0)
+        {
+            syn_bail!(
+                &input.path,
+                "模块 {module} 中的地址 {:X} 出现了多次",
+                pair[0].0
+            );
+        }
 
-        if !sorted_patches.is_empty() {
-            let mut current_addr = sorted_patches[0].0;
-            let mut current_bytes = vec![sorted_patches[0].1];
+        // 合并连续地址
+        let mut merged_patches: Vec<(u32, Vec<u8>, Vec<u8>)> = Vec::new();
 
-            for &(addr, byte) in sorted_patches.iter().skip(1) {
-                if addr == current_addr + current_bytes.len() as u32 {
-                    current_bytes.push(byte);
-                } else {
-                    merged_patches.push((current_addr, current_bytes));
-                    current_addr = addr;
-                    current_bytes = vec![byte];
+        for &(addr, old_byte, new_byte) in &sorted_patches {
+            match merged_patches.last_mut() {
+                Some((start, old_bytes, new_bytes)) if addr == *start + old_bytes.len() as u32 => {
+                    old_bytes.push(old_byte);
+                    new_bytes.push(new_byte);
                 }
+                _ => merged_patches.push((addr, vec![old_byte], vec![new_byte])),
             }
-            merged_patches.push((current_addr, current_bytes));
         }
 
-        // 生成patch代码
-        let patch_stmts: Vec<_> = merged_patches
+        let patch_items: Vec<_> = merged_patches
             .iter()
-            .map(|(addr, bytes)| {
+            .map(|(addr, old_bytes, new_bytes)| {
+                let addr = *addr as usize;
                 quote! {
-                    let target_addr = module_base.wrapping_add(#addr as usize);
-                    let data: &[u8] = &[#(#bytes),*];
-                    crate::utils::mem::patch::write_asm(target_addr as *mut u8, data)?;
+                    crate::utils::mem::patch::BytePatch {
+                        rva: #addr,
+                        original: &[#(#old_bytes),*],
+                        patched: &[#(#new_bytes),*],
+                    }
                 }
             })
             .collect();
 
         module_patches_ts.push(quote! {
-            // Patch模块: #module
-            let module_base = #module_handle_expr? as usize;
-            #(#patch_stmts)*
+            crate::utils::mem::patch::ModulePatches {
+                module: #module,
+                base: #module_handle_expr? as usize,
+                patches: &[#(#patch_items),*],
+            }
         });
     }
 
+    let modules_fn = format_ident!("__{}_modules", input.fn_ident);
+    let vis = &input.vis;
     let fn_ident = &input.fn_ident;
 
+    let revert_ts = input.revert.as_ref().map(|(revert_vis, revert_ident)| {
+        quote! {
+            #revert_vis fn #revert_ident() -> crate::Result<()> {
+                crate::utils::mem::patch::write_verified_patches(&#modules_fn()?, true)
+            }
+        }
+    });
+
     let output = quote! {
-        #vis_ts fn #fn_ident() -> crate::Result<()> {
-            #(#module_patches_ts)*
-            Ok(())
+        fn #modules_fn() -> crate::Result<Vec<crate::utils::mem::patch::ModulePatches>> {
+            Ok(vec![#(#module_patches_ts),*])
         }
+
+        #vis fn #fn_ident() -> crate::Result<()> {
+            crate::utils::mem::patch::write_verified_patches(&#modules_fn()?, false)
+        }
+
+        #revert_ts
     };
 
     Ok(output)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 空的临时目录，用于不读取 PE 文件的情况
+    fn empty_dir() -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("translate-macros-1337-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(parse_hex("1f"), Ok(0x1F));
+        assert_eq!(parse_hex(" 0x401000 "), Ok(0x40_1000));
+        assert_eq!(parse_hex("0X140001000"), Ok(0x1_4000_1000));
+        assert!(parse_hex("").is_err());
+        assert!(parse_hex("0x").is_err());
+        assert!(parse_hex("zz").is_err());
+        assert!(parse_hex("10000000000000000").is_err());
+    }
+
+    #[test]
+    fn module_header() {
+        let dir = empty_dir();
+
+        let (module, image) = parse_module_header("game.exe@0x400000", &dir).unwrap();
+        assert_eq!(module, "game.exe");
+        let image = image.unwrap();
+        assert_eq!((image.base, image.size), (0x40_0000, None));
+
+        let (module, image) = parse_module_header(" engine.dll @ 10000000 ", &dir).unwrap();
+        assert_eq!(module, "engine.dll");
+        assert_eq!(image.unwrap().base, 0x1000_0000);
+
+        let (module, image) = parse_module_header("game.exe", &dir).unwrap();
+        assert_eq!(module, "game.exe");
+        assert!(image.is_none());
+
+        assert!(parse_module_header("game.exe@base", &dir).is_err());
+    }
+
+    #[test]
+    fn rva_with_image_size() {
+        let image = Some(ImageInfo {
+            base: 0x40_0000,
+            size: Some(0x2_0000),
+        });
+        assert_eq!(to_rva(0x40_1234, "game.exe", image), Ok(0x1234));
+        assert_eq!(to_rva(0x1234, "game.exe", image), Ok(0x1234));
+        assert!(to_rva(0x42_0000, "game.exe", image).is_err());
+        assert!(to_rva(0x2_0000, "game.exe", image).is_err());
+    }
+
+    #[test]
+    fn rva_with_base_only() {
+        let image = Some(ImageInfo {
+            base: 0x1_4000_0000,
+            size: None,
+        });
+        assert_eq!(to_rva(0x1_4000_1000, "game.exe", image), Ok(0x1000));
+        assert_eq!(to_rva(0x40_1000, "game.exe", image), Ok(0x40_1000));
+        assert!(to_rva(0x2_4000_0000, "game.exe", image).is_err());
+    }
+
+    #[test]
+    fn rva_without_base() {
+        assert_eq!(to_rva(0x3F_FFFF, "game.exe", None), Ok(0x3F_FFFF));
+        assert_eq!(to_rva(0xFFF_FFFF, "engine.dll", None), Ok(0xFFF_FFFF));
+
+        // 可能是 VA 也可能是 RVA 的地址需要指定基址
+        let e = to_rva(0x40_1000, "game.exe", None).unwrap_err();
+        assert!(e.contains(">game.exe@"));
+        assert!(to_rva(0x1_4000_1000, "GAME.EXE", None).is_err());
+        assert!(to_rva(0x1000_0000, "engine.dll", None).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use regex::RegexBuilder;
+use serde::Deserialize;
+use serde_json::Value;
+use syn::LitStr;
+
+use crate::impls::utils::get_full_path_by_manifest;
+
+/// `REDIRECTION_RULES` 中的一项
+#[derive(Deserialize)]
+struct RuleConfig {
+    /// 匹配方式: exact / suffix / glob / regex
+    #[serde(rename = "match")]
+    kind: String,
+    pattern: String,
+    target: String,
+    /// 目标路径的基准: exe（默认）/ source / resource_pack
+    #[serde(default)]
+    base: Option<String>,
+}
+
+/// 检查 glob 模式中的方括号是否闭合
+fn check_glob(pattern: &str) -> Result<(), String> {
+    let mut in_class = false;
+    for c in pattern.chars() {
+        match c {
+            '[' if !in_class => in_class = true,
+            ']' if in_class => in_class = false,
+            _ => {}
+        }
+    }
+
+    if in_class {
+        return Err(format!("glob 模式 '{pattern}' 中的 '[' 未闭合"));
+    }
+    Ok(())
+}
+
+/// 将 glob 模式转换为正则表达式，每个通配符都会成为一个捕获组
+///
+/// `*`不跨越`/`，`**`可跨越`/`，`?`匹配单个字符，`[...]`匹配字符集，`[!...]`为取反
+fn glob_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str("(.*)");
+            }
+            '*' => regex.push_str("([^/]*)"),
+            '?' => regex.push_str("([^/])"),
+            '[' => {
+                regex.push_str("([");
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    regex.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if matches!(c, '\\' | '[' | '^') {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                }
+                regex.push_str("])");
+            }
+            _ => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+
+    regex.push('$');
+    regex
+}
+
+/// 按运行时相同的选项编译正则表达式，返回错误信息
+fn check_regex(source: &str) -> Result<(), String> {
+    RegexBuilder::new(source)
+        .case_insensitive(true)
+        .unicode(true)
+        .build()
+        .map(drop)
+        .map_err(|e| e.to_string())
+}
+
+pub fn generate_redirect_rules(input: TokenStream) -> syn::Result<TokenStream> {
+    let config_lit = syn::parse2::<LitStr>(input)?;
+    let config_path = get_full_path_by_manifest(config_lit.value())?;
+
+    // 用户配置不存在时视为没有规则
+    let config: HashMap<String, Value> =
+        serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap_or("{}".to_string()))
+            .map_err(|e| syn_err!(&config_lit, "解析配置 JSON 失败: {e}"))?;
+
+    let rules: Vec<RuleConfig> = match config.get("REDIRECTION_RULES") {
+        Some(rules) => serde_json::from_value(rules.clone())
+            .map_err(|e| syn_err!(&config_lit, "解析'REDIRECTION_RULES'失败: {e}"))?,
+        None => Vec::new(),
+    };
+
+    let mut items = Vec::with_capacity(rules.len());
+    for (i, rule) in rules.iter().enumerate() {
+        let (kind, regex) = match rule.kind.as_str() {
+            "exact" => (quote! { MatchKind::Exact }, None),
+            "suffix" => (quote! { MatchKind::Suffix }, None),
+            "glob" => {
+                check_glob(&rule.pattern).map_err(|e| syn_err!(&config_lit, "规则 {i}: {e}"))?;
+                (
+                    quote! { MatchKind::Glob },
+                    Some(glob_to_regex(&rule.pattern)),
+                )
+            }
+            "regex" => (quote! { MatchKind::Regex }, Some(rule.pattern.clone())),
+            other => syn_bail!(
+                &config_lit,
+                "规则 {i}: 未知的匹配方式 '{other}'，可选 exact / suffix / glob / regex"
+            ),
+        };
+
+        let base = match rule.base.as_deref().unwrap_or("exe") {
+            "exe" => quote! { TargetBase::Exe },
+            "source" => quote! { TargetBase::Source },
+            "resource_pack" => quote! { TargetBase::ResourcePack },
+            other => syn_bail!(
+                &config_lit,
+                "规则 {i}: 未知的目标基准 '{other}'，可选 exe / source / resource_pack"
+            ),
+        };
+
+        if rule.pattern.is_empty() {
+            syn_bail!(&config_lit, "规则 {i}: pattern 不能为空");
+        }
+
+        if let Some(regex) = &regex
+            && let Err(e) = check_regex(regex)
+        {
+            syn_bail!(&config_lit, "规则 {i}: 无效的模式 '{}': {e}", rule.pattern);
+        }
+
+        let pattern = &rule.pattern;
+        let target = &rule.target;
+        let regex = match regex {
+            Some(regex) => quote! { Some(#regex) },
+            None => quote! { None },
+        };
+        items.push(quote! {
+            RuleSpec {
+                kind: #kind,
+                pattern: #pattern,
+                regex: #regex,
+                target: #target,
+                base: #base,
+            }
+        });
+    }
+
+    Ok(quote! {
+        &[ #(#items),* ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob_matches(pattern: &str, subject: &str) -> Option<Vec<String>> {
+        let regex = RegexBuilder::new(&glob_to_regex(pattern))
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        let captures = regex.captures(subject)?;
+        Some(
+            captures
+                .iter()
+                .skip(1)
+                .map(|m| m.map_or(String::new(), |m| m.as_str().to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn glob_to_regex_source() {
+        assert_eq!(glob_to_regex("voice/*.ogg"), r"^voice/([^/]*)\.ogg$");
+        assert_eq!(glob_to_regex("**/a?.txt"), r"^(.*)/a([^/])\.txt$");
+        assert_eq!(glob_to_regex("[!a-c]"), "^([^a-c])$");
+        assert_eq!(glob_to_regex(r"[\^]"), r"^([\\\^])$");
+    }
+
+    #[test]
+    fn glob_wildcards() {
+        assert_eq!(
+            glob_matches("voice/*.ogg", "VOICE/a01.OGG"),
+            Some(vec!["a01".to_string()])
+        );
+        assert_eq!(glob_matches("voice/*.ogg", "voice/sub/a01.ogg"), None);
+        assert_eq!(
+            glob_matches("**/*.ogg", "voice/sub/a01.ogg"),
+            Some(vec!["voice/sub".to_string(), "a01".to_string()])
+        );
+        assert_eq!(
+            glob_matches("save?.dat", "save1.dat"),
+            Some(vec!["1".to_string()])
+        );
+        assert_eq!(glob_matches("save?.dat", "save10.dat"), None);
+        assert_eq!(glob_matches("save?.dat", "save/.dat"), None);
+    }
+
+    #[test]
+    fn glob_character_classes() {
+        assert_eq!(
+            glob_matches("cg[0-9].png", "cg5.png"),
+            Some(vec!["5".to_string()])
+        );
+        assert_eq!(glob_matches("cg[0-9].png", "cgx.png"), None);
+        assert_eq!(
+            glob_matches("cg[!0-9].png", "cgx.png").map(|c| c.len()),
+            Some(1)
+        );
+        assert_eq!(glob_matches("cg[!0-9].png", "cg5.png"), None);
+    }
+
+    #[test]
+    fn glob_escapes_regex_metacharacters() {
+        assert!(glob_matches("a+b(1).txt", "a+b(1).txt").is_some());
+        assert!(glob_matches("a.txt", "abtxt").is_none());
+    }
+
+    #[test]
+    fn check_glob_brackets() {
+        assert!(check_glob("a[bc].txt").is_ok());
+        assert!(check_glob("a[bc.txt").is_err());
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        assert!(check_regex(r"^save/(\d+)\.dat$").is_ok());
+        assert!(check_regex("save/(").is_err());
+        assert!(check_regex(&glob_to_regex("cg[z-a].png")).is_err());
+    }
+}
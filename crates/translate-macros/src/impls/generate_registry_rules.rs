@@ -0,0 +1,282 @@
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use syn::LitStr;
+
+use crate::impls::utils::get_full_path_by_manifest;
+
+/// 外置存储的默认文件名（相对于exe目录）
+const DEFAULT_STORE_FILE: &str = "registry.json";
+
+/// `assets/registry.json` 的结构
+#[derive(Deserialize, Default)]
+struct RegistryConfig {
+    /// 外置存储的文件路径，相对于exe目录
+    #[serde(default)]
+    store_file: Option<String>,
+    #[serde(default)]
+    keys: Vec<KeyConfig>,
+}
+
+/// `keys` 中的一项
+#[derive(Deserialize)]
+struct KeyConfig {
+    /// 完整的键路径，如`HKLM\Software\Company\Game`
+    key: String,
+    /// redirect / store / fake
+    mode: String,
+    /// redirect 的目标键路径，默认为 HKCU 下的同名路径
+    #[serde(default)]
+    target: Option<String>,
+    /// store 的默认值或 fake 的值
+    #[serde(default)]
+    values: Map<String, Value>,
+}
+
+/// 解析根键名称，返回对应的 `RegRoot` 变体名
+fn parse_root(root: &str) -> Option<&'static str> {
+    Some(match root.to_ascii_uppercase().as_str() {
+        "HKCR" | "HKEY_CLASSES_ROOT" => "ClassesRoot",
+        "HKCU" | "HKEY_CURRENT_USER" => "CurrentUser",
+        "HKLM" | "HKEY_LOCAL_MACHINE" => "LocalMachine",
+        "HKU" | "HKEY_USERS" => "Users",
+        "HKCC" | "HKEY_CURRENT_CONFIG" => "CurrentConfig",
+        _ => return None,
+    })
+}
+
+/// 将键路径拆分为根键与规范化后的子键路径（以`\`分隔，去掉多余的分隔符）
+pub(crate) fn parse_key(key: &str) -> Result<(&'static str, String), String> {
+    let mut parts = key.split(['\\', '/']).filter(|part| !part.is_empty());
+    let root = parts.next().unwrap_or_default();
+    let root = parse_root(root).ok_or_else(|| format!("未知的根键 '{root}'"))?;
+    Ok((root, parts.collect::<Vec<_>>().join("\\")))
+}
+
+/// 解析数值，支持 JSON 数字以及`0x`开头的十六进制字符串
+fn parse_number(value: &Value) -> Result<u64, String> {
+    match value {
+        Value::Number(n) => n
+            .as_u64()
+            .or_else(|| n.as_i64().map(|v| v as u64))
+            .ok_or_else(|| format!("无效的整数 {n}")),
+        Value::String(s) => {
+            let digits = s.trim_start_matches("0x").trim_start_matches("0X");
+            u64::from_str_radix(digits, if digits.len() < s.len() { 16 } else { 10 })
+                .map_err(|e| format!("无效的整数 '{s}': {e}"))
+        }
+        _ => Err(format!("需要整数，但得到了 {value}")),
+    }
+}
+
+/// 解析二进制数据，支持十六进制字符串（可含空格）以及字节数组
+fn parse_binary(value: &Value) -> Result<Vec<u8>, String> {
+    match value {
+        Value::String(s) => {
+            let hex: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+            if !hex.len().is_multiple_of(2) {
+                return Err(format!("十六进制字符串 '{s}' 的长度必须为偶数"));
+            }
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| {
+                    u8::from_str_radix(&hex[i..i + 2], 16)
+                        .map_err(|e| format!("无效的十六进制字符串 '{s}': {e}"))
+                })
+                .collect()
+        }
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                let n = parse_number(item)?;
+                u8::try_from(n).map_err(|_| format!("字节 {n} 超出范围"))
+            })
+            .collect(),
+        _ => Err(format!("需要十六进制字符串或字节数组，但得到了 {value}")),
+    }
+}
+
+/// 解析字符串数组
+fn parse_strings(value: &Value) -> Result<Vec<String>, String> {
+    value
+        .as_array()
+        .ok_or_else(|| format!("需要字符串数组，但得到了 {value}"))?
+        .iter()
+        .map(|item| {
+            item.as_str()
+                .map(str::to_string)
+                .ok_or_else(|| format!("需要字符串，但得到了 {item}"))
+        })
+        .collect()
+}
+
+/// 解析后的注册表值
+pub(crate) enum RegValue {
+    Sz(String),
+    ExpandSz(String),
+    MultiSz(Vec<String>),
+    Dword(u32),
+    Qword(u64),
+    Binary(Vec<u8>),
+}
+
+/// 解析配置中的值，字符串为 sz，整数为 dword/qword，字符串数组为 multi_sz，
+/// 对象形式为`{ "type": ..., "data": ... }`
+pub(crate) fn parse_reg_value(value: &Value) -> Result<RegValue, String> {
+    let (kind, data) = match value {
+        Value::String(_) => ("sz", value),
+        Value::Number(_) => {
+            // 能用 dword 表示的整数（包括负的 i32）使用 dword，否则使用 qword
+            let fits_dword = match (value.as_u64(), value.as_i64()) {
+                (Some(n), _) => n <= u32::MAX as u64,
+                (None, Some(n)) => n >= i32::MIN as i64,
+                _ => false,
+            };
+            (if fits_dword { "dword" } else { "qword" }, value)
+        }
+        Value::Array(_) => ("multi_sz", value),
+        Value::Object(obj) => {
+            let kind = obj
+                .get("type")
+                .and_then(Value::as_str)
+                .ok_or("对象形式的值需要字符串字段 'type'")?;
+            let data = obj.get("data").ok_or("对象形式的值需要字段 'data'")?;
+            (kind, data)
+        }
+        _ => return Err(format!("不支持的值 {value}")),
+    };
+
+    Ok(match kind {
+        "sz" | "expand_sz" => {
+            let s = data
+                .as_str()
+                .ok_or_else(|| format!("{kind} 需要字符串，但得到了 {data}"))?
+                .to_string();
+            if kind == "sz" {
+                RegValue::Sz(s)
+            } else {
+                RegValue::ExpandSz(s)
+            }
+        }
+        "multi_sz" => {
+            let items = parse_strings(data)?;
+            if items.iter().any(String::is_empty) {
+                return Err("multi_sz 中不能包含空字符串".to_string());
+            }
+            RegValue::MultiSz(items)
+        }
+        "dword" => {
+            let n = parse_number(data)?;
+            // 允许负数以补码形式写入
+            let n = u32::try_from(n)
+                .or_else(|_| i32::try_from(n as i64).map(|v| v as u32))
+                .map_err(|_| format!("dword {n} 超出范围"))?;
+            RegValue::Dword(n)
+        }
+        "qword" => RegValue::Qword(parse_number(data)?),
+        "binary" => RegValue::Binary(parse_binary(data)?),
+        other => {
+            return Err(format!(
+                "未知的值类型 '{other}'，可选 sz / expand_sz / multi_sz / dword / qword / binary"
+            ));
+        }
+    })
+}
+
+/// 将值解析为 `DataSpec` 表达式
+fn parse_value(value: &Value) -> Result<TokenStream, String> {
+    Ok(match parse_reg_value(value)? {
+        RegValue::Sz(s) => quote! { DataSpec::Sz(#s) },
+        RegValue::ExpandSz(s) => quote! { DataSpec::ExpandSz(#s) },
+        RegValue::MultiSz(items) => quote! { DataSpec::MultiSz(&[ #(#items),* ]) },
+        RegValue::Dword(n) => quote! { DataSpec::Dword(#n) },
+        RegValue::Qword(n) => quote! { DataSpec::Qword(#n) },
+        RegValue::Binary(bytes) => quote! { DataSpec::Binary(&[ #(#bytes),* ]) },
+    })
+}
+
+pub fn generate_registry_rules(input: TokenStream) -> syn::Result<TokenStream> {
+    let config_lit = syn::parse2::<LitStr>(input)?;
+    let config_path = get_full_path_by_manifest(config_lit.value())?;
+
+    // 文件不存在时视为没有任何规则
+    let config: RegistryConfig = match std::fs::read_to_string(&config_path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| syn_err!(&config_lit, "解析注册表配置失败: {e}"))?,
+        Err(_) => RegistryConfig::default(),
+    };
+
+    let store_file = config
+        .store_file
+        .unwrap_or_else(|| DEFAULT_STORE_FILE.to_string());
+
+    let mut seen = HashSet::new();
+    let mut keys = Vec::with_capacity(config.keys.len());
+    for (i, key) in config.keys.iter().enumerate() {
+        let (root, path) = parse_key(&key.key).map_err(|e| syn_err!(&config_lit, "键 {i}: {e}"))?;
+        if !seen.insert((root, path.to_lowercase())) {
+            syn_bail!(&config_lit, "键 {i}: '{}' 重复", key.key);
+        }
+
+        if key.mode != "redirect" && key.target.is_some() {
+            syn_bail!(&config_lit, "键 {i}: 只有 redirect 模式可以指定 target");
+        }
+        if key.mode == "redirect" && !key.values.is_empty() {
+            syn_bail!(&config_lit, "键 {i}: redirect 模式不能指定 values");
+        }
+
+        let mut values = Vec::with_capacity(key.values.len());
+        for (name, value) in &key.values {
+            let data = parse_value(value)
+                .map_err(|e| syn_err!(&config_lit, "键 {i} 的值 '{name}': {e}"))?;
+            values.push(quote! { ValueSpec { name: #name, data: #data } });
+        }
+
+        let mode = match key.mode.as_str() {
+            "redirect" => {
+                let (target_root, target_path) = match &key.target {
+                    Some(target) => {
+                        parse_key(target).map_err(|e| syn_err!(&config_lit, "键 {i}: {e}"))?
+                    }
+                    None => ("CurrentUser", path.clone()),
+                };
+                if target_root == root && target_path.eq_ignore_ascii_case(&path) {
+                    syn_bail!(&config_lit, "键 {i}: 不能重定向到自身");
+                }
+
+                let target_root = syn::Ident::new(target_root, proc_macro2::Span::call_site());
+                quote! {
+                    KeyMode::Redirect {
+                        root: RegRoot::#target_root,
+                        path: #target_path,
+                    }
+                }
+            }
+            "store" => quote! { KeyMode::Store(&[ #(#values),* ]) },
+            "fake" => quote! { KeyMode::Fake(&[ #(#values),* ]) },
+            other => syn_bail!(
+                &config_lit,
+                "键 {i}: 未知的模式 '{other}'，可选 redirect / store / fake"
+            ),
+        };
+
+        let root = syn::Ident::new(root, proc_macro2::Span::call_site());
+        keys.push(quote! {
+            KeySpec {
+                root: RegRoot::#root,
+                path: #path,
+                mode: #mode,
+            }
+        });
+    }
+
+    Ok(quote! {
+        RegistryConfig {
+            store_file: #store_file,
+            keys: &[ #(#keys),* ],
+        }
+    })
+}
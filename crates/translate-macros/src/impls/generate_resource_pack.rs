@@ -1,19 +1,26 @@
 use std::collections::HashMap;
+use std::path::Path;
 
 use proc_macro2::{Span, TokenStream};
-use quote::quote;
+use quote::{format_ident, quote};
+use serde::Deserialize;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use syn::{
-    LitByteStr, LitStr, Token,
+    Ident, LitByteStr, LitInt, LitStr, Token,
     parse::{Parse, ParseStream},
 };
 
 use crate::impls::utils::get_full_path_by_manifest;
 
+/// 未指定输出目录时，外置资源包的默认输出目录
+const DEFAULT_OUTPUT_DIR: &str = "assets/dist";
+
 struct PathInput {
     resource_dir: LitStr,
     config_path: LitStr,
     output: Option<LitStr>,
+    version: u32,
 }
 
 impl Parse for PathInput {
@@ -23,62 +30,125 @@ impl Parse for PathInput {
         let config_path: LitStr = input.parse()?;
 
         let mut output = None;
+        let mut version = 1;
 
-        if input.peek(Token![,]) {
+        while input.peek(Token![,]) {
             input.parse::<Token![,]>()?;
-            output = Some(input.parse()?);
+
+            if input.peek(LitStr) {
+                output = Some(input.parse()?);
+            } else {
+                let key: Ident = input.parse()?;
+                if key != "version" {
+                    syn_bail!(&key, "未知的参数: {key}");
+                }
+                input.parse::<Token![=]>()?;
+                let lit: LitInt = input.parse()?;
+                version = lit.base10_parse()?;
+                if !matches!(version, 1 | 2) {
+                    syn_bail!(lit, "不支持的资源包版本: {version}");
+                }
+            }
         }
 
         Ok(PathInput {
             resource_dir,
             config_path,
             output,
+            version,
         })
     }
 }
 
-pub fn generate_resource_pack(input: TokenStream) -> syn::Result<TokenStream> {
-    let parsed = syn::parse2::<PathInput>(input)?;
-    let resource_dir_path = get_full_path_by_manifest(parsed.resource_dir.value())?;
-    let config_path = get_full_path_by_manifest(parsed.config_path.value())?;
+/// `RESOURCE_PACKS` 中的一项
+#[derive(Deserialize)]
+struct LayerConfig {
+    /// 资源包名称，用于外置资源包的文件名与临时目录名
+    name: String,
+    /// 资源目录（相对于 `Cargo.toml`），默认为 `<resource_dir>/<name>`
+    #[serde(default)]
+    dir: Option<String>,
+    /// 是否内嵌，默认取决于宏是否指定了输出目录
+    #[serde(default)]
+    embedded: Option<bool>,
+    /// 外置资源包不存在时是否跳过该层，而不是报错
+    #[serde(default)]
+    optional: bool,
+}
 
-    let config_str = std::fs::read_to_string(&config_path)
-        .map_err(|e| syn_err2!("无法读取配置 {}: {}", config_path.display(), e))?;
-    let config: HashMap<String, Value> = serde_json::from_str(&config_str)
-        .map_err(|e| syn_err2!("解析配置 JSON 失败 ({}): {}", config_path.display(), e))?;
-    let Some(pack_name) = config.get("RESOURCE_PACK_NAME").and_then(|v| v.as_str()) else {
-        syn_bail!(
-            parsed.config_path,
-            "在用户配置json中无法找到'RESOURCE_PACK_NAME'"
-        );
+/// 生成单个资源包层所需的信息
+struct Layer {
+    name: String,
+    dir: LitStr,
+    embedded: bool,
+    optional: bool,
+}
+
+/// 从配置中读取资源包层，未配置 `RESOURCE_PACKS` 时只有一层 `RESOURCE_PACK_NAME`
+fn read_layers(parsed: &PathInput, config: &HashMap<String, Value>) -> syn::Result<Vec<Layer>> {
+    let default_embedded = parsed.output.is_none();
+
+    let Some(packs) = config.get("RESOURCE_PACKS") else {
+        let Some(pack_name) = config.get("RESOURCE_PACK_NAME").and_then(|v| v.as_str()) else {
+            syn_bail!(
+                &parsed.config_path,
+                "在用户配置json中无法找到'RESOURCE_PACK_NAME'或'RESOURCE_PACKS'"
+            );
+        };
+
+        return Ok(vec![Layer {
+            name: pack_name.to_string(),
+            dir: parsed.resource_dir.clone(),
+            embedded: default_embedded,
+            optional: false,
+        }]);
     };
 
-    let temp_dir_name = format!("text_hook_resource_pack_{pack_name}");
-    let pack_file_name = format!("{pack_name}.pak");
+    let packs: Vec<LayerConfig> = serde_json::from_value(packs.clone())
+        .map_err(|e| syn_err!(&parsed.config_path, "解析'RESOURCE_PACKS'失败: {e}"))?;
+    if packs.is_empty() {
+        syn_bail!(&parsed.config_path, "'RESOURCE_PACKS'不能为空");
+    }
 
-    // 用 WalkDir 收集所有文件
+    let mut layers: Vec<Layer> = Vec::with_capacity(packs.len());
+    for pack in packs {
+        if layers.iter().any(|l| l.name.eq_ignore_ascii_case(&pack.name)) {
+            syn_bail!(&parsed.config_path, "资源包名称重复: {}", pack.name);
+        }
+
+        let dir = pack
+            .dir
+            .unwrap_or_else(|| format!("{}/{}", parsed.resource_dir.value(), pack.name));
+
+        layers.push(Layer {
+            dir: LitStr::new(&dir, parsed.resource_dir.span()),
+            name: pack.name,
+            embedded: pack.embedded.unwrap_or(default_embedded),
+            optional: pack.optional,
+        });
+    }
+
+    Ok(layers)
+}
+
+/// 用 WalkDir 收集目录下的所有文件，路径统一为小写并使用 `/` 分隔，按路径排序
+fn collect_files(dir_lit: &LitStr, dir: &Path) -> syn::Result<Vec<(String, Vec<u8>)>> {
     let mut files: Vec<(String, Vec<u8>)> = Vec::new();
-    for entry in walkdir::WalkDir::new(&resource_dir_path)
+    for entry in walkdir::WalkDir::new(dir)
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
     {
         let path = entry.path();
         let relative = path
-            .strip_prefix(&resource_dir_path)
-            .map_err(|e| syn_err!(&parsed.resource_dir, "路径处理失败: {e}"))?
+            .strip_prefix(dir)
+            .map_err(|e| syn_err!(dir_lit, "路径处理失败: {e}"))?
             .to_string_lossy()
             .to_lowercase()
             .replace('\\', "/");
 
-        let content = std::fs::read(path).map_err(|e| {
-            syn_err!(
-                &parsed.resource_dir,
-                "读取文件失败 {}: {}",
-                path.display(),
-                e,
-            )
-        })?;
+        let content = std::fs::read(path)
+            .map_err(|e| syn_err!(dir_lit, "读取文件失败 {}: {}", path.display(), e,))?;
 
         files.push((relative, content));
     }
@@ -86,67 +156,123 @@ pub fn generate_resource_pack(input: TokenStream) -> syn::Result<TokenStream> {
     // 排序保证确定性
     files.sort_by(|a, b| a.0.cmp(&b.0));
 
+    Ok(files)
+}
+
+/// 生成单个资源包层的模块内容
+fn generate_layer(parsed: &PathInput, layer: &Layer) -> syn::Result<TokenStream> {
+    let resource_dir_path = get_full_path_by_manifest(layer.dir.value())?;
+    let files = collect_files(&layer.dir, &resource_dir_path)?;
+
+    let temp_dir_name = format!("text_hook_resource_pack_{}", layer.name);
+    let pack_file_name = format!("{}.pak", layer.name);
+
     // 提取路径列表用于 phf set
     let paths: Vec<&str> = files.iter().map(|(p, _)| p.as_str()).collect();
 
-    // cat 格式: [u32:路径长度][u8:路径][u64:内容长度][u8:内容]...
-    let mut cat_data: Vec<u8> = Vec::new();
-    for (path, content) in &files {
-        cat_data.extend_from_slice(&(path.len() as u32).to_le_bytes());
-        cat_data.extend_from_slice(path.as_bytes());
-        cat_data.extend_from_slice(&(content.len() as u64).to_le_bytes());
-        cat_data.extend_from_slice(content);
-    }
-
-    let original_len = cat_data.len();
-    let is_compressed = parsed.output.is_none() && original_len > 80 * 1024;
+    let (final_data, original_len, is_compressed, index_paths) = if parsed.version == 2 {
+        // v2 格式: 每个文件单独压缩，带定长索引
+        let mut writer = resource_pack_format::PakWriter::new();
+        for (path, content) in &files {
+            let compressed = zstd::bulk::compress(content, 3)
+                .map_err(|e| syn_err!(&layer.dir, "zstd压缩失败: {}", e))?;
+            writer.add(path, content, Some(compressed));
+        }
 
-    // 统一处理压缩逻辑
-    let final_data = if is_compressed {
-        zstd::bulk::compress(&cat_data, 3)
-            .map_err(|e| syn_err!(&parsed.resource_dir, "zstd压缩失败: {}", e))?
+        let (pak_data, index_paths) = writer.finish();
+        let len = pak_data.len();
+        (pak_data, len, false, index_paths)
     } else {
-        cat_data
+        // cat 格式: [u32:路径长度][u8:路径][u64:内容长度][u8:内容]...
+        let mut cat_data: Vec<u8> = Vec::new();
+        for (path, content) in &files {
+            cat_data.extend_from_slice(&(path.len() as u32).to_le_bytes());
+            cat_data.extend_from_slice(path.as_bytes());
+            cat_data.extend_from_slice(&(content.len() as u64).to_le_bytes());
+            cat_data.extend_from_slice(content);
+        }
+
+        let original_len = cat_data.len();
+        let is_compressed = layer.embedded && original_len > 80 * 1024;
+
+        // 统一处理压缩逻辑
+        let final_data = if is_compressed {
+            zstd::bulk::compress(&cat_data, 3)
+                .map_err(|e| syn_err!(&layer.dir, "zstd压缩失败: {}", e))?
+        } else {
+            cat_data
+        };
+
+        (final_data, original_len, is_compressed, Vec::new())
     };
 
-    // 根据是否是 external 生成不同的数据读取逻辑
-    let data_loading_code = if let Some(output) = parsed.output {
-        let out_path = get_full_path_by_manifest(format!("{}/{pack_file_name}", output.value()))?;
-        std::fs::create_dir_all(out_path.parent().unwrap())
-            .map_err(|e| syn_err!(&output, "创建输出目录失败 {}: {}", out_path.display(), e))?;
-        std::fs::write(&out_path, &final_data)
-            .map_err(|e| syn_err!(&output, "写入资源包文件失败 {}: {}", out_path.display(), e))?;
+    // 资源包数据的哈希，用于判断临时目录中解压的文件是否为最新
+    let pack_hash = Sha256::digest(&final_data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
 
+    // 根据是否是 external 生成不同的数据读取逻辑
+    let data_loading_code = if layer.embedded {
+        let data_lit = LitByteStr::new(&final_data, Span::call_site());
         quote! {
-            let pak_path = crate::utils::get_executable_dir().join(#pack_file_name);
-            let raw_data = std::fs::read(&pak_path)?;
-            let data_ref: &[u8] = &raw_data;
+            let data_ref: ::std::borrow::Cow<'static, [u8]> = ::std::borrow::Cow::Borrowed(#data_lit);
         }
     } else {
-        let data_lit = LitByteStr::new(&final_data, Span::call_site());
+        let output = parsed
+            .output
+            .as_ref()
+            .map(|o| o.value())
+            .unwrap_or_else(|| DEFAULT_OUTPUT_DIR.to_string());
+        let out_path = get_full_path_by_manifest(format!("{output}/{pack_file_name}"))?;
+        std::fs::create_dir_all(out_path.parent().unwrap()).map_err(|e| {
+            syn_err!(&layer.dir, "创建输出目录失败 {}: {}", out_path.display(), e)
+        })?;
+        std::fs::write(&out_path, &final_data).map_err(|e| {
+            syn_err!(&layer.dir, "写入资源包文件失败 {}: {}", out_path.display(), e)
+        })?;
+
         quote! {
-            let data_ref: &[u8] = #data_lit;
+            let pak_path = crate::utils::get_executable_dir().join(#pack_file_name);
+            let data_ref: ::std::borrow::Cow<'static, [u8]> =
+                ::std::borrow::Cow::Owned(std::fs::read(&pak_path)?);
         }
     };
 
     // 生成解压逻辑
     let decompression_code = if is_compressed {
         quote! {
-            let decompressed = crate::utils::decompress(data_ref, #original_len)?;
-            let data = &decompressed;
+            Ok(::std::borrow::Cow::Owned(crate::utils::decompress(&data_ref, #original_len)?))
         }
     } else {
         quote! {
-            let data = data_ref;
+            Ok(data_ref)
         }
     };
 
-    // --- 生成最终代码 ---
     let phf_entries = paths.iter().map(|&p| quote! { #p });
+    let size_entries = files.iter().map(|(p, content)| {
+        let size = content.len() as u64;
+        quote! { #p => #size }
+    });
+
+    // v2 格式的索引号即为写入器输出的路径顺序
+    let index_code = if parsed.version == 2 {
+        let index_entries = index_paths.iter().enumerate().map(|(i, p)| {
+            let i = i as u32;
+            quote! { #p => #i }
+        });
+        quote! {
+            pub(super) static RESOURCE_INDEX: phf::Map<&'static str, u32> = phf::phf_map! {
+                #(#index_entries),*
+            };
+        }
+    } else {
+        TokenStream::new()
+    };
 
-    let expanded = quote! {
-        use std::io::Write;
-
+    Ok(quote! {
+        #[cfg(not(feature = "resource_pack_vfs"))]
         pub(super) fn get_temp_dir() -> &'static std::path::Path {
             static TEMP_DIR: ::std::sync::LazyLock<::std::path::PathBuf> = ::std::sync::LazyLock::new(|| {
                 std::env::temp_dir().join(#temp_dir_name)
@@ -158,52 +284,86 @@ pub fn generate_resource_pack(input: TokenStream) -> syn::Result<TokenStream> {
             #(#phf_entries),*
         };
 
-        pub(super) fn is_resource(path: &str) -> bool {
-            RESOURCE_PATHS.contains(path)
-        }
+        /// 资源包中每个文件的原始大小
+        #[cfg(feature = "resource_pack_find")]
+        pub(super) static RESOURCE_SIZES: phf::Map<&'static str, u64> = phf::phf_map! {
+            #(#size_entries),*
+        };
 
-        pub(super) fn extract() -> crate::Result<()> {
-            let temp_dir = get_temp_dir();
-            if temp_dir.exists() {
-                clean_up()?;
-            }
+        #index_code
 
-            std::fs::create_dir_all(temp_dir)?;
+        /// 资源包数据的 SHA-256
+        #[cfg(not(feature = "resource_pack_vfs"))]
+        pub(super) const PACK_HASH: &str = #pack_hash;
 
-            // 加载数据 (内存或文件)
+        /// 加载资源包数据（内存或文件），v1 返回解压后的 cat 格式数据，v2 返回整个资源包
+        pub(super) fn load() -> crate::Result<::std::borrow::Cow<'static, [u8]>> {
             #data_loading_code
-            // 处理解压
             #decompression_code
+        }
+    })
+}
 
-            let mut offset = 0usize;
-            while offset < data.len() {
-                let path_len = u32::from_le_bytes(data[offset..offset+4].try_into()?) as usize;
-                offset += 4;
-                let path = std::str::from_utf8(&data[offset..offset+path_len])?;
-                offset += path_len;
-                let content_len = u64::from_le_bytes(data[offset..offset+8].try_into()?) as usize;
-                offset += 8;
-                let content = &data[offset..offset+content_len];
-                offset += content_len;
-
-                let file_path = temp_dir.join(path);
-                if let Some(parent) = file_path.parent() {
-                    std::fs::create_dir_all(parent)?;
-                }
-                std::fs::write(&file_path, content)?;
-            }
+pub fn generate_resource_pack(input: TokenStream) -> syn::Result<TokenStream> {
+    let parsed = syn::parse2::<PathInput>(input)?;
+    let config_path = get_full_path_by_manifest(parsed.config_path.value())?;
 
-            Ok(())
-        }
+    let config_str = std::fs::read_to_string(&config_path)
+        .map_err(|e| syn_err2!("无法读取配置 {}: {}", config_path.display(), e))?;
+    let config: HashMap<String, Value> = serde_json::from_str(&config_str)
+        .map_err(|e| syn_err2!("解析配置 JSON 失败 ({}): {}", config_path.display(), e))?;
 
-        pub(super) fn clean_up() -> crate::Result<()> {
-            let temp_dir = get_temp_dir();
-            if temp_dir.exists() {
-                std::fs::remove_dir_all(temp_dir)?;
+    let layers = read_layers(&parsed, &config)?;
+
+    let mut layer_mods = Vec::with_capacity(layers.len());
+    let mut layer_items = Vec::with_capacity(layers.len());
+    for (i, layer) in layers.iter().enumerate() {
+        let mod_ident = format_ident!("layer_{i}");
+        let content = generate_layer(&parsed, layer)?;
+        layer_mods.push(quote! {
+            pub(super) mod #mod_ident {
+                #content
             }
-            Ok(())
-        }
-    };
+        });
+
+        let name = &layer.name;
+        let file_name = if layer.embedded {
+            quote! { None }
+        } else {
+            let pack_file_name = format!("{name}.pak");
+            quote! { Some(#pack_file_name) }
+        };
+        let optional = layer.optional;
+        let index = if parsed.version == 2 {
+            quote! { index: &#mod_ident::RESOURCE_INDEX, }
+        } else {
+            TokenStream::new()
+        };
+
+        layer_items.push(quote! {
+            super::PackLayer {
+                name: #name,
+                file_name: #file_name,
+                optional: #optional,
+                #[cfg(not(feature = "resource_pack_vfs"))]
+                hash: #mod_ident::PACK_HASH,
+                paths: &#mod_ident::RESOURCE_PATHS,
+                #[cfg(feature = "resource_pack_find")]
+                sizes: &#mod_ident::RESOURCE_SIZES,
+                #index
+                load: #mod_ident::load,
+                #[cfg(not(feature = "resource_pack_vfs"))]
+                temp_dir: #mod_ident::get_temp_dir,
+            }
+        });
+    }
+
+    Ok(quote! {
+        #(#layer_mods)*
 
-    Ok(expanded)
+        /// 所有资源包层，按优先级从高到低排列
+        pub(super) static LAYERS: &[super::PackLayer] = &[
+            #(#layer_items),*
+        ];
+    })
 }
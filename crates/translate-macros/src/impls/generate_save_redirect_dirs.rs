@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::path::{Component, Path};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde::Deserialize;
+use serde_json::Value;
+use syn::LitStr;
+
+use crate::impls::utils::get_full_path_by_manifest;
+
+/// `SAVE_REDIRECT_DIRS` 中的一项
+#[derive(Deserialize)]
+struct SaveDirConfig {
+    /// 已知文件夹的名称，或者目录路径（可包含`%VAR%`环境变量，相对路径相对于exe目录）
+    source: String,
+    /// 存档目录下的子目录名，默认为已知文件夹的名称或路径的最后一级
+    #[serde(default)]
+    target: Option<String>,
+}
+
+/// 已知文件夹名称 -> (枚举变体, 默认子目录名)
+const KNOWN_FOLDERS: &[(&str, &str, &str)] = &[
+    ("appdata", "AppData", "AppData"),
+    ("local_appdata", "LocalAppData", "LocalAppData"),
+    ("local_appdata_low", "LocalAppDataLow", "LocalAppDataLow"),
+    ("common_appdata", "ProgramData", "ProgramData"),
+    ("documents", "Documents", "Documents"),
+    ("saved_games", "SavedGames", "SavedGames"),
+];
+
+/// 检查子目录名是否为不会跳出存档目录的相对路径
+fn check_target(target: &str) -> Result<(), String> {
+    if target.is_empty() {
+        return Err("target 不能为空".to_string());
+    }
+
+    let normal = Path::new(target)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !normal || target.contains(':') {
+        return Err(format!("target '{target}' 必须是不含 '..' 的相对路径"));
+    }
+
+    Ok(())
+}
+
+pub fn generate_save_redirect_dirs(input: TokenStream) -> syn::Result<TokenStream> {
+    let config_lit = syn::parse2::<LitStr>(input)?;
+    let config_path = get_full_path_by_manifest(config_lit.value())?;
+
+    // 用户配置不存在时视为没有需要重定向的目录
+    let config: HashMap<String, Value> =
+        serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap_or("{}".to_string()))
+            .map_err(|e| syn_err!(&config_lit, "解析配置 JSON 失败: {e}"))?;
+
+    let dirs: Vec<SaveDirConfig> = match config.get("SAVE_REDIRECT_DIRS") {
+        Some(dirs) => serde_json::from_value(dirs.clone())
+            .map_err(|e| syn_err!(&config_lit, "解析'SAVE_REDIRECT_DIRS'失败: {e}"))?,
+        None => Vec::new(),
+    };
+
+    let mut items = Vec::with_capacity(dirs.len());
+    for (i, dir) in dirs.iter().enumerate() {
+        let known = KNOWN_FOLDERS
+            .iter()
+            .find(|(name, _, _)| dir.source.eq_ignore_ascii_case(name));
+
+        let (source, default_target) = match known {
+            Some((_, variant, default_target)) => {
+                let variant = syn::Ident::new(variant, proc_macro2::Span::call_site());
+                (
+                    quote! { SaveSource::Folder(KnownFolder::#variant) },
+                    default_target.to_string(),
+                )
+            }
+            None => {
+                let path = dir.source.trim_end_matches(['/', '\\']);
+                if path.is_empty() {
+                    syn_bail!(&config_lit, "目录 {i}: source 不能为空");
+                }
+                let last = path
+                    .rsplit(['/', '\\'])
+                    .next()
+                    .unwrap_or_default()
+                    .trim_matches('%')
+                    .to_string();
+                (quote! { SaveSource::Path(#path) }, last)
+            }
+        };
+
+        let target = dir.target.clone().unwrap_or(default_target);
+        check_target(&target).map_err(|e| syn_err!(&config_lit, "目录 {i}: {e}"))?;
+
+        items.push(quote! {
+            SaveDir {
+                source: #source,
+                target: #target,
+            }
+        });
+    }
+
+    Ok(quote! {
+        &[ #(#items),* ]
+    })
+}
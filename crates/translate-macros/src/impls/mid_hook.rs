@@ -0,0 +1,178 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{ItemFn, LitInt, LitStr};
+
+use crate::impls::asm_bytes::assemble_relocatable;
+use crate::impls::byte_slice::byte_lit;
+
+/// jmp rel32 的长度
+const JMP_LEN: usize = 5;
+
+struct MidHookAttr {
+    module: Option<LitStr>,
+    rva: LitInt,
+    stolen: LitStr,
+}
+
+fn parse_mid_hook_attr(attr: TokenStream) -> syn::Result<MidHookAttr> {
+    let mut module: Option<LitStr> = None;
+    let mut rva: Option<LitInt> = None;
+    let mut stolen: Option<LitStr> = None;
+
+    let parser = syn::meta::parser(|meta| {
+        match meta.path.get_ident().map(|i| i.to_string()).as_deref() {
+            Some("module") => module = Some(meta.value()?.parse()?),
+            Some("rva") => rva = Some(meta.value()?.parse()?),
+            Some("stolen") => stolen = Some(meta.value()?.parse()?),
+            _ => return Err(meta.error("未知的key，只支持 module、rva 与 stolen")),
+        }
+        Ok(())
+    });
+    syn::parse::Parser::parse2(parser, attr)?;
+
+    match (rva, stolen) {
+        (Some(rva), Some(stolen)) => Ok(MidHookAttr {
+            module,
+            rva,
+            stolen,
+        }),
+        _ => syn_bail2!("mid_hook 属性必须包含 rva 与 stolen"),
+    }
+}
+
+pub fn mid_hook(attr: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let MidHookAttr {
+        module,
+        rva,
+        stolen,
+    } = parse_mid_hook_attr(attr)?;
+    let item_fn = syn::parse2::<ItemFn>(item)?;
+
+    let rva_value = rva.base10_parse::<usize>()?;
+
+    // 被覆盖的指令会在跳板中重新执行，安装时用汇编结果校验原始字节
+    let stolen_bytes =
+        assemble_relocatable(&stolen.value(), 32).map_err(|e| syn_err!(&stolen, "{e}"))?;
+    if stolen_bytes.len() < JMP_LEN {
+        syn_bail!(
+            &stolen,
+            "被覆盖的指令只有 {} 字节，至少需要 {JMP_LEN} 字节才能写入 jmp",
+            stolen_bytes.len()
+        );
+    }
+    let stolen_len = stolen_bytes.len();
+    let stolen_lits = stolen_bytes.iter().map(|&b| byte_lit(b));
+    // 原样嵌入 naked_asm!，需要转义其中的大括号
+    let stolen_asm = stolen.value().replace('{', "{{").replace('}', "}}");
+
+    // 没有指定模块或为 exe 时使用主模块
+    let module_handle_expr = match &module {
+        Some(module) if !module.value().to_lowercase().ends_with(".exe") => {
+            quote! { crate::utils::win32::get_module_handle(::windows_sys::w!(#module)) }
+        }
+        _ => quote! { crate::utils::win32::get_module_handle(core::ptr::null()) },
+    };
+
+    let fn_ident = &item_fn.sig.ident;
+    let vis = &item_fn.vis;
+    let fn_name = fn_ident.to_string();
+    let install_ident = format_ident!("install_{fn_ident}");
+    let entry_ident = format_ident!("__{fn_ident}_mid_hook_entry");
+    let trampoline_ident = format_ident!("__{fn_ident}_mid_hook_trampoline");
+    let return_ident = format_ident!("__{}_MID_HOOK_RETURN", fn_name.to_uppercase());
+
+    // 写入 jmp 后剩余的字节用 nop 填充
+    let padding = stolen_len - JMP_LEN;
+    let padding_ts = (padding > 0).then(|| {
+        quote! {
+            crate::utils::mem::patch::write_asm(
+                (address + #JMP_LEN) as *mut u8,
+                &[0x90u8; #padding],
+            )?;
+        }
+    });
+
+    // 跳板的栈布局（从低到高）：Registers（pushad 与 pushfd），然后是恢复地址
+    // 回调返回 0 时执行被覆盖的指令并跳回原处，否则 ret 到返回的地址
+    let asm = format!(
+        "
+        lea esp, [esp - 4]
+        pushfd
+        pushad
+        add dword ptr [esp + 12], 8
+        cld
+        push esp
+        call {{entry}}
+        test eax, eax
+        jnz 2f
+        popad
+        popfd
+        lea esp, [esp + 4]
+        {stolen_asm}
+        jmp dword ptr [{{ret}}]
+        2:
+        mov [esp + 36], eax
+        popad
+        popfd
+        ret
+        "
+    );
+
+    Ok(quote! {
+        #item_fn
+
+        static mut #return_ident: usize = 0;
+
+        #[translate_macros::ffi_catch_unwind(0)]
+        unsafe extern "system" fn #entry_ident(
+            regs: *mut crate::utils::mem::mid_hook::Registers,
+        ) -> usize {
+            let regs = unsafe { &mut *regs };
+            crate::utils::mem::mid_hook::IntoResume::into_resume(#fn_ident(regs))
+        }
+
+        #[unsafe(naked)]
+        #[unsafe(link_section = ".text")]
+        unsafe extern "system" fn #trampoline_ident() {
+            core::arch::naked_asm!(
+                #asm,
+                entry = sym #entry_ident,
+                ret = sym #return_ident,
+            )
+        }
+
+        /// 校验被覆盖的指令并安装 mid hook
+        ///
+        /// # Safety
+        /// - 目标模块必须已经加载，且在钩子存在期间不能被卸载。
+        /// - 安装期间不能有其他线程执行被覆盖的指令。
+        #vis unsafe fn #install_ident() -> crate::Result<()> {
+            const STOLEN: [u8; #stolen_len] = [ #(#stolen_lits),* ];
+
+            let base = #module_handle_expr? as usize;
+            let address = base + #rva_value;
+
+            let current = unsafe { core::slice::from_raw_parts(address as *const u8, STOLEN.len()) };
+            if current != STOLEN {
+                crate::bail!(
+                    "mid hook {}: expected {:02X?} at {:#x}, found {:02X?}",
+                    #fn_name,
+                    STOLEN,
+                    address,
+                    current
+                );
+            }
+
+            unsafe {
+                #return_ident = address + STOLEN.len();
+                crate::utils::mem::patch::write_jmp_instruction(
+                    address as *mut u8,
+                    #trampoline_ident as *const u8,
+                )?;
+            }
+            #padding_ts
+
+            Ok(())
+        }
+    })
+}
@@ -44,6 +44,7 @@ macro_rules! syn_err2 {
 
 pub(crate) mod utils;
 
+pub(crate) mod asm_bytes;
 pub(crate) mod byte_slice;
 pub(crate) mod derive_default_hook;
 pub(crate) mod detour;
@@ -54,9 +55,17 @@ pub(crate) mod generate_bitmap_font;
 pub(crate) mod generate_constants_from_json;
 pub(crate) mod generate_exports_from_hijacked_dll;
 pub(crate) mod generate_hook_lists_from_json;
+pub(crate) mod generate_ini_overrides;
+pub(crate) mod generate_locale_emulator_registry;
 pub(crate) mod generate_mapping_data;
+pub(crate) mod generate_message_box_rules;
 pub(crate) mod generate_patch_data;
 pub(crate) mod generate_patch_fn_from_1337;
+pub(crate) mod generate_redirect_rules;
+pub(crate) mod generate_registry_rules;
 pub(crate) mod generate_resource_pack;
+pub(crate) mod generate_save_redirect_dirs;
 pub(crate) mod generate_text_patch_data;
+pub(crate) mod mid_hook;
+pub(crate) mod pattern;
 pub(crate) mod search_hook_impls;
@@ -0,0 +1,27 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::LitStr;
+
+use crate::impls::byte_slice::{byte_lit, parse_hex_bytes};
+
+pub fn pattern(input: TokenStream) -> syn::Result<TokenStream> {
+    // 只接受一个字符串字面量
+    let lit = syn::parse2::<LitStr>(input)?;
+    let bytes = parse_hex_bytes(&lit, true)?;
+
+    // 扫描时以确定的字节定位候选位置，全是通配符的特征码没有意义
+    if bytes.iter().all(Option::is_none) {
+        syn_bail!(lit, "特征码中至少需要一个确定的字节，不能全部为 ??");
+    }
+
+    // 通配符位置的字节固定为 0，扫描时不会比较
+    let values = bytes.iter().map(|b| byte_lit(b.unwrap_or(0)));
+    let mask = bytes.iter().map(Option::is_some);
+
+    Ok(quote! {
+        crate::utils::mem::scan::Pattern {
+            bytes: &[ #(#values),* ],
+            mask: &[ #(#mask),* ],
+        }
+    })
+}
@@ -59,6 +59,153 @@ pub fn byte_slice(input: TokenStream) -> TokenStream {
     }
 }
 
+/// 将带通配符的十六进制特征码字符串转换为 `crate::utils::mem::scan::Pattern`
+///
+/// 格式与 [`byte_slice!`] 相同，但额外允许使用 `??` 表示任意字节。
+/// 生成的特征码可以直接传给 `crate::utils::mem::scan` 中的扫描函数。
+///
+/// # 语法
+///
+/// ```ignore
+/// pattern!("8B 44 24 ?? 50 E8 ?? ?? ?? ??")
+/// ```
+///
+/// 上述调用将生成：
+/// ```ignore
+/// crate::utils::mem::scan::Pattern {
+///     bytes: &[0x8Bu8, 0x44u8, 0x24u8, 0x00u8, 0x50u8, 0xE8u8, 0x00u8, 0x00u8, 0x00u8, 0x00u8],
+///     mask: &[true, true, true, false, true, true, false, false, false, false],
+/// }
+/// ```
+///
+/// # 参数要求
+///
+/// - 与 [`byte_slice!`] 相同，字节之间必须用**单个空格**分隔
+/// - 通配符必须写作 `??`
+/// - 至少需要一个确定的字节
+///
+/// # 示例用法
+///
+/// ```ignore
+/// use translate_macros::pattern;
+///
+/// const CALL_DRAW_TEXT: crate::utils::mem::scan::Pattern = pattern!("8B 44 24 ?? 50 E8 ?? ?? ?? ??");
+///
+/// let matches = unsafe { crate::utils::mem::scan::scan_module(base, &[".text"], &CALL_DRAW_TEXT)? };
+/// ```
+#[proc_macro]
+pub fn pattern(input: TokenStream) -> TokenStream {
+    match impls::pattern::pattern(input.into()) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// 在编译时汇编 Intel 语法的 x86/x64 指令
+///
+/// 用于代替手写的机器码（如 `byte_slice!("E9 EB 7A 0C 00 90")`），
+/// 指令之间用换行或 `;` 分隔，第一个参数为位数（`32` 或 `64`）。
+/// 每条指令会在所有可用的编码中选择最短的一个。
+///
+/// # 语法
+///
+/// ```ignore
+/// asm_bytes!(32, "pushad; pushfd; mov eax, [esp+0x28]")
+/// asm_bytes!(32, "push {imm32}; jmp {target}", at = addr, imm32 = value, target = func as usize)
+/// ```
+///
+/// # 支持的写法
+///
+/// - 寄存器、立即数（十进制、`0x` 前缀或 `h` 后缀的十六进制）
+/// - 内存操作数：`[base+index*scale+disp]`，可带 `byte/word/dword/qword ptr` 与段前缀（如 `fs:[0x30]`），
+///   大小不明确时（如 `push [eax]`）必须用 `ptr` 指定
+/// - 标签：`name:`，跳转到标签时会自动选择 rel8 或 rel32
+/// - `$+n`/`$-n`：相对于当前指令开头的跳转目标，如 `jmp $+0x3C`
+/// - 常见的条件跳转别名，如 `jz`、`jnz`
+///
+/// # 占位符
+///
+/// `{name}` 形式的占位符在运行时替换，值通过 `name = 表达式` 传入（表达式会被转换为 `usize`）：
+/// - 作为 `jmp`/`call`/`jcc` 的目标时编码为 rel32，运行时根据 `at = 写入地址` 计算相对偏移
+/// - 作为立即数时编码为 32 位立即数（64 位下 `mov r64, {x}` 为 64 位立即数）
+/// - 在内存操作数中时作为 32 位位移，如 `movsx ebx, byte ptr [ecx+{table}]`
+///
+/// 与 `format!` 一样，每个占位符都必须有对应的参数，每个参数也必须被使用；
+/// 只有存在相对跳转占位符时才需要（也才允许）`at`。
+///
+/// # 生成代码
+///
+/// 没有占位符时生成字节数组 `[u8; N]`，可以用在 `const` 中；
+/// 有占位符时生成 `crate::Result<[u8; N]>`，由 `crate::utils::mem::patch::apply_asm_fixups`
+/// 写入占位符的值，rel32 超出范围或值无法放入 32 位立即数时返回错误。
+///
+/// ```ignore
+/// // 输入：asm_bytes!(32, "push {imm32}; jmp {target}", at = addr, imm32 = value, target = func)
+/// // 输出：
+/// {
+///     let __asm_at: usize = (addr) as usize;
+///     let __asm_imm32: usize = (value) as usize;
+///     let __asm_target: usize = (func) as usize;
+///     crate::utils::mem::patch::apply_asm_fixups(
+///         [0x68u8, 0x78u8, 0x56u8, 0x34u8, 0x12u8, 0xE9u8, 0xFBu8, 0xFFu8, 0xFFu8, 0xFFu8],
+///         __asm_at,
+///         &[
+///             crate::utils::mem::patch::AsmFixup::Abs { offset: 1usize, size: 4usize, value: __asm_imm32 },
+///             crate::utils::mem::patch::AsmFixup::Rel32 { offset: 6usize, next: 10usize, target: __asm_target },
+///         ],
+///     )
+/// }
+/// ```
+#[proc_macro]
+pub fn asm_bytes(input: TokenStream) -> TokenStream {
+    match impls::asm_bytes::asm_bytes(input.into()) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// 在函数中间安装 hook，并把 hook 点的寄存器交给回调：`#[mid_hook(...)]`（仅 x86）
+///
+/// # 语法
+///
+/// ```rust
+/// #[mid_hook(module = "game.exe", rva = 0x1A8F1C, stolen = "mov ecx, [esp+0x40]; push ebx")]
+/// fn on_draw_text(regs: &mut Registers) { ... }
+/// ```
+///
+/// - `module`：可选，省略或为 exe 时使用主模块
+/// - `rva`：hook 点相对模块基址的偏移
+/// - `stolen`：被 jmp 覆盖的完整指令（至少 5 字节），使用 [`asm_bytes!`] 的语法，
+///   但不能包含标签、占位符与相对跳转。汇编结果必须与 hook 点的原始字节完全一致
+///   （例如`mov eax, ecx`有`89 C8`与`8B C1`两种编码），否则安装时返回错误
+///
+/// # 回调
+///
+/// 回调签名为`fn(&mut crate::utils::mem::mid_hook::Registers)`，返回值可以是：
+/// - `()`：执行被覆盖的指令后回到原地址继续执行
+/// - `Option<usize>`：`Some(addr)`时直接跳转到`addr`（不执行被覆盖的指令），`None`同`()`
+///
+/// 回调中对寄存器的修改在恢复执行时生效（`esp`除外），栈上的参数可以通过
+/// `Registers::stack`/`Registers::set_stack`读写。回调中的 panic 会被捕获并视为`None`。
+///
+/// # 生成代码
+///
+/// 保留原函数，并生成跳板、入口函数以及安装函数`install_<name>`：
+/// ```rust
+/// pub unsafe fn install_on_draw_text() -> crate::Result<()> {
+///     // 1. 获取模块基址并计算 hook 地址
+///     // 2. 校验 hook 点的原始字节与 stolen 一致
+///     // 3. write_jmp_instruction 写入 jmp，多余的字节用 nop 填充
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn mid_hook(attr: TokenStream, item: TokenStream) -> TokenStream {
+    match impls::mid_hook::mid_hook(attr.into(), item.into()) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
 /// 在 trait 上自动生成 detour：`#[detour_trait]`
 ///
 /// 应用于 trait 定义。该宏遍历 trait 中的每个方法，对于带有 `#[detour(...)]` 标记的 trait 方法，
@@ -300,6 +447,7 @@ pub fn search_hook_impls(input: TokenStream) -> TokenStream {
 /// - `type`: Rust 类型标识符（如 `"&str"`, `"u32"`, `"bool"`, `"&[u16]"` 等）
 /// - `value`: 常量的值，可以是字符串、数字、布尔值或数组
 /// - `encode_to_u16`（可选）: 仅对字符串有效，为 `true` 时将字符串编码为 UTF-16 字节数组
+/// - `skip`（可选）: 为 `true` 时不生成常量，用于只由其他宏读取的复杂配置（如 `RESOURCE_PACKS`）
 ///
 /// # 生成规则
 /// - 常量名：将配置键名中的非字母数字字符替换为下划线
@@ -650,18 +798,20 @@ pub fn generated_text_patch_data(input: TokenStream) -> TokenStream {
 
 /// 从1337补丁文件生成内存补丁函数的过程宏
 ///
-/// 这个宏在编译时读取指定目录下的所有 `.1337` 补丁文件，生成一个函数，该函数在运行时将补丁数据写入对应模块的内存地址。
+/// 这个宏在编译时读取指定目录下的所有 `.1337` 补丁文件，生成一个函数，该函数在运行时校验原始字节后将补丁数据写入对应模块的内存地址，
+/// 还可以额外生成一个将补丁恢复为原始字节的函数。
 /// 主要用于游戏修改、热修复等需要动态修改二进制代码的场景。
 ///
 /// # 语法
 /// ```ignore
 /// generate_patch_fn_from_1337! {
-///     "path/to/patches" => <pub> fn function_name
+///     "path/to/patches" => <pub> fn function_name <, <pub> fn revert_function_name>
 /// }
 /// ```
 /// - 第一个参数：1337补丁文件所在目录（相对于 `CARGO_MANIFEST_DIR`）
 /// - 可选的 `pub` 关键字：控制生成函数的可见性
 /// - 第二个参数：生成的函数名
+/// - 可选的第三个参数：生成的恢复函数名
 ///
 /// # 1337文件格式
 /// 宏会读取目录下所有扩展名为 `.1337` 的文件，每个文件格式如下：
@@ -673,27 +823,47 @@ pub fn generated_text_patch_data(input: TokenStream) -> TokenStream {
 ///
 /// >engine.dll            # 另一个模块的声明
 /// 0x180204A90: 0xE8 -> 0xC3
+///
+/// >plugin.dll@0x10000000 # 指定模块的首选基址
+/// 10001234: 90 -> CC
 /// ```
 ///
 /// ## 格式规则
-/// - 模块声明：以 `>` 开头，后跟模块名（如 `game.exe` 或 `library.dll`）
-/// - 补丁条目：`地址: 原始字节 -> 新字节`（地址为十六进制，字节值也必须为十六进制）
-/// - 注释：以 `//` 或 `#` 开头的行会被忽略
+/// - 模块声明：以 `>` 开头，后跟模块名（如 `game.exe` 或 `library.dll`），可以用 `@基址` 指定模块的首选基址
+/// - 补丁条目：`地址: 原始字节 -> 新字节`（地址与字节值均为十六进制，可带 `0x` 前缀），地址可以是 VA 也可以是 RVA
+/// - 注释：`//` 或 `#` 之后的内容会被忽略
 /// - 空行：自动忽略
 ///
+/// ## 地址转换
+/// 所有地址在编译时都会被转换为相对于模块基址的 RVA，运行时再加上模块的实际基址，
+/// 因此同一份补丁在 ASLR 下也能正确应用，`i686` 与 `x86_64` 构建均可使用。
+/// 模块的首选基址按以下顺序确定：
+/// 1. 模块声明中的 `@基址`，不小于该基址的地址视为 VA
+/// 2. 补丁目录中与模块同名的 PE 文件（不区分大小写，如 `patches/MyGame.exe`），
+///    位于其映像范围内的地址视为 VA，小于 `SizeOfImage` 的地址视为 RVA，其余地址会导致编译失败
+/// 3. 默认基址：exe 为 `0x140000000` 或 `0x400000`，dll 为 `0x180000000` 或 `0x10000000`，
+///    不小于默认基址的地址视为 VA
+///
+/// 转换后的 RVA 超出 32 位时编译失败
+///
 /// # 生成内容
 /// 宏展开后会生成一个返回 `crate::Result<()>` 的函数，该函数：
-/// - 自动获取各模块的基址（主模块使用空字符串获取）
+/// - 在运行时获取各模块的实际基址（主模块使用空字符串获取）
 /// - 按模块分组并应用所有补丁
 /// - 自动合并连续地址的补丁以优化内存写入操作
+/// - 写入前校验所有补丁的原始字节，任何一处不匹配（例如游戏版本不同）都不会写入任何字节，
+///   并通过 `debug!` 输出差异；已经应用过的补丁会被跳过
+///
+/// 恢复函数以相同的方式校验补丁后的字节，并写回原始字节
 ///
 /// # 处理规则
 /// - 自动处理路径解析（相对于 `CARGO_MANIFEST_DIR`）
 /// - **最多只能有一个主模块**（以 `.exe` 结尾的模块），否则编译失败
 /// - 补丁必须位于模块声明之后，否则编译失败
+/// - 同一模块中的地址不能重复，否则编译失败
 /// - 自动按地址排序并合并连续的内存补丁
 /// - 使用 `crate::utils::win32::get_module_handle` 获取模块句柄
-/// - 使用 `crate::utils::mem::patch::write_asm` 写入补丁数据
+/// - 使用 `crate::utils::mem::patch::write_verified_patches` 校验并写入补丁数据
 ///
 /// # 示例
 /// ## 1337文件内容 (`patches/game.1337`)
@@ -719,13 +889,21 @@ pub fn generated_text_patch_data(input: TokenStream) -> TokenStream {
 ///
 /// ## 生成的代码大致如下
 /// ```ignore
+/// fn __apply_game_patches_modules() -> crate::Result<Vec<crate::utils::mem::patch::ModulePatches>> {
+///     Ok(vec![crate::utils::mem::patch::ModulePatches {
+///         module: "MyGame.exe",
+///         base: crate::utils::win32::get_module_handle(core::ptr::null())? as usize,
+///         patches: &[crate::utils::mem::patch::BytePatch {
+///             // 0x140001000 - 0x140000000
+///             rva: 0x1000,
+///             original: &[0x74, 0x3C, 0x07],
+///             patched: &[0xEB, 0x90, 0x90],
+///         }],
+///     }])
+/// }
+///
 /// pub fn apply_game_patches() -> crate::Result<()> {
-///     // Patch模块: MyGame.exe
-///     let module_base = crate::utils::win32::get_module_handle(core::ptr::null())? as usize;
-///     let target_addr = module_base.wrapping_add(0x140001000 as usize);
-///     let data: &[u8] = &[0xEB, 0x90, 0x90];
-///     crate::utils::mem::patch::write_asm(target_addr as *mut u8, data)?;
-///     Ok(())
+///     crate::utils::mem::patch::write_verified_patches(&__apply_game_patches_modules()?, false)
 /// }
 /// ```
 #[proc_macro]
@@ -851,32 +1029,47 @@ pub fn generate_hook_lists_from_json(input: TokenStream) -> TokenStream {
 
 /// 生成资源包嵌入代码的过程宏。
 ///
-/// 该宏在编译时将指定目录下的资源文件打包，并生成用于运行时提取和访问这些资源的代码。
+/// 该宏在编译时将指定目录下的资源文件打包，并生成用于运行时加载这些资源的代码。
 ///
 /// # 语法
 ///
 /// ```ignore
 /// generate_resource_pack!(resource_dir, config_path);
 /// generate_resource_pack!(resource_dir, config_path, output_path);
+/// generate_resource_pack!(resource_dir, config_path, output_path, version = 2);
 /// ```
 ///
 /// # 参数
 ///
 /// - `resource_dir`: 资源文件所在的目录路径（相对于 `Cargo.toml` 的字符串字面量）。
 /// - `config_path`: JSON 配置文件路径（相对于 `Cargo.toml` 的字符串字面量），
-///   文件中必须包含 `RESOURCE_PACK_NAME` 字段用于指定资源包名称。
-/// - `output_path`（可选）: 若提供，资源包将输出为外部文件而非嵌入二进制；
-///   运行时将从该路径加载 `.pak` 文件。
+///   文件中必须包含 `RESOURCE_PACK_NAME` 或 `RESOURCE_PACKS` 字段。
+/// - `output_path`（可选）: 若提供，资源包默认输出为外部文件而非嵌入二进制；
+///   运行时将从exe所在目录加载 `.pak` 文件。
+/// - `version`（可选）: 资源包格式版本，`1`（默认）或 `2`。
 ///
-/// # 生成的模块内容
+/// # 多层资源包
+///
+/// 配置 `RESOURCE_PACKS` 后会生成多个资源包层，排在前面的优先级更高：
+///
+/// ```json
+/// "RESOURCE_PACKS": [
+///   { "name": "hotfix", "optional": true },
+///   { "name": "voice", "dir": "assets/voice", "optional": true },
+///   { "name": "base", "embedded": true }
+/// ]
+/// ```
+///
+/// - `name`: 资源包名称，对应外置资源包的文件名与临时目录名
+/// - `dir`（可选）: 资源目录，默认为 `<resource_dir>/<name>`
+/// - `embedded`（可选）: 是否内嵌，默认由是否提供了 `output_path` 决定
+/// - `optional`（可选）: 外置资源包不存在时跳过该层
 ///
-/// 宏会生成一个包含以下内容的内部模块：
+/// # 生成的模块内容
 ///
-/// - `get_temp_dir()`: 返回资源提取的临时目录路径（`&'static Path`）。
-/// - `RESOURCE_PATHS`: 包含所有资源相对路径的 `phf::Set<&'static str>` 静态集合。
-/// - `is_resource(path: &str) -> bool`: 检查给定路径是否为资源包中的文件。
-/// - `extract() -> Result<()>`: 将资源提取到临时目录，自动处理压缩解压。
-/// - `clean_up() -> Result<()>`: 清理临时目录。
+/// 每一层生成一个 `layer_N` 模块，包含 `get_temp_dir()`、`RESOURCE_PATHS`、
+/// `RESOURCE_SIZES`、`RESOURCE_INDEX`（仅 v2）、`PACK_HASH` 以及 `load()`，
+/// 并生成按优先级排列的 `LAYERS: &[super::PackLayer]`。
 #[proc_macro]
 pub fn generate_resource_pack(input: TokenStream) -> TokenStream {
     match impls::generate_resource_pack::generate_resource_pack(input.into()) {
@@ -950,3 +1143,215 @@ pub fn generate_bitmap_font(input: TokenStream) -> TokenStream {
         Err(err) => err.into_compile_error().into(),
     }
 }
+
+/// 从用户配置中读取路径重定向规则的过程宏。
+///
+/// # 语法
+///
+/// ```ignore
+/// static RULE_SPECS: &[RuleSpec] = generate_redirect_rules!("assets/config.json");
+/// ```
+///
+/// 读取配置中的 `REDIRECTION_RULES` 数组，展开为 `&[RuleSpec { .. }]` 表达式，
+/// 调用处需要能访问到 `RuleSpec`、`MatchKind` 与 `TargetBase`。配置文件或该字段不存在时展开为空数组。
+///
+/// ```json
+/// "REDIRECTION_RULES": [
+///   { "match": "suffix", "pattern": "DATA2.TCD", "target": "$1DATA_chs.TCD" },
+///   { "match": "glob", "pattern": "voice/*.ogg", "target": "voice_chs/$1.ogg" },
+///   { "match": "regex", "pattern": "^save/(\\d+)\\.dat$", "target": "save/$1.dat", "base": "resource_pack" }
+/// ]
+/// ```
+///
+/// - `match`: 匹配方式，`exact` / `suffix` / `glob` / `regex`
+/// - `pattern`: 匹配模式，glob 模式会在编译期转换为正则表达式，无效的 glob 或正则表达式会导致编译错误
+/// - `target`: 目标路径模板，可使用 `$N` / `${N}` / `${name}` 引用捕获组
+/// - `base`（可选）: 相对目标路径的基准，`exe`（默认）/ `source` / `resource_pack`
+#[proc_macro]
+pub fn generate_redirect_rules(input: TokenStream) -> TokenStream {
+    match impls::generate_redirect_rules::generate_redirect_rules(input.into()) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// 从用户配置中读取需要重定向到存档目录的目录列表的过程宏。
+///
+/// # 语法
+///
+/// ```ignore
+/// static SAVE_DIRS: &[SaveDir] = generate_save_redirect_dirs!("assets/config.json");
+/// ```
+///
+/// 读取配置中的 `SAVE_REDIRECT_DIRS` 数组，展开为 `&[SaveDir { .. }]` 表达式，
+/// 调用处需要能访问到 `SaveDir`、`SaveSource` 与 `KnownFolder`。配置文件或该字段不存在时展开为空数组。
+///
+/// ```json
+/// "SAVE_REDIRECT_DIRS": [
+///   { "source": "appdata" },
+///   { "source": "documents", "target": "MyDocs" },
+///   { "source": "%LOCALAPPDATA%/Company/Game" },
+///   { "source": "savedata" }
+/// ]
+/// ```
+///
+/// - `source`: 已知文件夹（`appdata` / `local_appdata` / `local_appdata_low` / `common_appdata` /
+///   `documents` / `saved_games`），或者目录路径，路径可包含 `%VAR%` 环境变量，相对路径相对于exe目录
+/// - `target`（可选）: 存档目录下的子目录名，默认为已知文件夹的名称或路径的最后一级，不能包含 `..`
+#[proc_macro]
+pub fn generate_save_redirect_dirs(input: TokenStream) -> TokenStream {
+    match impls::generate_save_redirect_dirs::generate_save_redirect_dirs(input.into()) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// 读取注册表虚拟化配置的过程宏。
+///
+/// # 语法
+///
+/// ```ignore
+/// static CONFIG: RegistryConfig = generate_registry_rules!("assets/registry.json");
+/// ```
+///
+/// 展开为 `RegistryConfig { store_file, keys: &[KeySpec { .. }] }` 表达式，
+/// 调用处需要能访问到 `RegistryConfig`、`KeySpec`、`KeyMode`、`RegRoot`、`ValueSpec` 与 `DataSpec`。
+/// 配置文件不存在时展开为没有任何规则的配置。
+///
+/// ```json
+/// {
+///   "store_file": "save/registry.json",
+///   "keys": [
+///     { "key": "HKLM\\Software\\Company\\Game", "mode": "redirect" },
+///     { "key": "HKLM\\Software\\Company\\Game2", "mode": "redirect", "target": "HKCU\\Software\\Game2" },
+///     { "key": "HKLM\\Software\\Company\\Save", "mode": "store", "values": { "Slot": 0 } },
+///     {
+///       "key": "HKLM\\Software\\Company\\Install",
+///       "mode": "fake",
+///       "values": {
+///         "InstallDir": "{exe_dir}",
+///         "Version": 100,
+///         "Big": { "type": "qword", "data": "0x100000000" },
+///         "Discs": ["CD1", "CD2"],
+///         "Magic": { "type": "binary", "data": "DE AD BE EF" }
+///       }
+///     }
+///   ]
+/// }
+/// ```
+///
+/// - `store_file`（可选）: 外置存储的路径，相对于exe目录，默认为 `registry.json`
+/// - `key`: 完整的键路径，根键可以是 `HKLM` / `HKCU` / `HKCR` / `HKU` / `HKCC` 或其完整名称
+/// - `mode`: `redirect` 重定向到 `target`（默认为 HKCU 下的同名路径）；
+///   `store` 读写外置存储，`values` 为默认值；`fake` 只读地返回 `values` 中的常量
+/// - 值可以是字符串（sz）、整数（dword/qword）、字符串数组（multi_sz），
+///   或者 `{ "type": "sz|expand_sz|multi_sz|dword|qword|binary", "data": .. }`
+#[proc_macro]
+pub fn generate_registry_rules(input: TokenStream) -> TokenStream {
+    match impls::generate_registry_rules::generate_registry_rules(input.into()) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// 读取 INI 覆盖配置的过程宏。
+///
+/// # 语法
+///
+/// ```ignore
+/// static RULES: &[IniRule] = generate_ini_overrides!("assets/ini_overrides.json");
+/// ```
+///
+/// 展开为 `&[IniRule { .. }]` 表达式，调用处需要能访问到 `IniRule` 与 `IniAction`。
+/// 配置文件不存在时展开为空数组。
+///
+/// ```json
+/// [
+///   { "file": "Assemblage.INI", "section": "LUSTS", "key": "CDROM", "value": "Y:\\" },
+///   { "file": "Assemblage.INI", "section": "Games", "key": "InstCount", "value": 1 },
+///   { "file": "*.ini", "section": "Config", "redirect": "save/config.ini" },
+///   { "file": "system.ini", "redirect": "save/system.ini" }
+/// ]
+/// ```
+///
+/// - `file`: INI 文件的匹配模式，支持 `*` 与 `?`，不含路径分隔符时只匹配文件名，否则匹配完整路径
+/// - `section`（可选）: 节名的匹配模式，不指定时匹配任意节
+/// - `key`（可选）: 键名的匹配模式，不指定时匹配任意键
+/// - `value`: 固定的值（字符串、整数或布尔值），需要同时指定 `section` 与 `key`
+/// - `redirect`: 将命中的读写重定向到另一个 INI 文件，相对路径相对于exe目录
+///
+/// `value` 与 `redirect` 必须且只能指定一个，规则按顺序匹配，使用第一个命中的规则。
+#[proc_macro]
+pub fn generate_ini_overrides(input: TokenStream) -> TokenStream {
+    match impls::generate_ini_overrides::generate_ini_overrides(input.into()) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// 从用户配置中读取`MessageBox`自动应答规则的过程宏。
+///
+/// # 语法
+///
+/// ```ignore
+/// static RULE_SPECS: &[RuleSpec] = generate_message_box_rules!("assets/config.json");
+/// ```
+///
+/// 读取配置中的 `MESSAGE_BOX_RULES` 数组，展开为 `&[RuleSpec { .. }]` 表达式，
+/// 调用处需要能访问到 `RuleSpec`、`MatchKind` 与 `MessageBoxAction`。配置文件或该字段不存在时展开为空数组。
+///
+/// ```json
+/// "MESSAGE_BOX_RULES": [
+///   { "caption": "日本語版Windows判定", "action": "result", "result": "cancel" },
+///   { "match": "regex", "text": "CD-ROM", "action": "suppress" },
+///   { "match": "regex", "text": "^(.+)が見つかりません", "action": "translate", "translated_text": "找不到$1" }
+/// ]
+/// ```
+///
+/// - `match`（可选）: 匹配方式，`exact`（默认）/ `regex`，正则表达式在运行时编译
+/// - `caption` / `text`: 标题与正文的匹配模式，至少需要指定一个，同时指定时都需要匹配
+/// - `action`: `result` 直接返回 `result`；`suppress` 不显示并返回默认按钮；`translate` 显示翻译后的对话框
+/// - `result`: 整数或按钮名（`ok` / `cancel` / `abort` / `retry` / `ignore` / `yes` / `no` / `try_again` / `continue`）
+/// - `translated_caption` / `translated_text`（可选）: 翻译后的标题与正文，正则表达式规则中可以使用 `$N` / `${name}` 引用捕获组
+#[proc_macro]
+pub fn generate_message_box_rules(input: TokenStream) -> TokenStream {
+    match impls::generate_message_box_rules::generate_message_box_rules(input.into()) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// 从用户配置中读取`locale_emulator`注册表重定向项的过程宏。
+///
+/// # 语法
+///
+/// ```ignore
+/// static ENTRIES: &[RegistryRedirection] = generate_locale_emulator_registry!("assets/config.json");
+/// ```
+///
+/// 读取配置中的 `EMULATE_LOCALE_REGISTRY` 数组，展开为 `&[RegistryRedirection { .. }]` 表达式，
+/// 调用处需要能访问到 `RegistryRedirection` 与 `windows_sys`，
+/// 根键展开为预定义句柄的值（如 `HKLM` 为 `0x80000002`）。配置文件或该字段不存在时展开为空数组。
+///
+/// ```json
+/// "EMULATE_LOCALE_REGISTRY": [
+///   {
+///     "key": "HKLM\\SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\FontSubstitutes",
+///     "name": "MS Shell Dlg",
+///     "value": "MS UI Gothic"
+///   },
+///   { "key": "HKCU\\Software\\Company\\Game", "name": "Language", "value": 1041 }
+/// ]
+/// ```
+///
+/// - `key`: 完整的键路径，根键可以是 `HKLM` / `HKCU` / `HKCR` / `HKU` / `HKCC` 或其完整名称
+/// - `name`（可选）: 值名，不指定时为默认值
+/// - `value`: 替换后的值，格式与 `generate_registry_rules` 中的值相同，数据在编译期编码
+#[proc_macro]
+pub fn generate_locale_emulator_registry(input: TokenStream) -> TokenStream {
+    match impls::generate_locale_emulator_registry::generate_locale_emulator_registry(input.into())
+    {
+        Ok(ts) => ts.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
@@ -6,6 +6,8 @@ use anyhow::{Context, anyhow, bail};
 use fs_extra::dir::{CopyOptions, copy as copy_dir, remove as remove_dir};
 use xshell::{Shell, cmd};
 
+mod pak;
+
 const TEST_ASSETS_DIR: &str = "xtask/test_assets";
 const TARGET_ASSETS_DIR: &str = "crates/text-hook/assets";
 
@@ -13,11 +15,13 @@ fn main() -> anyhow::Result<()> {
     let mut args = std::env::args().skip(1);
     match args.next().as_deref() {
         Some("check") => run_check_command(),
+        Some("pak") => pak::run_pak_command(args),
         Some(cmd_name) => bail!("未知的 xtask 命令: {cmd_name}"),
         None => {
             println!("用法: cargo xtask <命令>");
             println!("可用命令:");
             println!("  check    执行 text-hook feature 组合检查");
+            println!("  pak      创建、查看、解包、校验 v2 资源包");
             Ok(())
         }
     }
@@ -100,6 +104,42 @@ fn build_scenarios() -> Vec<Scenario> {
             ),
             run_x64: true,
         },
+        Scenario {
+            name: "default_impl/resource_pack/v2".to_string(),
+            features: feature_set(
+                all_functional_impl_base(),
+                &["default_impl", "resource_pack_v2"],
+                &[],
+            ),
+            run_x64: true,
+        },
+        Scenario {
+            name: "default_impl/resource_pack/vfs".to_string(),
+            features: feature_set(
+                all_functional_impl_base(),
+                &["default_impl", "resource_pack_vfs"],
+                &[],
+            ),
+            run_x64: true,
+        },
+        Scenario {
+            name: "default_impl/resource_pack/lazy".to_string(),
+            features: feature_set(
+                all_functional_impl_base(),
+                &["default_impl", "resource_pack_lazy"],
+                &[],
+            ),
+            run_x64: true,
+        },
+        Scenario {
+            name: "default_impl/locale_emulator_lite".to_string(),
+            features: feature_set(
+                all_functional_impl_base(),
+                &["default_impl", "locale_emulator_lite"],
+                &["locale_emulator"],
+            ),
+            run_x64: true,
+        },
         Scenario {
             name: "default_impl/hook_backend/inline".to_string(),
             features: feature_set(all_functional_impl_base(), &["default_impl"], &["iat_hook"]),
@@ -425,19 +465,34 @@ fn all_functional_impl_base<'a>() -> &'a [&'a str] {
         "worker_thread",
         "veh",
         "resource_pack",
+        "resource_pack_find",
+        "redirect_rules",
+        "save_redirect",
+        "registry_virtualization",
+        "ini_virtualization",
+        "message_box_rules",
         "create_file_redirect",
         "x64dbg_1337_patch",
+        "revert_1337_patch_on_clean_up",
         "text_patch",
         "patch",
         "read_file_patch_impl",
+        "file_mapping_patch_impl",
+        "crt_file_patch_impl",
+        "crt_msvcrt",
         "export_patch_process_fn",
         "custom_font",
         "export_default_dll_main",
         "locale_emulator",
         "text_hook",
         "file_hook",
+        "shell_hook",
+        "registry_hook",
+        "profile_hook",
+        "crt_file_hook",
         "window_hook",
         "code_cvt_hook",
+        "dbcs_hook",
         "life_cycle_hook",
         "export_hooks",
     ]
@@ -0,0 +1,215 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+use resource_pack_format::{Entry, PakReader, PakWriter};
+
+/// zstd 压缩等级，与 `generate_resource_pack` 保持一致
+const COMPRESSION_LEVEL: i32 = 3;
+
+pub fn run_pak_command(mut args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    match args.next().as_deref() {
+        Some("create") => {
+            let (Some(dir), Some(out)) = (args.next(), args.next()) else {
+                bail!("用法: cargo xtask pak create <资源目录> <输出.pak>");
+            };
+            create(Path::new(&dir), Path::new(&out))
+        }
+        Some("list") => {
+            let Some(pak) = args.next() else {
+                bail!("用法: cargo xtask pak list <资源包.pak>");
+            };
+            list(Path::new(&pak))
+        }
+        Some("extract") => {
+            let (Some(pak), Some(out)) = (args.next(), args.next()) else {
+                bail!("用法: cargo xtask pak extract <资源包.pak> <输出目录> [文件路径]");
+            };
+            extract(Path::new(&pak), Path::new(&out), args.next().as_deref())
+        }
+        Some("verify") => {
+            let Some(pak) = args.next() else {
+                bail!("用法: cargo xtask pak verify <资源包.pak>");
+            };
+            verify(Path::new(&pak))
+        }
+        Some(cmd_name) => bail!("未知的 pak 子命令: {cmd_name}"),
+        None => {
+            println!("用法: cargo xtask pak <子命令>");
+            println!("可用子命令:");
+            println!("  create <资源目录> <输出.pak>                打包目录为 v2 资源包");
+            println!("  list <资源包.pak>                           列出资源包中的文件");
+            println!("  extract <资源包.pak> <输出目录> [文件路径]  解包全部或单个文件");
+            println!("  verify <资源包.pak>                         校验所有文件的大小与 CRC32");
+            Ok(())
+        }
+    }
+}
+
+/// 收集目录下的所有文件，路径统一为小写并使用 `/` 分隔
+fn collect_files(dir: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+
+    while let Some(current) = stack.pop() {
+        for entry in std::fs::read_dir(&current)
+            .with_context(|| format!("无法读取目录 {}", current.display()))?
+        {
+            let path = entry?.path();
+            if path.is_dir() {
+                stack.push(path);
+                continue;
+            }
+
+            let relative = path
+                .strip_prefix(dir)?
+                .to_string_lossy()
+                .to_lowercase()
+                .replace('\\', "/");
+            files.push((relative, path));
+        }
+    }
+
+    Ok(files)
+}
+
+fn create(dir: &Path, out: &Path) -> anyhow::Result<()> {
+    let mut writer = PakWriter::new();
+
+    for (relative, path) in collect_files(dir)? {
+        let content =
+            std::fs::read(&path).with_context(|| format!("读取文件失败 {}", path.display()))?;
+        let compressed = zstd::bulk::compress(&content, COMPRESSION_LEVEL)
+            .with_context(|| format!("zstd压缩失败 {}", path.display()))?;
+        writer.add(&relative, &content, Some(compressed));
+    }
+
+    let (data, paths) = writer.finish();
+
+    if let Some(parent) = out.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(out, &data).with_context(|| format!("写入资源包失败 {}", out.display()))?;
+
+    println!(
+        "已写入 {}，共 {} 个文件，{} 字节",
+        out.display(),
+        paths.len(),
+        data.len()
+    );
+    Ok(())
+}
+
+fn read_pak(pak: &Path) -> anyhow::Result<Vec<u8>> {
+    let data = std::fs::read(pak).with_context(|| format!("读取资源包失败 {}", pak.display()))?;
+    if !resource_pack_format::is_v2(&data) {
+        bail!("{} 不是 v2 格式的资源包", pak.display());
+    }
+    Ok(data)
+}
+
+/// 读取并解压单个文件，同时校验大小与 CRC32
+fn decode(reader: &PakReader, path: &str, entry: &Entry) -> anyhow::Result<Vec<u8>> {
+    let stored = reader.stored_data(entry)?;
+    let content = if entry.is_compressed() {
+        zstd::bulk::decompress(stored, entry.size as usize)
+            .with_context(|| format!("zstd解压失败 {path}"))?
+    } else {
+        stored.to_vec()
+    };
+
+    if content.len() as u64 != entry.size {
+        bail!("{path} 大小不匹配: {} != {}", content.len(), entry.size);
+    }
+
+    let crc32 = resource_pack_format::crc32(&content);
+    if crc32 != entry.crc32 {
+        bail!("{path} CRC32 不匹配: {crc32:08x} != {:08x}", entry.crc32);
+    }
+
+    Ok(content)
+}
+
+fn list(pak: &Path) -> anyhow::Result<()> {
+    let data = read_pak(pak)?;
+    let reader = PakReader::new(&data)?;
+
+    println!(
+        "{:>12} {:>12} {:>8}  路径",
+        "大小", "存储大小", "CRC32"
+    );
+    for item in reader.entries() {
+        let (path, entry) = item?;
+        println!(
+            "{:>12} {:>12} {:08x}  {path}{}",
+            entry.size,
+            entry.compressed_size,
+            entry.crc32,
+            if entry.is_compressed() { "" } else { " (未压缩)" }
+        );
+    }
+    println!("共 {} 个文件", reader.len());
+
+    Ok(())
+}
+
+fn extract(pak: &Path, out: &Path, only: Option<&str>) -> anyhow::Result<()> {
+    let data = read_pak(pak)?;
+    let reader = PakReader::new(&data)?;
+
+    let write = |path: &str, entry: &Entry| -> anyhow::Result<()> {
+        let content = decode(&reader, path, entry)?;
+        let file_path = out.join(path);
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&file_path, content)
+            .with_context(|| format!("写入文件失败 {}", file_path.display()))
+    };
+
+    if let Some(only) = only {
+        let only = only.to_lowercase().replace('\\', "/");
+        let Some(index) = reader.find(&only) else {
+            bail!("资源包中不存在 {only}");
+        };
+        write(&only, &reader.entry(index)?)?;
+        println!("已解包 {only}");
+        return Ok(());
+    }
+
+    for item in reader.entries() {
+        let (path, entry) = item?;
+        write(path, &entry)?;
+    }
+    println!("已解包 {} 个文件到 {}", reader.len(), out.display());
+
+    Ok(())
+}
+
+fn verify(pak: &Path) -> anyhow::Result<()> {
+    let data = read_pak(pak)?;
+    let reader = PakReader::new(&data)?;
+
+    let mut failed = 0usize;
+    let mut prev: Option<&str> = None;
+    for item in reader.entries() {
+        let (path, entry) = item?;
+
+        if prev.is_some_and(|prev| prev >= path) {
+            println!("索引未按路径排序: {path}");
+            failed += 1;
+        }
+        prev = Some(path);
+
+        if let Err(e) = decode(&reader, path, &entry) {
+            println!("{e:#}");
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        bail!("校验失败，共 {failed} 处错误");
+    }
+
+    println!("校验通过，共 {} 个文件", reader.len());
+    Ok(())
+}